@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::app_state::{AppState, Command, DownloadItem, DownloadStatus, ItemEdit};
+use crate::output_template;
+
+/// Ways to make colliding file names unique
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Uniquify {
+    /// " (3)", the item's position in the batch
+    Index,
+    /// " [dQw4w9WgXcQ]", yt-dlp fills it in at download time
+    VideoId,
+}
+
+/// File names a playlist batch would write, checked before it starts
+#[derive(Debug, Clone)]
+pub struct CollisionReport {
+    pub batch_id: Uuid,
+    /// Items the batch would download
+    pub checked: usize,
+    /// Paths more than one item would write, with those items
+    pub collisions: Vec<(String, Vec<Uuid>)>,
+    /// Items whose file is already on disk, yt-dlp would skip them
+    pub existing: Vec<(String, Uuid)>,
+    pub scroll: usize,
+}
+
+impl CollisionReport {
+    pub fn is_clean(&self) -> bool {
+        self.collisions.is_empty() && self.existing.is_empty()
+    }
+
+    /// Items sharing a file name. Ones whose file is already on disk are left alone,
+    /// renaming them would download them again.
+    fn affected(&self) -> Vec<Uuid> {
+        self.collisions
+            .iter()
+            .flat_map(|(_, ids)| ids.iter().copied())
+            .filter(|id| !self.existing.iter().any(|(_, existing)| existing == id))
+            .collect()
+    }
+}
+
+/// Whether a batch item would be downloaded when the batch starts
+pub fn will_download(item: &DownloadItem) -> bool {
+    matches!(
+        item.status,
+        DownloadStatus::Pending | DownloadStatus::Ready | DownloadStatus::Failed | DownloadStatus::Cancelled
+    )
+}
+
+/// Group paths that would be the same file. Compared case-insensitively, as on Windows and macOS.
+fn find_collisions(paths: &[(Uuid, String)]) -> Vec<(String, Vec<Uuid>)> {
+    let mut groups: Vec<(String, Vec<Uuid>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for (id, path) in paths {
+        match index.get(&path.to_lowercase()) {
+            Some(&i) => groups[i].1.push(*id),
+            None => {
+                index.insert(path.to_lowercase(), groups.len());
+                groups.push((path.clone(), vec![*id]));
+            }
+        }
+    }
+    groups.retain(|(_, ids)| ids.len() > 1);
+    groups
+}
+
+/// Put `suffix` before the extension field, or at the end when there is none
+fn with_suffix(template: &str, suffix: &str) -> String {
+    match template.strip_suffix(".%(ext)s") {
+        Some(stem) => format!("{}{}.%(ext)s", stem, suffix),
+        None => format!("{}{}", template, suffix),
+    }
+}
+
+impl AppState {
+    /// Where yt-dlp would write an item, resolved locally from what is known so far
+    pub fn planned_path(&self, item: &DownloadItem) -> String {
+        let template = item.output_template.as_deref().unwrap_or(&self.config.output_template);
+        let ext = output_template::item_ext(item, &self.config.merge_container);
+        let name = output_template::substitute(template, &output_template::item_values(item, &ext));
+        let dir = item.output_dir.as_deref().unwrap_or(&self.output_dir);
+        Path::new(dir).join(name).to_string_lossy().into_owned()
+    }
+
+    /// Dry-run the file names of a batch's remaining items
+    pub fn check_batch_names(&self, batch_id: Uuid) -> CollisionReport {
+        let paths: Vec<(Uuid, String)> = self
+            .queue
+            .iter()
+            .filter(|i| i.batch_id == Some(batch_id) && will_download(i))
+            .map(|i| (i.id, self.planned_path(i)))
+            .collect();
+        CollisionReport {
+            batch_id,
+            checked: paths.len(),
+            collisions: find_collisions(&paths),
+            existing: paths
+                .iter()
+                .filter(|(_, path)| Path::new(path).exists())
+                .map(|(id, path)| (path.clone(), *id))
+                .collect(),
+            scroll: 0,
+        }
+    }
+
+    /// Give every colliding item in the report its own file name, then check again. Items
+    /// already downloaded or on disk keep theirs.
+    pub fn uniquify_batch(&mut self, report: &CollisionReport, mode: Uniquify) -> CollisionReport {
+        let affected = report.affected();
+        let batch: Vec<Uuid> = self
+            .queue
+            .iter()
+            .filter(|i| i.batch_id == Some(report.batch_id))
+            .map(|i| i.id)
+            .collect();
+        let mut edits = Vec::new();
+        let pending = self
+            .queue
+            .iter()
+            .filter(|i| affected.contains(&i.id) && will_download(i) && !Path::new(&self.planned_path(i)).exists());
+        for item in pending {
+            let suffix = match mode {
+                Uniquify::Index => {
+                    let position = batch.iter().position(|id| *id == item.id).unwrap_or(0) + 1;
+                    format!(" ({})", position)
+                }
+                Uniquify::VideoId => " [%(id)s]".to_string(),
+            };
+            // Built from the configured template, not the item's, so suffixes never stack
+            let renamed = with_suffix(&self.config.output_template, &suffix);
+            if item.output_template.as_ref() != Some(&renamed) {
+                edits.push((item.id, ItemEdit::OutputTemplate(Some(renamed))));
+            }
+        }
+        if !edits.is_empty() {
+            self.apply(Command::EditItems(edits));
+        }
+        self.check_batch_names(report.batch_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_collisions() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let paths = vec![
+            (a, "/dl/Intro.mp4".to_string()),
+            (b, "/dl/Outro.mp4".to_string()),
+            (c, "/dl/intro.mp4".to_string()),
+        ];
+        assert_eq!(find_collisions(&paths), vec![("/dl/Intro.mp4".to_string(), vec![a, c])]);
+
+        assert_eq!(with_suffix("%(title)s.%(ext)s", " (2)"), "%(title)s (2).%(ext)s");
+        assert_eq!(with_suffix("%(title)s", " [%(id)s]"), "%(title)s [%(id)s]");
+    }
+
+    #[test]
+    fn test_uniquify_batch() {
+        let mut state = AppState::default();
+        let batch_id = Uuid::new_v4();
+        for status in [DownloadStatus::Ready, DownloadStatus::Ready, DownloadStatus::Completed] {
+            let mut item = DownloadItem::new("https://example.com/v".to_string());
+            item.title = Some("Intro".to_string());
+            item.batch_id = Some(batch_id);
+            item.status = status;
+            state.queue.push(item);
+        }
+        let report = state.check_batch_names(batch_id);
+        assert_eq!(report.collisions.len(), 1);
+
+        let report = state.uniquify_batch(&report, Uniquify::Index);
+        assert!(report.is_clean());
+        let templates: Vec<Option<&str>> = state.queue.iter().map(|i| i.output_template.as_deref()).collect();
+        let template = state.config.output_template.clone();
+        let suffixed = |n: &str| with_suffix(&template, n);
+        assert_eq!(templates, [Some(suffixed(" (1)").as_str()), Some(suffixed(" (2)").as_str()), None]);
+
+        // Uniquifying again changes nothing and leaves nothing to undo
+        let report = state.check_batch_names(batch_id);
+        state.uniquify_batch(&report, Uniquify::Index);
+        let collided = CollisionReport { collisions: vec![(String::new(), vec![state.queue[0].id])], ..report };
+        state.uniquify_batch(&collided, Uniquify::Index);
+        assert_eq!(state.queue[0].output_template.as_deref(), Some(suffixed(" (1)").as_str()));
+        assert_eq!(state.undo().as_deref(), Some("file names of 2 items"));
+        assert!(state.queue.iter().all(|i| i.output_template.is_none()));
+    }
+}
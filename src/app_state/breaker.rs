@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::app_state::{AppState, DownloadStatus};
+
+/// Transient failures in a row from one site that trip its breaker
+pub const TRIP_FAILURES: u32 = 3;
+/// How long a tripped breaker holds back its site's downloads
+pub const COOLDOWN: Duration = Duration::from_secs(300);
+/// Transient failures an item waits out before it fails for good
+pub const ITEM_RETRIES: u32 = 5;
+/// How long an item waits after its first transient failure, doubling with each one after
+pub const RETRY_DELAY: Duration = Duration::from_secs(15);
+
+/// Hosts going by another site's domain
+const SITE_ALIASES: &[(&str, &str)] = &[
+    ("youtu.be", "youtube.com"),
+    ("youtube-nocookie.com", "youtube.com"),
+    ("redd.it", "reddit.com"),
+];
+/// Second-level labels countries register domains under, as in "bbc.co.uk"
+const COUNTRY_SECOND_LEVEL: &[&str] = &["co", "com", "org", "net", "ac", "gov", "edu", "ne", "or"];
+
+/// Where a site's breaker stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Failing now and then, downloads go ahead
+    Closed,
+    /// Tripped, downloads wait out the rest of the cooldown
+    Open(Duration),
+    /// Cooled down, one download tries the site before the rest follow
+    HalfOpen,
+}
+
+/// Transient failures of one site
+#[derive(Debug, Clone)]
+pub struct Breaker {
+    /// Transient failures in a row
+    pub failures: u32,
+    pub last_error: String,
+    open_until: Option<Instant>,
+    /// The download trying the site after a cooldown
+    trial: Option<Uuid>,
+}
+
+impl Breaker {
+    pub fn state(&self, now: Instant) -> BreakerState {
+        match self.open_until {
+            Some(until) if until > now => BreakerState::Open(until - now),
+            Some(_) => BreakerState::HalfOpen,
+            None => BreakerState::Closed,
+        }
+    }
+}
+
+/// Circuit breakers by site, so a site answering with timeouts and 5xx errors gets left alone
+/// for a while instead of failing every queued item from it in turn
+#[derive(Debug, Default)]
+pub struct Breakers {
+    sites: HashMap<String, Breaker>,
+}
+
+/// The registrable domain of a URL, what breakers are kept by, so "m.youtube.com" and
+/// "youtu.be" share youtube.com's breaker. Addresses are their own site.
+pub fn site_of(url: &str) -> Option<String> {
+    let url = reqwest::Url::parse(url).ok()?;
+    let Some(host) = url.domain().map(str::to_lowercase) else {
+        return url.host_str().map(str::to_string);
+    };
+    let labels: Vec<&str> = host.trim_end_matches('.').split('.').collect();
+    let country_domain = labels.len() > 2
+        && labels[labels.len() - 1].len() == 2
+        && COUNTRY_SECOND_LEVEL.contains(&labels[labels.len() - 2]);
+    let keep = if country_domain { 3 } else { 2 };
+    let site = labels[labels.len().saturating_sub(keep)..].join(".");
+    Some(SITE_ALIASES.iter().find(|(alias, _)| *alias == site).map_or(site, |(_, to)| to.to_string()))
+}
+
+/// How long an item waits out its `retries`-th transient failure
+pub fn retry_delay(retries: u32) -> Duration {
+    (RETRY_DELAY * 2u32.saturating_pow(retries.saturating_sub(1))).min(COOLDOWN)
+}
+
+impl Breakers {
+    /// Count a transient failure. The site's name when this one tripped the breaker.
+    pub fn record_failure(&mut self, url: &str, error: &str, now: Instant) -> Option<String> {
+        let site = site_of(url)?;
+        let breaker = self.sites.entry(site.clone()).or_insert_with(|| Breaker {
+            failures: 0,
+            last_error: String::new(),
+            open_until: None,
+            trial: None,
+        });
+        breaker.failures += 1;
+        breaker.last_error = error.to_string();
+        breaker.trial = None;
+        // A failed trial after the cooldown trips it again straight away
+        if breaker.failures >= TRIP_FAILURES && !matches!(breaker.state(now), BreakerState::Open(_)) {
+            breaker.open_until = Some(now + COOLDOWN);
+            return Some(site);
+        }
+        None
+    }
+
+    /// The site answered, close its breaker
+    pub fn record_success(&mut self, url: &str) {
+        if let Some(site) = site_of(url) {
+            self.sites.remove(&site);
+        }
+    }
+
+    /// Remember the download trying a cooled down site
+    pub fn record_start(&mut self, url: &str, id: Uuid, now: Instant) {
+        let breaker = site_of(url).and_then(|site| self.sites.get_mut(&site));
+        if let Some(breaker) = breaker.filter(|b| b.state(now) == BreakerState::HalfOpen) {
+            breaker.trial = Some(id);
+        }
+    }
+
+    /// Sites with transient failures, by name
+    pub fn sites(&self) -> Vec<(&str, &Breaker)> {
+        let mut sites: Vec<_> = self.sites.iter().map(|(site, b)| (site.as_str(), b)).collect();
+        sites.sort_by_key(|(site, _)| *site);
+        sites
+    }
+}
+
+impl AppState {
+    /// Why a download can't start yet while it waits out a transient failure, `None` when it may
+    pub fn retry_hold(&self, id: Uuid) -> Option<String> {
+        let item = self.queue.iter().find(|i| i.id == id)?;
+        let left = (item.retry_at? - chrono::Utc::now()).num_seconds();
+        (left >= 0).then(|| format!("retrying in {}s", left + 1))
+    }
+
+    /// Why a download can't start yet while its site's breaker is open, `None` when it may
+    pub fn breaker_hold(&self, id: Uuid) -> Option<String> {
+        let item = self.queue.iter().find(|i| i.id == id)?;
+        let site = site_of(&item.url)?;
+        let breaker = self.breakers.sites.get(&site)?;
+        match breaker.state(Instant::now()) {
+            BreakerState::Closed => None,
+            BreakerState::Open(left) => Some(format!(
+                "{} keeps failing, trying again in {}s",
+                site,
+                left.as_secs() + 1
+            )),
+            BreakerState::HalfOpen => {
+                // A trial that was stopped or removed doesn't hold the site any longer
+                let trying = breaker.trial.is_some_and(|trial| {
+                    trial != id
+                        && self
+                            .queue
+                            .iter()
+                            .any(|i| i.id == trial && i.status == DownloadStatus::Downloading)
+                });
+                trying.then(|| format!("one download is trying {} first", site))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaker_trips_and_recovers() {
+        let mut breakers = Breakers::default();
+        let start = Instant::now();
+        let url = "https://www.example.com/watch?v=1";
+        assert_eq!(site_of(url).as_deref(), Some("example.com"));
+        assert_eq!(site_of("https://youtu.be/x").as_deref(), Some("youtube.com"));
+        assert_eq!(site_of("https://m.youtube.com/watch?v=x").as_deref(), Some("youtube.com"));
+        assert_eq!(site_of("https://www.bbc.co.uk/iplayer").as_deref(), Some("bbc.co.uk"));
+        assert_eq!(site_of("http://192.168.1.5:8080/v.mp4").as_deref(), Some("192.168.1.5"));
+        assert_eq!(
+            (1..=ITEM_RETRIES).map(|r| retry_delay(r).as_secs()).collect::<Vec<_>>(),
+            [15, 30, 60, 120, 240]
+        );
+
+        assert_eq!(breakers.record_failure(url, "HTTP Error 503", start), None);
+        assert_eq!(breakers.record_failure(url, "timed out", start), None);
+        assert_eq!(breakers.record_failure(url, "timed out", start).as_deref(), Some("example.com"));
+        let (_, breaker) = breakers.sites()[0];
+        assert_eq!(breaker.state(start + Duration::from_secs(60)), BreakerState::Open(COOLDOWN - Duration::from_secs(60)));
+        // More failures while open don't stretch the cooldown
+        assert_eq!(breakers.record_failure(url, "timed out", start + Duration::from_secs(60)), None);
+
+        // The trial after the cooldown failing trips it again
+        let later = start + COOLDOWN;
+        assert_eq!(breakers.sites()[0].1.state(later), BreakerState::HalfOpen);
+        breakers.record_start(url, Uuid::new_v4(), later);
+        assert!(breakers.record_failure(url, "timed out", later).is_some());
+
+        breakers.record_success(url);
+        assert!(breakers.sites().is_empty());
+    }
+}
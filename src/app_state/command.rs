@@ -0,0 +1,317 @@
+use uuid::Uuid;
+
+use crate::app_state::{AlbumSplit, AppState, DownloadItem, DownloadStatus, FormatInfo};
+use crate::config::Config;
+use crate::schedule::Schedule;
+
+/// Undo history kept per session
+const UNDO_LIMIT: usize = 100;
+
+/// A reversible change to the queue or settings, applied through [`AppState::apply`]
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// Append items to the queue
+    AddItems(Vec<DownloadItem>),
+    /// Remove items from the queue, aborting their downloads
+    RemoveItems(Vec<Uuid>),
+    /// Put removed items back where they were, as (index, item) in insertion order
+    InsertItems(Vec<(usize, DownloadItem)>),
+    /// Choose the format and merge container for an item
+    SetFormat {
+        id: Uuid,
+        format: Option<Box<FormatInfo>>,
+        container: Option<String>,
+        status: DownloadStatus,
+    },
+    /// Replace the settings, saving them to the config file
+    SetConfig(Box<Config>),
+    /// Reorder the queue, ids missing from the list keep their relative order at the end
+    SetOrder(Vec<Uuid>),
+    /// Change a setting of some items, as (id, new value)
+    EditItems(Vec<(Uuid, ItemEdit)>),
+    /// Replace the recording schedules, saving them
+    SetSchedules(Vec<Schedule>),
+}
+
+/// A per-item setting [`Command::EditItems`] changes
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemEdit {
+    AlbumSplit(Option<AlbumSplit>),
+    SourceAddress(Option<String>),
+    Profile(Option<String>),
+    OutputTemplate(Option<String>),
+    /// Download from another URL, with the status and error that go with it
+    Source { url: String, status: DownloadStatus, error: Option<String> },
+}
+
+impl ItemEdit {
+    /// Set the value on the item, returning the edit that puts the old one back
+    fn swap(self, item: &mut DownloadItem) -> ItemEdit {
+        match self {
+            ItemEdit::AlbumSplit(split) => ItemEdit::AlbumSplit(std::mem::replace(&mut item.album_split, split)),
+            ItemEdit::SourceAddress(address) => {
+                ItemEdit::SourceAddress(std::mem::replace(&mut item.source_address, address))
+            }
+            ItemEdit::Profile(profile) => ItemEdit::Profile(std::mem::replace(&mut item.profile, profile)),
+            ItemEdit::OutputTemplate(template) => {
+                ItemEdit::OutputTemplate(std::mem::replace(&mut item.output_template, template))
+            }
+            ItemEdit::Source { url, status, error } => ItemEdit::Source {
+                url: std::mem::replace(&mut item.url, url),
+                status: std::mem::replace(&mut item.status, status),
+                error: std::mem::replace(&mut item.error, error),
+            },
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ItemEdit::AlbumSplit(_) => "album split",
+            ItemEdit::SourceAddress(_) => "source address",
+            ItemEdit::Profile(_) => "profile",
+            ItemEdit::OutputTemplate(_) => "file names",
+            ItemEdit::Source { .. } => "source URL",
+        }
+    }
+}
+
+/// An applied command and what reverses it
+#[derive(Debug, Clone)]
+struct Entry {
+    label: String,
+    inverse: Command,
+}
+
+/// Undo and redo stacks
+#[derive(Debug, Default)]
+pub struct UndoLog {
+    undo: Vec<Entry>,
+    redo: Vec<Entry>,
+}
+
+impl Command {
+    /// Short description for undo/redo notices
+    pub fn label(&self) -> String {
+        match self {
+            Command::AddItems(items) if items.len() == 1 => "add item".to_string(),
+            Command::AddItems(items) => format!("add {} items", items.len()),
+            Command::RemoveItems(ids) if ids.len() == 1 => "remove item".to_string(),
+            Command::RemoveItems(ids) => format!("remove {} items", ids.len()),
+            Command::InsertItems(items) => format!("restore {} item(s)", items.len()),
+            Command::SetFormat { .. } => "format choice".to_string(),
+            Command::SetConfig(_) => "settings change".to_string(),
+            Command::SetOrder(_) => "queue order".to_string(),
+            Command::EditItems(edits) => match edits.first() {
+                Some((_, edit)) if edits.len() == 1 => edit.label().to_string(),
+                Some((_, edit)) => format!("{} of {} items", edit.label(), edits.len()),
+                None => "item change".to_string(),
+            },
+            Command::SetSchedules(_) => "recording schedules".to_string(),
+        }
+    }
+
+    /// Perform the change, returning the command that reverses it
+    fn execute(self, state: &mut AppState) -> Command {
+        match self {
+            Command::AddItems(items) => {
+                let ids = items.iter().map(|i| i.id).collect();
+                state.queue.extend(items);
+                Command::RemoveItems(ids)
+            }
+            Command::RemoveItems(ids) => {
+                let mut removed = Vec::new();
+                for id in ids {
+                    let Some(index) = state.queue.iter().position(|i| i.id == id) else {
+                        continue;
+                    };
+                    stop_task(state, id);
+                    let mut item = state.queue.remove(index);
+                    // Nothing runs for it anymore, so coming back it must be restartable
+                    item.status = match item.status {
+                        DownloadStatus::Downloading => DownloadStatus::Paused,
+                        DownloadStatus::FetchingInfo => DownloadStatus::Pending,
+                        status => status,
+                    };
+                    removed.push((index, item));
+                }
+                // Indices were taken as the queue shrank, re-inserting in reverse restores them
+                removed.reverse();
+                Command::InsertItems(removed)
+            }
+            Command::InsertItems(items) => {
+                let ids = items.iter().map(|(_, item)| item.id).collect();
+                for (index, item) in items {
+                    let index = index.min(state.queue.len());
+                    state.queue.insert(index, item);
+                }
+                Command::RemoveItems(ids)
+            }
+            Command::SetFormat {
+                id,
+                format,
+                container,
+                status,
+            } => {
+                // Changing the format of a running download stops it
+                stop_task(state, id);
+                let Some(item) = state.queue.iter_mut().find(|i| i.id == id) else {
+                    return Command::SetFormat {
+                        id,
+                        format,
+                        container,
+                        status,
+                    };
+                };
+                let previous = Command::SetFormat {
+                    id,
+                    format: item.format.take().map(Box::new),
+                    container: item.container.take(),
+                    status: match item.status.clone() {
+                        DownloadStatus::Downloading => DownloadStatus::Ready,
+                        status => status,
+                    },
+                };
+                item.format = format.map(|f| *f);
+                item.container = container;
+                item.status = status;
+                previous
+            }
+            Command::SetConfig(config) => {
+                let previous = state.replace_config(*config);
+                if let Err(e) = state.config.save() {
+                    state.error_message = Some(format!("Failed to save config: {}", e));
+                }
+                Command::SetConfig(Box::new(previous))
+            }
+            Command::SetOrder(ids) => {
+                let previous = state.queue.iter().map(|i| i.id).collect();
+                let rank = |id: &Uuid| ids.iter().position(|i| i == id).unwrap_or(ids.len());
+                state.queue.sort_by_key(|i| rank(&i.id));
+                Command::SetOrder(previous)
+            }
+            Command::EditItems(edits) => {
+                let mut previous = Vec::new();
+                for (id, edit) in edits {
+                    if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                        previous.push((id, edit.swap(item)));
+                    }
+                }
+                // Undoing several edits of one item has to put back the oldest value last
+                previous.reverse();
+                Command::EditItems(previous)
+            }
+            Command::SetSchedules(entries) => {
+                let previous = std::mem::replace(&mut state.schedules.entries, entries);
+                if let Err(e) = state.schedules.save() {
+                    state.error_message = Some(format!("Failed to save schedules: {}", e));
+                }
+                Command::SetSchedules(previous)
+            }
+        }
+    }
+}
+
+/// Abort the download task of an item, if any
+fn stop_task(state: &mut AppState, id: Uuid) {
+    if let Some(task) = state.running_tasks.remove(&id) {
+        task.abort();
+    }
+    state.stop_signals.remove(&id);
+}
+
+impl AppState {
+    /// Apply a state change, recording how to undo it
+    pub fn apply(&mut self, command: Command) {
+        let label = command.label();
+        let inverse = command.execute(self);
+        self.undo_log.undo.push(Entry { label, inverse });
+        if self.undo_log.undo.len() > UNDO_LIMIT {
+            self.undo_log.undo.remove(0);
+        }
+        self.undo_log.redo.clear();
+        self.clamp_selection();
+    }
+
+    /// Revert the last change, returning its label
+    pub fn undo(&mut self) -> Option<String> {
+        let entry = self.undo_log.undo.pop()?;
+        let redo = entry.inverse.execute(self);
+        self.undo_log.redo.push(Entry {
+            label: entry.label.clone(),
+            inverse: redo,
+        });
+        self.clamp_selection();
+        Some(entry.label)
+    }
+
+    /// Re-apply the last undone change, returning its label
+    pub fn redo(&mut self) -> Option<String> {
+        let entry = self.undo_log.redo.pop()?;
+        let undo = entry.inverse.execute(self);
+        self.undo_log.undo.push(Entry {
+            label: entry.label.clone(),
+            inverse: undo,
+        });
+        self.clamp_selection();
+        Some(entry.label)
+    }
+
+    fn clamp_selection(&mut self) {
+        self.selected_index = self.selected_index.min(self.queue.len().saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo() {
+        let mut state = AppState::default();
+        let items: Vec<DownloadItem> = ["a", "b", "c"]
+            .iter()
+            .map(|name| DownloadItem::new(format!("https://example.com/{}", name)))
+            .collect();
+        let ids: Vec<Uuid> = items.iter().map(|i| i.id).collect();
+        state.apply(Command::AddItems(items));
+
+        state.queue[2].status = DownloadStatus::Downloading;
+        state.apply(Command::RemoveItems(vec![ids[0], ids[2]]));
+        assert_eq!(state.queue.len(), 1);
+
+        assert_eq!(state.undo().as_deref(), Some("remove 2 items"));
+        let order: Vec<Uuid> = state.queue.iter().map(|i| i.id).collect();
+        assert_eq!(order, ids);
+        assert_eq!(state.queue[2].status, DownloadStatus::Paused);
+
+        assert_eq!(state.redo().as_deref(), Some("remove 2 items"));
+        assert_eq!(state.queue.len(), 1);
+        assert_eq!(state.undo().as_deref(), Some("remove 2 items"));
+        assert_eq!(state.undo().as_deref(), Some("add 3 items"));
+        assert!(state.queue.is_empty());
+        assert!(state.undo().is_none());
+    }
+
+    #[test]
+    fn test_undo_item_edits() {
+        let mut state = AppState::default();
+        let item = DownloadItem::new("https://example.com/gone".to_string());
+        let id = item.id;
+        state.apply(Command::AddItems(vec![item]));
+        state.queue[0].status = DownloadStatus::Unavailable;
+
+        state.apply(Command::EditItems(vec![(id, ItemEdit::AlbumSplit(Some(AlbumSplit::Requested)))]));
+        state.apply(Command::EditItems(vec![(id, ItemEdit::Source {
+            url: "https://web.archive.org/web/2020/https://example.com/gone".to_string(),
+            status: DownloadStatus::FetchingInfo,
+            error: None,
+        })]));
+        assert_eq!(state.queue[0].status, DownloadStatus::FetchingInfo);
+
+        assert_eq!(state.undo().as_deref(), Some("source URL"));
+        assert_eq!(state.queue[0].url, "https://example.com/gone");
+        assert_eq!(state.queue[0].status, DownloadStatus::Unavailable);
+        assert_eq!(state.undo().as_deref(), Some("album split"));
+        assert_eq!(state.queue[0].album_split, None);
+    }
+}
@@ -0,0 +1,150 @@
+use uuid::Uuid;
+
+use crate::app_state::{AppState, DownloadStatus};
+
+/// An inconsistency between the queue and the running background tasks
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// Marked as downloading but no task is running for it
+    DownloadingWithoutTask(Uuid),
+    /// Marked ready but there is no format to download
+    ReadyWithoutFormat(Uuid),
+    /// A download task whose queue item is gone
+    OrphanTask(Uuid),
+    /// A stop signal left behind by a download that no longer runs
+    StaleStopSignal(Uuid),
+}
+
+impl Issue {
+    pub fn describe(&self, state: &AppState) -> String {
+        let name = |id: &Uuid| {
+            state
+                .queue
+                .iter()
+                .find(|i| i.id == *id)
+                .map(|i| i.title.clone().unwrap_or_else(|| i.url.clone()))
+                .unwrap_or_else(|| id.to_string())
+        };
+        match self {
+            Issue::DownloadingWithoutTask(id) => format!("{}: downloading, but no download is running", name(id)),
+            Issue::ReadyWithoutFormat(id) => format!("{}: ready, but no format is chosen", name(id)),
+            Issue::OrphanTask(id) => format!("Download task {} has no queue item", id),
+            Issue::StaleStopSignal(id) => format!("{}: leftover stop signal", name(id)),
+        }
+    }
+
+    /// What repairing this issue does
+    pub fn repair_label(&self) -> &'static str {
+        match self {
+            Issue::DownloadingWithoutTask(_) => "mark paused/ready so it can be restarted",
+            Issue::ReadyWithoutFormat(_) => "reset to pending, fetch formats again",
+            Issue::OrphanTask(_) => "abort the task",
+            Issue::StaleStopSignal(_) => "drop the signal",
+        }
+    }
+}
+
+/// Find items and tasks that disagree with each other
+pub fn scan(state: &AppState) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // A finished handle means the task ended without its completion being handled
+    let running = |id: &Uuid| state.running_tasks.get(id).is_some_and(|task| !task.is_finished());
+
+    for item in &state.queue {
+        match item.status {
+            DownloadStatus::Downloading if !running(&item.id) => {
+                issues.push(Issue::DownloadingWithoutTask(item.id));
+            }
+            DownloadStatus::Ready if item.format.is_none() => issues.push(Issue::ReadyWithoutFormat(item.id)),
+            _ => {}
+        }
+    }
+
+    let in_queue = |id: &Uuid| state.queue.iter().any(|i| i.id == *id);
+    issues.extend(state.running_tasks.keys().filter(|id| !in_queue(id)).map(|id| Issue::OrphanTask(*id)));
+    issues.extend(
+        state
+            .stop_signals
+            .keys()
+            .filter(|id| !state.running_tasks.contains_key(id))
+            .map(|id| Issue::StaleStopSignal(*id)),
+    );
+    issues
+}
+
+/// Bring the queue back to a state the user can act on. Returns how many issues were fixed.
+pub fn repair(state: &mut AppState, issues: &[Issue]) -> usize {
+    let mut fixed = 0;
+    for issue in issues {
+        match issue {
+            Issue::DownloadingWithoutTask(id) => {
+                if let Some(task) = state.running_tasks.remove(id) {
+                    task.abort();
+                }
+                state.stop_signals.remove(id);
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == *id) {
+                    // yt-dlp continues .part files, so anything started can be resumed
+                    item.status = match (item.progress.percent > 0.0, item.format.is_some()) {
+                        (true, _) => DownloadStatus::Paused,
+                        (false, true) => DownloadStatus::Ready,
+                        (false, false) => DownloadStatus::Pending,
+                    };
+                    item.push_log("[diagnostics] Download had no running task, status repaired".to_string());
+                    fixed += 1;
+                }
+            }
+            Issue::ReadyWithoutFormat(id) => {
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == *id) {
+                    item.status = DownloadStatus::Pending;
+                    item.push_log("[diagnostics] Ready without a format, reset to pending".to_string());
+                    fixed += 1;
+                }
+            }
+            Issue::OrphanTask(id) => {
+                if let Some(task) = state.running_tasks.remove(id) {
+                    task.abort();
+                    fixed += 1;
+                }
+                state.stop_signals.remove(id);
+            }
+            Issue::StaleStopSignal(id) => {
+                if state.stop_signals.remove(id).is_some() {
+                    fixed += 1;
+                }
+            }
+        }
+    }
+    fixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::{DownloadItem, FormatInfo};
+
+    #[test]
+    fn test_scan_and_repair() {
+        let mut state = AppState::default();
+        let mut downloading = DownloadItem::new("https://example.com/a".to_string());
+        downloading.status = DownloadStatus::Downloading;
+        downloading.format = Some(FormatInfo::best("mp4"));
+        let mut ready = DownloadItem::new("https://example.com/b".to_string());
+        ready.status = DownloadStatus::Ready;
+        let (a, b) = (downloading.id, ready.id);
+        state.queue = vec![downloading, ready];
+        let (stop_tx, _) = tokio::sync::watch::channel(Default::default());
+        state.stop_signals.insert(a, stop_tx);
+
+        let issues = scan(&state);
+        assert_eq!(
+            issues,
+            vec![Issue::DownloadingWithoutTask(a), Issue::ReadyWithoutFormat(b), Issue::StaleStopSignal(a)]
+        );
+
+        assert_eq!(repair(&mut state, &issues), 2);
+        assert_eq!(state.queue[0].status, DownloadStatus::Ready);
+        assert_eq!(state.queue[1].status, DownloadStatus::Pending);
+        assert!(state.stop_signals.is_empty());
+        assert!(scan(&state).is_empty());
+    }
+}
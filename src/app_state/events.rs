@@ -1,4 +1,16 @@
-use crate::app_state::{DownloadProgress, FormatInfo};
+use crate::app_state::{Chapter, DownloadOutcome, DownloadProgress, FormatInfo, PromptKind};
+use crate::bug_report::BugReport;
+use crate::bundle::StateBundle;
+use crate::commands::engagement::Engagement;
+use crate::commands::info_json::VideoMetadata;
+use crate::commands::network::LocalAddress;
+use crate::commands::postprocess::StageOutput;
+use crate::commands::probe::QualityReport;
+use crate::commands::troubleshoot::RetryStep;
+use crate::commands::yt_dlp::FetchedFormats;
+use crate::commands::album::AlbumTrack;
+use crate::hooks::HookEvent;
+use crate::notifications::Message;
 use uuid::Uuid;
 
 /// Events that can be sent to the main application
@@ -12,7 +24,7 @@ pub enum AppEvent {
         progress: DownloadProgress,
     },
     /// Download completed successfully
-    DownloadCompleted { id: Uuid },
+    DownloadCompleted { id: Uuid, outcome: DownloadOutcome },
     /// Download failed
     DownloadFailed { id: Uuid, error: String },
     /// Format information fetched for a URL
@@ -21,9 +33,37 @@ pub enum AppEvent {
         formats: Vec<FormatInfo>,
         title: String,
         duration: Option<String>,
+        chapters: Vec<Chapter>,
+        archive_key: Option<String>,
+        is_live: bool,
+        live_started_at: Option<i64>,
+        metadata: VideoMetadata,
     },
+    /// Once a second, for time-based checks
+    Tick,
+    /// A URL was copied to the clipboard
+    ClipboardUrl(String),
+    /// The config file was written, by gorlock or by hand
+    ConfigFileChanged,
     /// Failed to fetch formats
     FormatsFetchFailed { id: Uuid, error: String },
+    /// A troubleshooting retry finished, `args` are what it added to the fetch
+    RetryFinished {
+        id: Uuid,
+        tried: String,
+        args: Vec<String>,
+        result: Result<String, String>,
+    },
+    /// The desktop file chooser closed, `Ok(None)` when nothing was picked
+    PathPicked {
+        kind: PromptKind,
+        result: Result<Option<String>, String>,
+    },
+    /// A notification went out, the result per backend
+    NotificationSent {
+        test: bool,
+        results: Vec<(&'static str, Result<(), String>)>,
+    },
     /// URL validation completed
     UrlValidated {
         url: String,
@@ -44,6 +84,68 @@ pub enum AppEvent {
     PlaylistFetchFailed {
         error: String,
     },
+    /// Silence detection finished for an album split without chapters
+    SilenceDetected {
+        id: Uuid,
+        result: Result<(Vec<f64>, Option<f64>), String>, // (split points, duration)
+    },
+    /// Archive import file was read and parsed
+    ArchiveImportParsed {
+        path: String,
+        result: Result<(crate::archive::ImportFormat, Vec<String>), String>,
+    },
+    /// Archive export finished, with the number of entries written
+    ArchiveExported {
+        path: String,
+        result: Result<usize, String>,
+    },
+    /// Tail of yt-dlp's output for a download about to be reported failed
+    FailureOutput { id: Uuid, lines: Vec<String> },
+    /// Bug report written
+    BugReportExported { path: String, result: Result<(), String> },
+    /// State bundle written: (queue items, partial files)
+    BundleExported { path: String, result: Result<(usize, usize), String> },
+    /// State bundle read from disk, ready to merge
+    BundleLoaded { path: String, result: Result<StateBundle, String> },
+    /// Download halted gracefully after a fragment, partial data kept
+    DownloadStopped { id: Uuid, reason: String },
+    /// Disk usage of running live recordings sampled, with the disk's write speed if it was measured
+    RecordingsMeasured { usage: Vec<(Uuid, u64)>, disk_write_speed: Option<Result<f64, String>> },
+    /// A restored item's source checked, the error is yt-dlp's
+    AvailabilityChecked { id: Uuid, result: Result<(), String> },
+    /// Wayback Machine lookup for an unavailable item, `Ok(None)` when it has no snapshot
+    WaybackLooked { id: Uuid, asked: bool, result: Result<Option<String>, String> },
+    /// Metadata re-fetched for a queued item by a bulk refresh
+    MetadataRefreshed { id: Uuid, result: Result<Box<FetchedFormats>, String> },
+    /// View/like counts and live status fetched
+    EngagementFetched { id: Uuid, result: Result<Engagement, String> },
+    /// Network condition checked, the error says what doesn't hold
+    NetworkChecked { result: Result<(), String> },
+    /// Local network addresses listed
+    AddressesDetected { result: Result<Vec<LocalAddress>, String> },
+    /// ffprobe quality report of a completed download
+    QualityProbed { id: Uuid, result: Result<QualityReport, String> },
+    /// Disk speed test of a folder finished, bytes per second
+    DiskMeasured { dir: String, result: Result<f64, String> },
+    /// A custom extractor's links from a page added through the web API or `gorlock ctl`
+    LinksExtracted { page: String, result: Result<Vec<String>, String> },
+    /// A post-processing stage of a completed download finished
+    StageFinished { id: Uuid, index: usize, result: Result<StageOutput, String> },
+    /// The user gave up on a failed stage, the chain goes on without it
+    StageSkipped { id: Uuid, index: usize },
+    /// Comment archiving finished, with the comment count and file on success
+    CommentsArchived { id: Uuid, result: Result<(usize, String), String> },
+    /// Post-download verification finished, `problems` is empty when the file looks fine
+    FileVerified { id: Uuid, problems: Vec<String>, output: Vec<String> },
+    /// Content hash of a completed download computed
+    ContentHashed { id: Uuid, result: Result<String, String> },
+    /// A hook script finished, its output goes to the item log
+    HookFinished { id: Uuid, output: Vec<String> },
+    /// Album split finished, with the album folder and track count on success
+    AlbumSplitFinished {
+        id: Uuid,
+        result: Result<(String, usize), String>,
+    },
 }
 
 /// Input events from the terminal
@@ -62,16 +164,99 @@ pub enum InputEvent {
 pub enum DownloadAction {
     /// Add a new URL to the queue
     AddUrl(String),
+    /// Queue a URL from the web UI, downloading the best format once it's fetched
+    AddRemote(String),
     /// Start downloading an item
     StartDownload(Uuid),
     /// Pause a download
     PauseDownload(Uuid),
     /// Resume a paused download
     ResumeDownload(Uuid),
+    /// Pause every running download, from media keys
+    PauseAll,
+    /// Resume every paused download
+    ResumeAll,
     /// Cancel a download
     CancelDownload(Uuid),
-    /// Remove an item from the queue
-    RemoveItem(Uuid),
     /// Fetch available formats for a URL
     FetchFormats(Uuid),
+    /// Fetch formats again with a troubleshooting step applied, `tried` describes it for the log
+    RetryExtraction {
+        id: Uuid,
+        step: RetryStep,
+        tried: String,
+        args: Vec<String>,
+    },
+    /// Open the desktop file chooser to fill in a prompt
+    PickPath(PromptKind),
+    /// Send a notification to the configured backends, `test` reports the outcome either way
+    Notify { message: Message, test: bool },
+    /// Write a portable state bundle
+    ExportBundle(String),
+    /// Write a bug report, looking up tool versions first
+    ExportBugReport { path: String, report: BugReport },
+    /// Read a state bundle to merge into the queue
+    ImportBundle(String),
+    /// End a live recording, keeping what was recorded
+    FinishRecording(Uuid),
+    /// Sample disk usage of running live recordings
+    MeasureRecordings,
+    /// Let the current fragment finish, then stop the download
+    StopAfterFragment(Uuid),
+    /// Fetch view/like counts and live status; `force` skips the short-lived cache
+    RefreshEngagement { id: Uuid, force: bool },
+    /// Re-fetch titles, durations and availability of queued items
+    RefreshMetadata(Vec<Uuid>),
+    /// Check that a restored item's source still exists
+    CheckAvailability(Uuid),
+    /// Look for a Wayback Machine snapshot of an unavailable item, `asked` when the user pressed w
+    WaybackLookup { id: Uuid, asked: bool },
+    /// Check the configured network condition
+    CheckNetwork,
+    /// List local network addresses for the source address picker
+    DetectAddresses,
+    /// Measure the sustained write speed of the disk holding a folder, for the auto write limit
+    MeasureDisk(String),
+    /// Stop a running download and start it again with the current options, keeping its .part file
+    RestartDownload(Uuid),
+    /// Check a completed download's resolution, codecs and duration with ffprobe
+    ProbeQuality(Uuid),
+    /// Run a stage of a completed download's post-processing chain
+    RunStage { id: Uuid, index: usize },
+    /// Give up on a failed stage and carry on with the rest of the chain
+    SkipStage { id: Uuid, index: usize },
+    /// Save the comments of a completed download next to its file
+    ArchiveComments(Uuid),
+    /// Check a completed download's file type and run the scanner hook
+    VerifyFile(Uuid),
+    /// Fingerprint a completed download for duplicate detection
+    HashFile(Uuid),
+    /// Run the configured hook for an item
+    RunHook { id: Uuid, event: HookEvent },
+    /// Import already-archived video IDs from a file
+    ImportArchive(String),
+    /// Export the download archive to a file
+    ExportArchive(String),
+    /// Look for silences in a completed download to propose album split points
+    DetectSilence(Uuid),
+    /// Split a completed download into album tracks
+    SplitAlbum { id: Uuid, tracks: Vec<AlbumTrack> },
+}
+
+/// Queue an action from a handler without waiting. The handlers run on the loop that drains
+/// the action channel, so waiting there for room would never end: when the channel is full
+/// a task waits for room instead, the action is never dropped.
+pub fn queue_action(action_tx: &tokio::sync::mpsc::Sender<DownloadAction>, action: DownloadAction) {
+    match action_tx.try_send(action) {
+        Ok(()) => {}
+        Err(tokio::sync::mpsc::error::TrySendError::Full(action)) => {
+            let action_tx = action_tx.clone();
+            tokio::spawn(async move {
+                let _ = action_tx.send(action).await;
+            });
+        }
+        Err(tokio::sync::mpsc::error::TrySendError::Closed(action)) => {
+            tracing::warn!("Dropped an action, the channel is closed: {:?}", action);
+        }
+    }
 }
@@ -0,0 +1,175 @@
+use chrono::{DateTime, Local, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::{AppState, LogSource};
+
+/// Most entries an item or the application keeps, oldest dropped first
+const MAX_LOG_ENTRIES: usize = 500;
+/// Warnings and errors from each log a bug report carries
+pub const BUG_REPORT_ENTRIES: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+    Info,
+    Warning,
+    Error,
+}
+
+impl LogLevel {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogLevel::Info => "info",
+            LogLevel::Warning => "warn",
+            LogLevel::Error => "error",
+        }
+    }
+
+    /// The next level the log viewer's filter shows from: all, warnings and errors, errors only
+    pub fn next(&self) -> Self {
+        match self {
+            LogLevel::Info => LogLevel::Warning,
+            LogLevel::Warning => LogLevel::Error,
+            LogLevel::Error => LogLevel::Info,
+        }
+    }
+}
+
+/// One entry of an item's or the application's log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredEntry")]
+pub struct LogEntry {
+    pub at: DateTime<Utc>,
+    pub level: LogLevel,
+    /// What was going on: the item's phase, or the script, hook or check that wrote the entry
+    pub stage: Option<String>,
+    pub message: String,
+}
+
+/// Entries as saved, queues from before structured logs kept "HH:MM:SS message" lines
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredEntry {
+    Entry {
+        at: DateTime<Utc>,
+        level: LogLevel,
+        #[serde(default)]
+        stage: Option<String>,
+        message: String,
+    },
+    Line(String),
+}
+
+impl From<StoredEntry> for LogEntry {
+    fn from(stored: StoredEntry) -> Self {
+        match stored {
+            StoredEntry::Entry { at, level, stage, message } => Self { at, level, stage, message },
+            StoredEntry::Line(line) => {
+                // Old lines only have the time of day, today's date is the best guess
+                let time = line.get(..8).and_then(|t| NaiveTime::parse_from_str(t, "%H:%M:%S").ok());
+                let mut entry = Self::parse(line.get(9..).filter(|_| time.is_some()).unwrap_or(&line), None);
+                if let Some(time) = time {
+                    entry.at = Utc::now().date_naive().and_time(time).and_utc();
+                }
+                entry
+            }
+        }
+    }
+}
+
+impl LogEntry {
+    /// An entry from a log line, taking the level and stage from its leading tags
+    /// ("[error] [script name] ...") and `stage` when no tag names one
+    pub fn parse(line: &str, stage: Option<&str>) -> Self {
+        let mut level = LogLevel::Info;
+        let mut tag_stage = None;
+        let mut rest = line.trim_start();
+        while let Some((tag, after)) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            match tag {
+                "error" => level = LogLevel::Error,
+                "warning" => level = level.max(LogLevel::Warning),
+                _ => match tag.split_once(' ') {
+                    // "[script name]" and "[hook event]" keep their name in the message
+                    Some((kind, _)) if tag_stage.is_none() => {
+                        tag_stage = Some(kind.to_string());
+                        break;
+                    }
+                    None if tag_stage.is_none() => tag_stage = Some(tag.to_string()),
+                    _ => break,
+                },
+            }
+            rest = after.trim_start();
+        }
+        Self {
+            at: Utc::now(),
+            level,
+            stage: tag_stage.or_else(|| stage.map(str::to_string)),
+            message: rest.to_string(),
+        }
+    }
+
+    /// "14:02:11 warn  [download] message", in local time
+    pub fn line(&self) -> String {
+        let stage = self.stage.as_ref().map(|s| format!("[{}] ", s)).unwrap_or_default();
+        format!(
+            "{} {:<5} {}{}",
+            self.at.with_timezone(&Local).format("%H:%M:%S"),
+            self.level.label(),
+            stage,
+            self.message
+        )
+    }
+}
+
+/// Append an entry, dropping the oldest ones past the cap
+pub fn push_entry(log: &mut Vec<LogEntry>, entry: LogEntry) {
+    log.push(entry);
+    if log.len() > MAX_LOG_ENTRIES {
+        let excess = log.len() - MAX_LOG_ENTRIES;
+        log.drain(..excess);
+    }
+}
+
+/// The last `count` warnings and errors of a log, oldest first
+pub fn recent_problems(log: &[LogEntry], count: usize) -> Vec<&LogEntry> {
+    let mut problems: Vec<&LogEntry> = log.iter().rev().filter(|e| e.level >= LogLevel::Warning).take(count).collect();
+    problems.reverse();
+    problems
+}
+
+impl AppState {
+    /// Entries of a log the viewer shows at its level filter
+    pub fn filtered_log(&self, source: LogSource, level: LogLevel) -> Vec<&LogEntry> {
+        self.log_lines(source).iter().filter(|e| e.level >= level).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_entries() {
+        let entry = LogEntry::parse("[error] [script tidy] on_complete: boom", Some("download"));
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.stage.as_deref(), Some("script"));
+        assert_eq!(entry.message, "[script tidy] on_complete: boom");
+        let entry = LogEntry::parse("[diagnostics] Ready without a format", None);
+        assert_eq!((entry.level, entry.stage.as_deref()), (LogLevel::Info, Some("diagnostics")));
+        assert_eq!(LogEntry::parse("Verification passed", Some("merge")).stage.as_deref(), Some("merge"));
+
+        // Queues saved before structured logs
+        let old: Vec<LogEntry> = serde_json::from_str(r#"["12:00:01 [warning] slow", "no time here"]"#).unwrap();
+        assert_eq!((old[0].level, old[0].message.as_str()), (LogLevel::Warning, "slow"));
+        assert_eq!(old[1].message, "no time here");
+        let saved = serde_json::to_string(&old[0]).unwrap();
+        assert_eq!(serde_json::from_str::<LogEntry>(&saved).unwrap(), old[0]);
+
+        let log: Vec<LogEntry> = ["[error] a", "b", "[warning] c", "[error] d"]
+            .iter()
+            .map(|l| LogEntry::parse(l, None))
+            .collect();
+        let problems: Vec<&str> = recent_problems(&log, 2).iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(problems, ["c", "d"]);
+    }
+}
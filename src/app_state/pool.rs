@@ -0,0 +1,68 @@
+use std::collections::VecDeque;
+
+use crate::app_state::events::DownloadAction;
+
+/// Workers for CPU-bound post-processing (the profile's chain stages, album splits), limited
+/// apart from download slots so transcoding and downloading don't hold each other up
+#[derive(Debug, Default)]
+pub struct WorkerPool {
+    running: usize,
+    /// Tasks waiting for a worker, oldest first
+    waiting: VecDeque<DownloadAction>,
+}
+
+impl WorkerPool {
+    /// Take a worker for `action` when one of `limit` is free, or queue it. `true` when it may run now.
+    pub fn start(&mut self, action: &DownloadAction, limit: usize) -> bool {
+        if self.running < limit.max(1) {
+            self.running += 1;
+            true
+        } else {
+            self.waiting.push_back(action.clone());
+            false
+        }
+    }
+
+    /// A task is done and its worker free
+    pub fn finish(&mut self) {
+        self.running = self.running.saturating_sub(1);
+    }
+
+    /// Queued tasks to run again, one for each free worker
+    pub fn ready(&mut self, limit: usize) -> Vec<DownloadAction> {
+        let free = limit.max(1).saturating_sub(self.running).min(self.waiting.len());
+        self.waiting.drain(..free).collect()
+    }
+
+    pub fn running(&self) -> usize {
+        self.running
+    }
+
+    pub fn waiting(&self) -> usize {
+        self.waiting.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_pool() {
+        let mut pool = WorkerPool::default();
+        let stage = |index| DownloadAction::RunStage { id: uuid::Uuid::nil(), index };
+        assert!(pool.start(&stage(0), 2));
+        assert!(pool.start(&stage(1), 2));
+        assert!(!pool.start(&stage(2), 2));
+        assert!(!pool.start(&stage(3), 2));
+        assert_eq!((pool.running(), pool.waiting()), (2, 2));
+        assert!(pool.ready(2).is_empty());
+
+        pool.finish();
+        let ready = pool.ready(2);
+        assert!(matches!(ready[..], [DownloadAction::RunStage { index: 2, .. }]));
+        assert!(pool.start(&ready[0], 2));
+        // Raising the limit lets the rest go at once
+        assert_eq!(pool.ready(4).len(), 1);
+    }
+}
@@ -0,0 +1,209 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::app_state::{AppState, DownloadItem, DownloadProgress, DownloadStatus, parse_duration_to_seconds};
+
+/// Weight of the newest sample in the moving averages, lower is steadier
+const SMOOTHING: f64 = 0.3;
+
+/// Show sizes in SI units (kB, MB: powers of 1000) instead of binary ones (KiB, MiB)
+static SI_UNITS: AtomicBool = AtomicBool::new(false);
+
+/// Pick the units `format_bytes` uses, from the `si_units` setting
+pub fn set_si_units(si: bool) {
+    SI_UNITS.store(si, Ordering::Relaxed);
+}
+
+/// Size in the configured units, e.g. "12.3MiB" or "12.9MB"
+pub fn format_bytes(bytes: u64) -> String {
+    let (base, units): (f64, &[&str]) = if SI_UNITS.load(Ordering::Relaxed) {
+        (1000.0, &["B", "kB", "MB", "GB", "TB"])
+    } else {
+        (1024.0, &["B", "KiB", "MiB", "GiB", "TiB"])
+    };
+    let mut size = bytes as f64;
+    let mut unit_index = 0;
+
+    while size >= base && unit_index < units.len() - 1 {
+        size /= base;
+        unit_index += 1;
+    }
+
+    format!("{:.1}{}", size, units[unit_index])
+}
+
+/// Seconds as "1:23" or "1:02:03"
+pub fn format_eta(seconds: u64) -> String {
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
+
+/// Bytes from yt-dlp's sizes and rates: "45.67MiB", "~ 1.2GiB", "2.34MiB/s", "980KB/s".
+/// "Unknown" and other unparsable values give `None`.
+pub fn parse_size(text: &str) -> Option<f64> {
+    let text = text.trim().trim_start_matches('~').trim().trim_end_matches("/s");
+    let split = text.find(|c: char| !(c.is_ascii_digit() || c == '.'))?;
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let multiplier = match unit.trim() {
+        "B" => 1.0,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        "KB" | "kB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        _ => return None,
+    };
+    Some(number * multiplier)
+}
+
+/// A bar `width` cells wide, filled in eighths of a cell: "███▌░░░░░░"
+pub fn bar_cells(percent: f64, width: usize) -> String {
+    const PARTIAL: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+    let eighths = (percent.clamp(0.0, 100.0) / 100.0 * (width * 8) as f64).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(PARTIAL[eighths % 8 - 1]);
+    }
+    let filled = bar.chars().count();
+    bar.push_str(&"░".repeat(width - filled));
+    bar
+}
+
+impl DownloadItem {
+    /// Wall time from the first start to completion, or to now while running
+    pub fn elapsed(&self) -> Option<chrono::Duration> {
+        let end = self.finished_at.unwrap_or_else(chrono::Utc::now);
+        Some(end - self.started_at?)
+    }
+
+    /// Bytes downloaded so far over all of the item's files
+    pub fn downloaded_bytes(&self) -> Option<u64> {
+        let current = self.progress.total_bytes? as f64 * self.progress.percent / 100.0;
+        Some(self.earlier_files_bytes + current as u64)
+    }
+
+    /// Record a progress update, averaging it with the previous one
+    pub fn update_progress(&mut self, mut progress: DownloadProgress) {
+        // A new file means the previous one is done
+        if progress.destination.is_some() && self.progress.destination.is_some() && progress.destination != self.progress.destination {
+            self.earlier_files_bytes += self.progress.total_bytes.unwrap_or(0);
+        }
+        progress.smooth(&self.progress);
+        self.progress = progress;
+    }
+}
+
+impl AppState {
+    /// Progress of the running batch: (finished items, items, overall percent)
+    pub fn batch_progress(&self) -> Option<(usize, usize, f64)> {
+        let batch_id = self.batch.as_ref()?.batch_id;
+        let items: Vec<_> = self.queue.iter().filter(|i| i.batch_id == Some(batch_id)).collect();
+        if items.is_empty() {
+            return None;
+        }
+        let done = items.iter().filter(|i| i.status.is_completed()).count();
+        // The item in flight counts with its own progress
+        let in_flight: f64 = items
+            .iter()
+            .filter(|i| i.status == DownloadStatus::Downloading)
+            .map(|i| i.progress.percent / 100.0)
+            .sum();
+        Some((done, items.len(), (done as f64 + in_flight) / items.len() as f64 * 100.0))
+    }
+}
+
+impl DownloadProgress {
+    /// Fill in the numeric fields from yt-dlp's strings
+    pub fn parse_numbers(&mut self) {
+        self.speed_bps = self.speed.as_deref().and_then(parse_size);
+        self.total_bytes = self.total_size.as_deref().and_then(parse_size).map(|b| b as u64);
+        self.eta_secs = self.eta.as_deref().and_then(parse_duration_to_seconds);
+    }
+
+    /// Average speed and ETA with the previous update, so the numbers don't jump around.
+    /// A new file restarts the averages.
+    pub fn smooth(&mut self, previous: &DownloadProgress) {
+        let same_file = previous.destination.is_none() || previous.destination == self.destination;
+        if !same_file || self.percent < previous.percent {
+            return;
+        }
+        let average = |new: Option<f64>, old: Option<f64>| match (new, old) {
+            (Some(new), Some(old)) => Some(SMOOTHING * new + (1.0 - SMOOTHING) * old),
+            (new, old) => new.or(old),
+        };
+        self.speed_bps = average(self.speed_bps, previous.speed_bps);
+
+        // Remaining bytes over the averaged speed, or yt-dlp's ETA averaged when the size is unknown
+        let remaining = self.total_bytes.map(|total| total as f64 * (100.0 - self.percent) / 100.0);
+        self.eta_secs = match (remaining, self.speed_bps) {
+            (Some(remaining), Some(speed)) if speed > 0.0 => Some((remaining / speed).round() as u64),
+            _ => average(self.eta_secs.map(|s| s as f64), previous.eta_secs.map(|s| s as f64)).map(|s| s.round() as u64),
+        };
+    }
+
+    /// "2.3MiB/s", in the configured units
+    pub fn speed_label(&self) -> Option<String> {
+        match self.speed_bps {
+            Some(speed) => Some(format!("{}/s", format_bytes(speed as u64))),
+            None => self.speed.clone(),
+        }
+    }
+
+    pub fn eta_label(&self) -> Option<String> {
+        match self.eta_secs {
+            Some(seconds) => Some(format_eta(seconds)),
+            None => self.eta.clone(),
+        }
+    }
+
+    pub fn total_label(&self) -> Option<String> {
+        match self.total_bytes {
+            Some(total) => Some(format_bytes(total)),
+            None => self.total_size.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_smooth() {
+        assert_eq!(parse_size("2.00MiB/s"), Some(2.0 * 1024.0 * 1024.0));
+        assert_eq!(parse_size("~ 1.5GB"), Some(1.5e9));
+        assert_eq!(parse_size("Unknown B/s"), None);
+        assert_eq!(format_eta(3723), "1:02:03");
+
+        let mut previous = DownloadProgress {
+            percent: 10.0,
+            speed: Some("1.00MiB/s".to_string()),
+            total_size: Some("100.00MiB".to_string()),
+            ..Default::default()
+        };
+        previous.parse_numbers();
+        let mut next = DownloadProgress {
+            percent: 11.0,
+            speed: Some("11.00MiB/s".to_string()),
+            total_size: Some("100.00MiB".to_string()),
+            ..Default::default()
+        };
+        next.parse_numbers();
+        next.smooth(&previous);
+        // 0.3 * 11 + 0.7 * 1 = 4 MiB/s, 89 MiB left
+        assert_eq!(next.speed_bps.map(|s| (s / 1024.0 / 1024.0 * 100.0).round()), Some(400.0));
+        assert_eq!(next.eta_secs, Some(22));
+        assert_eq!(format_bytes(4 * 1024 * 1024), "4.0MiB");
+
+        assert_eq!(bar_cells(35.0, 10), "███▌░░░░░░");
+        assert_eq!(bar_cells(100.0, 4), "████");
+        assert_eq!(bar_cells(0.0, 4), "░░░░");
+    }
+}
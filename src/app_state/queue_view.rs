@@ -0,0 +1,278 @@
+use uuid::Uuid;
+
+use crate::app_state::{AppState, DownloadItem, DownloadStatus};
+
+/// Orders the queue can be sorted in, cycled with `o`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum QueueSort {
+    /// The order items were added in
+    #[default]
+    Added,
+    Uploader,
+    /// Newest upload first
+    UploadDate,
+    Title,
+}
+
+impl QueueSort {
+    pub fn next(self) -> Self {
+        match self {
+            QueueSort::Added => QueueSort::Uploader,
+            QueueSort::Uploader => QueueSort::UploadDate,
+            QueueSort::UploadDate => QueueSort::Title,
+            QueueSort::Title => QueueSort::Added,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            QueueSort::Added => "added",
+            QueueSort::Uploader => "uploader",
+            QueueSort::UploadDate => "upload date",
+            QueueSort::Title => "title",
+        }
+    }
+
+    /// Queue ids in this order; items missing the sort field go last, ties keep their place
+    pub fn order(&self, queue: &[DownloadItem]) -> Vec<Uuid> {
+        let mut items: Vec<&DownloadItem> = queue.iter().collect();
+        let lower = |s: Option<&String>| s.map(|s| s.to_lowercase());
+        match self {
+            QueueSort::Added => items.sort_by_key(|i| i.created_at),
+            QueueSort::Uploader => {
+                items.sort_by_key(|i| {
+                    let uploader = lower(i.metadata.uploader.as_ref());
+                    (uploader.is_none(), uploader)
+                });
+            }
+            QueueSort::UploadDate => {
+                items.sort_by_key(|i| std::cmp::Reverse(i.metadata.upload_date.clone()));
+            }
+            QueueSort::Title => {
+                items.sort_by_key(|i| {
+                    let title = lower(i.title.as_ref());
+                    (title.is_none(), title)
+                });
+            }
+        }
+        items.iter().map(|i| i.id).collect()
+    }
+}
+
+/// Queue filter typed after `/`: `uploader:<text>`, `date:<YYYY[-MM[-DD]]>` and plain words,
+/// all of which must match
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueueFilter {
+    pub query: String,
+    uploader: Vec<String>,
+    /// Upload date prefixes without dashes
+    date: Vec<String>,
+    words: Vec<String>,
+}
+
+impl QueueFilter {
+    /// Parse a filter, `None` for an empty query
+    pub fn parse(query: &str) -> Option<Self> {
+        let mut filter = Self {
+            query: query.trim().to_string(),
+            uploader: Vec::new(),
+            date: Vec::new(),
+            words: Vec::new(),
+        };
+        for term in query.split_whitespace() {
+            let term = term.to_lowercase();
+            if let Some(uploader) = term.strip_prefix("uploader:") {
+                filter.uploader.push(uploader.to_string());
+            } else if let Some(date) = term.strip_prefix("date:") {
+                filter.date.push(date.replace('-', ""));
+            } else {
+                filter.words.push(term);
+            }
+        }
+        (!filter.query.is_empty()).then_some(filter)
+    }
+
+    pub fn matches(&self, item: &DownloadItem) -> bool {
+        let uploader = item.metadata.uploader.as_deref().unwrap_or_default().to_lowercase();
+        let date = item.metadata.upload_date.as_deref().unwrap_or_default();
+        let title = item.title.as_deref().unwrap_or(&item.url).to_lowercase();
+        self.uploader.iter().all(|u| uploader.contains(u.as_str()))
+            && self.date.iter().all(|d| date.starts_with(d.as_str()))
+            && self
+                .words
+                .iter()
+                .all(|w| title.contains(w.as_str()) || uploader.contains(w.as_str()))
+    }
+}
+
+/// Format a YYYYMMDD upload date as YYYY-MM-DD
+pub fn format_upload_date(date: &str) -> String {
+    if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) {
+        format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])
+    } else {
+        date.to_string()
+    }
+}
+
+/// Whether a metadata refresh applies to the item: it hasn't been downloaded yet
+fn is_refreshable(item: &DownloadItem) -> bool {
+    matches!(
+        item.status,
+        DownloadStatus::Pending
+            | DownloadStatus::Ready
+            | DownloadStatus::Queued
+            | DownloadStatus::Failed
+            | DownloadStatus::Unavailable
+    )
+}
+
+impl AppState {
+    /// Whether a queue item passes the active filter
+    pub fn is_visible(&self, item: &DownloadItem) -> bool {
+        self.queue_filter.as_ref().is_none_or(|f| f.matches(item))
+    }
+
+    /// The selected item, unless the filter hides it. Queue keys act on this one only, so an
+    /// item the user can't see is never changed.
+    pub fn selected_item(&self) -> Option<&DownloadItem> {
+        self.queue.get(self.selected_index).filter(|i| self.is_visible(i))
+    }
+
+    /// Queue indices shown in the queue panel
+    pub fn visible_indices(&self) -> Vec<usize> {
+        (0..self.queue.len()).filter(|i| self.is_visible(&self.queue[*i])).collect()
+    }
+
+    /// Shown items that haven't been downloaded yet, for a bulk metadata refresh
+    pub fn refreshable_items(&self) -> Vec<Uuid> {
+        self.queue
+            .iter()
+            .filter(|i| self.is_visible(i) && is_refreshable(i))
+            .map(|i| i.id)
+            .collect()
+    }
+
+    /// The selected item and the `count - 1` shown below it, those not downloaded yet
+    pub fn refreshable_from_selection(&self, count: usize) -> Vec<Uuid> {
+        self.visible_ids_from_selection(count)
+            .into_iter()
+            .filter(|id| self.queue.iter().any(|i| i.id == *id && is_refreshable(i)))
+            .collect()
+    }
+
+    /// Move the selection `delta` visible items up or down, stopping at either end.
+    /// Returns whether it moved.
+    pub fn step_selection(&mut self, delta: isize) -> bool {
+        let visible = self.visible_indices();
+        let Some(position) = visible.iter().position(|i| *i == self.selected_index) else {
+            // The selection is hidden, jump to the first match
+            return match visible.first() {
+                Some(first) => {
+                    self.selected_index = *first;
+                    true
+                }
+                None => false,
+            };
+        };
+        let target = (position as isize + delta).clamp(0, visible.len() as isize - 1) as usize;
+        if target == position {
+            return false;
+        }
+        self.selected_index = visible[target];
+        true
+    }
+
+    /// Select the `n`th visible item counting from 0, or the last one if there are fewer.
+    /// Returns whether the selection moved.
+    pub fn select_nth_visible(&mut self, n: usize) -> bool {
+        let visible = self.visible_indices();
+        let Some(&target) = visible.get(n).or(visible.last()) else {
+            return false;
+        };
+        let moved = target != self.selected_index;
+        self.selected_index = target;
+        moved
+    }
+
+    /// Ids of up to `count` visible items, starting at the selected one
+    pub fn visible_ids_from_selection(&self, count: usize) -> Vec<Uuid> {
+        self.visible_indices()
+            .into_iter()
+            .skip_while(|i| *i != self.selected_index)
+            .take(count)
+            .map(|i| self.queue[i].id)
+            .collect()
+    }
+
+    /// Select the first visible item unless the selected one is visible
+    pub fn select_visible(&mut self) {
+        let visible = self.visible_indices();
+        if !visible.contains(&self.selected_index) {
+            if let Some(first) = visible.first() {
+                self.selected_index = *first;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(title: &str, uploader: &str, date: &str) -> DownloadItem {
+        let mut item = DownloadItem::new(format!("https://example.com/{}", title));
+        item.title = Some(title.to_string());
+        item.metadata.uploader = Some(uploader.to_string());
+        item.metadata.upload_date = Some(date.to_string());
+        item
+    }
+
+    #[test]
+    fn test_sort_and_filter() {
+        let queue = vec![
+            item("Keynote", "Conf", "20250301"),
+            item("Bread", "alice", "20260110"),
+            item("Pasta", "Alice", "20250620"),
+        ];
+        let ids: Vec<Uuid> = queue.iter().map(|i| i.id).collect();
+        assert_eq!(QueueSort::Uploader.order(&queue), vec![ids[1], ids[2], ids[0]]);
+        assert_eq!(QueueSort::UploadDate.order(&queue), vec![ids[1], ids[2], ids[0]]);
+        assert_eq!(QueueSort::Title.order(&queue), vec![ids[1], ids[0], ids[2]]);
+
+        let filter = QueueFilter::parse("uploader:alice date:2025-06").unwrap();
+        let matched: Vec<bool> = queue.iter().map(|i| filter.matches(i)).collect();
+        assert_eq!(matched, [false, false, true]);
+        assert!(QueueFilter::parse("conf").unwrap().matches(&queue[0]));
+        assert!(QueueFilter::parse("  ").is_none());
+        assert_eq!(format_upload_date("20260110"), "2026-01-10");
+    }
+
+    #[test]
+    fn test_counted_selection() {
+        let mut state = AppState::default();
+        for title in ["a", "b", "c", "d", "e"] {
+            state.queue.push(item(title, "x", "20250101"));
+        }
+        assert!(state.step_selection(3));
+        assert_eq!(state.selected_index, 3);
+        assert!(state.step_selection(10));
+        assert_eq!(state.selected_index, 4);
+        assert!(!state.step_selection(1));
+
+        assert!(state.select_nth_visible(1));
+        let ids = state.visible_ids_from_selection(3);
+        assert_eq!(ids, [state.queue[1].id, state.queue[2].id, state.queue[3].id]);
+        assert!(state.select_nth_visible(usize::MAX));
+        assert_eq!(state.visible_ids_from_selection(3).len(), 1);
+
+        state.selected_index = 1;
+        state.queue[2].status = DownloadStatus::Completed;
+        assert_eq!(state.refreshable_from_selection(3), [state.queue[1].id, state.queue[3].id]);
+
+        // A filter hiding the selected item leaves nothing for queue keys to act on
+        state.queue_filter = QueueFilter::parse("a");
+        assert!(state.selected_item().is_none());
+        state.selected_index = 0;
+        assert_eq!(state.selected_item().map(|i| i.id), Some(state.queue[0].id));
+    }
+}
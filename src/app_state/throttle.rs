@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Samples in a row without progress that make a stall sustained
+pub const STALL_SAMPLES: u32 = 3;
+
+/// Share of the disk's measured speed the auto write limit leaves to downloads
+const DISK_HEADROOM: f64 = 0.8;
+/// How long a folder's downloads go without stalling before its limit is lifted
+pub const RELAX_AFTER: Duration = Duration::from_secs(600);
+
+/// Running downloads watched for write stalls, for the auto write limit
+#[derive(Debug, Default)]
+pub struct WriteWatch {
+    /// Measured sustained write speed of each output folder, bytes per second
+    pub disk_speeds: HashMap<String, f64>,
+    /// A disk speed test is running
+    pub measuring: bool,
+    /// The limit auto mode settled on for each download to an output folder
+    caps: HashMap<String, Cap>,
+    samples: HashMap<Uuid, Sample>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Cap {
+    /// Bytes per second
+    limit: u64,
+    /// When a download to the folder last stalled
+    stalled_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    /// Output folder the download writes to
+    dir: String,
+    bytes: u64,
+    /// Samples in a row without progress
+    stalled: u32,
+    /// Fastest the download went, bytes per second
+    peak_rate: f64,
+}
+
+impl WriteWatch {
+    /// Record how far a download got in the `seconds` since its last sample.
+    /// `true` the moment it has gone `STALL_SAMPLES` samples without progress after making some.
+    pub fn sample(&mut self, id: Uuid, dir: &str, bytes: u64, seconds: f64) -> bool {
+        let Some(sample) = self.samples.get_mut(&id) else {
+            self.samples.insert(id, Sample { dir: dir.to_string(), bytes, stalled: 0, peak_rate: 0.0 });
+            return false;
+        };
+        if bytes > sample.bytes {
+            sample.peak_rate = sample.peak_rate.max((bytes - sample.bytes) as f64 / seconds.max(0.001));
+            sample.stalled = 0;
+        } else if sample.peak_rate > 0.0 {
+            sample.stalled += 1;
+        }
+        sample.bytes = bytes;
+        sample.stalled == STALL_SAMPLES
+    }
+
+    /// Forget downloads that aren't running anymore
+    pub fn retain(&mut self, running: &[Uuid]) {
+        self.samples.retain(|id, _| running.contains(id));
+    }
+
+    /// Peak rates together of the watched downloads writing to `dir`, bytes per second
+    pub fn peak_total(&self, dir: &str) -> f64 {
+        self.samples.values().filter(|s| s.dir == dir).map(|s| s.peak_rate).sum()
+    }
+
+    /// The per-download limit for `dir` on a disk writing `disk_speed` bytes per second,
+    /// `None` when the disk keeps up with the downloads and the stall was the network's
+    pub fn auto_cap(&self, dir: &str, disk_speed: f64) -> Option<u64> {
+        let downloads = self.samples.values().filter(|s| s.dir == dir).count().max(1) as f64;
+        (disk_speed < self.peak_total(dir)).then(|| (disk_speed * DISK_HEADROOM / downloads) as u64)
+    }
+
+    /// The limit for downloads to `dir`, bytes per second
+    pub fn cap(&self, dir: &str) -> Option<u64> {
+        self.caps.get(dir).map(|cap| cap.limit)
+    }
+
+    /// Limit downloads to `dir`. `false` when an equal or lower limit is already on.
+    pub fn set_cap(&mut self, dir: &str, limit: u64, now: Instant) -> bool {
+        match self.caps.get_mut(dir) {
+            Some(cap) if cap.limit <= limit => {
+                cap.stalled_at = now;
+                false
+            }
+            _ => {
+                self.caps.insert(dir.to_string(), Cap { limit, stalled_at: now });
+                true
+            }
+        }
+    }
+
+    /// Lift the limits of folders nothing writes to anymore, or whose downloads went
+    /// `RELAX_AFTER` without stalling. Their disk speed goes too, a stall measures it again.
+    pub fn relax(&mut self, busy: &[String], now: Instant) -> Vec<String> {
+        let lifted: Vec<String> = self
+            .caps
+            .iter()
+            .filter(|(dir, cap)| !busy.contains(dir) || now.duration_since(cap.stalled_at) >= RELAX_AFTER)
+            .map(|(dir, _)| dir.clone())
+            .collect();
+        for dir in &lifted {
+            self.caps.remove(dir);
+            self.disk_speeds.remove(dir);
+        }
+        lifted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_stalls() {
+        let mut watch = WriteWatch::default();
+        let (id, other) = (Uuid::new_v4(), Uuid::new_v4());
+        // Waiting for the first bytes isn't a stall
+        for _ in 0..5 {
+            assert!(!watch.sample(id, "/nas", 0, 5.0));
+        }
+        assert!(!watch.sample(id, "/nas", 50_000_000, 5.0));
+        let stalls: Vec<bool> = (0..4).map(|_| watch.sample(id, "/nas", 50_000_000, 5.0)).collect();
+        assert_eq!(stalls, [false, false, true, false]);
+        assert_eq!(watch.peak_total("/nas"), 10_000_000.0);
+
+        watch.sample(other, "/nas", 0, 5.0);
+        watch.sample(Uuid::new_v4(), "/ssd", 0, 5.0);
+        // A 4 MB/s disk behind two downloads: 80% of it, split between them
+        assert_eq!(watch.auto_cap("/nas", 4_000_000.0), Some(1_600_000));
+        assert_eq!(watch.auto_cap("/nas", 40_000_000.0), None);
+        assert_eq!(watch.auto_cap("/ssd", 4_000_000.0), None);
+
+        // The limit only applies to its folder and goes once writes there recover
+        let start = Instant::now();
+        assert!(watch.set_cap("/nas", 1_600_000, start));
+        assert!(!watch.set_cap("/nas", 2_000_000, start + Duration::from_secs(60)));
+        assert_eq!((watch.cap("/nas"), watch.cap("/ssd")), (Some(1_600_000), None));
+        let busy = ["/nas".to_string()];
+        assert!(watch.relax(&busy, start + RELAX_AFTER).is_empty());
+        assert_eq!(watch.relax(&busy, start + Duration::from_secs(60) + RELAX_AFTER), ["/nas"]);
+        assert_eq!(watch.cap("/nas"), None);
+        watch.set_cap("/nas", 1_600_000, start);
+        assert_eq!(watch.relax(&[], start), ["/nas"]);
+
+        watch.retain(&[other]);
+        assert_eq!(watch.peak_total("/nas"), 0.0);
+    }
+}
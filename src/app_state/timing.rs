@@ -0,0 +1,173 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::app_state::{AppState, DownloadItem, format_duration_from_seconds};
+
+/// What an item is spending its time on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Phase {
+    /// Fetching formats and metadata
+    Fetch,
+    Download,
+    /// yt-dlp merging video and audio
+    Merge,
+    /// yt-dlp's post-processors and the profile's post-processing chain
+    PostProcess,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 4] = [Phase::Fetch, Phase::Download, Phase::Merge, Phase::PostProcess];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Phase::Fetch => "fetch",
+            Phase::Download => "download",
+            Phase::Merge => "merge",
+            Phase::PostProcess => "post-process",
+        }
+    }
+
+    /// Waiting on the network rather than on the CPU
+    pub fn is_network(&self) -> bool {
+        matches!(self, Phase::Fetch | Phase::Download)
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Time an item spent in each phase
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timings {
+    /// Seconds of the finished spans, by `Phase::index`
+    seconds: [f64; 4],
+    /// The phase running now and since when
+    current: Option<(Phase, DateTime<Utc>)>,
+}
+
+impl Timings {
+    /// Start timing a phase, ending the one before
+    pub fn enter(&mut self, phase: Phase, now: DateTime<Utc>) {
+        if self.current.is_some_and(|(current, _)| current == phase) {
+            return;
+        }
+        self.leave(now);
+        self.current = Some((phase, now));
+    }
+
+    /// Stop timing the running phase
+    pub fn leave(&mut self, now: DateTime<Utc>) {
+        if let Some((phase, since)) = self.current.take() {
+            self.seconds[phase.index()] += (now - since).num_milliseconds().max(0) as f64 / 1000.0;
+        }
+    }
+
+    /// Seconds in a phase, the running span included
+    pub fn seconds(&self, phase: Phase, now: DateTime<Utc>) -> f64 {
+        let running = match self.current {
+            Some((current, since)) if current == phase => (now - since).num_milliseconds().max(0) as f64 / 1000.0,
+            _ => 0.0,
+        };
+        self.seconds[phase.index()] + running
+    }
+
+    /// The phase running now
+    pub fn current(&self) -> Option<Phase> {
+        self.current.map(|(phase, _)| phase)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current.is_none() && self.seconds.iter().all(|s| *s == 0.0)
+    }
+
+    /// "fetch 3s · download 1m 20s · merge 4s", phases never entered left out
+    pub fn breakdown(&self, now: DateTime<Utc>) -> String {
+        Phase::ALL
+            .iter()
+            .map(|phase| (phase, self.seconds(*phase, now)))
+            .filter(|(phase, seconds)| *seconds > 0.0 || self.current.is_some_and(|(current, _)| current == **phase))
+            .map(|(phase, seconds)| format!("{} {}", phase.label(), format_duration_from_seconds(seconds.round() as u64)))
+            .collect::<Vec<_>>()
+            .join(" · ")
+    }
+}
+
+/// Time spent by all items of the queue, per phase
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TimingSummary {
+    /// Items with any time recorded
+    pub items: usize,
+    /// Seconds per phase, in `Phase::ALL` order
+    pub seconds: [f64; 4],
+}
+
+impl TimingSummary {
+    pub fn from_items<'a>(items: impl IntoIterator<Item = &'a DownloadItem>, now: DateTime<Utc>) -> Self {
+        let mut summary = Self::default();
+        for item in items.into_iter().filter(|i| !i.timings.is_empty()) {
+            summary.items += 1;
+            for phase in Phase::ALL {
+                summary.seconds[phase.index()] += item.timings.seconds(phase, now);
+            }
+        }
+        summary
+    }
+
+    /// Share of the total time in each phase, in percent
+    pub fn shares(&self) -> Vec<(Phase, f64)> {
+        let total: f64 = self.seconds.iter().sum();
+        Phase::ALL
+            .iter()
+            .map(|phase| (*phase, if total > 0.0 { self.seconds[phase.index()] / total * 100.0 } else { 0.0 }))
+            .collect()
+    }
+
+    /// "network" or "transcoding", whichever took most of the time
+    pub fn bottleneck(&self) -> Option<&'static str> {
+        let (network, local): (Vec<_>, Vec<_>) = Phase::ALL.iter().partition(|p| p.is_network());
+        let sum = |phases: Vec<&Phase>| phases.iter().map(|p| self.seconds[p.index()]).sum::<f64>();
+        let (network, local) = (sum(network), sum(local));
+        if network + local == 0.0 {
+            None
+        } else if network >= local {
+            Some("network")
+        } else {
+            Some("transcoding")
+        }
+    }
+}
+
+impl AppState {
+    /// Where the queue's time went
+    pub fn timing_summary(&self) -> TimingSummary {
+        TimingSummary::from_items(&self.queue, Utc::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timings() {
+        let start = Utc::now();
+        let at = |secs| start + chrono::Duration::seconds(secs);
+        let mut timings = Timings::default();
+        timings.enter(Phase::Fetch, start);
+        timings.enter(Phase::Download, at(2));
+        timings.enter(Phase::Download, at(30));
+        timings.enter(Phase::Merge, at(62));
+        assert_eq!(timings.seconds(Phase::Merge, at(65)), 3.0);
+        timings.leave(at(66));
+        assert_eq!(timings.breakdown(at(100)), "fetch 2s · download 1m 0s · merge 4s");
+
+        let mut item = DownloadItem::new("https://example.com/v".to_string());
+        item.timings = timings;
+        let summary = TimingSummary::from_items([&item, &DownloadItem::new("https://example.com/w".to_string())], at(100));
+        assert_eq!(summary.items, 1);
+        assert_eq!(summary.bottleneck(), Some("network"));
+        assert_eq!(summary.shares()[2].1.round(), 6.0);
+    }
+}
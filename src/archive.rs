@@ -0,0 +1,274 @@
+use anyhow::Result;
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::{config, store};
+
+/// Videos the user already has, keyed like yt-dlp's download archive ("youtube dQw4w9WgXcQ")
+#[derive(Debug, Default)]
+pub struct Archive {
+    entries: HashSet<String>,
+    path: Option<PathBuf>,
+    /// Keys recorded while another instance was writing the file, for `flush`
+    pending: Vec<String>,
+}
+
+/// Which kind of file an import was recognised as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    YtDlpArchive,
+    TubeArchivist,
+    PlainIds,
+}
+
+impl Archive {
+    /// Load the archive from the data directory, starting empty if it doesn't exist
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("archive.txt"));
+        let entries = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .map(|contents| parse_ytdlp_archive(&contents).into_iter().collect())
+            .unwrap_or_default();
+
+        Self { entries, path, pending: Vec::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains(key)
+    }
+
+    /// Record a single download and append it to the archive file. Downloads other gorlock
+    /// instances recorded in the meantime are picked up on the way.
+    pub fn record(&mut self, key: String) -> Result<()> {
+        if self.entries.insert(key.clone()) && self.path.is_some() {
+            self.pending.push(key);
+        }
+        self.flush()
+    }
+
+    /// Append the recorded keys to the archive file. While another instance is writing it
+    /// they're kept for the next call, the main loop calls this every tick.
+    pub fn flush(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone().filter(|_| !self.pending.is_empty()) else {
+            return Ok(());
+        };
+        let Some(_lock) = store::try_lock(&path)? else {
+            return Ok(());
+        };
+        let written: HashSet<String> = self.catch_up(&path)?.into_iter().collect();
+        let keys: Vec<String> = std::mem::take(&mut self.pending).into_iter().filter(|k| !written.contains(k)).collect();
+        if !keys.is_empty() {
+            let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+            for key in keys {
+                writeln!(file, "{}", key)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Take in what other instances wrote to the archive file, returning the file's keys
+    fn catch_up(&mut self, path: &Path) -> Result<Vec<String>> {
+        let (contents, _) = store::read_appended(path, 0)?;
+        let keys = parse_ytdlp_archive(&contents);
+        self.entries.extend(keys.iter().cloned());
+        Ok(keys)
+    }
+
+    /// Merge imported keys into the archive, returning how many were new
+    pub fn extend(&mut self, keys: Vec<String>) -> Result<usize> {
+        let before = self.entries.len();
+        self.entries.extend(keys);
+        let added = self.entries.len() - before;
+        if added > 0 {
+            self.save()?;
+        }
+        Ok(added)
+    }
+
+    /// Rewrite the archive file, keeping what other instances added to it
+    pub fn save(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone() else {
+            return Ok(());
+        };
+        let _lock = store::lock(&path)?;
+        self.catch_up(&path)?;
+        store::write_atomic(&path, &self.to_ytdlp_archive())?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// All keys, sorted
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.entries.iter().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    /// Render the archive in yt-dlp's --download-archive format, one "extractor id" per line
+    pub fn to_ytdlp_archive(&self) -> String {
+        self.keys().into_iter().map(|k| format!("{}\n", k)).collect()
+    }
+}
+
+/// Archive key for a URL when the video ID can be read straight from it
+pub fn archive_key_for_url(url: &str) -> Option<String> {
+    youtube_id(url).map(|id| format!("youtube {}", id))
+}
+
+/// Extract the video ID from common YouTube URL shapes
+fn youtube_id(url: &str) -> Option<String> {
+    let candidate = if let Some(pos) = url.find("youtu.be/") {
+        &url[pos + "youtu.be/".len()..]
+    } else if url.contains("youtube.com/") {
+        if let Some(pos) = url.find("v=") {
+            &url[pos + 2..]
+        } else if let Some(pos) = url.find("/shorts/").or_else(|| url.find("/live/")) {
+            let rest = &url[pos + 1..];
+            &rest[rest.find('/')? + 1..]
+        } else {
+            return None;
+        }
+    } else {
+        return None;
+    };
+
+    let id: String = candidate
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    is_youtube_id(&id).then_some(id)
+}
+
+fn is_youtube_id(value: &str) -> bool {
+    value.len() == 11
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Parse an import file, detecting its format
+pub fn parse_import(contents: &str) -> (ImportFormat, Vec<String>) {
+    let trimmed = contents.trim_start();
+
+    if trimmed.starts_with('{') || trimmed.starts_with('[') {
+        // TubeArchivist exports are JSON or newline-delimited JSON with youtube_id fields
+        let mut ids = Vec::new();
+        match serde_json::from_str::<Value>(trimmed) {
+            Ok(value) => collect_youtube_ids(&value, &mut ids),
+            Err(_) => {
+                for line in trimmed.lines() {
+                    if let Ok(value) = serde_json::from_str::<Value>(line) {
+                        collect_youtube_ids(&value, &mut ids);
+                    }
+                }
+            }
+        }
+        let keys = ids.into_iter().map(|id| format!("youtube {}", id)).collect();
+        return (ImportFormat::TubeArchivist, keys);
+    }
+
+    let archive = parse_ytdlp_archive(contents);
+    if !archive.is_empty() {
+        return (ImportFormat::YtDlpArchive, archive);
+    }
+
+    // One ID (or URL) per line
+    let keys = contents
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| {
+            archive_key_for_url(line).or_else(|| is_youtube_id(line).then(|| format!("youtube {}", line)))
+        })
+        .collect();
+    (ImportFormat::PlainIds, keys)
+}
+
+/// Parse "extractor id" lines as written by yt-dlp --download-archive
+fn parse_ytdlp_archive(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next(), parts.next()) {
+                (Some(extractor), Some(id), None) if !extractor.contains("://") => {
+                    Some(format!("{} {}", extractor.to_lowercase(), id))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+fn collect_youtube_ids(value: &Value, ids: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match value.as_str() {
+                    Some(id) if key == "youtube_id" && is_youtube_id(id) => ids.push(id.to_string()),
+                    _ => collect_youtube_ids(value, ids),
+                }
+            }
+        }
+        Value::Array(list) => list.iter().for_each(|v| collect_youtube_ids(v, ids)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_key_for_url() {
+        assert_eq!(
+            archive_key_for_url("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=10").as_deref(),
+            Some("youtube dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            archive_key_for_url("https://youtu.be/dQw4w9WgXcQ").as_deref(),
+            Some("youtube dQw4w9WgXcQ")
+        );
+        assert_eq!(archive_key_for_url("https://vimeo.com/123456789"), None);
+    }
+
+    #[test]
+    fn test_import_detection() {
+        let (format, keys) = parse_import("youtube dQw4w9WgXcQ\nVimeo 123456\n");
+        assert_eq!(format, ImportFormat::YtDlpArchive);
+        assert_eq!(keys, vec!["youtube dQw4w9WgXcQ", "vimeo 123456"]);
+
+        let (format, keys) = parse_import(r#"{"data": [{"youtube_id": "dQw4w9WgXcQ", "title": "x"}]}"#);
+        assert_eq!(format, ImportFormat::TubeArchivist);
+        assert_eq!(keys, vec!["youtube dQw4w9WgXcQ"]);
+
+        let (format, keys) = parse_import("dQw4w9WgXcQ\nhttps://youtu.be/9bZkp7q19f0\n");
+        assert_eq!(format, ImportFormat::PlainIds);
+        assert_eq!(keys.len(), 2);
+    }
+
+    #[test]
+    fn test_record_while_locked() {
+        let dir = std::env::temp_dir().join(format!("gorlock-archive-{}", std::process::id()));
+        let path = dir.join("archive.txt");
+        let mut archive = Archive { path: Some(path.clone()), ..Archive::default() };
+
+        // Another instance writing: the key counts right away and reaches the file later
+        let held = store::lock(&path).unwrap();
+        std::fs::write(&path, "vimeo 1\n").unwrap();
+        archive.record("youtube a".to_string()).unwrap();
+        assert!(archive.contains("youtube a") && store::read_appended(&path, 0).unwrap().0 == "vimeo 1\n");
+        drop(held);
+        archive.flush().unwrap();
+        assert_eq!(store::read_appended(&path, 0).unwrap().0, "vimeo 1\nyoutube a\n");
+        assert!(archive.contains("vimeo 1"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
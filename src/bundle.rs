@@ -0,0 +1,188 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use uuid::Uuid;
+
+use crate::app_state::{DownloadItem, DownloadStatus};
+
+/// Bumped when the bundle layout changes incompatibly
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Everything needed to carry an in-progress job to another machine:
+/// the queue, the download archive and a manifest of partial files to copy along
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateBundle {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Output directory on the exporting machine, partial files are relative to it
+    pub output_dir: String,
+    pub queue: Vec<DownloadItem>,
+    pub archive: Vec<String>,
+    pub partial_files: Vec<PartialFile>,
+}
+
+/// A partially downloaded file (.part, .ytdl, fragments) in the output directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialFile {
+    pub name: String,
+    pub size: u64,
+    /// Queue item the file most likely belongs to
+    pub item_id: Option<Uuid>,
+}
+
+/// What an import changed
+#[derive(Debug, Clone, Default)]
+pub struct RestoreReport {
+    pub items_added: usize,
+    pub partial_present: usize,
+    /// Partial files listed in the manifest but not in the local output directory
+    pub partial_missing: Vec<String>,
+}
+
+impl StateBundle {
+    /// Snapshot the queue and archive, scanning the output directory for partial files
+    pub fn capture(queue: Vec<DownloadItem>, archive: Vec<String>, output_dir: String) -> Self {
+        let partial_files = scan_partial_files(Path::new(&output_dir), &queue);
+        Self {
+            version: BUNDLE_VERSION,
+            created_at: Utc::now(),
+            output_dir,
+            queue,
+            archive,
+            partial_files,
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub async fn read(path: &str) -> Result<Self> {
+        let bundle: Self = serde_json::from_str(&tokio::fs::read_to_string(path).await?)?;
+        if bundle.version > BUNDLE_VERSION {
+            return Err(anyhow!(
+                "bundle version {} is newer than this gorlock supports ({})",
+                bundle.version,
+                BUNDLE_VERSION
+            ));
+        }
+        Ok(bundle)
+    }
+
+    /// The bundle's items missing from `queue`, made ready to run here, using `output_dir` to find
+    /// copied partial files
+    pub fn restore(self, queue: &[DownloadItem], output_dir: &str) -> (Vec<DownloadItem>, RestoreReport) {
+        let mut report = RestoreReport::default();
+        let mut items = Vec::new();
+        let mut resumable = Vec::new();
+
+        for file in &self.partial_files {
+            if Path::new(output_dir).join(&file.name).exists() {
+                report.partial_present += 1;
+                resumable.extend(file.item_id);
+            } else {
+                report.partial_missing.push(file.name.clone());
+            }
+        }
+
+        for mut item in self.queue {
+            if queue.iter().any(|existing| existing.id == item.id) {
+                continue;
+            }
+            let has_partial = resumable.contains(&item.id);
+            restore_status(&mut item, has_partial);
+            items.push(item);
+            report.items_added += 1;
+        }
+
+        (items, report)
+    }
+}
+
+/// Nothing is running on the new machine: in-flight items become resumable or ready again
+fn restore_status(item: &mut DownloadItem, has_partial: bool) {
+    item.status = match item.status {
+        DownloadStatus::Downloading | DownloadStatus::Paused | DownloadStatus::Queued if has_partial => {
+            DownloadStatus::Paused
+        }
+        DownloadStatus::Downloading | DownloadStatus::Paused | DownloadStatus::Queued | DownloadStatus::FetchingInfo => {
+            if item.format.is_some() {
+                DownloadStatus::Ready
+            } else {
+                DownloadStatus::Pending
+            }
+        }
+        ref status => status.clone(),
+    };
+    // Batches don't survive the move
+    item.batch_id = None;
+}
+
+/// Whether a file name looks like yt-dlp's in-progress output
+fn is_partial_file(name: &str) -> bool {
+    name.ends_with(".part") || name.ends_with(".ytdl") || name.contains(".part-Frag")
+}
+
+fn scan_partial_files(dir: &Path, queue: &[DownloadItem]) -> Vec<PartialFile> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<PartialFile> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !is_partial_file(&name) {
+                return None;
+            }
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            // yt-dlp names files after the title, that's the best link back to the item
+            let item_id = queue
+                .iter()
+                .filter(|i| !i.status.is_completed())
+                .find(|i| i.title.as_deref().is_some_and(|t| !t.is_empty() && name.starts_with(t)))
+                .map(|i| i.id);
+            Some(PartialFile { name, size, item_id })
+        })
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore() {
+        let dir = std::env::temp_dir().join(format!("gorlock-bundle-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Concert.mp4.part"), b"partial").unwrap();
+
+        let mut downloading = DownloadItem::new("https://example.com/a".to_string());
+        downloading.title = Some("Concert".to_string());
+        downloading.status = DownloadStatus::Downloading;
+        let mut fetching = DownloadItem::new("https://example.com/b".to_string());
+        fetching.status = DownloadStatus::FetchingInfo;
+
+        let output_dir = dir.to_string_lossy().to_string();
+        let mut bundle = StateBundle::capture(vec![downloading.clone(), fetching], Vec::new(), output_dir.clone());
+        assert_eq!(bundle.partial_files.len(), 1);
+        assert_eq!(bundle.partial_files[0].item_id, Some(downloading.id));
+        bundle.partial_files.push(PartialFile {
+            name: "Other.webm.ytdl".to_string(),
+            size: 10,
+            item_id: None,
+        });
+
+        let (items, report) = bundle.restore(&[downloading], &output_dir);
+        assert_eq!(report.items_added, 1);
+        assert_eq!(report.partial_present, 1);
+        assert_eq!(report.partial_missing, vec!["Other.webm.ytdl"]);
+        assert_eq!(items[0].status, DownloadStatus::Pending);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -26,9 +26,8 @@ pub struct CacheStore {
 
 impl CacheStore {
     pub fn new() -> Result<Self> {
-        let cache_dir = dirs::cache_dir()
-            .ok_or_else(|| anyhow::anyhow!("Could not find cache directory"))?
-            .join("gorlock");
+        let cache_dir = crate::config::cache_dir()
+            .ok_or_else(|| anyhow::anyhow!("Could not find cache directory"))?;
         
         std::fs::create_dir_all(&cache_dir)?;
         let cache_file = cache_dir.join("metadata_cache.json");
@@ -75,9 +74,21 @@ impl CacheStore {
         let cache_file = self.cache_file.clone();
         
         tokio::task::spawn_blocking(move || {
-            let entries = entries.blocking_read();
-            if let Ok(json) = serde_json::to_string_pretty(&*entries) {
-                let _ = std::fs::write(cache_file, json);
+            let Ok(_lock) = crate::store::lock(&cache_file) else {
+                return;
+            };
+            // Keep what other instances cached, the newer of two entries for a URL wins
+            let mut merged: HashMap<String, CachedEntry> = std::fs::read_to_string(&cache_file)
+                .ok()
+                .and_then(|contents| serde_json::from_str(&contents).ok())
+                .unwrap_or_default();
+            for (url, entry) in entries.blocking_read().iter() {
+                if merged.get(url).is_none_or(|theirs| theirs.timestamp <= entry.timestamp) {
+                    merged.insert(url.clone(), entry.clone());
+                }
+            }
+            if let Ok(json) = serde_json::to_string_pretty(&merged) {
+                let _ = crate::store::write_atomic(&cache_file, &json);
             }
         });
         
@@ -0,0 +1,222 @@
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::app_state::Chapter;
+
+/// A single track to cut out of an album-length audio file
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlbumTrack {
+    pub number: usize,
+    pub title: String,
+    pub start: f64,
+    /// `None` runs to the end of the file
+    pub end: Option<f64>,
+}
+
+/// Turn chapter metadata into album tracks
+pub fn tracks_from_chapters(chapters: &[Chapter]) -> Vec<AlbumTrack> {
+    chapters
+        .iter()
+        .enumerate()
+        .map(|(i, chapter)| AlbumTrack {
+            number: i + 1,
+            title: chapter.title.clone(),
+            start: chapter.start_time,
+            end: (chapter.end_time > chapter.start_time).then_some(chapter.end_time),
+        })
+        .collect()
+}
+
+/// Turn user-approved split points (seconds) into consecutive tracks
+pub fn tracks_from_split_points(points: &[f64]) -> Vec<AlbumTrack> {
+    let mut bounds: Vec<f64> = points.iter().copied().filter(|p| *p > 0.0).collect();
+    bounds.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    bounds.dedup();
+
+    let mut start = 0.0;
+    let mut tracks = Vec::with_capacity(bounds.len() + 1);
+    for (i, end) in bounds.iter().chain(std::iter::once(&f64::INFINITY)).enumerate() {
+        tracks.push(AlbumTrack {
+            number: i + 1,
+            title: format!("Track {}", i + 1),
+            start,
+            end: end.is_finite().then_some(*end),
+        });
+        start = *end;
+    }
+    tracks
+}
+
+/// Silence detection result for a file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SilenceReport {
+    /// (start, end) of each silent stretch in seconds
+    pub silences: Vec<(f64, f64)>,
+    /// Total media duration in seconds, if ffmpeg reported it
+    pub duration: Option<f64>,
+}
+
+impl SilenceReport {
+    /// Propose split points in the middle of each silence, ignoring lead-in and tail silence
+    pub fn split_points(&self) -> Vec<f64> {
+        let end = self.duration.unwrap_or(f64::INFINITY);
+        self.silences
+            .iter()
+            .filter(|(start, stop)| *start > 1.0 && *stop < end - 1.0)
+            .map(|(start, stop)| (start + stop) / 2.0)
+            .collect()
+    }
+}
+
+/// Run ffmpeg's silencedetect filter over the file
+pub async fn detect_silence(source: &Path) -> Result<SilenceReport> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(source)
+        .args(["-af", "silencedetect=noise=-35dB:d=1.5", "-f", "null", "-"])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(anyhow!(
+            "ffmpeg silencedetect failed: {}",
+            String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default()
+        ));
+    }
+
+    Ok(parse_silencedetect(&String::from_utf8_lossy(&output.stderr)))
+}
+
+/// Parse ffmpeg silencedetect output (it is written to stderr)
+pub fn parse_silencedetect(output: &str) -> SilenceReport {
+    let mut report = SilenceReport::default();
+    let mut pending_start = None;
+
+    for line in output.lines() {
+        if let Some(rest) = line.split("silence_start: ").nth(1) {
+            pending_start = rest.trim().parse::<f64>().ok();
+        } else if let Some(rest) = line.split("silence_end: ").nth(1) {
+            let end = rest.split_whitespace().next().and_then(|v| v.parse::<f64>().ok());
+            if let (Some(start), Some(end)) = (pending_start.take(), end) {
+                report.silences.push((start.max(0.0), end));
+            }
+        } else if let Some(rest) = line.trim_start().strip_prefix("Duration: ") {
+            report.duration = rest.split(',').next().and_then(parse_timestamp);
+        }
+    }
+
+    report
+}
+
+/// Parse "HH:MM:SS.ss" into seconds
+fn parse_timestamp(value: &str) -> Option<f64> {
+    let mut seconds = 0.0;
+    for part in value.trim().split(':') {
+        seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Some(seconds)
+}
+
+/// Replace characters that aren't allowed in file names
+pub fn sanitize_filename(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    let trimmed = cleaned.trim().trim_matches('.');
+    if trimmed.is_empty() {
+        "untitled".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+/// Split `source` into one tagged file per track inside an album folder next to it.
+/// Returns the album folder.
+pub async fn split_album(source: &Path, album: &str, tracks: &[AlbumTrack]) -> Result<PathBuf> {
+    if tracks.is_empty() {
+        return Err(anyhow!("No tracks to split"));
+    }
+
+    let ext = source
+        .extension()
+        .and_then(|e| e.to_str())
+        .ok_or_else(|| anyhow!("Source file has no extension"))?;
+    let folder = source
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(sanitize_filename(album));
+    tokio::fs::create_dir_all(&folder).await?;
+
+    let total = tracks.len();
+    let digits = total.to_string().len().max(2);
+
+    for track in tracks {
+        let file_name = format!(
+            "{:0width$} - {}.{}",
+            track.number,
+            sanitize_filename(&track.title),
+            ext,
+            width = digits
+        );
+        let output = folder.join(file_name);
+
+        let mut cmd = Command::new("ffmpeg");
+        cmd.args(["-hide_banner", "-loglevel", "error", "-y", "-i"])
+            .arg(source)
+            .args(["-ss", &format!("{:.3}", track.start)]);
+        if let Some(end) = track.end {
+            cmd.args(["-to", &format!("{:.3}", end)]);
+        }
+        cmd.args(["-map", "0:a", "-c", "copy", "-map_metadata", "-1"])
+            .args(["-metadata", &format!("title={}", track.title)])
+            .args(["-metadata", &format!("album={}", album)])
+            .args(["-metadata", &format!("track={}/{}", track.number, total)])
+            .arg(&output);
+
+        let result = cmd.output().await?;
+        if !result.status.success() {
+            return Err(anyhow!(
+                "ffmpeg failed on track {}: {}",
+                track.number,
+                String::from_utf8_lossy(&result.stderr).trim()
+            ));
+        }
+    }
+
+    Ok(folder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_silencedetect_parsing() {
+        let output = "\
+  Duration: 00:10:00.00, start: 0.000000, bitrate: 128 kb/s
+[silencedetect @ 0x55d1] silence_start: 0
+[silencedetect @ 0x55d1] silence_end: 0.8 | silence_duration: 0.8
+[silencedetect @ 0x55d1] silence_start: 181.5
+[silencedetect @ 0x55d1] silence_end: 184.5 | silence_duration: 3
+[silencedetect @ 0x55d1] silence_start: 398
+[silencedetect @ 0x55d1] silence_end: 400 | silence_duration: 2
+";
+        let report = parse_silencedetect(output);
+        assert_eq!(report.duration, Some(600.0));
+        assert_eq!(report.silences.len(), 3);
+        assert_eq!(report.split_points(), vec![183.0, 399.0]);
+
+        let tracks = tracks_from_split_points(&report.split_points());
+        assert_eq!(tracks.len(), 3);
+        assert_eq!(tracks[1].start, 183.0);
+        assert_eq!(tracks[1].end, Some(399.0));
+        assert_eq!(tracks[2].end, None);
+    }
+}
@@ -0,0 +1,60 @@
+use anyhow::{Result, anyhow};
+use std::time::Duration;
+use super::yt_dlp::command;
+
+/// Whether a URL still resolves to a video, without downloading anything.
+/// The error is yt-dlp's own message, for `troubleshoot::source_gone` to judge.
+pub async fn check_available(url: &str, extra_args: &[String]) -> Result<()> {
+    let output = command()
+        .args(["--simulate", "--quiet", "--no-warnings", "--no-playlist"])
+        .args(extra_args)
+        .arg(url)
+        .output()
+        .await
+        .map_err(|e| anyhow!("could not run yt-dlp: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("{}", stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("yt-dlp failed").trim()));
+    }
+    Ok(())
+}
+
+/// The Wayback Machine's closest snapshot of a URL, `None` when it has none
+pub async fn wayback_snapshot(url: &str) -> Result<Option<String>> {
+    let api = reqwest::Url::parse_with_params("https://archive.org/wayback/available", &[("url", url)])?;
+    let body = reqwest::Client::new()
+        .get(api)
+        .timeout(Duration::from_secs(20))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    Ok(parse_snapshot(&body))
+}
+
+fn parse_snapshot(body: &str) -> Option<String> {
+    let json: serde_json::Value = serde_json::from_str(body).ok()?;
+    let closest = &json["archived_snapshots"]["closest"];
+    if closest["available"] != true {
+        return None;
+    }
+    let url = closest["url"].as_str()?;
+    Some(url.replacen("http://", "https://", 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_snapshot() {
+        let body = r#"{"url": "youtube.com/watch?v=abc", "archived_snapshots": {"closest": {"status": "200", "available": true,
+            "url": "http://web.archive.org/web/20200101000000/https://www.youtube.com/watch?v=abc", "timestamp": "20200101000000"}}}"#;
+        assert_eq!(
+            parse_snapshot(body).as_deref(),
+            Some("https://web.archive.org/web/20200101000000/https://www.youtube.com/watch?v=abc")
+        );
+        assert_eq!(parse_snapshot(r#"{"url": "x", "archived_snapshots": {}}"#), None);
+    }
+}
@@ -0,0 +1,212 @@
+use anyhow::{Result, anyhow};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+use crate::cache::{self, CachedEntry};
+use crate::commands::{fast_fetch, yt_dlp};
+
+/// Metadata fetch strategies compared by the benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Full JSON dump with formats (what the format popup uses)
+    FetchFormats,
+    /// `--print` of title/duration only
+    FastFetch,
+    /// Metadata cache, falling back to fetch_formats and filling the cache on a miss
+    Cached,
+}
+
+/// Benchmark settings, from `gorlock --bench-fetch <url file> [--concurrency N] [--rounds N]`
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    pub url_file: String,
+    pub concurrency: usize,
+    pub rounds: usize,
+}
+
+/// Latencies and failures of one strategy
+#[derive(Debug, Default)]
+struct StrategyReport {
+    latencies: Vec<Duration>,
+    failures: usize,
+    cache_hits: usize,
+    wall_time: Duration,
+}
+
+impl Strategy {
+    const ALL: [Strategy; 3] = [Strategy::FetchFormats, Strategy::FastFetch, Strategy::Cached];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Strategy::FetchFormats => "fetch_formats",
+            Strategy::FastFetch => "fast_fetch",
+            Strategy::Cached => "cached",
+        }
+    }
+
+    /// Fetch metadata for one URL, returning whether it came from the cache
+    async fn fetch(self, url: &str) -> Result<bool> {
+        match self {
+            Strategy::FetchFormats => yt_dlp::fetch_formats(url).await.map(|_| false),
+            Strategy::FastFetch => fast_fetch::fetch_video_info_fast(url).await.map(|_| false),
+            Strategy::Cached => {
+                let cache = cache::get_cache().await;
+                if cache.get(url).await.is_some_and(|entry| entry.formats.is_some()) {
+                    return Ok(true);
+                }
+                let fetched = yt_dlp::fetch_formats(url).await?;
+                let mut entry = CachedEntry::new(url.to_string(), fetched.title, fetched.duration);
+                entry.formats = Some(fetched.formats);
+                cache.set(url.to_string(), entry).await?;
+                Ok(false)
+            }
+        }
+    }
+}
+
+impl BenchOptions {
+    /// Parse the hidden `--bench-fetch` flag, `None` when it isn't given
+    pub fn from_args(args: &[String]) -> Option<Result<Self>> {
+        let position = args.iter().position(|a| a == "--bench-fetch")?;
+        let number = |flag: &str, default: usize| -> Result<usize> {
+            match args.iter().position(|a| a == flag) {
+                Some(i) => args
+                    .get(i + 1)
+                    .and_then(|v| v.parse().ok())
+                    .filter(|n| *n > 0)
+                    .ok_or_else(|| anyhow!("{} needs a positive number", flag)),
+                None => Ok(default),
+            }
+        };
+
+        Some((|| {
+            Ok(Self {
+                url_file: args
+                    .get(position + 1)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("--bench-fetch needs a file with one URL per line"))?,
+                concurrency: number("--concurrency", 1)?,
+                rounds: number("--rounds", 2)?,
+            })
+        })())
+    }
+}
+
+/// Run every strategy over the URL list and print latency percentiles
+pub async fn run(options: BenchOptions) -> Result<()> {
+    let urls: Vec<String> = std::fs::read_to_string(&options.url_file)?
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if urls.is_empty() {
+        return Err(anyhow!("no URLs in {}", options.url_file));
+    }
+
+    println!(
+        "Benchmarking {} URL(s), {} round(s), concurrency {}",
+        urls.len(),
+        options.rounds,
+        options.concurrency
+    );
+    println!(
+        "{:<14} {:>6} {:>9} {:>9} {:>9} {:>9} {:>6} {:>6} {:>9}",
+        "strategy", "ok", "p50", "p90", "p99", "max", "fail", "hits", "wall"
+    );
+
+    for strategy in Strategy::ALL {
+        let report = bench_strategy(strategy, &urls, &options).await;
+        let mut sorted = report.latencies.clone();
+        sorted.sort();
+        let p = |q: f64| percentile(&sorted, q).map(format_ms).unwrap_or_else(|| "-".to_string());
+        println!(
+            "{:<14} {:>6} {:>9} {:>9} {:>9} {:>9} {:>6} {:>6} {:>9}",
+            strategy.name(),
+            sorted.len(),
+            p(0.5),
+            p(0.9),
+            p(0.99),
+            p(1.0),
+            report.failures,
+            report.cache_hits,
+            format_ms(report.wall_time)
+        );
+    }
+    Ok(())
+}
+
+async fn bench_strategy(strategy: Strategy, urls: &[String], options: &BenchOptions) -> StrategyReport {
+    let semaphore = Arc::new(Semaphore::new(options.concurrency));
+    let started = Instant::now();
+    let mut report = StrategyReport::default();
+
+    // Rounds run one after the other so later rounds see what earlier ones cached
+    for _ in 0..options.rounds {
+        let mut tasks = tokio::task::JoinSet::new();
+        for url in urls {
+            let semaphore = semaphore.clone();
+            let url = url.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await;
+                let started = Instant::now();
+                let result = strategy.fetch(&url).await;
+                (started.elapsed(), result)
+            });
+        }
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((latency, Ok(hit))) => {
+                    report.latencies.push(latency);
+                    report.cache_hits += hit as usize;
+                }
+                Ok((_, Err(_))) => report.failures += 1,
+                // A panicked fetch counts as a failure, the rest of the round still finishes
+                Err(e) => {
+                    eprintln!("{}: fetch task failed: {}", strategy.name(), e);
+                    report.failures += 1;
+                }
+            }
+        }
+    }
+
+    report.wall_time = started.elapsed();
+    report
+}
+
+/// Nearest-rank percentile of sorted latencies
+fn percentile(sorted: &[Duration], q: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+fn format_ms(duration: Duration) -> String {
+    format!("{}ms", duration.as_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile() {
+        let sorted: Vec<Duration> = (1..=10).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&sorted, 0.5), Some(Duration::from_millis(5)));
+        assert_eq!(percentile(&sorted, 0.9), Some(Duration::from_millis(9)));
+        assert_eq!(percentile(&sorted, 1.0), Some(Duration::from_millis(10)));
+        assert_eq!(percentile(&sorted, 0.0), Some(Duration::from_millis(1)));
+        assert_eq!(percentile(&[], 0.5), None);
+
+        let args: Vec<String> = ["gorlock", "--bench-fetch", "urls.txt", "--rounds", "3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let options = BenchOptions::from_args(&args).unwrap().unwrap();
+        assert_eq!((options.url_file.as_str(), options.concurrency, options.rounds), ("urls.txt", 1, 3));
+        assert!(BenchOptions::from_args(&args[..1]).is_none());
+    }
+}
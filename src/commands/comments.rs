@@ -0,0 +1,63 @@
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use super::yt_dlp::command;
+
+/// The part of `yt-dlp -j --write-comments` output we keep
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CommentsDump {
+    comments: Option<Vec<serde_json::Value>>,
+}
+
+/// Fetch up to `max_comments` comments and write them next to the media file as
+/// `<name>.comments.json`, returning how many were saved and where
+pub async fn archive_comments(url: &str, media_path: &str, max_comments: u32) -> Result<(usize, PathBuf)> {
+    let output = command()
+        .args([
+            "--dump-json",
+            "--skip-download",
+            "--write-comments",
+            "--extractor-args",
+            &format!("youtube:max_comments={}", max_comments),
+            "--no-warnings",
+            url,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("yt-dlp failed: {}", stderr.lines().last().unwrap_or("unknown error")));
+    }
+
+    let dump: CommentsDump = serde_json::from_slice(&output.stdout)?;
+    let mut comments = dump
+        .comments
+        .ok_or_else(|| anyhow!("this site doesn't provide comments"))?;
+    // Only YouTube honours max_comments, cap the rest here
+    comments.truncate(max_comments as usize);
+
+    let path = comments_path(Path::new(media_path));
+    tokio::fs::write(&path, serde_json::to_vec_pretty(&comments)?).await?;
+    Ok((comments.len(), path))
+}
+
+/// `video.mp4` -> `video.comments.json`
+fn comments_path(media_path: &Path) -> PathBuf {
+    media_path.with_extension("comments.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comments_path() {
+        assert_eq!(
+            comments_path(Path::new("/media/Talk v2.mp4")),
+            PathBuf::from("/media/Talk v2.comments.json")
+        );
+        let dump: CommentsDump = serde_json::from_str(r#"{"title": "x", "comments": [{"text": "hi"}]}"#).unwrap();
+        assert_eq!(dump.comments.map(|c| c.len()), Some(1));
+    }
+}
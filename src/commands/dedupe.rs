@@ -0,0 +1,68 @@
+use anyhow::Result;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use xxhash_rust::xxh3::Xxh3;
+
+/// How much of each end of the file goes into the hash
+const SAMPLE_BYTES: u64 = 1024 * 1024;
+
+/// Fast content fingerprint: xxh3 over the size plus the first and last MiB, or the whole file up to 2 MiB.
+/// Not a full-file hash, but two different videos practically never collide.
+pub async fn content_hash(path: &str) -> Result<String> {
+    let path = path.to_string();
+    tokio::task::spawn_blocking(move || hash_file(Path::new(&path))).await?
+}
+
+fn hash_file(path: &Path) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Xxh3::new();
+    hasher.update(&size.to_le_bytes());
+
+    // Files up to two samples long are hashed whole
+    let head = if size <= SAMPLE_BYTES * 2 { size } else { SAMPLE_BYTES };
+    let mut buffer = Vec::with_capacity(head as usize);
+    (&mut file).take(head).read_to_end(&mut buffer)?;
+    hasher.update(&buffer);
+
+    if size > SAMPLE_BYTES * 2 {
+        buffer.clear();
+        file.seek(SeekFrom::End(-(SAMPLE_BYTES as i64)))?;
+        file.take(SAMPLE_BYTES).read_to_end(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(format!("{:016x}", hasher.digest()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file() {
+        let dir = std::env::temp_dir().join(format!("gorlock-dedupe-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (a, b, c) = (dir.join("a.bin"), dir.join("b.bin"), dir.join("c.bin"));
+
+        let mut data = vec![7u8; 3 * SAMPLE_BYTES as usize];
+        std::fs::write(&a, &data).unwrap();
+        std::fs::write(&b, &data).unwrap();
+        // Differs only in the tail sample
+        *data.last_mut().unwrap() = 8;
+        std::fs::write(&c, &data).unwrap();
+
+        assert_eq!(hash_file(&a).unwrap(), hash_file(&b).unwrap());
+        assert_ne!(hash_file(&a).unwrap(), hash_file(&c).unwrap());
+
+        // Past the head sample but short of two samples, the middle counts too
+        let mut data = vec![7u8; 3 * SAMPLE_BYTES as usize / 2];
+        std::fs::write(&a, &data).unwrap();
+        *data.last_mut().unwrap() = 8;
+        std::fs::write(&b, &data).unwrap();
+        assert_ne!(hash_file(&a).unwrap(), hash_file(&b).unwrap());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::Path;
+use std::time::Instant;
+
+/// Size of the file written by the disk speed test
+const SPEED_TEST_BYTES: usize = 64 * 1024 * 1024;
+
+/// Choices for a fixed write limit, bytes per second
+pub const WRITE_LIMITS: &[u64] = &[1_000_000, 2_000_000, 5_000_000, 10_000_000, 20_000_000, 50_000_000];
+
+/// How fast downloads may write, for slow targets like network shares and SD cards
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WriteLimit {
+    #[default]
+    Off,
+    /// Throttle once downloads keep stalling on a disk slower than they are
+    Auto,
+    /// Each download writes at most this many bytes per second
+    Fixed(u64),
+}
+
+impl WriteLimit {
+    /// Off, auto, then the fixed limits from slowest to fastest
+    pub fn choices() -> Vec<WriteLimit> {
+        [WriteLimit::Off, WriteLimit::Auto]
+            .into_iter()
+            .chain(WRITE_LIMITS.iter().map(|bytes| WriteLimit::Fixed(*bytes)))
+            .collect()
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            WriteLimit::Off => "off".to_string(),
+            WriteLimit::Auto => "auto".to_string(),
+            WriteLimit::Fixed(bytes) => format!("{}/s per download", crate::app_state::format_bytes(*bytes)),
+        }
+    }
+}
+
+/// yt-dlp arguments writing at most `bytes_per_sec`: a rate limit, and a small fixed buffer so
+/// the writes come in an even stream instead of bursts the target stalls on
+pub fn throttle_args(bytes_per_sec: u64) -> Vec<String> {
+    ["--limit-rate", &bytes_per_sec.to_string(), "--buffer-size", "64K", "--no-resize-buffer"]
+        .iter()
+        .map(|a| a.to_string())
+        .collect()
+}
+
+/// Bytes on disk for a download in progress: the destination and everything yt-dlp
+/// writes next to it while downloading (.part, fragments, per-format files)
+pub fn recording_usage(destination: &str) -> u64 {
+    let path = Path::new(destination);
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem()) else {
+        return 0;
+    };
+    let stem = stem.to_string_lossy();
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(stem.as_ref()))
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum()
+}
+
+/// Sustained write speed of the disk holding `dir`, in bytes per second.
+/// Writes and syncs a 64 MiB scratch file, so call it sparingly and off the UI thread.
+pub fn measure_write_speed(dir: &str) -> Result<f64> {
+    std::fs::create_dir_all(dir)?;
+    let path = Path::new(dir).join(format!(".gorlock-speedtest-{}", std::process::id()));
+    let chunk = vec![0x5Au8; 1024 * 1024];
+
+    let started = Instant::now();
+    let result = (|| -> Result<()> {
+        let mut file = std::fs::File::create(&path)?;
+        for _ in 0..SPEED_TEST_BYTES / chunk.len() {
+            file.write_all(&chunk)?;
+        }
+        // Without the sync we'd be measuring the page cache
+        file.sync_all()?;
+        Ok(())
+    })();
+    let elapsed = started.elapsed().as_secs_f64();
+    let _ = std::fs::remove_file(&path);
+    result?;
+
+    Ok(SPEED_TEST_BYTES as f64 / elapsed.max(0.001))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_usage() {
+        let dir = std::env::temp_dir().join(format!("gorlock-disk-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Stream 2026-10-16.mp4.part"), vec![0u8; 100]).unwrap();
+        std::fs::write(dir.join("Stream 2026-10-16.f140.m4a"), vec![0u8; 20]).unwrap();
+        std::fs::write(dir.join("Other.mp4"), vec![0u8; 7]).unwrap();
+
+        let destination = dir.join("Stream 2026-10-16.mp4");
+        assert_eq!(recording_usage(&destination.to_string_lossy()), 120);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
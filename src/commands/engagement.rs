@@ -0,0 +1,113 @@
+use anyhow::{Result, anyhow};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use super::yt_dlp::command;
+
+/// How long fetched counts are reused before asking yt-dlp again
+pub const ENGAGEMENT_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Counts that change after upload, fetched separately from formats
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Engagement {
+    pub view_count: Option<u64>,
+    pub like_count: Option<u64>,
+    pub live_status: Option<String>,
+}
+
+lazy_static::lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, (Instant, Engagement)>> = Mutex::new(HashMap::new());
+}
+
+/// Fetch view/like counts and live status, reusing a result younger than the TTL unless `force`
+pub async fn fetch_engagement(url: &str, force: bool) -> Result<Engagement> {
+    if !force {
+        if let Some((fetched, engagement)) = CACHE.lock().unwrap().get(url) {
+            if fetched.elapsed() < ENGAGEMENT_TTL {
+                return Ok(engagement.clone());
+            }
+        }
+    }
+
+    let output = command()
+        .args([
+            "--print",
+            "%(view_count)s|%(like_count)s|%(live_status)s",
+            "--skip-download",
+            "--no-warnings",
+            "--quiet",
+            url,
+        ])
+        .output()
+        .await?;
+    if !output.status.success() {
+        return Err(anyhow!("Failed to fetch view counts"));
+    }
+
+    let engagement = parse_line(String::from_utf8_lossy(&output.stdout).trim());
+    CACHE
+        .lock()
+        .unwrap()
+        .insert(url.to_string(), (Instant::now(), engagement.clone()));
+    Ok(engagement)
+}
+
+/// Parse "views|likes|live_status", yt-dlp prints "NA" for unknown fields
+fn parse_line(line: &str) -> Engagement {
+    let mut parts = line.split('|').map(|p| Some(p).filter(|p| !p.is_empty() && *p != "NA"));
+    let mut next = || parts.next().flatten();
+    // Some extractors give counts as floats ("1234.0")
+    let count = |v: &str| v.parse::<f64>().ok().filter(|n| n.is_finite() && *n >= 0.0).map(|n| n as u64);
+    Engagement {
+        view_count: next().and_then(count),
+        like_count: next().and_then(count),
+        live_status: next().map(String::from),
+    }
+}
+
+/// Readable form of yt-dlp's live_status
+pub fn live_status_label(status: &str) -> &str {
+    match status {
+        "is_live" => "live now",
+        "was_live" => "was live",
+        "is_upcoming" => "upcoming",
+        "post_live" => "just ended, processing",
+        "not_live" => "not live",
+        other => other,
+    }
+}
+
+/// Group digits in thousands, 1234567 -> "1,234,567"
+pub fn format_count(count: u64) -> String {
+    let digits = count.to_string();
+    let mut out = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            parse_line("1234567|NA|is_live"),
+            Engagement {
+                view_count: Some(1234567),
+                like_count: None,
+                live_status: Some("is_live".to_string()),
+            }
+        );
+        assert_eq!(parse_line("12.0|3|NA").like_count, Some(3));
+        assert_eq!(parse_line("12.0|3|NA").view_count, Some(12));
+        assert_eq!(parse_line(""), Engagement::default());
+        assert_eq!(format_count(1234567), "1,234,567");
+        assert_eq!(format_count(999), "999");
+    }
+}
@@ -0,0 +1,198 @@
+use anyhow::{Result, anyhow, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Links a page may yield, past that the rule is probably matching too much
+pub const MAX_LINKS: usize = 500;
+/// Most of a page read for links, the rest is left out
+const MAX_PAGE_BYTES: usize = 8 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A user rule pulling media links out of pages yt-dlp has no extractor for. The fields are
+/// private so the compiled regexes always belong to them.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExtractorRule {
+    /// Regex the page URL has to match, e.g. "^https://archive\\.example\\.org/item/"
+    page: String,
+    /// Regex over the page's HTML, its first matching group (or the whole match) is a link
+    pattern: Option<String>,
+    /// `tag[attr]` selector taking the attribute of every matching tag, e.g. "source[src]"
+    selector: Option<String>,
+    #[serde(skip)]
+    compiled: Compiled,
+}
+
+/// The page regex and the link finder, compiled the first time they're needed, `None` when
+/// one doesn't compile
+#[derive(Debug, Clone, Default)]
+struct Compiled(OnceLock<Option<(Regex, Regex)>>);
+
+impl PartialEq for Compiled {
+    /// Rules are the same by their settings, compiled or not
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl ExtractorRule {
+    pub fn new(page: &str, pattern: Option<&str>, selector: Option<&str>) -> Self {
+        Self {
+            page: page.to_string(),
+            pattern: pattern.map(str::to_string),
+            selector: selector.map(str::to_string),
+            compiled: Compiled::default(),
+        }
+    }
+
+    pub fn page(&self) -> &str {
+        &self.page
+    }
+
+    fn compiled(&self) -> Option<&(Regex, Regex)> {
+        self.compiled
+            .0
+            .get_or_init(|| {
+                let page = Regex::new(&self.page).ok()?;
+                let finder = match (&self.pattern, &self.selector) {
+                    (Some(pattern), _) => Regex::new(pattern).ok()?,
+                    (None, Some(selector)) => selector_regex(selector).ok()?,
+                    (None, None) => return None,
+                };
+                Some((page, finder))
+            })
+            .as_ref()
+    }
+
+    /// Whether the rule applies to a page URL
+    pub fn matches(&self, url: &str) -> bool {
+        self.compiled().is_some_and(|(page, _)| page.is_match(url))
+    }
+
+    /// A mistake that would keep the rule from extracting anything
+    pub fn check(&self) -> Result<()> {
+        Regex::new(&self.page).map_err(|e| anyhow!("extractor page regex \"{}\": {}", self.page, e))?;
+        match (&self.pattern, &self.selector) {
+            (None, None) => bail!("extractor for \"{}\" needs a pattern or a selector", self.page),
+            (Some(pattern), _) => {
+                Regex::new(pattern).map_err(|e| anyhow!("extractor pattern \"{}\": {}", pattern, e))?;
+            }
+            (None, Some(selector)) => {
+                selector_regex(selector)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Links in a page's HTML, made absolute against `page_url`, in page order without repeats
+    pub fn extract(&self, page_url: &str, html: &str) -> Result<Vec<String>> {
+        let Some((_, finder)) = self.compiled() else {
+            self.check()?;
+            bail!("the rule doesn't compile");
+        };
+        let base = reqwest::Url::parse(page_url)?;
+        let mut links: Vec<String> = Vec::new();
+        for found in finder.captures_iter(html) {
+            // The first group that took part, a selector's value is in one of three quote styles
+            let Some(raw) = found.iter().skip(1).flatten().next().or_else(|| found.get(0)) else {
+                continue;
+            };
+            let Ok(link) = base.join(&unescape(raw.as_str().trim())) else {
+                continue;
+            };
+            let link = link.to_string();
+            if !links.contains(&link) {
+                links.push(link);
+            }
+            if links.len() == MAX_LINKS {
+                break;
+            }
+        }
+        Ok(links)
+    }
+}
+
+/// The regex for a `tag[attr]` selector, its group the attribute's value
+fn selector_regex(selector: &str) -> Result<Regex> {
+    let (tag, attr) = selector
+        .trim()
+        .strip_suffix(']')
+        .and_then(|s| s.split_once('['))
+        .filter(|(tag, attr)| is_name(tag) && is_name(attr))
+        .ok_or_else(|| anyhow!("selector \"{}\" must look like tag[attr], e.g. source[src]", selector))?;
+    Ok(Regex::new(&format!(
+        r#"(?is)<{}\b[^>]*?\s{}\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s>]+))"#,
+        regex::escape(tag),
+        regex::escape(attr)
+    ))?)
+}
+
+fn is_name(s: &str) -> bool {
+    !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The entities likely in an attribute holding a URL
+fn unescape(s: &str) -> String {
+    s.replace("&amp;", "&").replace("&quot;", "\"").replace("&#39;", "'").replace("&#x2F;", "/")
+}
+
+/// Fetch a page and pull the rule's links out of it, as playlist entries (url, title, duration).
+/// yt-dlp downloads the links as direct files.
+pub async fn fetch_links(rule: &ExtractorRule, page_url: &str) -> Result<Vec<(String, String, Option<String>)>> {
+    let mut response = reqwest::Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()?
+        .get(page_url)
+        .send()
+        .await?
+        .error_for_status()?;
+    let mut page = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        page.extend_from_slice(&chunk);
+        if page.len() >= MAX_PAGE_BYTES {
+            page.truncate(MAX_PAGE_BYTES);
+            break;
+        }
+    }
+    let links = rule.extract(page_url, &String::from_utf8_lossy(&page))?;
+    Ok(links
+        .into_iter()
+        .map(|link| {
+            // The file name stands in for a title
+            let name = reqwest::Url::parse(&link)
+                .ok()
+                .and_then(|u| u.path_segments().and_then(|mut s| s.next_back().map(str::to_string)))
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| link.clone());
+            (link, name, None)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_links() {
+        let html = r#"<video controls><source type="video/mp4" src="/media/a.mp4">
+            <source src='https://cdn.example.org/b.webm?x=1&amp;y=2'></video>
+            <a href="/media/a.mp4">again</a> <a href=c.mp3>audio</a>"#;
+        let page = "https://example.org/item/42";
+
+        let rule = ExtractorRule::new("example\\.org/item/", None, Some("source[src]"));
+        assert!(rule.check().is_ok() && rule.matches(page) && !rule.matches("https://example.com/"));
+        assert_eq!(
+            rule.extract(page, html).unwrap(),
+            ["https://example.org/media/a.mp4", "https://cdn.example.org/b.webm?x=1&y=2"]
+        );
+
+        let rule = ExtractorRule::new(rule.page(), Some(r#"href="?([^" >]+\.mp[34])"#), None);
+        assert_eq!(rule.extract(page, html).unwrap(), ["https://example.org/media/a.mp4", "https://example.org/item/c.mp3"]);
+
+        let broken = ExtractorRule::new(rule.page(), None, Some("source"));
+        assert!(broken.check().is_err() && !broken.matches(page) && broken.extract(page, html).is_err());
+    }
+}
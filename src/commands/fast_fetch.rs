@@ -1,5 +1,5 @@
 use anyhow::{Result, anyhow};
-use tokio::process::Command;
+use super::yt_dlp::command;
 use std::time::Instant;
 
 /// Fast fetch for single video metadata - 5-10x faster than JSON
@@ -7,7 +7,7 @@ pub async fn fetch_video_info_fast(url: &str) -> Result<(String, Option<String>,
     let start = Instant::now();
     
     // Use --print to get only what we need, no JSON parsing
-    let output = Command::new("yt-dlp")
+    let output = command()
         .args([
             "--print", "%(title)s|%(duration_string)s|%(thumbnail)s",
             "--skip-download",
@@ -39,7 +39,7 @@ pub async fn fetch_video_info_fast(url: &str) -> Result<(String, Option<String>,
 
 /// Fast fetch for playlist entries - returns results immediately as they arrive
 pub async fn fetch_playlist_entries_fast(url: &str) -> Result<Vec<(String, String, Option<String>)>> {
-    let output = Command::new("yt-dlp")
+    let output = command()
         .args([
             "--flat-playlist",
             "--print", "%(id)s|%(title)s|%(duration_string)s",
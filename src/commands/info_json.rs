@@ -0,0 +1,269 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::app_state::{Chapter, FormatInfo};
+
+/// Output of `yt-dlp --dump-single-json`, for a video or a playlist.
+///
+/// Extractors leave out or null whatever they don't know, so every field is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VideoInfo {
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub duration_string: Option<String>,
+    pub thumbnail: Option<String>,
+    pub extractor_key: Option<String>,
+    pub webpage_url: Option<String>,
+    pub formats: Vec<FormatJson>,
+    pub chapters: Option<Vec<ChapterJson>>,
+    pub is_live: Option<bool>,
+    /// "is_live", "was_live", "is_upcoming", "post_live" or "not_live"
+    pub live_status: Option<String>,
+    pub was_live: Option<bool>,
+    /// When the video became available, for a stream when it actually went live
+    pub timestamp: Option<i64>,
+    /// When the video was or is scheduled to be released, for a stream its planned start
+    pub release_timestamp: Option<i64>,
+    pub uploader: Option<String>,
+    pub channel: Option<String>,
+    /// YYYYMMDD
+    pub upload_date: Option<String>,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub view_count: Option<u64>,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub like_count: Option<u64>,
+    /// Present for playlists, unavailable entries come through as null
+    pub entries: Option<Vec<Option<PlaylistEntry>>>,
+}
+
+/// One entry of the `formats` list
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FormatJson {
+    pub format_id: Option<String>,
+    pub ext: Option<String>,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub width: Option<u64>,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub height: Option<u64>,
+    pub resolution: Option<String>,
+    pub fps: Option<f64>,
+    pub vcodec: Option<String>,
+    pub acodec: Option<String>,
+    #[serde(deserialize_with = "lenient_u64")]
+    pub filesize: Option<u64>,
+    /// An estimate, some extractors give it as a float
+    pub filesize_approx: Option<f64>,
+    pub quality: Option<serde_json::Value>,
+    pub dynamic_range: Option<String>,
+    pub abr: Option<f64>,
+    pub protocol: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ChapterJson {
+    pub title: Option<String>,
+    pub start_time: Option<f64>,
+    pub end_time: Option<f64>,
+}
+
+/// A playlist entry, flat or fully extracted
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PlaylistEntry {
+    pub url: Option<String>,
+    pub webpage_url: Option<String>,
+    pub title: Option<String>,
+    pub duration_string: Option<String>,
+}
+
+/// Descriptive metadata kept with a queue item
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VideoMetadata {
+    /// Uploader, or the channel when the extractor only knows that
+    pub uploader: Option<String>,
+    /// YYYYMMDD
+    pub upload_date: Option<String>,
+    pub view_count: Option<u64>,
+    pub like_count: Option<u64>,
+    pub description: Option<String>,
+    /// yt-dlp's live_status, see [`crate::commands::engagement::live_status_label`]
+    pub live_status: Option<String>,
+    /// When the counts were last fetched
+    pub counts_fetched_at: Option<DateTime<Utc>>,
+    /// A count refresh is running
+    #[serde(skip)]
+    pub refreshing: bool,
+}
+
+/// A count or size, which some extractors give as a float ("1234.0") and others as null
+fn lenient_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u64>, D::Error> {
+    let value = Option::<f64>::deserialize(deserializer)?;
+    Ok(value.filter(|v| v.is_finite() && *v >= 0.0).map(|v| v as u64))
+}
+
+/// yt-dlp prints "NA" for missing template fields, treat it and empty strings as absent
+fn known(value: &Option<String>) -> Option<String> {
+    value.clone().filter(|s| !s.is_empty() && s != "NA")
+}
+
+impl VideoInfo {
+    pub fn parse(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    pub fn duration(&self) -> Option<String> {
+        known(&self.duration_string)
+    }
+
+    /// Download archive key ("extractor id")
+    pub fn archive_key(&self) -> Option<String> {
+        match (&self.extractor_key, &self.id) {
+            (Some(extractor), Some(id)) => Some(format!("{} {}", extractor.to_lowercase(), id)),
+            _ => None,
+        }
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.is_live.unwrap_or(false) || self.live_status.as_deref() == Some("is_live")
+    }
+
+    /// When a stream went live: the actual start once it's live or over, the planned one before
+    pub fn live_started_at(&self) -> Option<i64> {
+        let started = self.is_live()
+            || self.was_live.unwrap_or(false)
+            || matches!(self.live_status.as_deref(), Some("was_live" | "post_live"));
+        if started {
+            self.timestamp.or(self.release_timestamp)
+        } else {
+            self.release_timestamp
+        }
+    }
+
+    pub fn chapters(&self) -> Vec<Chapter> {
+        self.chapters
+            .iter()
+            .flatten()
+            .filter_map(|chapter| {
+                Some(Chapter {
+                    title: chapter.title.clone().unwrap_or_else(|| "Untitled".to_string()),
+                    start_time: chapter.start_time?,
+                    end_time: chapter.end_time.unwrap_or(0.0),
+                })
+            })
+            .collect()
+    }
+
+    pub fn metadata(&self) -> VideoMetadata {
+        VideoMetadata {
+            uploader: self.uploader.clone().or_else(|| self.channel.clone()),
+            upload_date: known(&self.upload_date),
+            view_count: self.view_count,
+            like_count: self.like_count,
+            description: known(&self.description),
+            live_status: known(&self.live_status),
+            counts_fetched_at: None,
+            refreshing: false,
+        }
+    }
+}
+
+impl FormatJson {
+    /// Convert to the app's format type, `None` without a format id
+    pub fn to_format_info(&self) -> Option<FormatInfo> {
+        let resolution = match (self.width, self.height) {
+            (Some(w), Some(h)) => Some(format!("{}x{}", w, h)),
+            _ => self.resolution.clone(),
+        };
+        // Audio-only when the video codec is explicitly "none", or only audio is known
+        let is_audio_only =
+            self.vcodec.as_deref() == Some("none") || (self.vcodec.is_none() && self.acodec.is_some());
+
+        Some(FormatInfo {
+            format_id: self.format_id.clone()?,
+            ext: self.ext.clone().unwrap_or_else(|| "unknown".to_string()),
+            resolution,
+            fps: self.fps,
+            vcodec: self.vcodec.clone(),
+            acodec: self.acodec.clone(),
+            filesize: self.filesize.or(self.filesize_approx.map(|size| size as u64)),
+            // yt-dlp's quality is a number ranking formats, only a string is worth showing
+            quality: self.quality.as_ref().and_then(|q| q.as_str()).map(String::from),
+            is_audio_only,
+            dynamic_range: self.dynamic_range.clone(),
+            abr: self.abr,
+            protocol: self.protocol.clone(),
+        })
+    }
+}
+
+impl PlaylistEntry {
+    /// The entry's video page, falling back to the raw URL of flat entries
+    pub fn url(&self) -> Option<&str> {
+        self.webpage_url.as_deref().or(self.url.as_deref())
+    }
+
+    pub fn title(&self) -> String {
+        self.title.clone().unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    pub fn duration(&self) -> Option<String> {
+        known(&self.duration_string)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_video_info() {
+        let info = VideoInfo::parse(
+            r#"{"id": "abc", "title": "Talk", "extractor_key": "Youtube", "duration_string": "NA",
+                "channel": "Conf", "upload_date": "20260102", "like_count": null, "view_count": 42.0,
+                "formats": [{"format_id": "18", "ext": "mp4", "height": 360.0, "filesize": null, "filesize_approx": 1234.5, "quality": 3},
+                            {"ext": "mp4"}],
+                "chapters": [{"title": "Intro", "start_time": 0, "end_time": 60.5}, {"title": "No start"}],
+                "live_status": "is_live", "timestamp": 1767351600, "release_timestamp": 1767350700}"#,
+        )
+        .unwrap();
+        assert_eq!(info.title(), "Talk");
+        assert_eq!(info.duration(), None);
+        assert_eq!(info.archive_key().as_deref(), Some("youtube abc"));
+        assert!(info.is_live());
+        assert_eq!(info.live_started_at(), Some(1767351600));
+        assert_eq!(info.chapters().len(), 1);
+        assert_eq!(
+            info.metadata(),
+            VideoMetadata {
+                uploader: Some("Conf".to_string()),
+                upload_date: Some("20260102".to_string()),
+                view_count: Some(42),
+                like_count: None,
+                live_status: Some("is_live".to_string()),
+                ..Default::default()
+            }
+        );
+
+        let formats: Vec<FormatInfo> = info.formats.iter().filter_map(FormatJson::to_format_info).collect();
+        assert_eq!(formats.len(), 1);
+        assert_eq!(formats[0].filesize, Some(1234));
+        assert_eq!(formats[0].quality, None);
+
+        let upcoming = VideoInfo::parse(r#"{"live_status": "is_upcoming", "timestamp": 1, "release_timestamp": 2}"#).unwrap();
+        assert_eq!(upcoming.live_started_at(), Some(2));
+
+        let playlist = VideoInfo::parse(r#"{"title": "List", "entries": [null, {"url": "https://example.com/1"}]}"#).unwrap();
+        let entries: Vec<&PlaylistEntry> = playlist.entries.iter().flatten().flatten().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].url(), Some("https://example.com/1"));
+    }
+}
@@ -1,6 +1,22 @@
+pub mod album;
+pub mod availability;
+pub mod bench;
+pub mod comments;
+pub mod dedupe;
+pub mod disk;
+pub mod engagement;
+pub mod extractor;
+pub mod verify;
 pub mod yt_dlp;
 pub mod fast_fetch;
+pub mod info_json;
+pub mod network;
+pub mod portal;
+pub mod postprocess;
 pub mod parallel_processor;
+pub mod probe;
+pub mod reddit;
+pub mod troubleshoot;
 
 pub use yt_dlp::*;
 pub use fast_fetch::*;
@@ -50,12 +66,40 @@ impl DownloadController {
             DownloadAction::CancelDownload(id) => {
                 self.cancel_download(id).await;
             }
-            DownloadAction::RemoveItem(id) => {
-                self.remove_item(id).await;
-            }
             DownloadAction::FetchFormats(id) => {
                 self.fetch_formats(id).await;
             }
+            DownloadAction::SplitAlbum { .. }
+            | DownloadAction::DetectSilence(_)
+            | DownloadAction::ImportArchive(_)
+            | DownloadAction::ExportArchive(_)
+            | DownloadAction::RunHook { .. }
+            | DownloadAction::HashFile(_)
+            | DownloadAction::VerifyFile(_)
+            | DownloadAction::RefreshEngagement { .. }
+            | DownloadAction::RefreshMetadata(_)
+            | DownloadAction::CheckAvailability(_)
+            | DownloadAction::WaybackLookup { .. }
+            | DownloadAction::ArchiveComments(_)
+            | DownloadAction::RunStage { .. }
+            | DownloadAction::SkipStage { .. }
+            | DownloadAction::MeasureDisk(_)
+            | DownloadAction::RestartDownload(_)
+            | DownloadAction::ProbeQuality(_)
+            | DownloadAction::DetectAddresses
+            | DownloadAction::RetryExtraction { .. }
+            | DownloadAction::PickPath(_)
+            | DownloadAction::Notify { .. }
+            | DownloadAction::AddRemote(_)
+            | DownloadAction::PauseAll
+            | DownloadAction::ResumeAll
+            | DownloadAction::CheckNetwork
+            | DownloadAction::StopAfterFragment(_)
+            | DownloadAction::FinishRecording(_)
+            | DownloadAction::MeasureRecordings
+            | DownloadAction::ExportBundle(_)
+            | DownloadAction::ExportBugReport { .. }
+            | DownloadAction::ImportBundle(_) => {}
         }
     }
 
@@ -69,12 +113,17 @@ impl DownloadController {
         let app_tx = self.app_tx.clone();
         let fetch_task = tokio::spawn(async move {
             match yt_dlp::fetch_formats(&url).await {
-                Ok((formats, title, duration)) => {
+                Ok(yt_dlp::FetchedFormats { formats, title, duration, chapters, archive_key, is_live, live_started_at, metadata }) => {
                     let _ = app_tx.send(AppEvent::FormatsFetched {
                         id,
                         formats,
                         title,
                         duration,
+                        chapters,
+                        archive_key,
+                        is_live,
+                        live_started_at,
+                        metadata,
                     });
                 }
                 Err(e) => {
@@ -120,13 +169,6 @@ impl DownloadController {
         });
     }
 
-    async fn remove_item(&mut self, id: Uuid) {
-        // Cancel any running task for this item
-        if let Some(handle) = self.tasks.remove(&id) {
-            handle.abort();
-        }
-    }
-
     async fn fetch_formats(&mut self, id: Uuid) {
         // This would need the URL from the item
         // For now, this is a placeholder
@@ -0,0 +1,229 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use tokio::process::Command;
+
+/// Network condition that must hold for downloads to run (a VPN kill switch).
+/// Every condition that is set must pass.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkGuard {
+    /// This interface must be up with an address, e.g. "tun0" or "wg0"
+    pub interface: Option<String>,
+    /// The public IP must fall in one of these CIDR ranges, e.g. "185.65.134.0/24"
+    pub public_ip_ranges: Vec<String>,
+    /// Service answering with the public IP as plain text
+    pub ip_check_url: String,
+    /// Seconds between checks
+    pub interval_secs: u64,
+}
+
+impl Default for NetworkGuard {
+    fn default() -> Self {
+        Self {
+            interface: None,
+            public_ip_ranges: Vec::new(),
+            ip_check_url: "https://api.ipify.org".to_string(),
+            interval_secs: 30,
+        }
+    }
+}
+
+impl NetworkGuard {
+    /// Check the condition, the error says what doesn't hold
+    pub async fn check(&self) -> Result<()> {
+        if let Some(interface) = &self.interface {
+            let addresses = local_addresses().await?;
+            if !addresses.iter().any(|a| &a.interface == interface) {
+                return Err(anyhow!("interface {} is down", interface));
+            }
+        }
+
+        if !self.public_ip_ranges.is_empty() {
+            let response = reqwest::Client::new()
+                .get(&self.ip_check_url)
+                .timeout(std::time::Duration::from_secs(10))
+                .send()
+                .await
+                .map_err(|e| anyhow!("could not get the public IP: {}", e))?;
+            let text = response.text().await?;
+            let ip: IpAddr = text
+                .trim()
+                .parse()
+                .map_err(|_| anyhow!("{} did not answer with an IP address", self.ip_check_url))?;
+            if !self.public_ip_ranges.iter().any(|range| in_range(ip, range)) {
+                return Err(anyhow!("public IP {} is outside the allowed ranges", ip));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Whether `ip` is inside a CIDR range ("10.0.0.0/8", or a single address without a prefix)
+fn in_range(ip: IpAddr, range: &str) -> bool {
+    let (network, prefix) = match range.split_once('/') {
+        Some((network, prefix)) => (network, prefix.parse::<u32>().ok()),
+        None => (range, None),
+    };
+    match (ip, network.trim().parse::<IpAddr>()) {
+        (IpAddr::V4(ip), Ok(IpAddr::V4(network))) => {
+            let prefix = prefix.unwrap_or(32).min(32);
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(network) & mask
+        }
+        (IpAddr::V6(ip), Ok(IpAddr::V6(network))) => {
+            let prefix = prefix.unwrap_or(128).min(128);
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(network) & mask
+        }
+        _ => false,
+    }
+}
+
+/// An address of a local network interface, usable with yt-dlp's --source-address
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalAddress {
+    pub interface: String,
+    pub address: IpAddr,
+}
+
+/// List local addresses with the platform's own tool (`ip`, `ifconfig` or `ipconfig`),
+/// leaving out loopback and link-local ones, which can't reach the internet
+pub async fn local_addresses() -> Result<Vec<LocalAddress>> {
+    let addresses = if cfg!(windows) {
+        parse_ipconfig(&run("ipconfig", &[]).await?)
+    } else {
+        match run("ip", &["-o", "addr", "show"]).await {
+            Ok(output) => parse_ip_addr(&output),
+            Err(_) => parse_ifconfig(&run("ifconfig", &[]).await?),
+        }
+    };
+    Ok(addresses.into_iter().filter(|a| is_routable(&a.address)).collect())
+}
+
+async fn run(program: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| anyhow!("could not run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow!("{} failed", program));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn is_routable(address: &IpAddr) -> bool {
+    match address {
+        IpAddr::V4(v4) => !v4.is_loopback() && !v4.is_link_local(),
+        // fe80::/10
+        IpAddr::V6(v6) => !v6.is_loopback() && (v6.segments()[0] & 0xffc0) != 0xfe80,
+    }
+}
+
+/// `ip -o addr show`: "2: eth0    inet 192.168.1.5/24 brd ... scope global eth0"
+fn parse_ip_addr(output: &str) -> Vec<LocalAddress> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            let interface = fields.next()?;
+            let _family = fields.next().filter(|f| *f == "inet" || *f == "inet6")?;
+            let address = fields.next()?.split('/').next()?.parse().ok()?;
+            Some(LocalAddress {
+                interface: interface.to_string(),
+                address,
+            })
+        })
+        .collect()
+}
+
+/// BSD/macOS `ifconfig`: an unindented "en0: flags=..." line per interface, then indented
+/// "inet 192.168.1.5 netmask ..." lines
+fn parse_ifconfig(output: &str) -> Vec<LocalAddress> {
+    let mut interface = String::new();
+    let mut addresses = Vec::new();
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) {
+            interface = line.split(':').next().unwrap_or_default().to_string();
+            continue;
+        }
+        let mut fields = line.split_whitespace();
+        if let (Some("inet" | "inet6"), Some(address)) = (fields.next(), fields.next()) {
+            // Drop "addr:" (old Linux ifconfig) and "%en0" scope suffixes
+            let address = address.trim_start_matches("addr:").split('%').next().unwrap_or_default();
+            if let Ok(address) = address.parse() {
+                addresses.push(LocalAddress {
+                    interface: interface.clone(),
+                    address,
+                });
+            }
+        }
+    }
+    addresses
+}
+
+/// Windows `ipconfig`: "Ethernet adapter Ethernet:" headers, then
+/// "   IPv4 Address. . . . : 192.168.1.5(Preferred)" lines
+fn parse_ipconfig(output: &str) -> Vec<LocalAddress> {
+    let mut interface = String::new();
+    let mut addresses = Vec::new();
+    for line in output.lines() {
+        if !line.starts_with(char::is_whitespace) && line.trim_end().ends_with(':') {
+            interface = line.trim_end().trim_end_matches(':').to_string();
+            continue;
+        }
+        let line = line.trim();
+        if !(line.starts_with("IPv4 Address") || line.starts_with("IPv6 Address")) {
+            continue;
+        }
+        let Some((_, value)) = line.split_once(" : ") else {
+            continue;
+        };
+        let address = value.trim().trim_end_matches("(Preferred)").split('%').next().unwrap_or_default();
+        if let Ok(address) = address.parse() {
+            addresses.push(LocalAddress {
+                interface: interface.clone(),
+                address,
+            });
+        }
+    }
+    addresses
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_addresses() {
+        let ip = "1: lo    inet 127.0.0.1/8 scope host lo\\       valid_lft forever\n\
+                  2: eth0    inet 192.168.1.5/24 brd 192.168.1.255 scope global eth0\n\
+                  5: tun0    inet 10.8.0.2/24 scope global tun0\n\
+                  2: eth0    inet6 fe80::1/64 scope link";
+        let found: Vec<String> = parse_ip_addr(ip)
+            .into_iter()
+            .filter(|a| is_routable(&a.address))
+            .map(|a| format!("{} {}", a.interface, a.address))
+            .collect();
+        assert_eq!(found, ["eth0 192.168.1.5", "tun0 10.8.0.2"]);
+
+        let ifconfig = "en0: flags=8863<UP> mtu 1500\n\
+                        \tinet6 fe80::1%en0 prefixlen 64\n\
+                        \tinet 192.168.1.7 netmask 0xffffff00\n\
+                        utun3: flags=8051<UP> mtu 1380\n\
+                        \tinet 10.2.0.2 --> 10.2.0.1 netmask 0xff000000";
+        let found: Vec<String> = parse_ifconfig(ifconfig).iter().map(|a| format!("{} {}", a.interface, a.address)).collect();
+        assert_eq!(found, ["en0 fe80::1", "en0 192.168.1.7", "utun3 10.2.0.2"]);
+
+        let ip = |s: &str| s.parse::<IpAddr>().unwrap();
+        assert!(in_range(ip("185.65.134.7"), "185.65.134.0/24"));
+        assert!(!in_range(ip("185.65.135.7"), "185.65.134.0/24"));
+        assert!(in_range(ip("8.8.8.8"), "0.0.0.0/0"));
+        assert!(in_range(ip("2a03:1b20::5"), "2a03:1b20::/32"));
+        assert!(!in_range(ip("10.0.0.1"), "2a03:1b20::/32"));
+
+        let ipconfig = "Ethernet adapter Ethernet:\r\n\r\n   IPv4 Address. . . . . . . . . . . : 192.168.1.9(Preferred)\r\n";
+        assert_eq!(parse_ipconfig(ipconfig)[0].address.to_string(), "192.168.1.9");
+    }
+}
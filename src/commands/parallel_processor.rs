@@ -41,12 +41,17 @@ impl ParallelPlaylistProcessor {
                 
                 // Fetch formats for this video
                 match yt_dlp::fetch_formats(&url).await {
-                    Ok((formats, title, duration)) => {
+                    Ok(yt_dlp::FetchedFormats { formats, title, duration, chapters, archive_key, is_live, live_started_at, metadata }) => {
                         let _ = app_tx_clone.send(AppEvent::FormatsFetched {
                             id,
                             formats,
                             title,
                             duration,
+                            chapters,
+                            archive_key,
+                            is_live,
+                            live_started_at,
+                            metadata,
                         }).await;
                     }
                     Err(e) => {
@@ -78,12 +83,17 @@ impl ParallelPlaylistProcessor {
             // Try to acquire permit, but don't wait if busy
             if let Ok(_permit) = semaphore.try_acquire() {
                 // Silently fetch formats in background
-                if let Ok((formats, title, duration)) = yt_dlp::fetch_formats(&url).await {
+                if let Ok(yt_dlp::FetchedFormats { formats, title, duration, chapters, archive_key, is_live, live_started_at, metadata }) = yt_dlp::fetch_formats(&url).await {
                     let _ = app_tx.send(AppEvent::FormatsFetched {
                         id,
                         formats,
                         title,
                         duration,
+                        chapters,
+                        archive_key,
+                        is_live,
+                        live_started_at,
+                        metadata,
                     }).await;
                 }
             }
@@ -0,0 +1,48 @@
+use anyhow::{Result, anyhow};
+
+/// What the desktop file chooser should let the user pick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickKind {
+    Directory,
+    File,
+}
+
+/// Ask the desktop's file chooser (the XDG desktop portal) for a path, starting in `start_in`.
+/// `Ok(None)` means the user closed the dialog. An error means there is no usable portal,
+/// so the path has to be typed instead.
+#[cfg(target_os = "linux")]
+pub async fn pick_path(title: &str, kind: PickKind, start_in: Option<&str>) -> Result<Option<String>> {
+    use ashpd::desktop::file_chooser::SelectedFiles;
+
+    // Over SSH or on a bare console there is no session bus to find a portal on
+    let has_session_bus = std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some()
+        || std::env::var_os("XDG_RUNTIME_DIR").is_some_and(|dir| std::path::Path::new(&dir).join("bus").exists());
+    if !has_session_bus {
+        return Err(anyhow!("no D-Bus session"));
+    }
+
+    let mut request = SelectedFiles::open_file()
+        .title(title)
+        .modal(true)
+        .directory(kind == PickKind::Directory);
+    // The portal rejects folders that don't exist, start in its default then
+    if let Some(dir) = start_in.filter(|dir| std::path::Path::new(dir).is_dir()) {
+        request = request.current_folder(dir)?;
+    }
+    let response = request.send().await.map_err(|e| anyhow!("desktop portal unavailable: {}", e))?;
+    let files = match response.response() {
+        Ok(files) => files,
+        Err(ashpd::Error::Response(_)) => return Ok(None),
+        Err(e) => return Err(anyhow!("file chooser failed: {}", e)),
+    };
+    let uri = files.uris().first().ok_or_else(|| anyhow!("file chooser returned nothing"))?;
+    let path = uri
+        .to_file_path()
+        .map_err(|_| anyhow!("file chooser returned a non-local location: {}", uri))?;
+    Ok(Some(path.to_string_lossy().into_owned()))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn pick_path(_title: &str, _kind: PickKind, _start_in: Option<&str>) -> Result<Option<String>> {
+    Err(anyhow!("desktop portals are only available on Linux"))
+}
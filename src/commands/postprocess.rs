@@ -0,0 +1,304 @@
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+/// SponsorBlock's public API
+const SPONSORBLOCK_API: &str = "https://sponsor.ajay.app/api/skipSegments";
+
+/// One stage of a profile's post-processing chain, run on the downloaded file in order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "stage", rename_all = "kebab-case")]
+pub enum Stage {
+    /// Cut the segments SponsorBlock users marked, YouTube only
+    Sponsorblock {
+        #[serde(default = "default_categories")]
+        categories: Vec<String>,
+    },
+    /// Keep only the audio, encoded as `codec` (mp3, m4a or opus)
+    ExtractAudio {
+        #[serde(default = "default_codec")]
+        codec: String,
+    },
+    /// Even out the loudness (EBU R128)
+    Normalize,
+    /// Write title, artist and date tags from the metadata
+    Tag,
+    /// Move the file into a folder
+    Move { to: String },
+}
+
+fn default_categories() -> Vec<String> {
+    vec!["sponsor".to_string()]
+}
+
+fn default_codec() -> String {
+    "mp3".to_string()
+}
+
+impl Stage {
+    pub fn label(&self) -> String {
+        match self {
+            Stage::Sponsorblock { .. } => "sponsorblock".to_string(),
+            Stage::ExtractAudio { codec } => format!("extract audio ({})", codec),
+            Stage::Normalize => "normalize".to_string(),
+            Stage::Tag => "tag".to_string(),
+            Stage::Move { to } => format!("move to {}", to),
+        }
+    }
+}
+
+/// How far a stage of an item's chain got
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StageStatus {
+    Waiting,
+    Running,
+    Done,
+    /// Nothing to do for this file, e.g. no sponsor segments
+    Skipped(String),
+    Failed(String),
+}
+
+/// A stage of an item's chain and its status
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageRun {
+    pub stage: Stage,
+    pub status: StageStatus,
+}
+
+impl StageRun {
+    pub fn new(stage: Stage) -> Self {
+        Self { stage, status: StageStatus::Waiting }
+    }
+}
+
+/// What a stage needs to know about the download
+#[derive(Debug, Clone)]
+pub struct StageInput {
+    pub path: PathBuf,
+    pub url: String,
+    pub title: Option<String>,
+    pub uploader: Option<String>,
+    pub upload_date: Option<String>,
+}
+
+/// What a stage left behind
+#[derive(Debug, Clone, PartialEq)]
+pub enum StageOutput {
+    /// The file to carry on with, moved or converted or changed in place
+    File(PathBuf),
+    Skipped(String),
+}
+
+/// Run one stage on the file
+pub async fn run(stage: &Stage, input: &StageInput) -> Result<StageOutput> {
+    let path = &input.path;
+    match stage {
+        Stage::Sponsorblock { categories } => {
+            let Some(video_id) = crate::archive::archive_key_for_url(&input.url)
+                .and_then(|key| key.strip_prefix("youtube ").map(str::to_string))
+            else {
+                return Ok(StageOutput::Skipped("not a YouTube video".to_string()));
+            };
+            let segments = sponsor_segments(&video_id, categories).await?;
+            if segments.is_empty() {
+                return Ok(StageOutput::Skipped("no segments marked".to_string()));
+            }
+            // The cut joins the audio, a video-only file has none to join
+            if !has_stream(path, "a").await {
+                return Ok(StageOutput::Skipped("no audio stream".to_string()));
+            }
+            let video = has_stream(path, "v").await;
+            let temp = temp_next_to(path, extension(path));
+            let mut args = vec![os("-i"), path.into(), os("-filter_complex"), cut_filter(&kept_ranges(segments), video).into()];
+            if video {
+                args.extend([os("-map"), os("[v]")]);
+            }
+            args.extend([os("-map"), os("[a]"), temp.clone().into()]);
+            ffmpeg(args).await?;
+            std::fs::rename(&temp, path)?;
+            Ok(StageOutput::File(path.clone()))
+        }
+        Stage::ExtractAudio { codec } => {
+            let encoder: &[&str] = match codec.as_str() {
+                "mp3" => &["-c:a", "libmp3lame", "-q:a", "2"],
+                "m4a" => &["-c:a", "aac", "-b:a", "192k"],
+                "opus" => &["-c:a", "libopus", "-b:a", "160k"],
+                other => bail!("can't extract audio as {}, use mp3, m4a or opus", other),
+            };
+            let target = path.with_extension(codec);
+            let temp = temp_next_to(path, codec);
+            ffmpeg([os("-i"), path.into(), os("-vn")].into_iter().chain(encoder.iter().copied().map(os)).chain([temp.clone().into()]))
+                .await?;
+            std::fs::rename(&temp, &target)?;
+            if target != *path {
+                std::fs::remove_file(path)?;
+            }
+            Ok(StageOutput::File(target))
+        }
+        Stage::Normalize => {
+            let temp = temp_next_to(path, extension(path));
+            ffmpeg([os("-i"), path.into(), os("-af"), os("loudnorm=I=-16:TP=-1.5:LRA=11"), os("-c:v"), os("copy"), temp.clone().into()])
+                .await?;
+            std::fs::rename(&temp, path)?;
+            Ok(StageOutput::File(path.clone()))
+        }
+        Stage::Tag => {
+            let tags = [
+                ("title", input.title.clone()),
+                ("artist", input.uploader.clone()),
+                ("date", input.upload_date.as_deref().map(tag_date)),
+            ];
+            let temp = temp_next_to(path, extension(path));
+            let mut args = vec![os("-i"), path.into(), os("-map"), os("0"), os("-c"), os("copy")];
+            for (key, value) in tags {
+                if let Some(value) = value {
+                    args.extend([os("-metadata"), os(&format!("{}={}", key, value))]);
+                }
+            }
+            args.push(temp.clone().into());
+            ffmpeg(args).await?;
+            std::fs::rename(&temp, path)?;
+            Ok(StageOutput::File(path.clone()))
+        }
+        Stage::Move { to } => {
+            let folder = match to.strip_prefix("~/") {
+                Some(rest) => crate::config::home_dir().ok_or_else(|| anyhow!("no home folder"))?.join(rest),
+                None => PathBuf::from(to),
+            };
+            std::fs::create_dir_all(&folder)?;
+            let target = folder.join(path.file_name().ok_or_else(|| anyhow!("no file name"))?);
+            // A rename can't cross file systems, copy then
+            if std::fs::rename(path, &target).is_err() {
+                std::fs::copy(path, &target)?;
+                std::fs::remove_file(path)?;
+            }
+            Ok(StageOutput::File(target))
+        }
+    }
+}
+
+fn os(arg: &str) -> OsString {
+    OsString::from(arg)
+}
+
+fn extension(path: &Path) -> &str {
+    path.extension().and_then(|e| e.to_str()).unwrap_or("mkv")
+}
+
+/// A hidden file next to `path` for ffmpeg to write, renamed over the original when done
+fn temp_next_to(path: &Path, ext: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.gorlock-tmp.{}", stem, ext))
+}
+
+/// "20240131" as "2024-01-31", other dates as they are
+fn tag_date(date: &str) -> String {
+    match (date.len(), date.chars().all(|c| c.is_ascii_digit())) {
+        (8, true) => format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..]),
+        _ => date.to_string(),
+    }
+}
+
+async fn ffmpeg(args: impl IntoIterator<Item = OsString>) -> Result<()> {
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| anyhow!("could not run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        bail!("ffmpeg failed: {}", String::from_utf8_lossy(&output.stderr).lines().last().unwrap_or_default());
+    }
+    Ok(())
+}
+
+/// Whether the file has a stream of a kind, "v" for video or "a" for audio
+async fn has_stream(path: &Path, kind: &str) -> bool {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", kind, "-show_entries", "stream=index", "-of", "csv=p=0"])
+        .arg(path)
+        .output()
+        .await;
+    output.is_ok_and(|o| !String::from_utf8_lossy(&o.stdout).trim().is_empty())
+}
+
+#[derive(Deserialize)]
+struct Segment {
+    segment: (f64, f64),
+}
+
+/// Segments of the categories marked on a video, in seconds
+async fn sponsor_segments(video_id: &str, categories: &[String]) -> Result<Vec<(f64, f64)>> {
+    let response = reqwest::Client::new()
+        .get(SPONSORBLOCK_API)
+        .query(&[("videoID", video_id), ("categories", &serde_json::to_string(categories)?)])
+        .send()
+        .await?;
+    // SponsorBlock answers 404 for a video without segments
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(Vec::new());
+    }
+    let body = response.error_for_status()?.text().await?;
+    let segments: Vec<Segment> = serde_json::from_str(&body)?;
+    Ok(segments.into_iter().map(|s| s.segment).collect())
+}
+
+/// The parts of the file to keep around the cut segments, the last one running to the end
+fn kept_ranges(mut segments: Vec<(f64, f64)>) -> Vec<(f64, Option<f64>)> {
+    segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut kept = Vec::new();
+    let mut from = 0.0;
+    for (start, end) in segments {
+        if start > from {
+            kept.push((from, Some(start)));
+        }
+        from = f64::max(from, end);
+    }
+    kept.push((from, None));
+    kept
+}
+
+/// An ffmpeg filter joining the kept ranges into `[v]` and `[a]`
+fn cut_filter(ranges: &[(f64, Option<f64>)], video: bool) -> String {
+    let mut filter = String::new();
+    let mut inputs = String::new();
+    for (i, (start, end)) in ranges.iter().enumerate() {
+        let end = end.map(|e| format!(":end={:.3}", e)).unwrap_or_default();
+        if video {
+            filter.push_str(&format!("[0:v]trim=start={:.3}{},setpts=PTS-STARTPTS[v{}];", start, end, i));
+            inputs.push_str(&format!("[v{}]", i));
+        }
+        filter.push_str(&format!("[0:a]atrim=start={:.3}{},asetpts=PTS-STARTPTS[a{}];", start, end, i));
+        inputs.push_str(&format!("[a{}]", i));
+    }
+    let outputs = if video { "[v][a]" } else { "[a]" };
+    format!("{}{}concat=n={}:v={}:a=1{}", filter, inputs, ranges.len(), u8::from(video), outputs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stages() {
+        let chain: Vec<Stage> = serde_json::from_str(
+            r#"[{"stage": "sponsorblock"}, {"stage": "extract-audio"}, {"stage": "normalize"}, {"stage": "move", "to": "~/Music"}]"#,
+        )
+        .unwrap();
+        assert_eq!(chain[0], Stage::Sponsorblock { categories: vec!["sponsor".to_string()] });
+        assert_eq!(chain[1].label(), "extract audio (mp3)");
+        assert_eq!(chain[3].label(), "move to ~/Music");
+
+        // Overlapping segments merge, the start and the end are kept
+        let kept = kept_ranges(vec![(60.0, 90.0), (10.0, 20.0), (80.0, 100.0)]);
+        assert_eq!(kept, [(0.0, Some(10.0)), (20.0, Some(60.0)), (100.0, None)]);
+        assert_eq!(
+            cut_filter(&kept[..2], false),
+            "[0:a]atrim=start=0.000:end=10.000,asetpts=PTS-STARTPTS[a0];\
+             [0:a]atrim=start=20.000:end=60.000,asetpts=PTS-STARTPTS[a1];[a0][a1]concat=n=2:v=0:a=1[a]"
+        );
+        assert_eq!(tag_date("20240131"), "2024-01-31");
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+/// What ffprobe found in a completed download
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QualityReport {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub audio_channels: Option<u32>,
+    /// Overall bitrate in bit/s
+    pub bitrate: Option<u64>,
+    /// Seconds
+    pub duration: Option<f64>,
+    /// Differences from what was requested, empty when the file is as expected
+    pub mismatches: Vec<String>,
+}
+
+/// What the download was supposed to be, from the chosen format and source metadata
+#[derive(Debug, Clone, Default)]
+pub struct Expected {
+    /// (width, height) of the chosen format
+    pub resolution: Option<(u32, u32)>,
+    pub audio_only: bool,
+    /// Seconds, `None` for live recordings and unknown durations
+    pub duration: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ProbeJson {
+    streams: Vec<StreamJson>,
+    format: FormatJson,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct StreamJson {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    channels: Option<u32>,
+}
+
+/// ffprobe prints numbers in the format section as strings
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct FormatJson {
+    duration: Option<String>,
+    bit_rate: Option<String>,
+}
+
+/// Run ffprobe over a file and compare it with what was requested
+pub async fn probe_quality(path: &str, expected: &Expected) -> Result<QualityReport> {
+    let output = Command::new("ffprobe")
+        .args([
+            "-v",
+            "error",
+            "-show_entries",
+            "stream=codec_type,codec_name,width,height,channels:format=duration,bit_rate",
+            "-of",
+            "json",
+            path,
+        ])
+        .output()
+        .await
+        .map_err(|e| anyhow!("could not run ffprobe: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow!("ffprobe failed: {}", stderr.trim()));
+    }
+    parse_report(&output.stdout, expected).map_err(|e| anyhow!("unreadable ffprobe output: {}", e))
+}
+
+fn parse_report(json: &[u8], expected: &Expected) -> serde_json::Result<QualityReport> {
+    let probe: ProbeJson = serde_json::from_slice(json)?;
+    let stream = |kind: &str| probe.streams.iter().find(|s| s.codec_type.as_deref() == Some(kind));
+    let video = stream("video");
+    let audio = stream("audio");
+
+    let mut report = QualityReport {
+        width: video.and_then(|v| v.width),
+        height: video.and_then(|v| v.height),
+        video_codec: video.and_then(|v| v.codec_name.clone()),
+        audio_codec: audio.and_then(|a| a.codec_name.clone()),
+        audio_channels: audio.and_then(|a| a.channels),
+        bitrate: probe.format.bit_rate.and_then(|b| b.parse().ok()),
+        duration: probe.format.duration.and_then(|d| d.parse().ok()),
+        mismatches: Vec::new(),
+    };
+    report.mismatches = report.compare(expected);
+    Ok(report)
+}
+
+impl QualityReport {
+    fn compare(&self, expected: &Expected) -> Vec<String> {
+        let mut mismatches = Vec::new();
+
+        if self.audio_codec.is_none() {
+            mismatches.push("no audio track".to_string());
+        }
+        if !expected.audio_only && self.video_codec.is_none() {
+            mismatches.push("no video track".to_string());
+        }
+
+        // Compare the short side so portrait videos aren't flagged
+        if let (Some((want_w, want_h)), Some(w), Some(h)) = (expected.resolution, self.width, self.height) {
+            if w.min(h) * 10 < want_w.min(want_h) * 9 {
+                mismatches.push(format!("got {}x{}, requested {}x{}", w, h, want_w, want_h));
+            }
+        }
+
+        // A couple of seconds off is normal rounding, more means a truncated file
+        if let (Some(want), Some(got)) = (expected.duration, self.duration) {
+            if (want - got).abs() > (want * 0.01).max(2.0) {
+                mismatches.push(format!("duration {:.0}s, expected {:.0}s", got, want));
+            }
+        }
+        mismatches
+    }
+}
+
+/// "1920x1080" -> (1920, 1080)
+pub fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let (w, h) = resolution.split_once('x')?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_report() {
+        let json = br#"{"streams": [{"codec_type": "video", "codec_name": "h264", "width": 640, "height": 360},
+                                     {"codec_type": "audio", "codec_name": "aac", "channels": 2}],
+                        "format": {"duration": "590.2", "bit_rate": "800000"}}"#;
+        let expected = Expected {
+            resolution: parse_resolution("1920x1080"),
+            audio_only: false,
+            duration: Some(600.0),
+        };
+        let report = parse_report(json, &expected).unwrap();
+        assert_eq!(report.audio_channels, Some(2));
+        assert_eq!(report.bitrate, Some(800_000));
+        assert_eq!(
+            report.mismatches,
+            ["got 640x360, requested 1920x1080", "duration 590s, expected 600s"]
+        );
+
+        let fine = Expected {
+            resolution: Some((640, 360)),
+            audio_only: false,
+            duration: Some(589.0),
+        };
+        assert!(parse_report(json, &fine).unwrap().mismatches.is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::{Result, anyhow};
+use serde_json::Value;
+use std::time::Duration;
+
+use crate::app_state::format_timestamp;
+use crate::description::extract_urls;
+
+/// Hosts whose links are worth downloading, subdomains included
+const MEDIA_HOSTS: &[&str] = &[
+    "v.redd.it",
+    "i.redd.it",
+    "imgur.com",
+    "gfycat.com",
+    "redgifs.com",
+    "streamable.com",
+    "youtube.com",
+    "youtu.be",
+    "vimeo.com",
+];
+
+/// Posts a subreddit listing asks for
+const LISTING_LIMIT: u32 = 100;
+
+/// A playlist preview entry: (url, title, duration)
+type Entry = (String, String, Option<String>);
+
+/// Whether the URL is a reddit post or subreddit
+pub fn is_reddit(url: &str) -> bool {
+    let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(str::to_lowercase)) else {
+        return false;
+    };
+    host == "redd.it" || host == "reddit.com" || host.ends_with(".reddit.com")
+}
+
+/// Reddit's JSON for a post or subreddit page, with text left unescaped
+fn json_url(url: &str) -> Result<String> {
+    let parsed = reqwest::Url::parse(url)?;
+    let path = parsed.path().trim_end_matches('/');
+    // redd.it/<id> short links name the post only
+    let path = if parsed.host_str().is_some_and(|host| host.eq_ignore_ascii_case("redd.it")) {
+        format!("/comments{}", path)
+    } else {
+        path.to_string()
+    };
+    if !path.starts_with("/r/") && !path.starts_with("/comments/") && !path.starts_with("/user/") {
+        return Err(anyhow!("not a reddit post or subreddit: {}", url));
+    }
+    Ok(format!("https://www.reddit.com{}/.json?raw_json=1&limit={}", path, LISTING_LIMIT))
+}
+
+/// Videos and images linked from a reddit post or subreddit, as playlist preview entries.
+/// `comments` also takes media links from a post's comments.
+pub async fn media_links(url: &str, comments: bool) -> Result<Vec<Entry>> {
+    let body = reqwest::Client::new()
+        .get(json_url(url)?)
+        // Reddit turns away clients without a user agent of their own
+        .header(reqwest::header::USER_AGENT, concat!("gorlock/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(20))
+        .send()
+        .await?
+        .error_for_status()?
+        .text()
+        .await?;
+    let json: Value = serde_json::from_str(&body)?;
+    Ok(parse_listing(&json, comments))
+}
+
+/// Entries of a post page (an array of the post and its comments) or a subreddit listing
+fn parse_listing(json: &Value, comments: bool) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let (posts, replies) = match json {
+        Value::Array(pages) => (pages.first(), pages.get(1).filter(|_| comments)),
+        listing => (Some(listing), None),
+    };
+    for post in posts.and_then(|p| p["data"]["children"].as_array()).into_iter().flatten() {
+        post_media(&post["data"], &mut entries);
+    }
+    if let Some(replies) = replies {
+        let title = posts.and_then(|p| p["data"]["children"][0]["data"]["title"].as_str()).unwrap_or("comment");
+        comment_links(replies, title, &mut entries);
+    }
+    // A video linked from several posts or comments is listed once
+    let mut unique: Vec<Entry> = Vec::new();
+    for entry in entries {
+        if !unique.iter().any(|(url, _, _)| *url == entry.0) {
+            unique.push(entry);
+        }
+    }
+    unique
+}
+
+/// The media of one post: reddit's own video, a GIF's MP4 rendition or a link to a media host
+fn post_media(post: &Value, entries: &mut Vec<Entry>) {
+    // Crossposts carry their media in the original post
+    let source = post["crosspost_parent_list"].get(0).unwrap_or(post);
+    let title = post["title"].as_str().unwrap_or("reddit post").to_string();
+    let link = source["url_overridden_by_dest"].as_str().or(source["url"].as_str()).unwrap_or_default();
+
+    if source["is_video"] == true {
+        // yt-dlp merges a reddit video's separate audio when given the post
+        let permalink = source["permalink"].as_str().unwrap_or_default();
+        let duration = source["media"]["reddit_video"]["duration"].as_f64().map(format_timestamp);
+        entries.push((format!("https://www.reddit.com{}", permalink), title, duration));
+    } else if let Some(mp4) = source["preview"]["images"][0]["variants"]["mp4"]["source"]["url"].as_str() {
+        entries.push((mp4.to_string(), title, None));
+    } else if is_media_host(link) {
+        entries.push((link.to_string(), title, None));
+    }
+}
+
+/// Media links in the comment tree, depth first
+fn comment_links(listing: &Value, title: &str, entries: &mut Vec<Entry>) {
+    for comment in listing["data"]["children"].as_array().into_iter().flatten() {
+        let data = &comment["data"];
+        let author = data["author"].as_str().unwrap_or("[deleted]");
+        for url in extract_urls(data["body"].as_str().unwrap_or_default()) {
+            if is_media_host(&url) {
+                entries.push((url, format!("{} (comment by u/{})", title, author), None));
+            }
+        }
+        // Leaf comments have "" instead of a replies listing
+        if data["replies"].is_object() {
+            comment_links(&data["replies"], title, entries);
+        }
+    }
+}
+
+/// A link to a video or an image on one of `MEDIA_HOSTS`, yt-dlp downloads direct image links as they are
+fn is_media_host(url: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(url) else {
+        return false;
+    };
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    MEDIA_HOSTS.iter().any(|h| host == *h || host.ends_with(&format!(".{}", h)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reddit_media() {
+        assert!(is_reddit("https://old.reddit.com/r/videos/") && !is_reddit("https://notreddit.com/r/x"));
+        assert_eq!(
+            json_url("https://www.reddit.com/r/videos/comments/abc/some_title/?utm=x").unwrap(),
+            "https://www.reddit.com/r/videos/comments/abc/some_title/.json?raw_json=1&limit=100"
+        );
+        assert_eq!(json_url("https://redd.it/abc").unwrap(), "https://www.reddit.com/comments/abc/.json?raw_json=1&limit=100");
+        assert_eq!(json_url("https://REDD.IT/abc").unwrap(), "https://www.reddit.com/comments/abc/.json?raw_json=1&limit=100");
+        assert!(json_url("https://www.reddit.com/settings").is_err());
+
+        let post = |data: Value| json!({ "kind": "t3", "data": data });
+        let page = json!([
+            { "data": { "children": [post(json!({
+                "title": "Clip", "is_video": true, "permalink": "/r/videos/comments/abc/clip/",
+                "media": { "reddit_video": { "duration": 75 } }
+            }))] } },
+            { "data": { "children": [{ "kind": "t1", "data": {
+                "author": "someone", "body": "source: https://youtu.be/xyz and https://example.com/page",
+                "replies": { "data": { "children": [{ "kind": "t1", "data": {
+                    "author": "other", "body": "mirror https://streamable.com/q1", "replies": ""
+                } }] } }
+            } }] } }
+        ]);
+        let entries = parse_listing(&page, true);
+        assert_eq!(entries[0], ("https://www.reddit.com/r/videos/comments/abc/clip/".to_string(), "Clip".to_string(), Some("1:15".to_string())));
+        assert_eq!(entries[1].0, "https://youtu.be/xyz");
+        assert_eq!(entries[2].1, "Clip (comment by u/other)");
+        assert_eq!(parse_listing(&page, false).len(), 1);
+
+        // A subreddit listing: a GIF's MP4, a link to a media host, a self post and two images
+        let listing = json!({ "data": { "children": [
+            post(json!({ "title": "Gif", "url": "https://i.redd.it/a.gif",
+                "preview": { "images": [{ "variants": { "mp4": { "source": { "url": "https://i.redd.it/a.gif?format=mp4" } } } }] } })),
+            post(json!({ "title": "Imgur", "url": "https://i.imgur.com/b.gifv" })),
+            post(json!({ "title": "Text", "url": "https://www.reddit.com/r/x/comments/c/text/" })),
+            post(json!({ "title": "Photo", "url": "https://i.redd.it/d.jpg" })),
+            post(json!({ "title": "Imgur photo", "url": "https://i.imgur.com/e.png" })),
+        ] } });
+        let titles: Vec<String> = parse_listing(&listing, true).into_iter().map(|(_, title, _)| title).collect();
+        assert_eq!(titles, ["Gif", "Imgur", "Photo", "Imgur photo"]);
+    }
+}
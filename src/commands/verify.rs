@@ -0,0 +1,130 @@
+use anyhow::Result;
+use std::io::Read;
+use std::path::Path;
+
+/// File types recognisable from their first bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FileKind {
+    /// ISO base media (mp4, m4a, mov, 3gp)
+    Mp4,
+    /// Matroska / WebM
+    Matroska,
+    Mp3,
+    Aac,
+    Ogg,
+    Flac,
+    Wav,
+    Html,
+    Image,
+    Archive,
+    Executable,
+}
+
+impl FileKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            FileKind::Mp4 => "MP4/ISO media",
+            FileKind::Matroska => "Matroska/WebM",
+            FileKind::Mp3 => "MP3",
+            FileKind::Aac => "AAC",
+            FileKind::Ogg => "Ogg",
+            FileKind::Flac => "FLAC",
+            FileKind::Wav => "WAV",
+            FileKind::Html => "an HTML page",
+            FileKind::Image => "an image",
+            FileKind::Archive => "an archive",
+            FileKind::Executable => "an executable",
+        }
+    }
+
+    /// What a file with this extension should contain, if we know
+    pub fn for_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "mp4" | "m4a" | "m4v" | "mov" | "3gp" => Some(FileKind::Mp4),
+            "mkv" | "webm" | "mka" => Some(FileKind::Matroska),
+            "mp3" => Some(FileKind::Mp3),
+            "aac" => Some(FileKind::Aac),
+            "ogg" | "oga" | "opus" => Some(FileKind::Ogg),
+            "flac" => Some(FileKind::Flac),
+            "wav" => Some(FileKind::Wav),
+            _ => None,
+        }
+    }
+}
+
+/// Identify a file from its leading bytes
+pub fn sniff(header: &[u8]) -> Option<FileKind> {
+    let starts = |magic: &[u8]| header.starts_with(magic);
+
+    if header.len() >= 8 && &header[4..8] == b"ftyp" {
+        return Some(FileKind::Mp4);
+    }
+    if starts(&[0x1A, 0x45, 0xDF, 0xA3]) {
+        return Some(FileKind::Matroska);
+    }
+    if starts(b"ID3") || (header.len() >= 2 && header[0] == 0xFF && header[1] & 0xE6 == 0xE2) {
+        return Some(FileKind::Mp3);
+    }
+    if header.len() >= 2 && header[0] == 0xFF && header[1] & 0xF6 == 0xF0 {
+        return Some(FileKind::Aac);
+    }
+    if starts(b"OggS") {
+        return Some(FileKind::Ogg);
+    }
+    if starts(b"fLaC") {
+        return Some(FileKind::Flac);
+    }
+    if starts(b"RIFF") && header.len() >= 12 && &header[8..12] == b"WAVE" {
+        return Some(FileKind::Wav);
+    }
+    if starts(&[0xFF, 0xD8, 0xFF]) || starts(b"\x89PNG") || starts(b"GIF8") {
+        return Some(FileKind::Image);
+    }
+    if starts(b"PK\x03\x04") || starts(&[0x1F, 0x8B]) || starts(b"Rar!") || starts(b"7z\xBC\xAF") {
+        return Some(FileKind::Archive);
+    }
+    if starts(b"MZ") || starts(b"\x7FELF") || starts(b"#!") {
+        return Some(FileKind::Executable);
+    }
+
+    let text = String::from_utf8_lossy(&header[..header.len().min(64)]).trim_start().to_lowercase();
+    if text.starts_with("<!doctype html") || text.starts_with("<html") {
+        return Some(FileKind::Html);
+    }
+    None
+}
+
+/// Check the file's content matches what its extension promises.
+/// Returns a description of the mismatch, if any.
+pub fn check_magic(path: &str) -> Result<Option<String>> {
+    let path = Path::new(path);
+    let Some(expected) = path.extension().and_then(|e| FileKind::for_extension(&e.to_string_lossy())) else {
+        return Ok(None);
+    };
+
+    let mut header = Vec::with_capacity(64);
+    std::fs::File::open(path)?.take(64).read_to_end(&mut header)?;
+
+    Ok(match sniff(&header) {
+        Some(found) if found == expected => None,
+        Some(found) => Some(format!("expected {} but the file is {}", expected.name(), found.name())),
+        None if header.is_empty() => Some("file is empty".to_string()),
+        None => Some(format!("expected {} but the content is unrecognised", expected.name())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"\x00\x00\x00\x20ftypisom\x00\x00"), Some(FileKind::Mp4));
+        assert_eq!(sniff(&[0x1A, 0x45, 0xDF, 0xA3, 0x9F]), Some(FileKind::Matroska));
+        assert_eq!(sniff(b"ID3\x04\x00"), Some(FileKind::Mp3));
+        assert_eq!(sniff(b"OggS\x00\x02"), Some(FileKind::Ogg));
+        assert_eq!(sniff(b"  <!DOCTYPE html><html>"), Some(FileKind::Html));
+        assert_eq!(sniff(b"MZ\x90\x00"), Some(FileKind::Executable));
+        assert_eq!(sniff(b"hello"), None);
+    }
+}
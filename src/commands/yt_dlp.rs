@@ -1,12 +1,51 @@
 use anyhow::{Result, anyhow};
 use regex::Regex;
-use serde_json::Value;
 use std::process::Stdio;
+use std::sync::RwLock;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 
-use crate::app_state::{DownloadProgress, FormatInfo};
+use crate::app_state::timing::Phase;
+use crate::app_state::{Chapter, DownloadOutcome, DownloadProgress, FormatInfo, ThumbnailEmbed};
+use crate::commands::info_json::{FormatJson, VideoInfo, VideoMetadata};
+
+/// Program and proxy of every yt-dlp call, from the `yt_dlp_path` and `proxy` settings
+static INVOCATION: RwLock<(Option<String>, Option<String>)> = RwLock::new((None, None));
+
+/// Set what `command` runs, from the config
+pub fn set_invocation(program: Option<String>, proxy: Option<String>) {
+    if let Ok(mut invocation) = INVOCATION.write() {
+        *invocation = (program.filter(|p| !p.is_empty()), proxy.filter(|p| !p.is_empty()));
+    }
+}
+
+/// A yt-dlp command with the configured program and proxy
+pub fn command() -> Command {
+    let (program, proxy) = INVOCATION.read().map(|i| i.clone()).unwrap_or_default();
+    let mut cmd = Command::new(program.as_deref().unwrap_or("yt-dlp"));
+    if let Some(proxy) = proxy {
+        cmd.args(["--proxy", &proxy]);
+    }
+    cmd
+}
+
+/// Metadata returned by a format fetch
+#[derive(Debug, Clone)]
+pub struct FetchedFormats {
+    pub formats: Vec<FormatInfo>,
+    pub title: String,
+    pub duration: Option<String>,
+    pub chapters: Vec<Chapter>,
+    /// Download archive key ("extractor id")
+    pub archive_key: Option<String>,
+    /// The URL is a stream that is live right now
+    pub is_live: bool,
+    /// When the live stream started, if the platform says
+    pub live_started_at: Option<i64>,
+    /// Uploader, upload date, engagement counts
+    pub metadata: VideoMetadata,
+}
 
 /// Validate if a URL is potentially supported by yt-dlp
 pub fn is_valid_url(url: &str) -> bool {
@@ -18,9 +57,16 @@ pub fn is_valid_url(url: &str) -> bool {
 }
 
 /// Fetch available formats for a given URL - handles both single videos and playlists
-pub async fn fetch_formats(url: &str) -> Result<(Vec<FormatInfo>, String, Option<String>)> {
-    let output = Command::new("yt-dlp")
-        .args(["--dump-single-json", "--no-warnings", url])
+pub async fn fetch_formats(url: &str) -> Result<FetchedFormats> {
+    fetch_formats_with(url, &[]).await
+}
+
+/// `fetch_formats` with extra yt-dlp arguments, such as per-site extractor options
+pub async fn fetch_formats_with(url: &str, extra_args: &[String]) -> Result<FetchedFormats> {
+    let output = command()
+        .args(["--dump-single-json", "--no-warnings"])
+        .args(extra_args)
+        .arg(url)
         .output()
         .await?;
 
@@ -31,29 +77,12 @@ pub async fn fetch_formats(url: &str) -> Result<(Vec<FormatInfo>, String, Option
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     
-    // Parse JSON output directly
-    let video_info: Value = serde_json::from_str(&stdout)?;
-    
-    // Extract title and duration from JSON
-    let title = video_info["title"]
-        .as_str()
-        .unwrap_or("Unknown")
-        .to_string();
-        
-    let duration = video_info["duration_string"]
-        .as_str()
-        .filter(|s| !s.is_empty() && *s != "NA")
-        .map(|s| s.to_string());
-
-    let mut formats = Vec::new();
-
-    if let Some(format_list) = video_info["formats"].as_array() {
-        for format in format_list {
-            if let Some(format_info) = parse_format_json(format) {
-                formats.push(format_info);
-            }
-        }
-    }
+    let video_info = VideoInfo::parse(&stdout)?;
+    let mut formats: Vec<FormatInfo> = video_info
+        .formats
+        .iter()
+        .filter_map(FormatJson::to_format_info)
+        .collect();
 
     // Sort formats: video formats first (by resolution), then audio formats
     formats.sort_by(|a, b| {
@@ -73,12 +102,21 @@ pub async fn fetch_formats(url: &str) -> Result<(Vec<FormatInfo>, String, Option
         }
     });
 
-    Ok((formats, title, duration))
+    Ok(FetchedFormats {
+        formats,
+        title: video_info.title(),
+        duration: video_info.duration(),
+        chapters: video_info.chapters(),
+        archive_key: video_info.archive_key(),
+        is_live: video_info.is_live(),
+        live_started_at: video_info.live_started_at(),
+        metadata: video_info.metadata(),
+    })
 }
 
 /// Fetch playlist entries for a given URL
 pub async fn fetch_playlist_entries(url: &str) -> Result<Vec<(String, String, Option<String>)>> {
-    let output = Command::new("yt-dlp")
+    let output = command()
         .args(["--dump-single-json", "--no-warnings", url])
         .output()
         .await?;
@@ -89,45 +127,18 @@ pub async fn fetch_playlist_entries(url: &str) -> Result<Vec<(String, String, Op
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let playlist_info: Value = serde_json::from_str(&stdout)?;
-    
-    let mut entries = Vec::new();
-    
-    // Check if this is a playlist
-    if let Some(entry_list) = playlist_info["entries"].as_array() {
-        for entry in entry_list {
-            // Use webpage_url for proper video URL, fallback to url if not available
-            let entry_url = entry["webpage_url"]
-                .as_str()
-                .or_else(|| entry["url"].as_str());
-                
-            if let Some(url) = entry_url {
-                let title = entry["title"]
-                    .as_str()
-                    .unwrap_or("Unknown")
-                    .to_string();
-                let duration = entry["duration_string"]
-                    .as_str()
-                    .filter(|s| !s.is_empty() && *s != "NA")
-                    .map(|s| s.to_string());
-                    
-                entries.push((url.to_string(), title, duration));
-            }
-        }
-    } else {
+    let playlist_info = VideoInfo::parse(&stdout)?;
+
+    let entries = match &playlist_info.entries {
+        Some(entry_list) => entry_list
+            .iter()
+            .flatten()
+            .filter_map(|entry| Some((entry.url()?.to_string(), entry.title(), entry.duration())))
+            .collect(),
         // Not a playlist, return the single video
-        let title = playlist_info["title"]
-            .as_str()
-            .unwrap_or("Unknown")
-            .to_string();
-        let duration = playlist_info["duration_string"]
-            .as_str()
-            .filter(|s| !s.is_empty() && *s != "NA")
-            .map(|s| s.to_string());
-            
-        entries.push((url.to_string(), title, duration));
-    }
-    
+        None => vec![(url.to_string(), playlist_info.title(), playlist_info.duration())],
+    };
+
     Ok(entries)
 }
 
@@ -182,6 +193,9 @@ fn parse_format_line(line: &str) -> Option<FormatInfo> {
         filesize: None,
         quality: None,
         is_audio_only,
+        dynamic_range: None,
+        abr: None,
+        protocol: None,
     })
 }
 
@@ -210,41 +224,6 @@ fn shorten_url(url: &str) -> String {
     }
 }
 
-/// Parse a format from yt-dlp JSON output
-fn parse_format_json(format: &Value) -> Option<FormatInfo> {
-    let format_id = format["format_id"].as_str()?.to_string();
-    let ext = format["ext"].as_str().unwrap_or("unknown").to_string();
-
-    let resolution = match (format["width"].as_u64(), format["height"].as_u64()) {
-        (Some(w), Some(h)) => Some(format!("{}x{}", w, h)),
-        _ => format["resolution"].as_str().map(|s| s.to_string()),
-    };
-
-    let fps = format["fps"].as_f64();
-    let vcodec = format["vcodec"].as_str().map(|s| s.to_string());
-    let acodec = format["acodec"].as_str().map(|s| s.to_string());
-    let filesize = format["filesize"]
-        .as_u64()
-        .or_else(|| format["filesize_approx"].as_u64());
-
-    let quality = format["quality"].as_str().map(|s| s.to_string());
-
-    // Determine if this is audio-only
-    let is_audio_only = vcodec.as_deref() == Some("none") || (vcodec.is_none() && acodec.is_some());
-
-    Some(FormatInfo {
-        format_id,
-        ext,
-        resolution,
-        fps,
-        vcodec,
-        acodec,
-        filesize,
-        quality,
-        is_audio_only,
-    })
-}
-
 /// Parse height from resolution string for sorting
 fn parse_height(resolution: &Option<String>) -> u32 {
     if let Some(res) = resolution {
@@ -265,7 +244,7 @@ fn parse_height(resolution: &Option<String>) -> u32 {
 
 /// Fetch complete video information including thumbnail
 pub async fn fetch_video_info(url: &str) -> Result<(String, Option<String>, Option<String>)> {
-    let output = Command::new("yt-dlp")
+    let output = command()
         .args(["--dump-single-json", "--no-warnings", url])
         .output()
         .await?;
@@ -276,88 +255,389 @@ pub async fn fetch_video_info(url: &str) -> Result<(String, Option<String>, Opti
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
-    let video_info: Value = serde_json::from_str(&stdout)?;
-    
-    let title = video_info["title"]
-        .as_str()
-        .unwrap_or("Unknown")
-        .to_string();
-        
-    let duration = video_info["duration_string"]
-        .as_str()
-        .filter(|s| !s.is_empty() && *s != "NA")
-        .map(|s| s.to_string());
-        
-    let thumbnail_url = video_info["thumbnail"]
-        .as_str()
-        .map(|s| s.to_string());
-    
-    Ok((title, duration, thumbnail_url))
+    let video_info = VideoInfo::parse(&stdout)?;
+
+    Ok((video_info.title(), video_info.duration(), video_info.thumbnail))
 }
 
 
-/// Start a download with progress updates
-pub async fn start_download(
-    url: &str,
-    format_id: &str,
-    output_dir: &str,
-    progress_tx: mpsc::UnboundedSender<DownloadProgress>,
-) -> Result<()> {
+/// Options controlling a single download
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    pub format_id: String,
+    pub output_dir: String,
+    /// yt-dlp output template, relative to `output_dir`
+    pub output_template: String,
+    pub merge_container: String,
+    /// Passed as --concurrent-fragments for HLS/DASH formats
+    pub concurrent_fragments: Option<u8>,
+    /// Embed the thumbnail, converted to this image format
+    pub thumbnail_format: Option<String>,
+    /// Extract the audio track (-x), keeping its original codec
+    pub extract_audio: bool,
+    /// Set when recording a live stream
+    pub live: Option<LiveOptions>,
+    /// Local address to connect from (--source-address)
+    pub source_address: Option<String>,
+    /// Per-site extractor options, passed through as-is
+    pub extra_args: Vec<String>,
+    /// Write at most this many bytes per second, for slow targets
+    pub write_limit: Option<u64>,
+}
+
+/// How to record a live stream
+#[derive(Debug, Clone, Default)]
+pub struct LiveOptions {
+    /// Record from the beginning of the stream where the platform keeps it (--live-from-start)
+    pub from_start: bool,
+    /// Seconds into the stream to start at, used with `from_start`
+    pub start_offset: Option<u64>,
+}
+
+/// Ways to stop a running download without throwing it away
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum StopRequest {
+    #[default]
+    None,
+    /// Let the fragment in flight finish, then stop (resumable later)
+    AfterFragment,
+    /// Interrupt yt-dlp so it finalizes what it has, used to end live recordings
+    Finish,
+}
+
+/// Build the yt-dlp argument list for a download
+pub fn build_download_args(url: &str, options: &DownloadOptions) -> Vec<String> {
     // Determine the actual format string to use
-    let format_string = if format_id.contains("audio_only") {
+    let format_string = if options.format_id.contains("audio_only") {
         // For audio-only downloads, use the format as-is
-        format_id.replace("audio_only_", "")
+        options.format_id.replace("audio_only_", "")
     } else {
         // For video downloads, ensure we get both video and audio
         // Use format+bestaudio to merge video with best audio
-        format!("{}+bestaudio/best", format_id)
+        format!("{}+bestaudio/best", options.format_id)
     };
 
-    let mut cmd = Command::new("yt-dlp")
-        .args([
-            "--format", &format_string,
-            "--output", &format!("{}/%(title)s.%(ext)s", output_dir),
-            "--merge-output-format", "mp4", // Ensure merged output is mp4
-            "--newline",
-            "--progress",
-            url,
-        ])
+    let mut args = vec![
+        "--format".to_string(),
+        format_string,
+        "--output".to_string(),
+        format!("{}/{}", options.output_dir, options.output_template),
+        "--merge-output-format".to_string(),
+        options.merge_container.clone(),
+    ];
+
+    if let Some(fragments) = options.concurrent_fragments.filter(|n| *n > 1) {
+        args.push("--concurrent-fragments".to_string());
+        args.push(fragments.to_string());
+    }
+
+    if options.extract_audio {
+        args.push("--extract-audio".to_string());
+    }
+
+    if let Some(address) = &options.source_address {
+        args.push("--source-address".to_string());
+        args.push(address.clone());
+    }
+
+    if let Some(thumbnail_format) = &options.thumbnail_format {
+        args.push("--embed-thumbnail".to_string());
+        args.push("--convert-thumbnails".to_string());
+        args.push(thumbnail_format.clone());
+    }
+
+    if let Some(live) = &options.live {
+        // MPEG-TS stays playable if the recording is cut off
+        args.push("--hls-use-mpegts".to_string());
+        if live.from_start {
+            args.push("--live-from-start".to_string());
+            if let Some(offset) = live.start_offset.filter(|o| *o > 0) {
+                args.push("--download-sections".to_string());
+                args.push(format!("*{}-inf", offset));
+            }
+        }
+    }
+
+    if let Some(bytes_per_sec) = options.write_limit {
+        args.extend(crate::commands::disk::throttle_args(bytes_per_sec));
+    }
+
+    args.extend(options.extra_args.iter().cloned());
+    args.extend(["--newline".to_string(), "--progress".to_string(), url.to_string()]);
+    args
+}
+
+/// Start a download with progress updates
+pub async fn start_download(
+    url: &str,
+    options: &DownloadOptions,
+    progress_tx: mpsc::UnboundedSender<DownloadProgress>,
+    mut stop_rx: watch::Receiver<StopRequest>,
+) -> Result<DownloadOutcome> {
+    let mut cmd = command()
+        .args(build_download_args(url, options))
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .spawn()?;
 
     let stdout = cmd
         .stdout
         .take()
         .ok_or_else(|| anyhow!("Failed to capture stdout"))?;
+    let stderr = cmd
+        .stderr
+        .take()
+        .ok_or_else(|| anyhow!("Failed to capture stderr"))?;
+
+    // Drain stderr concurrently so a chatty process can't block on a full pipe
+    let stderr_task = tokio::spawn(async move {
+        let mut lines = Vec::new();
+        let mut reader = BufReader::new(stderr).lines();
+        while let Ok(Some(line)) = reader.next_line().await {
+            lines.push(line);
+        }
+        lines
+    });
+
     let mut reader = BufReader::new(stdout).lines();
+    let mut output_lines = Vec::new();
+    let mut current_fragment = None;
+    // File being written, reported with progress so disk usage can be tracked
+    let mut destination: Option<String> = None;
+    // Fragment that was in flight when a graceful stop was requested
+    let mut stop_at: Option<Option<u32>> = None;
+    let mut finishing = false;
+    let mut phase = Phase::Download;
 
     // Read progress lines
-    while let Some(line) = reader.next_line().await? {
-        if let Some(progress) = parse_progress_line(&line) {
+    loop {
+        let line = tokio::select! {
+            line = reader.next_line() => match line? {
+                Some(line) => line,
+                None => break,
+            },
+            changed = stop_rx.changed(), if stop_at.is_none() && !finishing => {
+                // Copy the request out so the watch guard isn't held across the await below
+                let request = if changed.is_ok() { *stop_rx.borrow() } else { StopRequest::None };
+                match request {
+                    StopRequest::AfterFragment => stop_at = Some(current_fragment),
+                    StopRequest::Finish => {
+                        finishing = true;
+                        interrupt(&mut cmd).await;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+        };
+
+        if let Some(path) = line.strip_prefix("[download] Destination: ") {
+            destination = Some(path.trim().to_string());
+            // Live recordings through ffmpeg print no progress, so report the file right away
+            let _ = progress_tx.send(DownloadProgress {
+                destination: destination.clone(),
+                ..DownloadProgress::default()
+            });
+        }
+
+        // Merging and post-processing are timed apart from the download
+        if let Some(next) = parse_phase(&line).filter(|next| *next != phase) {
+            phase = next;
+            let _ = progress_tx.send(DownloadProgress {
+                phase: Some(phase),
+                ..DownloadProgress::default()
+            });
+        }
+
+        if let Some(mut progress) = parse_progress_line(&line) {
+            // Once the fragment in flight has finished, stop and keep the partial data. A stop
+            // asked for before any fragment was reported stops at the first boundary after it.
+            if stop_at.is_some_and(|fragment| progress.fragment_index.is_some() && progress.fragment_index != fragment) {
+                cmd.kill().await?;
+                return Err(StoppedEarly { fragment: current_fragment }.into());
+            }
+            current_fragment = progress.fragment_index;
+            progress.destination = destination.clone();
             if progress_tx.send(progress).is_err() {
                 break; // Channel closed, download cancelled
             }
+        } else {
+            output_lines.push(line);
         }
     }
 
     let status = cmd.wait().await?;
-    if !status.success() {
-        return Err(anyhow!("Download failed with exit code: {}", status));
+    let error_lines = stderr_task.await.unwrap_or_default();
+
+    // An interrupted recording exits non-zero but still leaves a usable file
+    if !status.success() && !finishing {
+        let reason = error_lines
+            .iter()
+            .rev()
+            .find(|l| l.starts_with("ERROR"))
+            .map(|l| format!(": {}", l))
+            .unwrap_or_default();
+        output_lines.extend(error_lines);
+        let skip = output_lines.len().saturating_sub(FAILURE_OUTPUT_LINES);
+        return Err(DownloadFailure {
+            message: format!("Download failed with exit code: {}{}", status, reason),
+            output: output_lines.split_off(skip),
+        }
+        .into());
+    }
+
+    output_lines.extend(error_lines);
+
+    let destination = parse_destination(&output_lines);
+    Ok(DownloadOutcome {
+        thumbnail: options
+            .thumbnail_format
+            .as_ref()
+            .map(|_| parse_thumbnail_embed(&output_lines)),
+        warning: verify_destination(destination.as_deref()).await,
+        destination,
+    })
+}
+
+/// Check the final file before calling the download complete: a clean exit doesn't
+/// mean the merge or extraction wrote anything. Returns what looks wrong.
+async fn verify_destination(destination: Option<&str>) -> Option<String> {
+    let Some(path) = destination else {
+        return Some("yt-dlp didn't say where it wrote the file".to_string());
+    };
+    match tokio::fs::metadata(path).await {
+        Ok(meta) if meta.len() == 0 => Some(format!("{} is empty", path)),
+        Ok(_) => None,
+        Err(e) => Some(format!("{} not found after download: {}", path, e)),
+    }
+}
+
+/// Ask yt-dlp to wrap up: SIGINT makes it finalize the file like Ctrl+C would
+async fn interrupt(child: &mut tokio::process::Child) {
+    #[cfg(unix)]
+    if let Some(pid) = child.id() {
+        let sent = Command::new("kill")
+            .args(["-INT", &pid.to_string()])
+            .status()
+            .await
+            .is_ok_and(|s| s.success());
+        if sent {
+            return;
+        }
     }
+    let _ = child.start_kill();
+}
+
+/// Lines of yt-dlp's output a failed download keeps for bug reports
+pub const FAILURE_OUTPUT_LINES: usize = 40;
 
-    Ok(())
+/// yt-dlp exited with an error, `output` is the tail of what it printed
+#[derive(Debug)]
+pub struct DownloadFailure {
+    pub message: String,
+    pub output: Vec<String>,
+}
+
+impl std::fmt::Display for DownloadFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DownloadFailure {}
+
+/// A download halted on request after finishing a fragment.
+/// yt-dlp keeps the .part file, so downloading again resumes from there.
+#[derive(Debug)]
+pub struct StoppedEarly {
+    /// Last fragment that was completely written
+    pub fragment: Option<u32>,
+}
+
+impl std::fmt::Display for StoppedEarly {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.fragment {
+            Some(fragment) => write!(f, "Stopped after fragment {}", fragment),
+            None => write!(f, "Stopped"),
+        }
+    }
+}
+
+impl std::error::Error for StoppedEarly {}
+
+/// Find the final output file in yt-dlp's output, preferring post-processed files
+fn parse_destination(lines: &[String]) -> Option<String> {
+    let mut download = None;
+    let mut merged = None;
+    let mut extracted = None;
+
+    for line in lines {
+        if let Some(path) = line.strip_prefix("[ExtractAudio] Destination: ") {
+            extracted = Some(path.to_string());
+        } else if let Some(rest) = line.strip_prefix("[Merger] Merging formats into ") {
+            merged = Some(rest.trim_matches('"').to_string());
+        } else if let Some(path) = line.strip_prefix("[download] Destination: ") {
+            download = Some(path.to_string());
+        } else if let Some(rest) = line.strip_prefix("[download] ") {
+            if let Some(path) = rest.strip_suffix(" has already been downloaded") {
+                download = Some(path.to_string());
+            }
+        }
+    }
+
+    extracted.or(merged).or(download)
+}
+
+/// The phase a line of yt-dlp's output starts, from its post-processor prefix
+fn parse_phase(line: &str) -> Option<Phase> {
+    const POST_PROCESSORS: &[&str] = &[
+        "[ExtractAudio]",
+        "[EmbedThumbnail]",
+        "[Metadata]",
+        "[FixupM3u8]",
+        "[FixupM4a]",
+        "[FixupStretched]",
+        "[VideoConvertor]",
+        "[VideoRemuxer]",
+        "[SponsorBlock]",
+        "[ModifyChapters]",
+    ];
+    if line.starts_with("[Merger]") {
+        Some(Phase::Merge)
+    } else if POST_PROCESSORS.iter().any(|prefix| line.starts_with(prefix)) {
+        Some(Phase::PostProcess)
+    } else {
+        None
+    }
+}
+
+/// Work out whether yt-dlp managed to embed the thumbnail from its output
+fn parse_thumbnail_embed(lines: &[String]) -> ThumbnailEmbed {
+    let failure = lines.iter().find(|l| {
+        let lower = l.to_lowercase();
+        (l.starts_with("WARNING") || l.starts_with("ERROR")) && lower.contains("thumbnail")
+    });
+
+    if let Some(line) = failure {
+        ThumbnailEmbed::Failed(line.clone())
+    } else if lines.iter().any(|l| l.starts_with("[EmbedThumbnail]")) {
+        ThumbnailEmbed::Embedded
+    } else {
+        ThumbnailEmbed::Failed("yt-dlp did not report embedding a thumbnail".to_string())
+    }
 }
 
 /// Parse a progress line from yt-dlp output
 fn parse_progress_line(line: &str) -> Option<DownloadProgress> {
     // yt-dlp progress format: [download]  12.3% of 45.67MiB at 1.23MiB/s ETA 00:34
+    // fragmented downloads append "(frag 12/240)" and may estimate the total as "~ 45.67MiB"
     if !line.starts_with("[download]") {
         return None;
     }
 
     let progress_regex = Regex::new(
-        r"\[download\]\s+(?P<percent>\d+\.?\d*)%(?:\s+of\s+(?P<total>\S+))?(?:\s+at\s+(?P<speed>\S+))?(?:\s+ETA\s+(?P<eta>\S+))?"
+        r"\[download\]\s+(?P<percent>\d+\.?\d*)%(?:\s+of\s+~?\s*(?P<total>\S+))?(?:\s+at\s+(?P<speed>\S+))?(?:\s+ETA\s+(?P<eta>\S+))?(?:\s+\(frag\s+(?P<frag>\d+)/(?P<frags>\d+)\))?"
     ).unwrap();
 
     if let Some(captures) = progress_regex.captures(line) {
@@ -365,14 +645,21 @@ fn parse_progress_line(line: &str) -> Option<DownloadProgress> {
         let total_size = captures.name("total").map(|m| m.as_str().to_string());
         let speed = captures.name("speed").map(|m| m.as_str().to_string());
         let eta = captures.name("eta").map(|m| m.as_str().to_string());
+        let fragment_index = captures.name("frag").and_then(|m| m.as_str().parse().ok());
+        let fragment_count = captures.name("frags").and_then(|m| m.as_str().parse().ok());
 
-        return Some(DownloadProgress {
+        let mut progress = DownloadProgress {
             percent,
             speed,
             eta,
             downloaded: None, // Could be calculated from percent and total
             total_size,
-        });
+            fragment_index,
+            fragment_count,
+            ..Default::default()
+        };
+        progress.parse_numbers();
+        return Some(progress);
     }
 
     None
@@ -380,7 +667,7 @@ fn parse_progress_line(line: &str) -> Option<DownloadProgress> {
 
 /// Validate a URL by attempting to extract info without downloading
 pub async fn validate_url(url: &str) -> Result<bool> {
-    let output = Command::new("yt-dlp")
+    let output = command()
         .args(["--simulate", "--quiet", "--no-warnings", url])
         .output()
         .await?;
@@ -410,6 +697,137 @@ mod tests {
         assert_eq!(progress.total_size, Some("123.45MiB".to_string()));
         assert_eq!(progress.speed, Some("2.34MiB/s".to_string()));
         assert_eq!(progress.eta, Some("01:23".to_string()));
+        assert_eq!(progress.fragment_index, None);
+
+        let line = "[download]  12.0% of ~ 512.00MiB at 8.00MiB/s ETA 00:52 (frag 30/250)";
+        let progress = parse_progress_line(line).unwrap();
+
+        assert_eq!(progress.total_size, Some("512.00MiB".to_string()));
+        assert_eq!(progress.fragment_index, Some(30));
+        assert_eq!(progress.fragment_count, Some(250));
+    }
+
+    #[test]
+    fn test_concurrent_fragments_args() {
+        let mut options = DownloadOptions {
+            format_id: "137".to_string(),
+            output_dir: "/tmp".to_string(),
+            output_template: "%(title)s.%(ext)s".to_string(),
+            merge_container: "mkv".to_string(),
+            concurrent_fragments: Some(4),
+            thumbnail_format: None,
+            extract_audio: false,
+            live: None,
+            source_address: None,
+            extra_args: Vec::new(),
+            write_limit: None,
+        };
+        let args = build_download_args("https://example.com/v", &options);
+        assert!(args.windows(2).any(|w| w == ["--concurrent-fragments", "4"]));
+        assert!(args.windows(2).any(|w| w == ["--merge-output-format", "mkv"]));
+
+        options.concurrent_fragments = None;
+        let args = build_download_args("https://example.com/v", &options);
+        assert!(!args.iter().any(|a| a == "--concurrent-fragments"));
+
+        options.live = Some(LiveOptions { from_start: true, start_offset: Some(600) });
+        let args = build_download_args("https://example.com/v", &options);
+        assert!(args.iter().any(|a| a == "--live-from-start"));
+        assert!(args.windows(2).any(|w| w == ["--download-sections", "*600-inf"]));
+    }
+
+    #[test]
+    fn test_thumbnail_embed_detection() {
+        let ok = vec![
+            "[EmbedThumbnail] ffmpeg: Adding thumbnail to \"/tmp/video.mp4\"".to_string(),
+        ];
+        assert_eq!(parse_thumbnail_embed(&ok), ThumbnailEmbed::Embedded);
+
+        let failed = vec![
+            "[EmbedThumbnail] mutagen: Adding thumbnail to \"/tmp/video.m4a\"".to_string(),
+            "WARNING: unable to embed thumbnail: unsupported image".to_string(),
+        ];
+        assert!(matches!(parse_thumbnail_embed(&failed), ThumbnailEmbed::Failed(_)));
+    }
+
+    #[test]
+    fn test_destination_parsing() {
+        let lines: Vec<String> = [
+            "[download] Destination: /tmp/Mix.f251.webm",
+            "[download] 100% of 80.00MiB in 00:10",
+            "[ExtractAudio] Destination: /tmp/Mix.opus",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        assert_eq!(parse_destination(&lines).as_deref(), Some("/tmp/Mix.opus"));
+
+        let merged = vec!["[Merger] Merging formats into \"/tmp/Video.mp4\"".to_string()];
+        assert_eq!(parse_destination(&merged).as_deref(), Some("/tmp/Video.mp4"));
+        assert_eq!(parse_phase(&merged[0]), Some(Phase::Merge));
+        assert_eq!(parse_phase(&lines[2]), Some(Phase::PostProcess));
+        assert_eq!(parse_phase(&lines[1]), None);
+    }
+
+    #[tokio::test]
+    async fn test_verify_destination() {
+        let dir = std::env::temp_dir().join(format!("gorlock-verify-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let written = dir.join("Video.mp4");
+        std::fs::write(&written, b"data").unwrap();
+        let empty = dir.join("Empty.mp4");
+        std::fs::write(&empty, b"").unwrap();
+
+        assert_eq!(verify_destination(Some(&written.to_string_lossy())).await, None);
+        assert!(verify_destination(Some(&empty.to_string_lossy())).await.unwrap().contains("empty"));
+        assert!(verify_destination(Some(&dir.join("Missing.mp4").to_string_lossy())).await.is_some());
+        assert!(verify_destination(None).await.is_some());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_format_badges() {
+        let video: FormatJson = serde_json::from_str(
+            r#"{"format_id": "401", "ext": "mp4", "width": 3840, "height": 2160, "fps": 60,
+                "vcodec": "av01.0.13M.10.0.110.09.16.09.0", "acodec": "none", "dynamic_range": "HDR10"}"#,
+        ).unwrap();
+        let video = video.to_format_info().unwrap();
+        assert_eq!(video.badges(), vec!["AV1", "HDR10", "60fps"]);
+
+        let audio: FormatJson = serde_json::from_str(
+            r#"{"format_id": "251", "ext": "webm", "vcodec": "none", "acodec": "opus", "abr": 159.8}"#,
+        ).unwrap();
+        let audio = audio.to_format_info().unwrap();
+        assert!(audio.is_audio_only);
+        assert_eq!(audio.badges(), vec!["Opus 160k"]);
+
+        let film: FormatJson = serde_json::from_str(
+            r#"{"format_id": "137", "ext": "mp4", "height": 1080, "fps": 23.976, "vcodec": "avc1.640028", "acodec": "none"}"#,
+        ).unwrap();
+        assert_eq!(film.to_format_info().unwrap().badges(), vec!["H.264", "24fps"]);
+    }
+
+    #[test]
+    fn test_transcode_warning() {
+        use crate::app_state::FormatPopup;
+
+        let parse = |json: &str| serde_json::from_str::<FormatJson>(json).unwrap().to_format_info().unwrap();
+        let vp9 = parse(r#"{"format_id": "248", "ext": "webm", "vcodec": "vp9", "acodec": "none"}"#);
+        let opus = parse(r#"{"format_id": "251", "ext": "webm", "vcodec": "none", "acodec": "opus", "abr": 130}"#);
+
+        let mut popup = FormatPopup {
+            item_id: uuid::Uuid::new_v4(),
+            formats: vec![vp9.clone(), opus],
+            selected_index: 0,
+            audio_only_filter: false,
+            container: "mp4".to_string(),
+        };
+        let warning = popup.transcode_warning(&vp9).unwrap();
+        assert_eq!(warning.suggested_container.as_deref(), Some("webm"));
+
+        popup.container = "mkv".to_string();
+        assert!(popup.transcode_warning(&vp9).is_none());
     }
 
 }
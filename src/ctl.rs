@@ -0,0 +1,226 @@
+use anyhow::{Result, anyhow, bail};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+
+use crate::app_state::events::DownloadAction;
+use crate::config;
+
+/// A request to a running gorlock, one line over its local socket
+#[derive(Debug, Clone, PartialEq)]
+pub enum CtlCommand {
+    /// Pause every running download
+    Pause,
+    /// Resume every paused download
+    Resume,
+    /// Queue a URL, downloading the best format once it's fetched
+    Add(String),
+    /// Print the queue, as JSON with `--json`
+    Status { json: bool },
+}
+
+impl CtlCommand {
+    /// Parse `gorlock ctl <pause|resume|add URL|status [--json]>`, `None` without `ctl`
+    pub fn from_args(args: &[String]) -> Option<Result<Self>> {
+        if args.get(1).map(String::as_str) != Some("ctl") {
+            return None;
+        }
+        let command = match (args.get(2).map(String::as_str), args.get(3)) {
+            (Some("pause"), _) => Ok(Self::Pause),
+            (Some("resume"), _) => Ok(Self::Resume),
+            (Some("add"), Some(url)) => Ok(Self::Add(url.clone())),
+            (Some("add"), None) => Err(anyhow!("gorlock ctl add needs a URL")),
+            (Some("status"), _) => Ok(Self::Status { json: args[3..].iter().any(|a| a == "--json") }),
+            _ => Err(anyhow!("Usage: gorlock ctl <pause|resume|add URL|status [--json]>")),
+        };
+        Some(command)
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        match line.trim().split_once(' ') {
+            Some(("add", url)) => Some(Self::Add(url.trim().to_string())),
+            None if line.trim() == "pause" => Some(Self::Pause),
+            None if line.trim() == "resume" => Some(Self::Resume),
+            None if line.trim() == "status" => Some(Self::Status { json: true }),
+            _ => None,
+        }
+    }
+
+    fn line(&self) -> String {
+        match self {
+            Self::Pause => "pause".to_string(),
+            Self::Resume => "resume".to_string(),
+            Self::Add(url) => format!("add {}", url),
+            Self::Status { .. } => "status".to_string(),
+        }
+    }
+}
+
+/// How long a client gets to send its command, and the longest command read
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_LINE: u64 = 8 * 1024;
+
+/// The socket a running instance listens on, in the data folder
+fn socket_path() -> Option<PathBuf> {
+    config::data_dir().map(|dir| dir.join("gorlock.sock"))
+}
+
+/// What a request does: the action to send and the reply, or the reply alone
+fn answer(line: &str, snapshot: &str) -> (Option<DownloadAction>, String) {
+    match CtlCommand::parse(line) {
+        Some(CtlCommand::Pause) => (Some(DownloadAction::PauseAll), "ok".to_string()),
+        Some(CtlCommand::Resume) => (Some(DownloadAction::ResumeAll), "ok".to_string()),
+        Some(CtlCommand::Add(url)) if url.starts_with("http://") || url.starts_with("https://") => {
+            (Some(DownloadAction::AddRemote(url)), "ok".to_string())
+        }
+        Some(CtlCommand::Add(_)) => (None, "error: expected an http(s) URL".to_string()),
+        Some(CtlCommand::Status { .. }) => (None, snapshot.to_string()),
+        None => (None, "error: unknown command".to_string()),
+    }
+}
+
+/// Answer `gorlock ctl` on the local socket until the snapshot channel closes
+#[cfg(unix)]
+pub async fn serve(action_tx: mpsc::Sender<DownloadAction>, mut snapshots: watch::Receiver<String>) {
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::{UnixListener, UnixStream};
+
+    let Some(path) = socket_path() else {
+        return;
+    };
+    // Another instance sharing the data folder keeps the socket, a dead one's socket is left over
+    if UnixStream::connect(&path).await.is_ok() {
+        tracing::debug!("Another gorlock answers on {}", path.display());
+        return;
+    }
+    let _ = std::fs::remove_file(&path);
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let listener = match UnixListener::bind(&path) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::debug!("No control socket at {}: {}", path.display(), e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, _)) = accepted else {
+                    continue;
+                };
+                // A client that never sends its command holds up nobody else
+                let (action_tx, snapshots) = (action_tx.clone(), snapshots.clone());
+                tokio::spawn(async move {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut line = String::new();
+                    let mut reader = BufReader::new(reader.take(MAX_LINE));
+                    if !matches!(tokio::time::timeout(READ_TIMEOUT, reader.read_line(&mut line)).await, Ok(Ok(_))) {
+                        return;
+                    }
+                    let snapshot = snapshots.borrow().clone();
+                    let (action, reply) = answer(&line, &snapshot);
+                    if let Some(action) = action {
+                        let _ = action_tx.send(action).await;
+                    }
+                    let _ = writer.write_all(format!("{}\n", reply).as_bytes()).await;
+                });
+            }
+            changed = snapshots.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    let _ = std::fs::remove_file(&path);
+}
+
+#[cfg(not(unix))]
+pub async fn serve(_action_tx: mpsc::Sender<DownloadAction>, _snapshots: watch::Receiver<String>) {}
+
+/// SIGUSR1 pauses every running download and SIGUSR2 resumes them, until the channel closes
+#[cfg(unix)]
+pub async fn signals(action_tx: mpsc::Sender<DownloadAction>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let (Ok(mut pause), Ok(mut resume)) = (signal(SignalKind::user_defined1()), signal(SignalKind::user_defined2())) else {
+        return;
+    };
+    loop {
+        let action = tokio::select! {
+            _ = pause.recv() => DownloadAction::PauseAll,
+            _ = resume.recv() => DownloadAction::ResumeAll,
+        };
+        if action_tx.send(action).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn signals(_action_tx: mpsc::Sender<DownloadAction>) {}
+
+/// Send a command to the running instance and print its answer
+#[cfg(unix)]
+pub async fn run(command: CtlCommand) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let path = socket_path().ok_or_else(|| anyhow!("Could not find the data directory"))?;
+    let mut stream = tokio::net::UnixStream::connect(&path)
+        .await
+        .map_err(|e| anyhow!("No gorlock running with data in {} ({})", path.parent().unwrap_or(&path).display(), e))?;
+    stream.write_all(format!("{}\n", command.line()).as_bytes()).await?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).await?;
+    let reply = reply.trim();
+
+    if let Some(error) = reply.strip_prefix("error: ") {
+        bail!("{}", error);
+    }
+    match command {
+        CtlCommand::Status { json: false } => print!("{}", status_table(reply)?),
+        _ => println!("{}", reply),
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn run(_command: CtlCommand) -> Result<()> {
+    bail!("gorlock ctl needs Unix sockets, use the web API instead")
+}
+
+/// The queue snapshot as lines for a terminal
+fn status_table(snapshot: &str) -> Result<String> {
+    let snapshot: crate::web::monitor::Snapshot = serde_json::from_str(snapshot)?;
+    let mut table = String::new();
+    for item in &snapshot.items {
+        let rate = item.speed.as_deref().map(|s| format!(" {}", s)).unwrap_or_default();
+        table.push_str(&format!("{:<21} {:>5.1}%{:<14} {}\n", item.status, item.percent, rate, item.title));
+    }
+    if snapshot.items.is_empty() {
+        table.push_str("The queue is empty\n");
+    }
+    Ok(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ctl_commands() {
+        let args: Vec<String> = ["gorlock", "ctl", "add", "https://example.com/v"].iter().map(|a| a.to_string()).collect();
+        let command = CtlCommand::from_args(&args).unwrap().unwrap();
+        assert_eq!(CtlCommand::parse(&command.line()), Some(command));
+        assert!(CtlCommand::from_args(&args[..1]).is_none());
+
+        assert!(matches!(answer("pause\n", "{}"), (Some(DownloadAction::PauseAll), _)));
+        assert!(matches!(answer("add https://example.com/v\n", "{}").0, Some(DownloadAction::AddRemote(_))));
+        assert_eq!(answer("add ftp://example.com\n", "{}").1, "error: expected an http(s) URL");
+        assert_eq!(answer("status\n", "{\"items\":[]}").1, "{\"items\":[]}");
+        assert_eq!(answer("reboot\n", "{}").1, "error: unknown command");
+    }
+}
@@ -0,0 +1,123 @@
+use regex::Regex;
+
+use crate::app_state::Chapter;
+
+lazy_static::lazy_static! {
+    static ref URL: Regex = Regex::new(r#"https?://[^\s<>"'`]+"#).unwrap();
+    static ref NUMBERING: Regex = Regex::new(r"^\d{1,3}[.)]\s*").unwrap();
+    static ref TIMESTAMP: Regex = Regex::new(r"\b(?:(\d{1,2}):)?(\d{1,3}):([0-5]\d)\b").unwrap();
+}
+
+/// URLs found in a video description, in order of appearance without duplicates
+pub fn extract_urls(text: &str) -> Vec<String> {
+    let mut urls: Vec<String> = Vec::new();
+    for found in URL.find_iter(text) {
+        // Sentence punctuation and closing brackets usually aren't part of the link
+        let mut url = found.as_str().trim_end_matches(['.', ',', ';', ':', '!', '?']);
+        if url.ends_with(')') && !url.contains('(') {
+            url = url.trim_end_matches(')');
+        }
+        if !urls.iter().any(|u| u == url) {
+            urls.push(url.to_string());
+        }
+    }
+    urls
+}
+
+/// Chapters from a timestamped tracklist such as "00:00 Intro", "1. Song A - 03:21" or
+/// "00:00 Intro, 03:21 Song A". Timestamps elsewhere in the text are ignored by keeping the
+/// longest run of increasing ones; `duration` ends the last chapter.
+pub fn parse_tracklist(text: &str, duration: Option<f64>) -> Vec<Chapter> {
+    let mut entries: Vec<(f64, String)> = Vec::new();
+
+    for line in text.lines() {
+        let found: Vec<regex::Captures> = TIMESTAMP.captures_iter(line).collect();
+        for (i, caps) in found.iter().enumerate() {
+            let number = |n: usize| caps.get(n).map_or(0.0, |m| m.as_str().parse::<f64>().unwrap_or(0.0));
+            let (hours, minutes, seconds) = (number(1), number(2), number(3));
+            if caps.get(1).is_some() && minutes >= 60.0 {
+                continue;
+            }
+            let whole = caps.get(0).unwrap();
+            // The title runs to the next timestamp on the line, or precedes a lone trailing timestamp
+            let end = found.get(i + 1).map_or(line.len(), |next| next.get(0).unwrap().start());
+            let mut title = clean_title(&line[whole.end()..end]);
+            if title.is_empty() && found.len() == 1 {
+                title = clean_title(&line[..whole.start()]);
+            }
+            entries.push((hours * 3600.0 + minutes * 60.0 + seconds, title));
+        }
+    }
+
+    // Longest run of strictly increasing timestamps
+    let mut best = 0..0;
+    let mut start = 0;
+    for i in 1..=entries.len() {
+        if i == entries.len() || entries[i].0 <= entries[i - 1].0 {
+            if i - start > best.len() {
+                best = start..i;
+            }
+            start = i;
+        }
+    }
+    let run = &entries[best];
+    if run.len() < 2 {
+        return Vec::new();
+    }
+
+    run.iter()
+        .enumerate()
+        .map(|(i, (start, title))| Chapter {
+            title: if title.is_empty() { format!("Track {}", i + 1) } else { title.clone() },
+            start_time: *start,
+            end_time: run.get(i + 1).map(|(next, _)| *next).or(duration).unwrap_or(0.0),
+        })
+        .collect()
+}
+
+/// Strip separators and track numbering around a tracklist title
+fn clean_title(text: &str) -> String {
+    let trimmed = text.trim_matches(|c: char| c.is_whitespace() || "-–—|:,.()[]•".contains(c));
+    NUMBERING.replace(trimmed, "").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_urls() {
+        let text = "Mirror: https://example.com/a.mp4.\n\
+                    Tracklist (https://example.com/list) and https://en.wikipedia.org/wiki/Foo_(bar)\n\
+                    again https://example.com/a.mp4, plain example.com";
+        assert_eq!(
+            extract_urls(text),
+            vec![
+                "https://example.com/a.mp4",
+                "https://example.com/list",
+                "https://en.wikipedia.org/wiki/Foo_(bar)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_tracklist() {
+        let text = "Recorded live, the drop at 12:00 is wild\n\
+                    Tracklist:\n\
+                    1. 00:00 Intro\n\
+                    2. Song A - 03:21\n\
+                    [1:02:03] Finale";
+        let chapters = parse_tracklist(text, Some(4000.0));
+        let starts: Vec<f64> = chapters.iter().map(|c| c.start_time).collect();
+        assert_eq!(starts, [0.0, 201.0, 3723.0]);
+        assert_eq!(chapters[1].title, "Song A");
+        assert_eq!(chapters[2].title, "Finale");
+        assert_eq!(chapters[2].end_time, 4000.0);
+
+        let inline = parse_tracklist("00:00 Intro, 03:21 Song A", None);
+        assert_eq!(inline[0].title, "Intro");
+        assert_eq!(inline[0].end_time, 201.0);
+        assert_eq!(inline[1].title, "Song A");
+        assert!(parse_tracklist("Only one at 5:00", None).is_empty());
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::watch;
+
+use crate::app_state::{AppState, DownloadStatus};
+
+/// Discord accepts a presence update about every 15 seconds, faster ones get dropped
+const UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How much Discord Rich Presence shows, off unless chosen in settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresenceMode {
+    #[default]
+    Off,
+    /// "Downloading: <title> (42%)"
+    Titles,
+    /// "Downloading 3 videos", nothing about what
+    CountsOnly,
+}
+
+impl PresenceMode {
+    pub const ALL: &'static [PresenceMode] = &[PresenceMode::Off, PresenceMode::Titles, PresenceMode::CountsOnly];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PresenceMode::Off => "off",
+            PresenceMode::Titles => "titles",
+            PresenceMode::CountsOnly => "counts only",
+        }
+    }
+}
+
+/// What the presence shows, `None` clears it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Presence {
+    pub client_id: String,
+    pub details: String,
+    pub state: Option<String>,
+}
+
+impl Presence {
+    /// The presence for the queue as it is, if the setting is on and something is downloading
+    pub fn of(state: &AppState) -> Option<Self> {
+        let client_id = state.config.discord_client_id.clone().filter(|id| !id.is_empty())?;
+        let active: Vec<_> = state.queue.iter().filter(|i| i.status == DownloadStatus::Downloading).collect();
+        let first = active.first()?;
+        let waiting = state
+            .queue
+            .iter()
+            .filter(|i| matches!(i.status, DownloadStatus::Queued | DownloadStatus::Ready))
+            .count();
+        let waiting = (waiting > 0).then(|| format!("{} more in the queue", waiting));
+
+        let (details, state_line) = match state.config.discord_presence {
+            PresenceMode::Off => return None,
+            PresenceMode::Titles => {
+                let title = first.title.as_deref().unwrap_or("a video");
+                let details = format!("Downloading: {} ({:.0}%)", title, first.progress.percent);
+                let others = (active.len() > 1).then(|| format!("and {} other download(s)", active.len() - 1));
+                (details, others.or(waiting))
+            }
+            PresenceMode::CountsOnly => {
+                let details = match active.len() {
+                    1 => "Downloading 1 video".to_string(),
+                    n => format!("Downloading {} videos", n),
+                };
+                (details, waiting)
+            }
+        };
+        // Discord rejects fields over 128 characters
+        let clip = |text: String| text.chars().take(128).collect::<String>();
+        Some(Self {
+            client_id,
+            details: clip(details),
+            state: state_line.map(clip),
+        })
+    }
+}
+
+trait Ipc: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Ipc for T {}
+
+/// A frame is a little-endian opcode and length followed by JSON
+fn frame(opcode: u32, payload: &serde_json::Value) -> Vec<u8> {
+    let json = payload.to_string();
+    let mut bytes = Vec::with_capacity(8 + json.len());
+    bytes.extend_from_slice(&opcode.to_le_bytes());
+    bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(json.as_bytes());
+    bytes
+}
+
+async fn send(ipc: &mut Box<dyn Ipc>, opcode: u32, payload: serde_json::Value) -> Result<serde_json::Value> {
+    ipc.write_all(&frame(opcode, &payload)).await?;
+    let mut header = [0u8; 8];
+    ipc.read_exact(&mut header).await?;
+    let opcode = u32::from_le_bytes(header[..4].try_into()?);
+    let mut body = vec![0; u32::from_le_bytes(header[4..].try_into()?) as usize];
+    ipc.read_exact(&mut body).await?;
+    let reply: serde_json::Value = serde_json::from_slice(&body)?;
+    // Opcode 2 is Discord closing the connection, usually over a bad client ID
+    if opcode == 2 || reply["evt"] == "ERROR" {
+        return Err(anyhow!("Discord refused: {}", reply["message"].as_str().or(reply["data"]["message"].as_str()).unwrap_or("unknown error")));
+    }
+    Ok(reply)
+}
+
+/// Discord's IPC socket, in the runtime dir for native installs and per-sandbox dirs for Flatpak and Snap
+#[cfg(unix)]
+async fn connect_socket() -> Result<Box<dyn Ipc>> {
+    let bases: Vec<std::path::PathBuf> = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .filter_map(std::env::var_os)
+        .map(Into::into)
+        .chain(std::iter::once("/tmp".into()))
+        .collect();
+    for base in &bases {
+        for sandbox in ["", "app/com.discordapp.Discord", "snap.discord"] {
+            for i in 0..10 {
+                let path = base.join(sandbox).join(format!("discord-ipc-{}", i));
+                if let Ok(stream) = tokio::net::UnixStream::connect(&path).await {
+                    return Ok(Box::new(stream));
+                }
+            }
+        }
+    }
+    Err(anyhow!("Discord isn't running"))
+}
+
+#[cfg(windows)]
+async fn connect_socket() -> Result<Box<dyn Ipc>> {
+    for i in 0..10 {
+        if let Ok(pipe) = tokio::net::windows::named_pipe::ClientOptions::new().open(format!(r"\\?\pipe\discord-ipc-{}", i)) {
+            return Ok(Box::new(pipe));
+        }
+    }
+    Err(anyhow!("Discord isn't running"))
+}
+
+async fn connect(client_id: &str) -> Result<Box<dyn Ipc>> {
+    let mut ipc = connect_socket().await?;
+    send(&mut ipc, 0, serde_json::json!({ "v": 1, "client_id": client_id })).await?;
+    Ok(ipc)
+}
+
+async fn set_activity(ipc: &mut Box<dyn Ipc>, presence: Option<&Presence>, started: i64) -> Result<()> {
+    let activity = presence.map(|p| {
+        serde_json::json!({
+            "details": p.details,
+            "state": p.state,
+            "timestamps": { "start": started },
+        })
+    });
+    send(
+        ipc,
+        1,
+        serde_json::json!({
+            "cmd": "SET_ACTIVITY",
+            "args": { "pid": std::process::id(), "activity": activity },
+            "nonce": uuid::Uuid::new_v4().to_string(),
+        }),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Keep the Discord presence in line with `presences`, connecting when there is something to show.
+/// When the sender is dropped the presence is cleared and the connection closed.
+pub async fn run(mut presences: watch::Receiver<Option<Presence>>) {
+    let mut ipc: Option<(String, Box<dyn Ipc>)> = None;
+    let mut last_update: Option<Instant> = None;
+    let mut shown: Option<Presence> = None;
+    let mut started = chrono::Utc::now().timestamp();
+
+    loop {
+        // Wait for a change, or for the rate limit to pass with one still unsent
+        let pending = *presences.borrow() != shown;
+        let wait = last_update.map_or(Duration::ZERO, |at| UPDATE_INTERVAL.saturating_sub(at.elapsed()));
+        tokio::select! {
+            changed = presences.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                continue;
+            }
+            _ = tokio::time::sleep(wait), if pending => {}
+        }
+
+        let presence = presences.borrow().clone();
+        if shown.is_none() {
+            started = chrono::Utc::now().timestamp();
+        }
+        // A different client ID in the settings needs a new handshake
+        if let (Some((id, _)), Some(p)) = (&ipc, &presence) {
+            if *id != p.client_id {
+                ipc = None;
+            }
+        }
+        if ipc.is_none() {
+            if let Some(p) = &presence {
+                match connect(&p.client_id).await {
+                    Ok(connection) => ipc = Some((p.client_id.clone(), connection)),
+                    Err(e) => tracing::debug!("Discord presence unavailable: {}", e),
+                }
+            }
+        }
+        if let Some((_, connection)) = &mut ipc {
+            if let Err(e) = set_activity(connection, presence.as_ref(), started).await {
+                tracing::debug!("Discord presence update failed: {}", e);
+                ipc = None;
+            }
+        }
+        // Without Discord, try again at the next interval rather than on every progress update
+        shown = presence;
+        last_update = Some(Instant::now());
+    }
+
+    if let Some((_, mut connection)) = ipc {
+        let _ = set_activity(&mut connection, None, started).await;
+        let _ = connection.write_all(&frame(2, &serde_json::json!({}))).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::DownloadItem;
+
+    #[test]
+    fn test_presence() {
+        let mut state = AppState::default();
+        state.config.discord_client_id = Some("1234".to_string());
+        let mut item = DownloadItem::new("https://example.com/v".to_string());
+        item.title = Some("Big Buck Bunny".to_string());
+        item.status = DownloadStatus::Downloading;
+        item.progress.percent = 42.4;
+        state.queue.push(item);
+
+        assert_eq!(Presence::of(&state), None);
+        state.config.discord_presence = PresenceMode::Titles;
+        assert_eq!(Presence::of(&state).unwrap().details, "Downloading: Big Buck Bunny (42%)");
+        state.config.discord_presence = PresenceMode::CountsOnly;
+        assert_eq!(Presence::of(&state).unwrap().details, "Downloading 1 video");
+
+        let bytes = frame(1, &serde_json::json!({}));
+        assert_eq!(bytes, [1, 0, 0, 0, 2, 0, 0, 0, b'{', b'}']);
+    }
+}
@@ -0,0 +1,241 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::app_state::DownloadItem;
+use crate::{config, store};
+
+/// A completed download, persisted one JSON object per line in history.jsonl
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub uploader: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub file_path: Option<String>,
+    /// Content fingerprint, when duplicate detection is on
+    #[serde(default)]
+    pub content_hash: Option<String>,
+    pub completed_at: DateTime<Utc>,
+    /// Seconds from the first start to completion
+    #[serde(default)]
+    pub wall_time_secs: Option<u64>,
+}
+
+/// Download history with an in-memory inverted index over titles, uploaders and tags.
+///
+/// Tokens live in a sorted map so prefix queries are a range scan, which keeps
+/// search instant with tens of thousands of entries.
+#[derive(Debug, Default)]
+pub struct History {
+    entries: Vec<HistoryEntry>,
+    /// Lowercased token -> entry indices (ascending)
+    index: BTreeMap<String, Vec<usize>>,
+    path: Option<PathBuf>,
+    /// Bytes of the file already read, what follows was appended by other instances
+    read_len: u64,
+    /// Entries recorded while another instance was writing the file, for `flush`
+    pending: Vec<HistoryEntry>,
+}
+
+impl HistoryEntry {
+    pub fn from_item(item: &DownloadItem) -> Self {
+        Self {
+            url: item.url.clone(),
+            title: item.title.clone(),
+            uploader: item.metadata.uploader.clone(),
+            tags: item.tags.clone(),
+            file_path: item.file_path.clone(),
+            content_hash: None,
+            completed_at: item.finished_at.unwrap_or_else(Utc::now),
+            wall_time_secs: item.elapsed().and_then(|d| u64::try_from(d.num_seconds()).ok()),
+        }
+    }
+}
+
+impl History {
+    /// Load history from the data directory, skipping lines that don't parse
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("history.jsonl"));
+        let contents = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .unwrap_or_default();
+
+        let mut history = Self {
+            path,
+            read_len: contents.len() as u64,
+            ..Self::default()
+        };
+        history.insert_lines(&contents);
+        history
+    }
+
+    /// Insert entries from history lines, skipping lines that don't parse
+    fn insert_lines(&mut self, contents: &str) {
+        for line in contents.lines() {
+            if let Ok(entry) = serde_json::from_str(line) {
+                self.insert(entry);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&HistoryEntry> {
+        self.entries.get(index)
+    }
+
+    /// An earlier download with the same content whose file is still on disk
+    pub fn find_duplicate(&self, hash: &str, path: &str) -> Option<&HistoryEntry> {
+        self.entries.iter().rev().find(|entry| {
+            entry.content_hash.as_deref() == Some(hash)
+                && entry
+                    .file_path
+                    .as_deref()
+                    .is_some_and(|p| p != path && std::path::Path::new(p).exists())
+        })
+    }
+
+    /// Record a completion, updating the index and appending it to the history file
+    pub fn record(&mut self, entry: HistoryEntry) -> Result<()> {
+        if self.path.is_some() {
+            self.pending.push(entry.clone());
+        }
+        self.insert(entry);
+        self.flush()
+    }
+
+    /// Append the recorded completions to the history file, indexing what other gorlock
+    /// instances appended in the meantime. While another instance is writing the file they're
+    /// kept for the next call, the main loop calls this every tick.
+    pub fn flush(&mut self) -> Result<()> {
+        let Some(path) = self.path.clone().filter(|_| !self.pending.is_empty()) else {
+            return Ok(());
+        };
+        let Some(_lock) = store::try_lock(&path)? else {
+            return Ok(());
+        };
+        let (appended, _) = store::read_appended(&path, self.read_len)?;
+        self.insert_lines(&appended);
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+        for entry in std::mem::take(&mut self.pending) {
+            writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        }
+        self.read_len = store::file_len(&path);
+        Ok(())
+    }
+
+    fn insert(&mut self, entry: HistoryEntry) {
+        let position = self.entries.len();
+        let text = [
+            entry.title.as_deref().unwrap_or_default(),
+            entry.uploader.as_deref().unwrap_or_default(),
+            &entry.tags.join(" "),
+        ]
+        .join(" ");
+
+        for token in tokenize(&text).into_iter().collect::<HashSet<_>>() {
+            self.index.entry(token).or_default().push(position);
+        }
+        self.entries.push(entry);
+    }
+
+    /// Entry indices matching every word of the query (as prefixes), newest first
+    pub fn search(&self, query: &str) -> Vec<usize> {
+        let terms = tokenize(query);
+        if terms.is_empty() {
+            return (0..self.entries.len()).rev().collect();
+        }
+
+        let mut result: Option<Vec<usize>> = None;
+        for term in terms {
+            let mut matches: Vec<usize> = self
+                .index
+                .range(term.clone()..)
+                .take_while(|(token, _)| token.starts_with(&term))
+                .flat_map(|(_, ids)| ids.iter().copied())
+                .collect();
+            matches.sort_unstable();
+            matches.dedup();
+
+            result = Some(match result {
+                None => matches,
+                Some(previous) => intersect(&previous, &matches),
+            });
+            if result.as_ref().is_some_and(|r| r.is_empty()) {
+                break;
+            }
+        }
+
+        let mut result = result.unwrap_or_default();
+        result.reverse();
+        result
+    }
+}
+
+/// Split text into lowercase alphanumeric words
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Intersection of two ascending lists
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                out.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(title: &str, tags: &[&str]) -> HistoryEntry {
+        HistoryEntry {
+            url: format!("https://example.com/{}", title),
+            title: Some(title.to_string()),
+            uploader: Some("Some Channel".to_string()),
+            tags: tags.iter().map(|t| t.to_string()).collect(),
+            file_path: None,
+            content_hash: None,
+            completed_at: Utc::now(),
+            wall_time_secs: None,
+        }
+    }
+
+    #[test]
+    fn test_history_search() {
+        let mut history = History::default();
+        history.record(entry("Rust in 100 Seconds", &["programming"])).unwrap();
+        history.record(entry("Lo-fi beats to relax", &["music"])).unwrap();
+        history.record(entry("Rusty Lake soundtrack", &["music"])).unwrap();
+
+        assert_eq!(history.search("rust"), vec![2, 0]);
+        assert_eq!(history.search("rust mus"), vec![2]);
+        assert_eq!(history.search("channel").len(), 3);
+        assert!(history.search("jazz").is_empty());
+        assert_eq!(history.search("  "), vec![2, 1, 0]);
+    }
+}
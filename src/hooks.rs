@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::app_state::DownloadItem;
+
+/// A user script run at a lifecycle point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hook {
+    /// Shell command line, run with `sh -c` (`cmd /C` on Windows)
+    pub command: String,
+    /// Kill the script after this many seconds
+    pub timeout_secs: u64,
+}
+
+impl Default for Hook {
+    fn default() -> Self {
+        Self {
+            command: String::new(),
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// Hooks for each lifecycle point, all optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hooks {
+    pub on_add: Option<Hook>,
+    /// A non-zero exit status vetoes the download
+    pub pre_download: Option<Hook>,
+    pub post_download: Option<Hook>,
+    pub on_fail: Option<Hook>,
+    /// File scanner run on completed downloads, a non-zero exit marks the item suspect
+    pub scan: Option<Hook>,
+}
+
+/// Lifecycle points hooks can attach to
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum HookEvent {
+    OnAdd,
+    PreDownload,
+    PostDownload,
+    OnFail,
+    Scan,
+}
+
+/// Item metadata handed to hooks as JSON on stdin (and as GORLOCK_* env vars)
+#[derive(Debug, Clone, Serialize)]
+pub struct HookPayload {
+    pub event: HookEvent,
+    pub id: String,
+    pub url: String,
+    pub title: Option<String>,
+    pub status: String,
+    pub file_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// What a hook run produced
+#[derive(Debug, Clone)]
+pub struct HookResult {
+    pub success: bool,
+    /// Combined stdout/stderr lines, plus a summary line
+    pub output: Vec<String>,
+}
+
+impl Hooks {
+    pub fn get(&self, event: HookEvent) -> Option<&Hook> {
+        let hook = match event {
+            HookEvent::OnAdd => &self.on_add,
+            HookEvent::PreDownload => &self.pre_download,
+            HookEvent::PostDownload => &self.post_download,
+            HookEvent::OnFail => &self.on_fail,
+            HookEvent::Scan => &self.scan,
+        };
+        hook.as_ref().filter(|h| !h.command.trim().is_empty())
+    }
+}
+
+impl HookEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookEvent::OnAdd => "on-add",
+            HookEvent::PreDownload => "pre-download",
+            HookEvent::PostDownload => "post-download",
+            HookEvent::OnFail => "on-fail",
+            HookEvent::Scan => "scan",
+        }
+    }
+}
+
+impl HookPayload {
+    pub fn new(event: HookEvent, item: &DownloadItem) -> Self {
+        Self {
+            event,
+            id: item.id.to_string(),
+            url: item.url.clone(),
+            title: item.title.clone(),
+            status: item.status.to_string(),
+            file_path: item.file_path.clone(),
+            error: item.error.clone(),
+        }
+    }
+}
+
+/// Run a hook, feeding it the payload and capturing its output
+pub async fn run_hook(hook: &Hook, payload: &HookPayload) -> HookResult {
+    let name = payload.event.name();
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", &hook.command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", &hook.command]);
+        cmd
+    };
+
+    cmd.env("GORLOCK_EVENT", name)
+        .env("GORLOCK_ID", &payload.id)
+        .env("GORLOCK_URL", &payload.url)
+        .env("GORLOCK_TITLE", payload.title.as_deref().unwrap_or_default())
+        .env("GORLOCK_STATUS", &payload.status)
+        .env("GORLOCK_FILE", payload.file_path.as_deref().unwrap_or_default())
+        .env("GORLOCK_ERROR", payload.error.as_deref().unwrap_or_default())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            return HookResult {
+                success: false,
+                output: vec![format!("[hook {}] failed to start: {}", name, e)],
+            };
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let json = serde_json::to_vec(payload).unwrap_or_default();
+        // Scripts that ignore stdin close it early, that's fine
+        let _ = stdin.write_all(&json).await;
+    }
+
+    let timeout = Duration::from_secs(hook.timeout_secs.max(1));
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => {
+            let mut lines: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .chain(String::from_utf8_lossy(&output.stderr).lines())
+                .map(|l| format!("[hook {}] {}", name, l))
+                .collect();
+            lines.push(format!("[hook {}] exited with {}", name, output.status));
+            HookResult {
+                success: output.status.success(),
+                output: lines,
+            }
+        }
+        Ok(Err(e)) => HookResult {
+            success: false,
+            output: vec![format!("[hook {}] failed: {}", name, e)],
+        },
+        // Dropping the future kills the child
+        Err(_) => HookResult {
+            success: false,
+            output: vec![format!("[hook {}] timed out after {}s", name, timeout.as_secs())],
+        },
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn hook(command: &str) -> Hook {
+        Hook { command: command.to_string(), timeout_secs: 5 }
+    }
+
+    #[tokio::test]
+    async fn test_hook_sees_item() {
+        let mut item = DownloadItem::new("https://example.com/v?id=1&t=2".to_string());
+        item.title = Some("It's a \"title\"".to_string());
+        let payload = HookPayload::new(HookEvent::PostDownload, &item);
+
+        // Item fields reach the script as variables and as JSON, quotes and all
+        let result = run_hook(&hook(r#"echo "$GORLOCK_EVENT $GORLOCK_URL $GORLOCK_TITLE"; head -c 24"#), &payload).await;
+        assert!(result.success);
+        assert_eq!(result.output[0], r#"[hook post-download] post-download https://example.com/v?id=1&t=2 It's a "title""#);
+        assert_eq!(result.output[1], r#"[hook post-download] {"event":"post-download""#);
+        assert!(result.output.last().unwrap().contains("exited with"));
+    }
+
+    #[tokio::test]
+    async fn test_hook_failures() {
+        let payload = HookPayload::new(HookEvent::PreDownload, &DownloadItem::new("https://example.com/v".to_string()));
+
+        let result = run_hook(&hook("echo nope >&2; exit 3"), &payload).await;
+        assert!(!result.success);
+        assert_eq!(result.output[0], "[hook pre-download] nope");
+        assert!(result.output[1].contains('3'));
+
+        let result = run_hook(&Hook { command: "sleep 5".to_string(), timeout_secs: 1 }, &payload).await;
+        assert!(!result.success);
+        assert_eq!(result.output, ["[hook pre-download] timed out after 1s"]);
+    }
+}
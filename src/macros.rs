@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::app_state::{AlbumSplit, AppState, Command, DownloadStatus, FormatInfo, ItemEdit, events::DownloadAction};
+
+/// Keys a macro can be bound to, pressed after @ in the queue to replay it on the selected item
+pub const MACRO_KEYS: &[char] = &['1', '2', '3', '4', '5', '6', '7', '8', '9'];
+
+/// One recorded step of a macro
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MacroStep {
+    /// Download the item with another profile, the active one stays as it is
+    Profile(String),
+    /// Split the download into tracks by chapter
+    AlbumSplit,
+    /// Pick the format closest to the one picked while recording
+    Format {
+        /// Video height, `None` for audio-only or a format without one
+        height: Option<u32>,
+        audio_only: bool,
+        container: String,
+    },
+    /// Start the download
+    Start,
+}
+
+impl MacroStep {
+    pub fn label(&self) -> String {
+        match self {
+            MacroStep::Profile(name) => format!("profile {}", name),
+            MacroStep::AlbumSplit => "album split".to_string(),
+            MacroStep::Format { audio_only: true, .. } => "best audio".to_string(),
+            MacroStep::Format { height: Some(height), container, .. } => format!("{}p {}", height, container),
+            MacroStep::Format { height: None, container, .. } => format!("best {}", container),
+            MacroStep::Start => "start".to_string(),
+        }
+    }
+}
+
+/// A named sequence of steps replayed on queue items, kept in the config
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    #[serde(default)]
+    pub key: Option<char>,
+    pub steps: Vec<MacroStep>,
+}
+
+impl Macro {
+    pub fn summary(&self) -> String {
+        self.steps.iter().map(MacroStep::label).collect::<Vec<_>>().join(" → ")
+    }
+}
+
+/// Height of a video format from its "1280x720" resolution
+pub fn format_height(format: &FormatInfo) -> Option<u32> {
+    format.resolution.as_deref()?.split_once('x')?.1.parse().ok()
+}
+
+/// The format matching a recorded choice: the tallest video no taller than the recorded
+/// height (the shortest one above it when there is none), or the best audio
+pub fn pick_format(formats: &[FormatInfo], height: Option<u32>, audio_only: bool) -> Option<&FormatInfo> {
+    if audio_only {
+        return formats.iter().filter(|f| f.is_audio_only).max_by(|a, b| {
+            a.abr.unwrap_or(0.0).partial_cmp(&b.abr.unwrap_or(0.0)).unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+    let videos = || formats.iter().filter(|f| !f.is_audio_only).filter_map(|f| Some((format_height(f)?, f)));
+    let target = height.unwrap_or(u32::MAX);
+    videos()
+        .filter(|(h, _)| *h <= target)
+        .max_by_key(|(h, _)| *h)
+        .or_else(|| videos().min_by_key(|(h, _)| *h))
+        .map(|(_, f)| f)
+}
+
+/// Replay steps on an item, returning the action to send next. A format step needs the item's
+/// formats: without them the rest of the steps wait on the item until `FormatsFetched`.
+pub fn replay(state: &mut AppState, id: Uuid, steps: Vec<MacroStep>, formats: Option<&[FormatInfo]>) -> Option<DownloadAction> {
+    let mut steps = steps.into_iter();
+    while let Some(step) = steps.next() {
+        match step {
+            MacroStep::Profile(name) => {
+                if !state.config.profiles.iter().any(|p| p.name == name) {
+                    log(state, id, format!("Macro: no profile named {}, skipped", name));
+                } else if state.queue.iter().any(|i| i.id == id && i.profile.as_deref() != Some(name.as_str())) {
+                    state.apply(Command::EditItems(vec![(id, ItemEdit::Profile(Some(name)))]));
+                }
+            }
+            MacroStep::AlbumSplit => {
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    item.album_split.get_or_insert(AlbumSplit::Requested);
+                }
+            }
+            MacroStep::Format { height, audio_only, container } => {
+                let Some(formats) = formats else {
+                    let item = state.queue.iter_mut().find(|i| i.id == id)?;
+                    item.pending_macro = std::iter::once(MacroStep::Format { height, audio_only, container }).chain(steps).collect();
+                    return Some(DownloadAction::FetchFormats(id));
+                };
+                let Some(format) = pick_format(formats, height, audio_only) else {
+                    log(state, id, "Macro: no matching format, pick one with f".to_string());
+                    return None;
+                };
+                state.apply(Command::SetFormat {
+                    id,
+                    format: Some(Box::new(format.clone())),
+                    container: Some(container),
+                    status: DownloadStatus::Ready,
+                });
+            }
+            MacroStep::Start => return Some(DownloadAction::StartDownload(id)),
+        }
+    }
+    None
+}
+
+fn log(state: &mut AppState, id: Uuid, line: String) {
+    if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+        item.push_log(line.clone());
+    }
+    state.notice = Some(line);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::DownloadItem;
+
+    fn video(id: &str, resolution: &str) -> FormatInfo {
+        FormatInfo {
+            format_id: id.to_string(),
+            resolution: Some(resolution.to_string()),
+            ..FormatInfo::best("mp4")
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let formats = vec![video("18", "640x360"), video("22", "1280x720"), video("137", "1920x1080")];
+        assert_eq!(pick_format(&formats, Some(720), false).unwrap().format_id, "22");
+        assert_eq!(pick_format(&formats, Some(900), false).unwrap().format_id, "22");
+        assert_eq!(pick_format(&formats, Some(240), false).unwrap().format_id, "18");
+        assert_eq!(pick_format(&formats, None, false).unwrap().format_id, "137");
+
+        let mut state = AppState::default();
+        state.config.profiles.push(crate::config::Profile {
+            name: "archive".to_string(),
+            ..Default::default()
+        });
+        let item = DownloadItem::new("https://example.com/v".to_string());
+        let id = item.id;
+        state.queue.push(item);
+        let steps = vec![
+            MacroStep::Profile("archive".to_string()),
+            MacroStep::AlbumSplit,
+            MacroStep::Format { height: Some(720), audio_only: false, container: "mkv".to_string() },
+            MacroStep::Start,
+        ];
+
+        // Without formats the rest waits for them
+        assert!(matches!(replay(&mut state, id, steps, None), Some(DownloadAction::FetchFormats(_))));
+        assert_eq!(state.queue[0].album_split, Some(AlbumSplit::Requested));
+        // The profile is the item's own, the active one doesn't change
+        assert_eq!(state.queue[0].profile.as_deref(), Some("archive"));
+        assert_eq!(state.config.active_profile, "default");
+        let pending = std::mem::take(&mut state.queue[0].pending_macro);
+        assert_eq!(pending.len(), 2);
+
+        assert!(matches!(replay(&mut state, id, pending, Some(&formats)), Some(DownloadAction::StartDownload(_))));
+        assert_eq!(state.queue[0].format.as_ref().unwrap().format_id, "22");
+        assert_eq!(state.queue[0].container.as_deref(), Some("mkv"));
+    }
+}
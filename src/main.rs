@@ -12,15 +12,52 @@ use std::{
 use tokio::{sync::mpsc, time};
 
 mod app_state;
+mod archive;
+mod bug_report;
+mod bundle;
 mod cache;
 mod commands;
+mod config;
+mod ctl;
+mod description;
+mod discord;
+mod history;
+mod hooks;
+mod macros;
+mod mpris;
+mod notifications;
+mod output_template;
+mod recent;
+mod schedule;
+mod scripting;
+mod store;
 mod ui;
+mod web;
 
 use app_state::{AppState, events::*};
 use ui::{App, handle_input};
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    config::Paths::from_args(&args)?.init();
+    let args = config::Paths::other_args(&args);
+
+    if let Some(command) = ctl::CtlCommand::from_args(&args) {
+        return ctl::run(command?).await;
+    }
+
+    // Hidden developer mode: compare metadata fetch strategies and exit
+    if let Some(options) = commands::bench::BenchOptions::from_args(&args) {
+        return commands::bench::run(options?).await;
+    }
+    if let Some(options) = web::WebOptions::from_args(&args) {
+        return run_daemon(load_state(), options?).await;
+    }
+    if let Some(options) = web::monitor::MonitorOptions::from_args(&args) {
+        return web::monitor::run(options?).await;
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -29,7 +66,7 @@ async fn main() -> Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     // Setup application state
-    let mut app_state = AppState::default();
+    let mut app_state = load_state();
     let mut app = App::default();
 
     // Use bounded channels for better backpressure and memory control
@@ -81,6 +118,12 @@ async fn main() -> Result<()> {
         })
     };
 
+    let tick_task = spawn_ticker(app_tx.clone());
+    let (watch_clipboard, watch_clipboard_rx) = tokio::sync::watch::channel(app_state.config.watch_clipboard);
+    let clipboard_task = spawn_clipboard_watch(app_tx.clone(), watch_clipboard_rx);
+    let config_task = spawn_config_watch(app_tx.clone());
+    let (outputs, output_tasks) = Outputs::spawn(&app_state, &action_tx);
+
     // Download controller is now handled directly in the main event loop
 
     // Event-driven rendering with smart updates
@@ -97,6 +140,8 @@ async fn main() -> Result<()> {
             input_event = input_rx.recv() => {
                 if let Some(event) = input_event {
                     handle_input(event, &mut app_state, &action_tx).await;
+                    // The settings panel switches clipboard watching, so can a config reload below
+                    watch_clipboard.send_if_modified(|on| std::mem::replace(on, app_state.config.watch_clipboard) != *on);
                     needs_render = true; // Input always triggers render
                     if app_state.should_quit {
                         break Ok(());
@@ -108,6 +153,7 @@ async fn main() -> Result<()> {
             action = action_rx.recv() => {
                 if let Some(action) = action {
                     handle_download_action(action, &mut app_state, &app_tx).await;
+                    outputs.publish(&app_state);
                     needs_render = true; // Actions trigger render
                 }
             }
@@ -117,6 +163,10 @@ async fn main() -> Result<()> {
                 if let Some(event) = app_event {
                     // Throttle progress updates
                     let should_process = match &event {
+                        // The first update of a file carries its destination, never drop it
+                        AppEvent::ProgressUpdate { progress, .. } if progress.percent == 0.0 && progress.destination.is_some() => true,
+                        // Nor one moving to merging or post-processing, the stage timings start there
+                        AppEvent::ProgressUpdate { progress, .. } if progress.phase.is_some() => true,
                         AppEvent::ProgressUpdate { .. } => {
                             if last_progress_update.elapsed() >= progress_throttle {
                                 last_progress_update = Instant::now();
@@ -129,7 +179,14 @@ async fn main() -> Result<()> {
                     };
                     
                     if should_process {
-                        handle_app_event(event, &mut app_state).await;
+                        // `gorlock ctl status` is fine with a snapshot a second old
+                        let tick = matches!(event, AppEvent::Tick);
+                        handle_app_event(event, &mut app_state, &action_tx).await;
+                        outputs.publish(&app_state);
+                        watch_clipboard.send_if_modified(|on| std::mem::replace(on, app_state.config.watch_clipboard) != *on);
+                        if tick {
+                            outputs.publish_snapshot(&app_state);
+                        }
                         needs_render = true;
                     }
                     
@@ -156,6 +213,10 @@ async fn main() -> Result<()> {
 
     // Cleanup
     input_task.abort();
+    tick_task.abort();
+    clipboard_task.abort();
+    config_task.abort();
+    outputs.shut_down(output_tasks).await;
 
     // Restore terminal
     disable_raw_mode()?;
@@ -169,6 +230,306 @@ async fn main() -> Result<()> {
     result
 }
 
+/// Load the config, archive, history, schedules, recent URLs and scripts
+fn load_state() -> AppState {
+    let mut app_state = AppState {
+        archive: archive::Archive::load(),
+        history: history::History::load(),
+        schedules: schedule::Schedules::load(),
+        recent_urls: recent::RecentUrls::load(),
+        ..AppState::default()
+    };
+    app_state.replace_config(config::Config::load());
+    for o in app_state.config.env_overrides.clone() {
+        match o.error {
+            None => app_state.push_app_log(format!("Setting {} from {}", o.key, o.var)),
+            Some(error) => app_state.push_app_log(format!("[error] Ignoring {}: {}", o.var, error)),
+        }
+    }
+    let (scripts, script_errors) = scripting::ScriptHost::load();
+    if scripts.len() > 0 || !script_errors.is_empty() {
+        app_state.push_app_log(format!("Loaded {} script(s)", scripts.len()));
+    }
+    app_state.scripts = scripts;
+    if !script_errors.is_empty() {
+        app_state.notice = Some(format!("{} script(s) failed to load, press L for details", script_errors.len()));
+        for error in script_errors {
+            app_state.push_app_log(format!("[error] {}", error));
+        }
+    }
+    app_state
+}
+
+/// Once-a-second tick for time-based checks (recording limits etc.)
+fn spawn_ticker(app_tx: mpsc::Sender<AppEvent>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            interval.tick().await;
+            if app_tx.send(AppEvent::Tick).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Watch the clipboard for copied URLs, checked every couple of seconds while `enabled`
+fn spawn_clipboard_watch(
+    app_tx: mpsc::Sender<AppEvent>,
+    mut enabled: tokio::sync::watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(2));
+        // Whatever was copied before starting is not something seen now
+        let mut last = None;
+        let mut first = true;
+        loop {
+            interval.tick().await;
+            // Turned off, the clipboard isn't read at all until the setting is back on
+            if !*enabled.borrow_and_update() {
+                if enabled.wait_for(|on| *on).await.is_err() {
+                    break;
+                }
+                first = true;
+            }
+            let Ok(text) = tokio::task::spawn_blocking(recent::read_clipboard).await else {
+                break;
+            };
+            let text = text.map(|t| t.trim().to_string());
+            if text == last {
+                continue;
+            }
+            last = text.clone();
+            if std::mem::take(&mut first) {
+                continue;
+            }
+            if let Some(url) = text.filter(|t| recent::looks_like_url(t)) {
+                if app_tx.send(AppEvent::ClipboardUrl(url)).await.is_err() {
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Watch the config file, telling the app once a burst of writes to it has settled
+fn spawn_config_watch(app_tx: mpsc::Sender<AppEvent>) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let Some(path) = config::config_file() else {
+            return;
+        };
+        let (tx, mut changes) = mpsc::unbounded_channel();
+        let file_name = path.file_name().map(|n| n.to_os_string());
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if let Ok(event) = event {
+                let ours = event.paths.iter().any(|p| p.file_name().map(|n| n.to_os_string()) == file_name);
+                if ours && !event.kind.is_access() {
+                    let _ = tx.send(());
+                }
+            }
+        });
+        // Editors save by replacing the file, so watch the directory rather than the file
+        let watching = watcher.and_then(|mut watcher| {
+            let dir = path.parent().unwrap_or(&path);
+            let _ = std::fs::create_dir_all(dir);
+            notify::Watcher::watch(&mut watcher, dir, notify::RecursiveMode::NonRecursive)?;
+            Ok(watcher)
+        });
+        let _watcher = match watching {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                tracing::debug!("Not watching the config file: {}", e);
+                return;
+            }
+        };
+        while changes.recv().await.is_some() {
+            // A save can be several writes, read the file once they are done
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            while changes.try_recv().is_ok() {}
+            if app_tx.send(AppEvent::ConfigFileChanged).await.is_err() {
+                break;
+            }
+        }
+    })
+}
+
+/// Apply the config file's settings if all of them are valid, reporting what changed.
+/// Only `RELOAD_SETTINGS` apply, the others keep their running values until the next start.
+async fn reload_config(state: &mut AppState) {
+    let Some(path) = config::config_file() else {
+        return;
+    };
+    let Ok(contents) = tokio::fs::read_to_string(&path).await else {
+        // Removed or being replaced, keep the running settings
+        return;
+    };
+    let mut config = match config::Config::parse(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            let message = format!("Config not reloaded, {}: {}", path.display(), e);
+            state.push_app_log(format!("[error] {}", message));
+            state.notice = Some(message);
+            return;
+        }
+    };
+    config.apply_env(std::env::vars());
+    let changed = config.changed_settings(&state.config);
+    // gorlock's own saves change nothing
+    if changed.is_empty() {
+        return;
+    }
+    let config = state.config.reload(config);
+    let later: Vec<&String> = changed.iter().filter(|c| config.restart_pending.iter().any(|p| &&p.key == c)).collect();
+    state.replace_config(config);
+    let mut message = format!("Config reloaded: {}", changed.join(", ").replace('_', " "));
+    if !later.is_empty() {
+        message.push_str(&format!(" ({} after a restart)", later.iter().map(|c| c.replace('_', " ")).collect::<Vec<_>>().join(", ")));
+    }
+    state.push_app_log(message.clone());
+    state.notice = Some(message);
+}
+
+/// Where the queue's state is shown outside the terminal: MPRIS, Discord and `gorlock ctl`,
+/// which with SIGUSR1/SIGUSR2 can also pause and resume the queue
+struct Outputs {
+    mpris: tokio::sync::watch::Sender<mpris::QueueStatus>,
+    presence: tokio::sync::watch::Sender<Option<discord::Presence>>,
+    /// The queue as JSON, for `gorlock ctl status` and the web UI
+    snapshots: tokio::sync::watch::Sender<String>,
+}
+
+impl Outputs {
+    fn spawn(state: &AppState, action_tx: &mpsc::Sender<DownloadAction>) -> (Self, Vec<tokio::task::JoinHandle<()>>) {
+        let (mpris, mpris_rx) = tokio::sync::watch::channel(mpris::QueueStatus::of(state));
+        let (presence, presence_rx) = tokio::sync::watch::channel(None);
+        let (snapshots, snapshots_rx) = tokio::sync::watch::channel(web::snapshot(state));
+        let action_tx = action_tx.clone();
+        let tasks = vec![
+            tokio::spawn(ctl::serve(action_tx.clone(), snapshots_rx)),
+            tokio::spawn(ctl::signals(action_tx.clone())),
+            // Media keys and desktop widgets, where there's a session bus
+            tokio::spawn(async move {
+                if let Err(e) = mpris::serve(action_tx, mpris_rx).await {
+                    tracing::debug!("MPRIS unavailable: {}", e);
+                }
+            }),
+            tokio::spawn(discord::run(presence_rx)),
+        ];
+        let outputs = Self { mpris, presence, snapshots };
+        outputs.publish(state);
+        (outputs, tasks)
+    }
+
+    fn publish(&self, state: &AppState) {
+        let status = mpris::QueueStatus::of(state);
+        self.mpris.send_if_modified(|current| std::mem::replace(current, status) != status);
+        let presence = discord::Presence::of(state);
+        self.presence.send_if_modified(|current| {
+            let changed = *current != presence;
+            *current = presence;
+            changed
+        });
+    }
+
+    /// Publish the queue snapshot, every now and then as it's the whole queue
+    fn publish_snapshot(&self, state: &AppState) {
+        let snapshot = web::snapshot(state);
+        self.snapshots.send_if_modified(|current| {
+            let changed = *current != snapshot;
+            *current = snapshot;
+            changed
+        });
+    }
+
+    /// Clear the Discord presence before exiting, without holding up the exit for long
+    async fn shut_down(self, tasks: Vec<tokio::task::JoinHandle<()>>) {
+        drop(self);
+        for mut task in tasks {
+            if tokio::time::timeout(Duration::from_secs(2), &mut task).await.is_err() {
+                task.abort();
+            }
+        }
+    }
+}
+
+/// Resolves on Ctrl+C, or SIGTERM from `docker stop` and service managers
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+                return;
+            }
+            Err(e) => tracing::warn!("Can't listen for SIGTERM: {}", e),
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// Run without the TUI, managed through the web UI, until Ctrl+C or SIGTERM
+async fn run_daemon(mut state: AppState, options: web::WebOptions) -> Result<()> {
+    tracing_subscriber::fmt().with_writer(io::stderr).init();
+
+    let (app_tx, mut app_rx) = mpsc::channel::<AppEvent>(256);
+    let (action_tx, mut action_rx) = mpsc::channel::<DownloadAction>(128);
+    let (outputs, output_tasks) = Outputs::spawn(&state, &action_tx);
+
+    let listener = tokio::net::TcpListener::bind(&options.listen).await?;
+    eprintln!("gorlock web UI on http://{}/", options.listen);
+    let auth = web::Auth::new(options.token, state.config.api_tokens.clone());
+    if auth.is_open() && !listener.local_addr()?.ip().is_loopback() {
+        eprintln!("Warning: no --token set, anyone who can reach {} can control downloads", options.listen);
+    }
+    let (events_tx, _) = tokio::sync::broadcast::channel::<String>(256);
+    let control = web::Control {
+        action_tx: action_tx.clone(),
+        snapshots: outputs.snapshots.subscribe(),
+        events: events_tx.clone(),
+    };
+    let (auth_tx, auth_rx) = tokio::sync::watch::channel(auth);
+    let server = tokio::spawn(web::serve(listener, auth_rx, control));
+    let tick_task = spawn_ticker(app_tx.clone());
+    let config_task = spawn_config_watch(app_tx.clone());
+    let mut publish = tokio::time::interval(Duration::from_millis(250));
+    let shutdown = shutdown_signal();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            Some(action) = action_rx.recv() => handle_download_action(action, &mut state, &app_tx).await,
+            Some(event) = app_rx.recv() => {
+                let streamed = web::StreamEvent::from_app_event(&event);
+                handle_app_event(event, &mut state, &action_tx).await;
+                // Tokens added or revoked in config.json apply to the next request
+                auth_tx.send_if_modified(|auth| auth.set_tokens(&state.config.api_tokens));
+                // Without subscribers there's nobody to send to, skip the JSON
+                if let Some(streamed) = streamed.filter(|_| events_tx.receiver_count() > 0) {
+                    let _ = events_tx.send(streamed.into_json(&state));
+                }
+            }
+            _ = publish.tick() => {
+                outputs.publish_snapshot(&state);
+                outputs.publish(&state);
+            }
+            _ = &mut shutdown => break,
+        }
+    }
+
+    server.abort();
+    tick_task.abort();
+    config_task.abort();
+    outputs.shut_down(output_tasks).await;
+    for (_, task) in state.running_tasks.drain() {
+        task.abort();
+    }
+    Ok(())
+}
+
 /// Handle download actions in the main event loop
 async fn handle_download_action(
     action: DownloadAction,
@@ -177,10 +538,47 @@ async fn handle_download_action(
 ) {
     match action {
         DownloadAction::AddUrl(url) => {
-            // First check if this might be a playlist by trying to get entries
+            state.remember_url(&url, recent::RecentSource::Added);
             let app_tx_clone = app_tx.clone();
             let url_clone = url.clone();
-            
+
+            // Pages a custom extractor rule covers are scraped for direct links, which go through
+            // the playlist preview like a playlist's entries
+            if let Some(rule) = state.config.extractor_for(&url).cloned() {
+                tokio::spawn(async move {
+                    let event = match commands::extractor::fetch_links(&rule, &url_clone).await {
+                        Ok(entries) if entries.is_empty() => AppEvent::PlaylistFetchFailed {
+                            error: format!("The extractor for {} found no links on the page", rule.page()),
+                        },
+                        Ok(entries) => AppEvent::PlaylistDetected { entries },
+                        Err(e) => AppEvent::PlaylistFetchFailed {
+                            error: format!("Failed to extract links from {}: {}", url_clone, e),
+                        },
+                    };
+                    let _ = app_tx_clone.send(event).await;
+                });
+                return;
+            }
+
+            // Reddit posts and subreddits list the videos they link to, for picking in the preview
+            if commands::reddit::is_reddit(&url) {
+                let comments = state.config.reddit_comment_links;
+                tokio::spawn(async move {
+                    let event = match commands::reddit::media_links(&url_clone, comments).await {
+                        Ok(entries) if entries.is_empty() => AppEvent::PlaylistFetchFailed {
+                            error: format!("No videos linked from {}", url_clone),
+                        },
+                        Ok(entries) => AppEvent::PlaylistDetected { entries },
+                        Err(e) => AppEvent::PlaylistFetchFailed {
+                            error: format!("Failed to read {}: {}", url_clone, e),
+                        },
+                    };
+                    let _ = app_tx_clone.send(event).await;
+                });
+                return;
+            }
+
+            // First check if this might be a playlist by trying to get entries
             tokio::spawn(async move {
                 match commands::yt_dlp::fetch_playlist_entries(&url_clone).await {
                     Ok(entries) => {
@@ -206,18 +604,174 @@ async fn handle_download_action(
                 }
             });
         }
+        DownloadAction::AddRemote(url) => {
+            state.remember_url(&url, recent::RecentSource::Added);
+            // Nobody is there to pick from a preview, every link a custom extractor finds is queued
+            if let Some(rule) = state.config.extractor_for(&url).cloned() {
+                let app_tx_clone = app_tx.clone();
+                tokio::spawn(async move {
+                    let result = commands::extractor::fetch_links(&rule, &url)
+                        .await
+                        .map(|entries| entries.into_iter().map(|(link, _, _)| link).collect())
+                        .map_err(|e| e.to_string());
+                    let _ = app_tx_clone.send(AppEvent::LinksExtracted { page: url, result }).await;
+                });
+                return;
+            }
+            let mut item = app_state::DownloadItem::new(url);
+            let id = item.id;
+            item.auto_start = true;
+            item.status = app_state::DownloadStatus::FetchingInfo;
+            if let Some(reason) = state.scripts.run(hooks::HookEvent::OnAdd, &mut item) {
+                state.notice = Some(format!("Rejected by script: {}", reason));
+                return;
+            }
+            state.apply(app_state::Command::AddItems(vec![item]));
+            let run_hook = DownloadAction::RunHook { id, event: hooks::HookEvent::OnAdd };
+            Box::pin(handle_download_action(run_hook, state, app_tx)).await;
+            Box::pin(handle_download_action(DownloadAction::FetchFormats(id), state, app_tx)).await;
+        }
         DownloadAction::StartDownload(id) => {
+            if let Some(reason) = state.network_hold() {
+                state.error_message = Some(format!("Downloads are held by the network guard: {}", reason));
+                return;
+            }
+            // Over the download slots, its site's limits, while its site's breaker is open or while it
+            // waits out a transient failure the item waits in the queue, the ticker starts it later
+            if let Some(reason) = state
+                .slot_hold(id)
+                .or_else(|| state.site_hold(id))
+                .or_else(|| state.breaker_hold(id))
+                .or_else(|| state.retry_hold(id))
+            {
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    if !item.site_wait {
+                        item.push_log(format!("Waiting: {}", reason));
+                    }
+                    item.site_wait = true;
+                    item.status = app_state::DownloadStatus::Queued;
+                }
+                return;
+            }
+            if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                if let Some(reason) = state.scripts.run(hooks::HookEvent::PreDownload, item) {
+                    // Goes through the usual failure path so hooks and batches see it. Sent from a task,
+                    // this loop drains the channel so it can't wait for room itself.
+                    let app_tx_clone = app_tx.clone();
+                    tokio::spawn(async move {
+                        let _ = app_tx_clone
+                            .send(AppEvent::DownloadFailed { id, error: format!("Rejected by script: {}", reason) })
+                            .await;
+                    });
+                    return;
+                }
+            }
+            // Only a download that really starts counts towards its site's spacing, or tries its breaker
+            if let Some(url) = state.queue.iter().find(|i| i.id == id && i.format.is_some()).map(|i| i.url.clone()) {
+                state.record_site_start(&url);
+                state.breakers.record_start(&url, id, std::time::Instant::now());
+            }
             if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
                 if let Some(format) = &item.format {
-                    let format_id = format.format_id.clone();
+                    state.notify_run.start();
                     let url = item.url.clone();
-                    let output_dir = state.output_dir.clone();
+                    let profile = state.config.profile_for(item.profile.as_deref());
+                    let concurrent_fragments = format
+                        .is_fragmented()
+                        .then_some(profile.concurrent_fragments);
+                    let merge_container = item
+                        .container
+                        .clone()
+                        .unwrap_or_else(|| state.config.merge_container.clone());
+
+                    // Audio-only downloads keep their own extension, video ends up in the merge container
+                    let target_container = if format.is_audio_only {
+                        format.ext.clone()
+                    } else {
+                        merge_container.clone()
+                    };
+                    // Album splits re-cut the audio, so cover art would be dropped anyway
+                    let extract_audio = item.album_split.is_some();
+                    let thumbnail_format = app_state::thumbnail_format_for(&target_container);
+                    item.thumbnail = match (profile.embed_thumbnail && !extract_audio, thumbnail_format) {
+                        (false, _) => None,
+                        (true, Some(_)) => Some(app_state::ThumbnailEmbed::Pending),
+                        (true, None) => Some(app_state::ThumbnailEmbed::Skipped(format!(
+                            "{} does not support embedded thumbnails",
+                            target_container
+                        ))),
+                    };
+
+                    let options = commands::yt_dlp::DownloadOptions {
+                        format_id: format.format_id.clone(),
+                        output_dir: item.output_dir.clone().unwrap_or_else(|| state.output_dir.clone()),
+                        output_template: item
+                            .output_template
+                            .clone()
+                            .unwrap_or_else(|| state.config.output_template.clone()),
+                        merge_container,
+                        concurrent_fragments,
+                        thumbnail_format: thumbnail_format
+                            .filter(|_| profile.embed_thumbnail && !extract_audio)
+                            .map(|f| f.to_string()),
+                        extract_audio,
+                        live: item.live.as_ref().map(|live| live.download_options(chrono::Utc::now())),
+                        source_address: item.source_address.clone().or_else(|| profile.source_address.clone()),
+                        extra_args: [state.config.site_args(&item.url), item.retry_args.clone()].concat(),
+                        // A throttled recording would fall behind the stream
+                        write_limit: match state.config.write_limit {
+                            _ if item.live.is_some() => None,
+                            commands::disk::WriteLimit::Off => None,
+                            commands::disk::WriteLimit::Auto => state.write_watch.cap(
+                                item.output_dir.as_deref().unwrap_or(&state.output_dir),
+                            ),
+                            commands::disk::WriteLimit::Fixed(bytes) => Some(bytes),
+                        },
+                    };
+                    if let Some(limit) = options.write_limit {
+                        item.push_log(format!("Writing at most {}/s", app_state::format_bytes(limit)));
+                    }
                     let app_tx_clone = app_tx.clone();
 
+                    if let Some(live) = &mut item.live {
+                        live.recording_since = Some(chrono::Utc::now());
+                        live.stopping = false;
+                        let message = format!("Recording from {}, stopping {}", live.start.label(), live.stop_label());
+                        item.push_log(message);
+                    }
+
+                    item.concurrent_fragments = concurrent_fragments;
                     item.status = app_state::DownloadStatus::Downloading;
+                    item.site_wait = false;
+                    item.started_at.get_or_insert_with(chrono::Utc::now);
+                    item.finished_at = None;
+                    item.timings.enter(app_state::timing::Phase::Download, chrono::Utc::now());
+
+                    let (stop_tx, stop_rx) = tokio::sync::watch::channel(commands::yt_dlp::StopRequest::None);
+                    state.stop_signals.insert(id, stop_tx);
+
+                    let pre_download = state
+                        .config
+                        .hooks
+                        .get(hooks::HookEvent::PreDownload)
+                        .cloned()
+                        .map(|hook| (hook, hooks::HookPayload::new(hooks::HookEvent::PreDownload, item)));
 
                     // Start download in background
                     let download_task = tokio::spawn(async move {
+                        // The pre-download hook can veto the download with a non-zero exit
+                        if let Some((hook, payload)) = pre_download {
+                            let result = hooks::run_hook(&hook, &payload).await;
+                            let _ = app_tx_clone.send(AppEvent::HookFinished { id, output: result.output }).await;
+                            if !result.success {
+                                let _ = app_tx_clone.send(AppEvent::DownloadFailed {
+                                    id,
+                                    error: "Download rejected by pre-download hook".to_string(),
+                                }).await;
+                                return Ok(());
+                            }
+                        }
+
                         let (progress_tx, mut progress_rx) = mpsc::unbounded_channel();
 
                         // Spawn progress forwarding task
@@ -231,18 +785,18 @@ async fn handle_download_action(
                         };
 
                         // Start actual download
-                        match commands::yt_dlp::start_download(
-                            &url,
-                            &format_id,
-                            &output_dir,
-                            progress_tx,
-                        )
-                        .await
-                        {
-                            Ok(()) => {
-                                let _ = app_tx_clone.send(AppEvent::DownloadCompleted { id }).await;
+                        match commands::yt_dlp::start_download(&url, &options, progress_tx, stop_rx).await {
+                            Ok(outcome) => {
+                                let _ = app_tx_clone.send(AppEvent::DownloadCompleted { id, outcome }).await;
+                            }
+                            Err(e) if e.is::<commands::yt_dlp::StoppedEarly>() => {
+                                let _ = app_tx_clone.send(AppEvent::DownloadStopped { id, reason: e.to_string() }).await;
                             }
                             Err(e) => {
+                                if let Some(failure) = e.downcast_ref::<commands::yt_dlp::DownloadFailure>() {
+                                    let lines = failure.output.clone();
+                                    let _ = app_tx_clone.send(AppEvent::FailureOutput { id, lines }).await;
+                                }
                                 let _ = app_tx_clone.send(AppEvent::DownloadFailed {
                                     id,
                                     error: format!("Download failed: {}", e),
@@ -263,35 +817,50 @@ async fn handle_download_action(
             if let Some(handle) = state.running_tasks.remove(&id) {
                 handle.abort();
             }
+            state.stop_signals.remove(&id);
 
             if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
                 item.status = app_state::DownloadStatus::Cancelled;
+                item.timings.leave(chrono::Utc::now());
+                // Cancelling an item of the running batch cancels the rest of it too
+                if item.batch_id.is_some() && item.batch_id == state.batch.as_ref().map(|b| b.batch_id) {
+                    state.abort_batch();
+                }
             }
         }
-        DownloadAction::RemoveItem(id) => {
-            // Cancel any running task
-            if let Some(handle) = state.running_tasks.remove(&id) {
-                handle.abort();
+        DownloadAction::FinishRecording(id) => {
+            if let Some(stop_tx) = state.stop_signals.get(&id) {
+                let _ = stop_tx.send(commands::yt_dlp::StopRequest::Finish);
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    item.push_log("Stopping recording");
+                    if let Some(live) = &mut item.live {
+                        live.stopping = true;
+                    }
+                }
+            }
+        }
+        DownloadAction::StopAfterFragment(id) => {
+            if let Some(stop_tx) = state.stop_signals.get(&id) {
+                let _ = stop_tx.send(commands::yt_dlp::StopRequest::AfterFragment);
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    item.push_log("Stopping after the current fragment");
+                }
+                state.notice = Some("Stopping after the current fragment...".to_string());
             }
-
-            // Remove from queue - this is already handled in the input handler
-            // for immediate UI responsiveness
         }
         DownloadAction::FetchFormats(id) => {
             if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
                 let url = item.url.clone();
                 item.status = app_state::DownloadStatus::FetchingInfo;
+                item.timings.enter(app_state::timing::Phase::Fetch, chrono::Utc::now());
+                let mut extra_args = state.config.site_args(&url);
+                extra_args.extend(item.retry_args.iter().cloned());
 
                 let app_tx_clone = app_tx.clone();
                 tokio::spawn(async move {
-                    match commands::yt_dlp::fetch_formats(&url).await {
-                        Ok((formats, title, duration)) => {
-                            let _ = app_tx_clone.send(AppEvent::FormatsFetched {
-                                id,
-                                formats,
-                                title,
-                                duration,
-                            }).await;
+                    match commands::yt_dlp::fetch_formats_with(&url, &extra_args).await {
+                        Ok(fetched) => {
+                            let _ = app_tx_clone.send(formats_fetched_event(id, fetched)).await;
                         }
                         Err(e) => {
                             let _ = app_tx_clone.send(AppEvent::FormatsFetchFailed {
@@ -303,81 +872,1204 @@ async fn handle_download_action(
                 });
             }
         }
-        // TODO: Implement pause/resume functionality
-        DownloadAction::PauseDownload(_id) => {
-            // Placeholder - requires process management
-        }
-        DownloadAction::ResumeDownload(_id) => {
-            // Placeholder - requires process management
-        }
-    }
-}
+        DownloadAction::RetryExtraction { id, step, tried, args } => {
+            let Some(item) = state.queue.iter_mut().find(|i| i.id == id) else {
+                return;
+            };
+            let url = item.url.clone();
+            item.status = app_state::DownloadStatus::FetchingInfo;
+            item.timings.enter(app_state::timing::Phase::Fetch, chrono::Utc::now());
+            item.push_log(format!("Trying: {}", tried));
+            let mut fetch_args = state.config.site_args(&url);
+            fetch_args.extend(args.iter().cloned());
+            let app_tx_clone = app_tx.clone();
 
-/// Handle application events from background tasks
-async fn handle_app_event(event: AppEvent, state: &mut AppState) {
-    match event {
-        AppEvent::Quit => {
-            state.should_quit = true;
-        }
-        AppEvent::ProgressUpdate { id, progress } => {
-            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
-                item.progress = progress;
-                if item.status != app_state::DownloadStatus::Downloading {
-                    item.status = app_state::DownloadStatus::Downloading;
+            tokio::spawn(async move {
+                let attempt: Result<(String, commands::yt_dlp::FetchedFormats)> = async {
+                    let mut summary = String::new();
+                    if step == commands::troubleshoot::RetryStep::UpdateYtDlp {
+                        summary = format!("{}, ", commands::troubleshoot::update_yt_dlp().await?);
+                    }
+                    let fetched = commands::yt_dlp::fetch_formats_with(&url, &fetch_args).await?;
+                    summary.push_str(&format!("found {} formats", fetched.formats.len()));
+                    Ok((summary, fetched))
                 }
-            }
+                .await;
+
+                // Report first so the wizard closes before the format popup opens
+                match attempt {
+                    Ok((summary, fetched)) => {
+                        let _ = app_tx_clone
+                            .send(AppEvent::RetryFinished { id, tried, args, result: Ok(summary) })
+                            .await;
+                        let _ = app_tx_clone.send(formats_fetched_event(id, fetched)).await;
+                    }
+                    Err(e) => {
+                        let _ = app_tx_clone
+                            .send(AppEvent::RetryFinished { id, tried, args, result: Err(e.to_string()) })
+                            .await;
+                    }
+                }
+            });
         }
-        AppEvent::DownloadCompleted { id } => {
-            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
-                item.status = app_state::DownloadStatus::Completed;
-            }
-            state.running_tasks.remove(&id);
+        DownloadAction::PickPath(kind) => {
+            let Some(pick) = kind.pick() else {
+                return;
+            };
+            let start_in = match pick {
+                commands::portal::PickKind::Directory => state.output_dir.clone(),
+                commands::portal::PickKind::File => config::home_dir().unwrap_or_default().to_string_lossy().into_owned(),
+            };
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::portal::pick_path(kind.title(), pick, Some(&start_in)).await;
+                let _ = app_tx_clone
+                    .send(AppEvent::PathPicked { kind, result: result.map_err(|e| e.to_string()) })
+                    .await;
+            });
         }
-        AppEvent::DownloadFailed { id, error } => {
-            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
-                item.status = app_state::DownloadStatus::Failed;
-                item.error = Some(error);
+        DownloadAction::Notify { message, test } => {
+            let settings = state.config.notifications.clone();
+            if !settings.is_configured() {
+                return;
             }
-            state.running_tasks.remove(&id);
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let results = notifications::send(&settings, &message)
+                    .await
+                    .into_iter()
+                    .map(|(backend, result)| (backend, result.map_err(|e| e.to_string())))
+                    .collect();
+                let _ = app_tx_clone.send(AppEvent::NotificationSent { test, results }).await;
+            });
         }
-        AppEvent::FormatsFetched {
-            id,
-            formats,
-            title,
-            duration,
-        } => {
-            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
-                item.title = Some(title);
-                item.duration = duration;
-                item.status = app_state::DownloadStatus::Ready;
+        DownloadAction::VerifyFile(id) => {
+            let Some(item) = state.queue.iter().find(|i| i.id == id) else {
+                return;
+            };
+            let Some(path) = item.file_path.clone() else {
+                return;
+            };
+            let check_magic = state.config.verify_file_type;
+            let scanner = state
+                .config
+                .hooks
+                .get(hooks::HookEvent::Scan)
+                .cloned()
+                .map(|hook| (hook, hooks::HookPayload::new(hooks::HookEvent::Scan, item)));
+            let app_tx_clone = app_tx.clone();
 
-                // Show format selection popup
-                state.format_popup = Some(app_state::FormatPopup {
-                    item_id: id,
-                    formats,
-                    selected_index: 0,
-                    audio_only_filter: false,
-                });
-            }
+            tokio::spawn(async move {
+                let mut problems = Vec::new();
+                let mut output = Vec::new();
+
+                if check_magic {
+                    match commands::verify::check_magic(&path) {
+                        Ok(Some(mismatch)) => problems.push(format!("File type mismatch: {}", mismatch)),
+                        Ok(None) => {}
+                        Err(e) => problems.push(format!("Could not read file: {}", e)),
+                    }
+                }
+
+                if let Some((hook, payload)) = scanner {
+                    let result = hooks::run_hook(&hook, &payload).await;
+                    if !result.success {
+                        problems.push(format!(
+                            "Scanner flagged the file: {}",
+                            result.output.last().cloned().unwrap_or_default()
+                        ));
+                    }
+                    output = result.output;
+                }
+
+                let _ = app_tx_clone.send(AppEvent::FileVerified { id, problems, output }).await;
+            });
         }
-        AppEvent::FormatsFetchFailed { id, error } => {
-            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
-                item.status = app_state::DownloadStatus::Failed;
-                item.error = Some(error.clone());
-            }
-            state.error_message = Some(error);
+        DownloadAction::HashFile(id) => {
+            let Some(path) = state.queue.iter().find(|i| i.id == id).and_then(|i| i.file_path.clone()) else {
+                return;
+            };
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::dedupe::content_hash(&path).await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::ContentHashed { id, result }).await;
+            });
         }
-        AppEvent::UrlValidated {
-            url: _,
-            is_valid: _,
-            error,
-        } => {
-            if let Some(error) = error {
-                state.error_message = Some(error);
+        DownloadAction::CheckNetwork => {
+            let Some(guard) = state.config.network_guard.clone() else {
+                return;
+            };
+            state.network_watch.checking = true;
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = guard.check().await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::NetworkChecked { result }).await;
+            });
+        }
+        DownloadAction::DetectAddresses => {
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::network::local_addresses().await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::AddressesDetected { result }).await;
+            });
+        }
+        DownloadAction::ProbeQuality(id) => {
+            let Some(item) = state.queue.iter().find(|i| i.id == id) else {
+                return;
+            };
+            let Some(path) = item.file_path.clone() else {
+                return;
+            };
+            let expected = commands::probe::Expected {
+                resolution: item
+                    .format
+                    .as_ref()
+                    .and_then(|f| f.resolution.as_deref())
+                    .and_then(commands::probe::parse_resolution),
+                audio_only: item.format.as_ref().is_some_and(|f| f.is_audio_only) || item.album_split.is_some(),
+                // Recordings are cut wherever they were stopped
+                duration: if item.live.is_some() {
+                    None
+                } else {
+                    item.duration
+                        .as_deref()
+                        .and_then(app_state::parse_duration_to_seconds)
+                        .map(|secs| secs as f64)
+                },
+            };
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::probe::probe_quality(&path, &expected).await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::QualityProbed { id, result }).await;
+            });
+        }
+        DownloadAction::RunStage { id, index } => {
+            let Some(item) = state.queue.iter_mut().find(|i| i.id == id) else {
+                return;
+            };
+            let (Some(path), Some(run)) = (item.file_path.clone(), item.post_processing.get_mut(index)) else {
+                return;
+            };
+            // CPU-bound, so it waits for a post-processing worker rather than a download slot
+            let workers = state.config.post_processing_workers as usize;
+            if !state.post_workers.start(&DownloadAction::RunStage { id, index }, workers) {
+                let message = format!("Post-processing: {} waiting for a worker", run.stage.label());
+                item.push_log(message);
+                return;
             }
+            run.status = commands::postprocess::StageStatus::Running;
+            item.timings.enter(app_state::timing::Phase::PostProcess, chrono::Utc::now());
+            let stage = run.stage.clone();
+            let input = commands::postprocess::StageInput {
+                path: path.into(),
+                url: item.url.clone(),
+                title: item.title.clone(),
+                uploader: item.metadata.uploader.clone(),
+                upload_date: item.metadata.upload_date.clone(),
+            };
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::postprocess::run(&stage, &input).await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::StageFinished { id, index, result }).await;
+            });
         }
-        AppEvent::PlaylistDetected { entries } => {
-            // Clear loading state
+        DownloadAction::SkipStage { id, index } => {
+            // Going on with the chain can start stages and follow-up work, which is the app loop's
+            let _ = app_tx.send(AppEvent::StageSkipped { id, index }).await;
+        }
+        DownloadAction::ArchiveComments(id) => {
+            let Some(item) = state.queue.iter_mut().find(|i| i.id == id) else {
+                return;
+            };
+            let max_comments = state.config.profile_for(item.profile.as_deref()).comment_limit;
+            let Some(path) = item.file_path.clone() else {
+                return;
+            };
+            if max_comments == 0 {
+                return;
+            }
+            item.comments = Some(app_state::CommentArchive::Running(max_comments));
+            let url = item.url.clone();
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::comments::archive_comments(&url, &path, max_comments)
+                    .await
+                    .map(|(count, path)| (count, path.display().to_string()))
+                    .map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::CommentsArchived { id, result }).await;
+            });
+        }
+        DownloadAction::RefreshEngagement { id, force } => {
+            let Some(item) = state.queue.iter_mut().find(|i| i.id == id) else {
+                return;
+            };
+            if item.metadata.refreshing {
+                return;
+            }
+            item.metadata.refreshing = true;
+            let url = item.url.clone();
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::engagement::fetch_engagement(&url, force)
+                    .await
+                    .map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::EngagementFetched { id, result }).await;
+            });
+        }
+        DownloadAction::RefreshMetadata(ids) => {
+            let targets: Vec<(uuid::Uuid, String, Vec<String>)> = state
+                .queue
+                .iter()
+                .filter(|i| ids.contains(&i.id))
+                .map(|i| (i.id, i.url.clone(), [state.config.site_args(&i.url), i.retry_args.clone()].concat()))
+                .collect();
+            state.metadata_refresh = Some(app_state::MetadataRefresh {
+                remaining: targets.len(),
+                ..Default::default()
+            });
+            state.notice = Some(format!("Refreshing metadata of {} item(s)...", targets.len()));
+            // A few at a time, a restored queue can be long
+            let permits = std::sync::Arc::new(tokio::sync::Semaphore::new(4));
+            for (id, url, extra_args) in targets {
+                let (app_tx_clone, permits) = (app_tx.clone(), permits.clone());
+                tokio::spawn(async move {
+                    let _permit = permits.acquire().await;
+                    let result = commands::yt_dlp::fetch_formats_with(&url, &extra_args).await;
+                    if let Ok(fetched) = &result {
+                        let mut entry = cache::CachedEntry::new(url.clone(), fetched.title.clone(), fetched.duration.clone());
+                        entry.formats = Some(fetched.formats.clone());
+                        if let Err(e) = cache::get_cache().await.set(url, entry).await {
+                            tracing::debug!("Failed to update the metadata cache: {}", e);
+                        }
+                    }
+                    let result = result.map(Box::new).map_err(|e| e.to_string());
+                    let _ = app_tx_clone.send(AppEvent::MetadataRefreshed { id, result }).await;
+                });
+            }
+        }
+        DownloadAction::CheckAvailability(id) => {
+            let Some(item) = state.queue.iter().find(|i| i.id == id) else {
+                state.availability_checks.in_flight = state.availability_checks.in_flight.saturating_sub(1);
+                return;
+            };
+            let url = item.url.clone();
+            let extra_args = [state.config.site_args(&url), item.retry_args.clone()].concat();
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::availability::check_available(&url, &extra_args)
+                    .await
+                    .map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::AvailabilityChecked { id, result }).await;
+            });
+        }
+        DownloadAction::WaybackLookup { id, asked } => {
+            let Some(item) = state.queue.iter().find(|i| i.id == id) else {
+                return;
+            };
+            let url = item.url.clone();
+            state.notice = Some("Looking for a Wayback Machine snapshot...".to_string());
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = commands::availability::wayback_snapshot(&url).await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::WaybackLooked { id, asked, result }).await;
+            });
+        }
+        DownloadAction::RunHook { id, event } => {
+            let Some(hook) = state.config.hooks.get(event).cloned() else {
+                return;
+            };
+            if let Some(item) = state.queue.iter().find(|i| i.id == id) {
+                let payload = hooks::HookPayload::new(event, item);
+                let app_tx_clone = app_tx.clone();
+                tokio::spawn(async move {
+                    let result = hooks::run_hook(&hook, &payload).await;
+                    let _ = app_tx_clone.send(AppEvent::HookFinished { id, output: result.output }).await;
+                });
+            }
+        }
+        DownloadAction::ImportArchive(path) => {
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = tokio::fs::read_to_string(&path)
+                    .await
+                    .map(|contents| archive::parse_import(&contents))
+                    .map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::ArchiveImportParsed { path, result }).await;
+            });
+        }
+        DownloadAction::ExportArchive(path) => {
+            let contents = state.archive.to_ytdlp_archive();
+            let count = state.archive.len();
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = tokio::fs::write(&path, contents)
+                    .await
+                    .map(|_| count)
+                    .map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::ArchiveExported { path, result }).await;
+            });
+        }
+        DownloadAction::ExportBundle(path) => {
+            let queue = state.queue.clone();
+            let archive = state.archive.keys();
+            let output_dir = state.output_dir.clone();
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = tokio::task::spawn_blocking({
+                    let path = path.clone();
+                    move || {
+                        let bundle = bundle::StateBundle::capture(queue, archive, output_dir);
+                        bundle.write(&path)?;
+                        Ok::<_, anyhow::Error>((bundle.queue.len(), bundle.partial_files.len()))
+                    }
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|r| r.map_err(|e| e.to_string()));
+                let _ = app_tx_clone.send(AppEvent::BundleExported { path, result }).await;
+            });
+        }
+        DownloadAction::ExportBugReport { path, report } => {
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = report.write(&path).await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::BugReportExported { path, result }).await;
+            });
+        }
+        DownloadAction::ImportBundle(path) => {
+            let app_tx_clone = app_tx.clone();
+            tokio::spawn(async move {
+                let result = bundle::StateBundle::read(&path).await.map_err(|e| e.to_string());
+                let _ = app_tx_clone.send(AppEvent::BundleLoaded { path, result }).await;
+            });
+        }
+        DownloadAction::DetectSilence(id) => {
+            if let Some(source) = state.queue.iter().find(|i| i.id == id).and_then(|i| i.file_path.clone()) {
+                let app_tx_clone = app_tx.clone();
+                tokio::spawn(async move {
+                    let result = commands::album::detect_silence(std::path::Path::new(&source))
+                        .await
+                        .map(|report| (report.split_points(), report.duration))
+                        .map_err(|e| e.to_string());
+                    let _ = app_tx_clone.send(AppEvent::SilenceDetected { id, result }).await;
+                });
+            } else {
+                // No post worker was started for detection, so this mustn't go through AlbumSplitFinished
+                let _ = app_tx.send(AppEvent::SilenceDetected {
+                    id,
+                    result: Err("Downloaded file location is unknown".to_string()),
+                }).await;
+            }
+        }
+        DownloadAction::MeasureRecordings => {
+            let recordings: Vec<(uuid::Uuid, String)> = state
+                .queue
+                .iter()
+                .filter(|i| i.live.is_some() && i.status == app_state::DownloadStatus::Downloading)
+                .filter_map(|i| Some((i.id, i.progress.destination.clone()?)))
+                .collect();
+            // The speed test only matters once several recordings compete for the disk
+            let speed_test_dir = (recordings.len() >= 2
+                && state.recording_guard.disk_write_speed.is_none()
+                && !state.recording_guard.measuring_disk)
+                .then(|| state.output_dir.clone());
+            state.recording_guard.measuring_disk |= speed_test_dir.is_some();
+            let app_tx_clone = app_tx.clone();
+
+            tokio::task::spawn_blocking(move || {
+                let usage = recordings
+                    .into_iter()
+                    .map(|(id, destination)| (id, commands::disk::recording_usage(&destination)))
+                    .collect();
+                let disk_write_speed = speed_test_dir.map(|dir| commands::disk::measure_write_speed(&dir).map_err(|e| e.to_string()));
+                let _ = app_tx_clone.blocking_send(AppEvent::RecordingsMeasured { usage, disk_write_speed });
+            });
+        }
+        DownloadAction::MeasureDisk(dir) => {
+            let app_tx_clone = app_tx.clone();
+            tokio::task::spawn_blocking(move || {
+                let result = commands::disk::measure_write_speed(&dir).map_err(|e| e.to_string());
+                let _ = app_tx_clone.blocking_send(AppEvent::DiskMeasured { dir, result });
+            });
+        }
+        DownloadAction::RestartDownload(id) => {
+            // Stopping keeps the .part file, so the download carries on from there with the new options
+            // Aborting now would lose a merge or post-processing run yt-dlp is in the middle of
+            let finishing = state.queue.iter().find(|i| i.id == id).is_some_and(|i| {
+                matches!(i.timings.current(), Some(app_state::timing::Phase::Merge | app_state::timing::Phase::PostProcess))
+            });
+            if finishing {
+                return;
+            }
+            let Some(handle) = state.running_tasks.remove(&id) else {
+                return;
+            };
+            handle.abort();
+            state.stop_signals.remove(&id);
+            if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                item.status = app_state::DownloadStatus::Paused;
+                item.timings.leave(chrono::Utc::now());
+            }
+            Box::pin(handle_download_action(DownloadAction::StartDownload(id), state, app_tx)).await;
+        }
+        DownloadAction::SplitAlbum { id, tracks } => {
+            let workers = state.config.post_processing_workers as usize;
+            if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                if !state.post_workers.start(&DownloadAction::SplitAlbum { id, tracks: tracks.clone() }, workers) {
+                    return;
+                }
+                item.timings.enter(app_state::timing::Phase::PostProcess, chrono::Utc::now());
+                let Some(source) = item.file_path.clone() else {
+                    let _ = app_tx.send(AppEvent::AlbumSplitFinished {
+                        id,
+                        result: Err("Downloaded file location is unknown".to_string()),
+                    }).await;
+                    return;
+                };
+                let album = item.title.clone().unwrap_or_else(|| "Album".to_string());
+
+                let app_tx_clone = app_tx.clone();
+                tokio::spawn(async move {
+                    let result = commands::album::split_album(std::path::Path::new(&source), &album, &tracks)
+                        .await
+                        .map(|folder| (folder.to_string_lossy().to_string(), tracks.len()))
+                        .map_err(|e| e.to_string());
+                    let _ = app_tx_clone.send(AppEvent::AlbumSplitFinished { id, result }).await;
+                });
+            }
+        }
+        DownloadAction::PauseDownload(id) => {
+            // Stopping yt-dlp keeps its .part file, resuming starts it again from there
+            if let Some(handle) = state.running_tasks.remove(&id) {
+                handle.abort();
+            }
+            state.stop_signals.remove(&id);
+            if let Some(item) = state.queue.iter_mut().find(|i| i.id == id && i.status == app_state::DownloadStatus::Downloading) {
+                item.status = app_state::DownloadStatus::Paused;
+                item.timings.leave(chrono::Utc::now());
+                item.push_log("Paused, partial download kept - press p to resume");
+                if item.batch_id.is_some() && item.batch_id == state.batch.as_ref().map(|b| b.batch_id) {
+                    state.abort_batch();
+                }
+            }
+        }
+        DownloadAction::PauseAll => {
+            let ids: Vec<uuid::Uuid> = state
+                .queue
+                .iter()
+                .filter(|i| i.status == app_state::DownloadStatus::Downloading)
+                .map(|i| i.id)
+                .collect();
+            for id in ids {
+                Box::pin(handle_download_action(DownloadAction::PauseDownload(id), state, app_tx)).await;
+            }
+        }
+        DownloadAction::ResumeAll => {
+            // Paused downloads wait in the queue like held ones, the ticker starts them as the
+            // download slots, site limits, breakers and network guard allow
+            for item in &mut state.queue {
+                if item.status == app_state::DownloadStatus::Paused && !state.running_tasks.contains_key(&item.id) {
+                    item.status = app_state::DownloadStatus::Queued;
+                    item.site_wait = true;
+                    item.push_log("Queued to resume");
+                }
+            }
+        }
+        DownloadAction::ResumeDownload(id) => {
+            // Downloads stopped after a fragment resume from their .part file
+            let stopped = state
+                .queue
+                .iter()
+                .any(|i| i.id == id && i.status == app_state::DownloadStatus::Paused && !state.running_tasks.contains_key(&id));
+            if stopped {
+                Box::pin(handle_download_action(DownloadAction::StartDownload(id), state, app_tx)).await;
+            }
+        }
+    }
+}
+
+fn formats_fetched_event(id: uuid::Uuid, fetched: commands::yt_dlp::FetchedFormats) -> AppEvent {
+    AppEvent::FormatsFetched {
+        id,
+        formats: fetched.formats,
+        title: fetched.title,
+        duration: fetched.duration,
+        chapters: fetched.chapters,
+        archive_key: fetched.archive_key,
+        is_live: fetched.is_live,
+        live_started_at: fetched.live_started_at,
+        metadata: fetched.metadata,
+    }
+}
+
+/// Stop every running download and the batch because the network condition broke.
+/// Partial files are kept, so the items resume with `p` once it holds again.
+fn halt_downloads(state: &mut AppState, reason: &str) {
+    let mut halted = 0;
+    for item in &mut state.queue {
+        if item.status != app_state::DownloadStatus::Downloading {
+            continue;
+        }
+        if let Some(task) = state.running_tasks.remove(&item.id) {
+            task.abort();
+        }
+        state.stop_signals.remove(&item.id);
+        item.status = app_state::DownloadStatus::Paused;
+        item.timings.leave(chrono::Utc::now());
+        item.push_log(format!("Paused by the network guard: {}", reason));
+        halted += 1;
+    }
+    state.abort_batch();
+    state.push_app_log(format!("Network guard: {}, paused {} download(s)", reason, halted));
+    state.error_message = Some(format!(
+        "NETWORK GUARD: {} - {} download(s) paused, nothing starts until it holds again",
+        reason, halted
+    ));
+}
+
+/// Send the "queue finished" notification once the last download of a run is done
+fn notify_if_finished(state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    let now = chrono::Utc::now();
+    if let Some(message) = state.notify_run.finish(&state.queue, &state.config.notifications, now) {
+        queue_action(action_tx, DownloadAction::Notify { message, test: false });
+    }
+}
+
+/// A download stopped making progress: measure its disk if that's not known yet, else throttle
+/// running downloads when the disk is slower than they were
+fn on_write_stall(state: &mut AppState, id: uuid::Uuid, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(item) = state.queue.iter_mut().find(|i| i.id == id) else {
+        return;
+    };
+    let dir = item.output_dir.clone().unwrap_or_else(|| state.output_dir.clone());
+    match state.write_watch.disk_speeds.get(&dir) {
+        Some(_) => throttle_writes(state, &dir, action_tx),
+        None if !state.write_watch.measuring => {
+            item.push_log("Stalled, measuring how fast the disk writes");
+            state.write_watch.measuring = true;
+            queue_action(action_tx, DownloadAction::MeasureDisk(dir));
+        }
+        None => {}
+    }
+}
+
+/// Limit running downloads to what the disk holding `dir` sustains, if it's slower than they are
+fn throttle_writes(state: &mut AppState, dir: &str, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(speed) = state.write_watch.disk_speeds.get(dir).copied() else {
+        return;
+    };
+    let Some(cap) = state.write_watch.auto_cap(dir, speed) else {
+        state.push_app_log(format!(
+            "Downloads stalled, but {} writes {}/s and keeps up: the network is the bottleneck",
+            dir,
+            app_state::format_bytes(speed as u64)
+        ));
+        return;
+    };
+    if !state.write_watch.set_cap(dir, cap, std::time::Instant::now()) {
+        return;
+    }
+    let message = format!(
+        "Downloads stall writing to {} ({}/s sustained), throttling each to {}/s",
+        dir,
+        app_state::format_bytes(speed as u64),
+        app_state::format_bytes(cap)
+    );
+    state.push_app_log(message.clone());
+    state.notice = Some(message);
+    restart_writes_to(state, dir, action_tx);
+}
+
+/// Restart the downloads writing to `dir`, so they pick up its new write limit
+fn restart_writes_to(state: &AppState, dir: &str, action_tx: &mpsc::Sender<DownloadAction>) {
+    let running = state.queue.iter().filter(|i| {
+        i.status == app_state::DownloadStatus::Downloading
+            && i.live.is_none()
+            && i.output_dir.as_deref().unwrap_or(&state.output_dir) == dir
+    });
+    for item in running {
+        queue_action(action_tx, DownloadAction::RestartDownload(item.id));
+    }
+}
+
+/// Free a post-processing worker and hand the free workers to waiting tasks
+fn release_post_worker(state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    state.post_workers.finish();
+    for action in state.post_workers.ready(state.config.post_processing_workers as usize) {
+        queue_action(action_tx, action);
+    }
+}
+
+/// Work on a completed download's file: verification, history, hooks, quality check, comments and album split
+fn follow_up_download(state: &mut AppState, id: uuid::Uuid, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(item) = state.queue.iter_mut().find(|item| item.id == id) else {
+        return;
+    };
+    state.scripts.run(hooks::HookEvent::PostDownload, item);
+    if item.file_path.is_some()
+        && (state.config.verify_file_type || state.config.hooks.get(hooks::HookEvent::Scan).is_some())
+    {
+        queue_action(action_tx, DownloadAction::VerifyFile(id));
+    }
+    // With duplicate detection on, history is recorded once the hash is known
+    if state.config.detect_duplicates && item.file_path.is_some() {
+        queue_action(action_tx, DownloadAction::HashFile(id));
+    } else if let Err(e) = state.history.record(history::HistoryEntry::from_item(item)) {
+        tracing::warn!("Failed to record history: {}", e);
+    }
+    state.refresh_history_search();
+    queue_action(action_tx, DownloadAction::RunHook {
+        id,
+        event: hooks::HookEvent::PostDownload,
+    });
+    let Some(item) = state.queue.iter_mut().find(|item| item.id == id) else {
+        return;
+    };
+    if item.file_path.is_some() {
+        queue_action(action_tx, DownloadAction::ProbeQuality(id));
+    }
+    // Comments are slow to fetch, so they come after the media as their own task
+    if state.config.profile_for(item.profile.as_deref()).comment_limit > 0 && item.live.is_none() && item.file_path.is_some() {
+        queue_action(action_tx, DownloadAction::ArchiveComments(id));
+    }
+
+    // Kick off album split post-processing
+    if item.album_split == Some(app_state::AlbumSplit::Requested) {
+        if item.chapters.is_empty() {
+            // Fall back to proposing split points from silences
+            item.album_split = Some(app_state::AlbumSplit::DetectingSilence);
+            queue_action(action_tx, DownloadAction::DetectSilence(id));
+        } else {
+            item.album_split = Some(app_state::AlbumSplit::Splitting);
+            let tracks = commands::album::tracks_from_chapters(&item.chapters);
+            queue_action(action_tx, DownloadAction::SplitAlbum { id, tracks });
+        }
+    }
+}
+
+/// Run the chain's stage after `index`, or the follow-up work once the chain is done
+fn continue_chain(state: &mut AppState, id: uuid::Uuid, index: usize, action_tx: &mpsc::Sender<DownloadAction>) {
+    let stages = state.queue.iter().find(|i| i.id == id).map_or(0, |i| i.post_processing.len());
+    if index + 1 < stages {
+        queue_action(action_tx, DownloadAction::RunStage { id, index: index + 1 });
+    } else {
+        follow_up_download(state, id, action_tx);
+    }
+}
+
+/// Handle application events from background tasks
+async fn handle_app_event(
+    event: AppEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    match event {
+        AppEvent::Quit => {
+            state.should_quit = true;
+        }
+        AppEvent::ClipboardUrl(url) => {
+            if state.config.watch_clipboard {
+                state.remember_url(&url, recent::RecentSource::Clipboard);
+            }
+        }
+        AppEvent::ConfigFileChanged => reload_config(state).await,
+        AppEvent::Tick => {
+            // Archive and history lines held back while another instance was writing the files
+            if let Err(e) = state.archive.flush() {
+                tracing::warn!("Failed to update download archive: {}", e);
+            }
+            if let Err(e) = state.history.flush() {
+                tracing::warn!("Failed to record history: {}", e);
+            }
+            // Re-check the network condition every interval
+            if let Some(guard) = &state.config.network_guard {
+                let watch = &state.network_watch;
+                let due = watch
+                    .checked_at
+                    .is_none_or(|at| at.elapsed().as_secs() >= guard.interval_secs);
+                if due && !watch.checking {
+                    queue_action(action_tx, DownloadAction::CheckNetwork);
+                }
+            }
+
+            // Start recordings whose schedule fires this minute, unless the network guard holds downloads
+            let due = match state.network_hold() {
+                Some(_) => Vec::new(),
+                None => state.schedules.take_due(chrono::Local::now()),
+            };
+            if !due.is_empty() {
+                if let Err(e) = state.schedules.save() {
+                    state.push_app_log(format!("[error] Failed to save schedules: {}", e));
+                }
+            }
+            for schedule in due {
+                let mut item = app_state::DownloadItem::new(schedule.url.clone());
+                item.schedule_id = Some(schedule.id);
+                item.status = app_state::DownloadStatus::FetchingInfo;
+                item.live = Some(app_state::LiveRecording {
+                    stop_after_minutes: schedule.stop_after_minutes,
+                    ..app_state::LiveRecording::new(None)
+                });
+                item.push_log(format!("Started by schedule \"{}\"", schedule.rule));
+                state.push_app_log(format!("Schedule \"{}\" fired for {}", schedule.rule, schedule.url));
+                queue_action(action_tx, DownloadAction::FetchFormats(item.id));
+                state.apply(app_state::Command::AddItems(vec![item]));
+            }
+
+            // Check restored items' sources a couple at a time
+            while state.availability_checks.in_flight < 2 {
+                let Some(id) = state.availability_checks.waiting.pop_front() else {
+                    break;
+                };
+                state.availability_checks.in_flight += 1;
+                queue_action(action_tx, DownloadAction::CheckAvailability(id));
+            }
+
+            // The auto write limit watches running downloads for stalls on a slow disk
+            if state.config.write_limit == commands::disk::WriteLimit::Auto
+                && chrono::Utc::now().timestamp() % app_state::RECORDING_SAMPLE_SECS == 0
+            {
+                let running: Vec<(uuid::Uuid, String, u64)> = state
+                    .queue
+                    .iter()
+                    .filter(|i| i.status == app_state::DownloadStatus::Downloading && i.live.is_none())
+                    .map(|i| (i.id, i.output_dir.clone().unwrap_or_else(|| state.output_dir.clone()), i.downloaded_bytes().unwrap_or(0)))
+                    .collect();
+                state.write_watch.retain(&running.iter().map(|(id, _, _)| *id).collect::<Vec<_>>());
+                let seconds = app_state::RECORDING_SAMPLE_SECS as f64;
+                let mut stalled = None;
+                for (id, dir, bytes) in &running {
+                    if state.write_watch.sample(*id, dir, *bytes, seconds) {
+                        stalled = Some(*id);
+                    }
+                }
+                if let Some(id) = stalled {
+                    on_write_stall(state, id, action_tx);
+                }
+                // Limits come off once their folder's writes recover
+                let busy: Vec<String> = running.into_iter().map(|(_, dir, _)| dir).collect();
+                for dir in state.write_watch.relax(&busy, std::time::Instant::now()) {
+                    state.push_app_log(format!("Writes to {} recovered, lifting its write limit", dir));
+                    restart_writes_to(state, &dir, action_tx);
+                }
+            }
+
+            // Hand free post-processing workers to waiting tasks, also after a waiting task's item went away
+            for action in state.post_workers.ready(state.config.post_processing_workers as usize) {
+                queue_action(action_tx, action);
+            }
+
+            // Start downloads the slots, their site's rules or breaker held back, once they allow
+            if state.network_hold().is_none() {
+                let waiting: Vec<uuid::Uuid> = state
+                    .queue
+                    .iter()
+                    .filter(|i| i.site_wait && i.status == app_state::DownloadStatus::Queued)
+                    .map(|i| i.id)
+                    .collect();
+                for id in waiting {
+                    if state.slot_hold(id).is_none()
+                        && state.site_hold(id).is_none()
+                        && state.breaker_hold(id).is_none()
+                        && state.retry_hold(id).is_none()
+                    {
+                        queue_action(action_tx, DownloadAction::StartDownload(id));
+                    }
+                }
+            }
+
+            // End live recordings that reached their length limit
+            let now = chrono::Utc::now();
+            let recording = state
+                .queue
+                .iter()
+                .any(|i| i.live.is_some() && i.status == app_state::DownloadStatus::Downloading);
+            if recording && now.timestamp() % app_state::RECORDING_SAMPLE_SECS == 0 {
+                queue_action(action_tx, DownloadAction::MeasureRecordings);
+            }
+            for item in &mut state.queue {
+                let Some(live) = &mut item.live else {
+                    continue;
+                };
+                if item.status == app_state::DownloadStatus::Downloading
+                    && !live.stopping
+                    && live.deadline().is_some_and(|deadline| now >= deadline)
+                {
+                    live.stopping = true;
+                    let message = format!("Recording limit reached ({}), stopping", live.stop_label());
+                    item.push_log(message);
+                    if let Some(stop_tx) = state.stop_signals.get(&item.id) {
+                        let _ = stop_tx.send(commands::yt_dlp::StopRequest::Finish);
+                    }
+                }
+            }
+        }
+        AppEvent::DiskMeasured { dir, result } => {
+            state.write_watch.measuring = false;
+            match result {
+                Ok(speed) => {
+                    state.write_watch.disk_speeds.insert(dir.clone(), speed);
+                    throttle_writes(state, &dir, action_tx);
+                }
+                Err(error) => state.push_app_log(format!("[warning] Could not measure the disk under {}: {}", dir, error)),
+            }
+        }
+        AppEvent::RecordingsMeasured { usage, disk_write_speed } => {
+            let now = chrono::Utc::now();
+            if let Some(result) = disk_write_speed {
+                // A failed measurement is tried again with the next sample
+                state.recording_guard.measuring_disk = false;
+                match result {
+                    Ok(speed) => state.recording_guard.disk_write_speed = Some(speed),
+                    Err(error) => state.push_app_log(format!("[warning] Could not measure the disk for recordings: {}", error)),
+                }
+            }
+
+            for (id, bytes) in usage {
+                let Some(live) = state.queue.iter_mut().find(|i| i.id == id).and_then(|i| i.live.as_mut()) else {
+                    continue;
+                };
+                if let Some(sampled_at) = live.sampled_at {
+                    let seconds = (now - sampled_at).num_milliseconds() as f64 / 1000.0;
+                    if seconds > 0.0 {
+                        live.write_rate = Some(bytes.saturating_sub(live.disk_usage) as f64 / seconds);
+                    }
+                }
+                live.disk_usage = bytes;
+                live.sampled_at = Some(now);
+            }
+
+            let active: Vec<(uuid::Uuid, &app_state::LiveRecording)> = state
+                .queue
+                .iter()
+                .filter(|i| i.status == app_state::DownloadStatus::Downloading)
+                .filter_map(|i| Some((i.id, i.live.as_ref().filter(|live| !live.stopping)?)))
+                .collect();
+            let total_usage: u64 = active.iter().map(|(_, live)| live.disk_usage).sum();
+            let total_rate: f64 = active.iter().filter_map(|(_, live)| live.write_rate).sum();
+            let oldest = active.iter().min_by_key(|(_, live)| live.recording_since).map(|(id, _)| *id);
+
+            // Over the disk limit: the oldest recording gives way
+            let limit = state.config.max_recording_disk_gb as u64 * 1_000_000_000;
+            if let (true, Some(id)) = (limit > 0 && total_usage > limit, oldest) {
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    if let Some(live) = &mut item.live {
+                        live.stopping = true;
+                    }
+                    let message = format!(
+                        "Live recordings use {} (limit {}), stopping the oldest",
+                        app_state::format_bytes(total_usage),
+                        app_state::format_bytes(limit)
+                    );
+                    item.push_log(message.clone());
+                    state.push_app_log(message.clone());
+                    state.notice = Some(message);
+                }
+                if let Some(stop_tx) = state.stop_signals.get(&id) {
+                    let _ = stop_tx.send(commands::yt_dlp::StopRequest::Finish);
+                }
+            }
+
+            // Writing faster than the disk can sustain for a while means recordings will fall behind
+            let guard = &mut state.recording_guard;
+            match guard.disk_write_speed {
+                Some(speed) if total_rate > speed => {
+                    let since = *guard.overloaded_since.get_or_insert(now);
+                    if !guard.alerted && (now - since).num_seconds() >= app_state::DISK_OVERLOAD_ALERT_SECS {
+                        guard.alerted = true;
+                        let message = format!(
+                            "Live recordings write {}/s but the disk sustains {}/s, recordings may drop data",
+                            app_state::format_bytes(total_rate as u64),
+                            app_state::format_bytes(speed as u64)
+                        );
+                        state.push_app_log(message.clone());
+                        state.error_message = Some(message);
+                    }
+                }
+                _ => {
+                    guard.overloaded_since = None;
+                    guard.alerted = false;
+                }
+            }
+        }
+        AppEvent::ProgressUpdate { id, progress } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                // A phase change only marks the time, the progress stays
+                match progress.phase {
+                    Some(phase) => item.timings.enter(phase, chrono::Utc::now()),
+                    None => item.update_progress(progress),
+                }
+                if item.status != app_state::DownloadStatus::Downloading {
+                    item.status = app_state::DownloadStatus::Downloading;
+                }
+            }
+        }
+        AppEvent::DownloadCompleted { id, outcome } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                state.breakers.record_success(&item.url);
+                item.transient_retries = 0;
+                item.retry_at = None;
+                item.status = app_state::DownloadStatus::Completed;
+                item.finished_at = Some(chrono::Utc::now());
+                item.timings.leave(chrono::Utc::now());
+                if let Some(warning) = outcome.warning {
+                    item.status = app_state::DownloadStatus::CompletedWithWarnings;
+                    item.push_log(format!("[warning] {}", warning));
+                    state.notice = Some(format!(
+                        "{} finished with warnings, press L for details",
+                        item.title.as_deref().unwrap_or(&item.url)
+                    ));
+                }
+                if outcome.thumbnail.is_some() {
+                    item.thumbnail = outcome.thumbnail;
+                }
+                // A missing or empty file is nothing to archive, post-process or follow up on
+                let usable = item.status == app_state::DownloadStatus::Completed;
+                item.file_path = outcome.destination.filter(|_| usable);
+
+                // Remember the video so playlists skip it next time
+                let key = item.archive_key.clone().or_else(|| archive::archive_key_for_url(&item.url));
+                if let Some(key) = key.filter(|_| usable) {
+                    if let Err(e) = state.archive.record(key) {
+                        tracing::warn!("Failed to update download archive: {}", e);
+                    }
+                }
+                state.notify_run.record(item);
+
+                // The profile's post-processing chain runs first, the rest works on its result
+                if usable {
+                    let chain = &state.config.profile_for(item.profile.as_deref()).post_processing;
+                    if !chain.is_empty() && item.file_path.is_some() && item.live.is_none() {
+                        item.post_processing = chain.iter().cloned().map(commands::postprocess::StageRun::new).collect();
+                        queue_action(action_tx, DownloadAction::RunStage { id, index: 0 });
+                    } else {
+                        follow_up_download(state, id, action_tx);
+                    }
+                }
+            }
+            state.running_tasks.remove(&id);
+            state.stop_signals.remove(&id);
+            if let Some(next) = state.next_batch_item() {
+                queue_action(action_tx, DownloadAction::StartDownload(next));
+            }
+            notify_if_finished(state, action_tx);
+        }
+        AppEvent::DownloadStopped { id, reason } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.status = app_state::DownloadStatus::Paused;
+                item.timings.leave(chrono::Utc::now());
+                item.push_log(format!("{}, partial download kept - press p to resume", reason));
+            }
+            state.running_tasks.remove(&id);
+            state.stop_signals.remove(&id);
+            state.abort_batch();
+        }
+        AppEvent::FailureOutput { id, lines } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.failure_output = lines;
+            }
+        }
+        AppEvent::DownloadFailed { id, error } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.timings.leave(chrono::Utc::now());
+                // Timeouts and 5xx errors count against the site's breaker and the item waits them out
+                // in the queue, up to a few times, instead of failing
+                let transient = commands::troubleshoot::transient(&error);
+                if transient {
+                    if let Some(site) = state.breakers.record_failure(&item.url, &error, std::time::Instant::now()) {
+                        state.notice = Some(format!(
+                            "{} keeps failing, holding its downloads for {} min",
+                            site,
+                            app_state::breaker::COOLDOWN.as_secs() / 60
+                        ));
+                    }
+                }
+                if transient && item.transient_retries < app_state::breaker::ITEM_RETRIES {
+                    item.transient_retries += 1;
+                    let delay = app_state::breaker::retry_delay(item.transient_retries);
+                    item.retry_at = chrono::Duration::from_std(delay).ok().map(|d| chrono::Utc::now() + d);
+                    item.status = app_state::DownloadStatus::Queued;
+                    item.site_wait = true;
+                    item.push_log(format!(
+                        "[warning] Retrying in {}s: {} (attempt {}/{})",
+                        delay.as_secs(),
+                        error,
+                        item.transient_retries,
+                        app_state::breaker::ITEM_RETRIES
+                    ));
+                    state.running_tasks.remove(&id);
+                    state.stop_signals.remove(&id);
+                    return;
+                }
+                if commands::troubleshoot::source_gone(&error) {
+                    item.mark_unavailable(error);
+                    if state.config.wayback_fallback {
+                        queue_action(action_tx, DownloadAction::WaybackLookup { id, asked: false });
+                    }
+                } else {
+                    item.status = app_state::DownloadStatus::Failed;
+                    item.push_log(format!("[error] {}", error));
+                    item.error = Some(error);
+                }
+                state.scripts.run(hooks::HookEvent::OnFail, item);
+                queue_action(action_tx, DownloadAction::RunHook {
+                    id,
+                    event: hooks::HookEvent::OnFail,
+                });
+                state.notify_run.record(item);
+                if state.config.notifications.on_failure {
+                    let message = notifications::Message::item_failed(item, item.error.as_deref().unwrap_or_default());
+                    queue_action(action_tx, DownloadAction::Notify { message, test: false });
+                }
+            }
+            state.running_tasks.remove(&id);
+            state.stop_signals.remove(&id);
+            // A failed item doesn't hold up the rest of its batch
+            if let Some(next) = state.next_batch_item() {
+                queue_action(action_tx, DownloadAction::StartDownload(next));
+            }
+            notify_if_finished(state, action_tx);
+        }
+        AppEvent::FormatsFetched {
+            id,
+            formats,
+            title,
+            duration,
+            chapters,
+            archive_key,
+            is_live,
+            live_started_at,
+            metadata,
+        } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.timings.leave(chrono::Utc::now());
+                item.title = Some(title);
+                item.duration = duration;
+                item.chapters = chapters;
+                item.metadata = metadata;
+                item.metadata.counts_fetched_at = Some(chrono::Utc::now());
+
+                // Without chapter metadata, fall back to a tracklist in the description
+                item.chapters_inferred = false;
+                if item.chapters.is_empty() {
+                    let duration = item.duration.as_deref().and_then(app_state::parse_duration_to_seconds);
+                    let inferred = item.metadata.description.as_deref().map_or_else(Vec::new, |text| {
+                        description::parse_tracklist(text, duration.map(|secs| secs as f64))
+                    });
+                    if !inferred.is_empty() {
+                        item.push_log(format!("No chapters, inferred {} from the description tracklist", inferred.len()));
+                        item.chapters = inferred;
+                        item.chapters_inferred = true;
+                    }
+                }
+                item.archive_key = archive_key.or_else(|| archive::archive_key_for_url(&item.url));
+                item.live = match (is_live, item.live.take()) {
+                    (false, _) => None,
+                    (true, Some(live)) => Some(live),
+                    (true, None) => Some(app_state::LiveRecording::new(
+                        live_started_at.and_then(|ts| chrono::DateTime::from_timestamp(ts, 0)),
+                    )),
+                };
+                item.status = app_state::DownloadStatus::Ready;
+
+                // Scheduled recordings and items added from the web UI start on their own with the best streams
+                if item.schedule_id.is_some() || item.auto_start {
+                    if item.live.is_some() || item.auto_start {
+                        item.format = Some(app_state::FormatInfo::best(&state.config.merge_container));
+                        queue_action(action_tx, DownloadAction::StartDownload(id));
+                    } else {
+                        let error = "Not live at the scheduled time".to_string();
+                        item.status = app_state::DownloadStatus::Failed;
+                        item.push_log(format!("[error] {}", error));
+                        item.error = Some(error);
+                    }
+                    return;
+                }
+
+                // A macro replayed on the item picks the format itself
+                if !item.pending_macro.is_empty() {
+                    let steps = std::mem::take(&mut item.pending_macro);
+                    if let Some(action) = macros::replay(state, id, steps, Some(&formats)) {
+                        queue_action(action_tx, action);
+                    }
+                    return;
+                }
+
+                // Show format selection popup
+                state.format_popup = Some(app_state::FormatPopup {
+                    item_id: id,
+                    formats,
+                    selected_index: 0,
+                    audio_only_filter: false,
+                    container: item
+                        .container
+                        .clone()
+                        .unwrap_or_else(|| state.config.merge_container.clone()),
+                });
+            }
+        }
+        AppEvent::FormatsFetchFailed { id, error } => {
+            let gone = commands::troubleshoot::source_gone(&error);
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.timings.leave(chrono::Utc::now());
+                if commands::troubleshoot::transient(&error) {
+                    state.breakers.record_failure(&item.url, &error, std::time::Instant::now());
+                }
+                if gone {
+                    item.mark_unavailable(error.clone());
+                } else {
+                    item.status = app_state::DownloadStatus::Failed;
+                    item.push_log(format!("[error] {}", error));
+                    item.error = Some(error.clone());
+                }
+            }
+            state.error_message = Some(match (gone, state.config.wayback_fallback) {
+                (false, _) => format!("{}\n\nPress F on the item for things to try.", error),
+                (true, false) => format!("{}\n\nThe video is gone. Press w on the item to look for a Wayback Machine copy.", error),
+                (true, true) => {
+                    queue_action(action_tx, DownloadAction::WaybackLookup { id, asked: false });
+                    format!("{}\n\nThe video is gone, looking for a Wayback Machine copy...", error)
+                }
+            });
+        }
+        AppEvent::RetryFinished { id, tried, args, result } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.timings.leave(chrono::Utc::now());
+                match &result {
+                    Ok(summary) => {
+                        item.push_log(format!("\"{}\" worked: {}", tried, summary));
+                        // Keep what worked for the download itself
+                        item.retry_args = args;
+                        item.error = None;
+                    }
+                    Err(error) => {
+                        item.status = app_state::DownloadStatus::Failed;
+                        item.push_log(format!("[error] \"{}\" didn't help: {}", tried, error));
+                        item.error = Some(error.clone());
+                    }
+                }
+            }
+            if let Some(wizard) = state.retry_wizard.as_mut().filter(|w| w.item_id == id) {
+                wizard.running = None;
+                if result.is_ok() {
+                    state.retry_wizard = None;
+                    state.notice = Some(format!("\"{}\" worked, pick a format", tried));
+                } else {
+                    wizard.results.push((tried, result));
+                }
+            }
+        }
+        AppEvent::UrlValidated {
+            url: _,
+            is_valid: _,
+            error,
+        } => {
+            if let Some(error) = error {
+                state.error_message = Some(error);
+            }
+        }
+        AppEvent::PlaylistDetected { entries } => {
+            // Clear loading state
             state.is_loading = false;
             state.loading_message = None;
             
@@ -392,10 +2084,13 @@ async fn handle_app_event(event: AppEvent, state: &mut AppState) {
                             total_seconds += seconds;
                         }
                     }
+                    let archived = archive::archive_key_for_url(&url)
+                        .is_some_and(|key| state.archive.contains(&key));
                     app_state::PlaylistEntry {
                         url,
                         title,
                         duration,
+                        archived,
                     }
                 })
                 .collect();
@@ -409,6 +2104,7 @@ async fn handle_app_event(event: AppEvent, state: &mut AppState) {
             
             // Show playlist preview popup
             state.playlist_preview = Some(app_state::PlaylistPreviewPopup {
+                marked: vec![false; playlist_entries.len()],
                 entries: playlist_entries,
                 selected_index: 0,
                 total_duration,
@@ -425,7 +2121,15 @@ async fn handle_app_event(event: AppEvent, state: &mut AppState) {
             item.title = Some(title);
             item.duration = duration;
             item.status = app_state::DownloadStatus::FetchingInfo;
-            state.queue.push(item);
+            if let Some(reason) = state.scripts.run(hooks::HookEvent::OnAdd, &mut item) {
+                state.notice = Some(format!("Rejected by script: {}", reason));
+                return;
+            }
+            state.apply(app_state::Command::AddItems(vec![item]));
+            queue_action(action_tx, DownloadAction::RunHook {
+                id,
+                event: hooks::HookEvent::OnAdd,
+            });
             
             // Trigger format fetching for this single video
             // This will be handled by the existing FormatsFetched event
@@ -435,11 +2139,433 @@ async fn handle_app_event(event: AppEvent, state: &mut AppState) {
                 item.status = app_state::DownloadStatus::Ready;
             }
         }
+        AppEvent::LinksExtracted { page, result } => match result {
+            Ok(links) => {
+                // A link to another page the rules cover would be scraped in turn, without end
+                let links: Vec<String> = links.into_iter().filter(|link| state.config.extractor_for(link).is_none()).collect();
+                state.push_app_log(match links.len() {
+                    0 => format!("[warning] The extractor found no links on {}", page),
+                    found => format!("Queued {} links extracted from {}", found, page),
+                });
+                for link in links {
+                    queue_action(action_tx, DownloadAction::AddRemote(link));
+                }
+            }
+            Err(error) => state.push_app_log(format!("[error] Failed to extract links from {}: {}", page, error)),
+        },
         AppEvent::PlaylistFetchFailed { error } => {
             // Clear loading state
             state.is_loading = false;
             state.loading_message = None;
             state.error_message = Some(error);
         }
+        AppEvent::ArchiveImportParsed { path, result } => match result {
+            Ok((format, keys)) => {
+                let found = keys.len();
+                match state.archive.extend(keys) {
+                    Ok(added) => {
+                        state.notice = Some(format!(
+                            "Imported {} new of {} IDs from {} ({:?}), archive now holds {}",
+                            added,
+                            found,
+                            path,
+                            format,
+                            state.archive.len()
+                        ));
+                    }
+                    Err(e) => state.error_message = Some(format!("Failed to save archive: {}", e)),
+                }
+            }
+            Err(error) => {
+                state.error_message = Some(format!("Failed to import {}: {}", path, error));
+            }
+        },
+        AppEvent::FileVerified { id, problems, output } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                for line in output {
+                    item.push_log(line);
+                }
+                if problems.is_empty() {
+                    item.push_log("Verification passed");
+                } else {
+                    for problem in &problems {
+                        item.push_log(format!("[error] {}", problem));
+                    }
+                    item.status = app_state::DownloadStatus::Suspect;
+                    item.error = Some(problems.join("; "));
+                    state.notice = Some(format!(
+                        "{} marked suspect, press L for details",
+                        item.title.as_deref().unwrap_or(&item.url)
+                    ));
+                }
+            }
+        }
+        AppEvent::PathPicked { kind, result } => {
+            // The prompt may have been closed or replaced while the dialog was open
+            let Some(prompt) = state.prompt.as_mut().filter(|p| p.kind == kind) else {
+                return;
+            };
+            match result {
+                Ok(Some(path)) => prompt.input = path,
+                Ok(None) => {}
+                Err(e) => {
+                    state.push_app_log(format!("Desktop file chooser: {}", e));
+                    state.notice = Some("No desktop file chooser available, type the path instead".to_string());
+                }
+            }
+        }
+        AppEvent::NotificationSent { test, results } => {
+            let mut failed = Vec::new();
+            for (backend, result) in &results {
+                if let Err(e) = result {
+                    state.push_app_log(format!("[error] {} notification failed: {}", backend, e));
+                    failed.push(format!("{}: {}", backend, e));
+                }
+            }
+            if test {
+                if failed.is_empty() {
+                    let backends: Vec<&str> = results.iter().map(|(backend, _)| *backend).collect();
+                    state.notice = Some(format!("Test notification sent via {}", backends.join(" and ")));
+                } else {
+                    state.error_message = Some(format!("Test notification failed - {}", failed.join("; ")));
+                }
+            }
+        }
+        AppEvent::NetworkChecked { result } => {
+            let watch = &mut state.network_watch;
+            watch.checking = false;
+            watch.checked_at = Some(std::time::Instant::now());
+            match result {
+                Ok(()) => {
+                    if watch.failure.take().is_some() {
+                        state.push_app_log("Network condition holds again");
+                        state.notice = Some("Network condition holds again, resume paused downloads with p".to_string());
+                    }
+                    state.network_watch.ok = true;
+                }
+                Err(reason) => {
+                    let newly_broken = watch.ok || watch.failure.is_none();
+                    watch.ok = false;
+                    watch.failure = Some(reason.clone());
+                    if newly_broken {
+                        halt_downloads(state, &reason);
+                    }
+                }
+            }
+        }
+        AppEvent::AddressesDetected { result } => {
+            if let Some(popup) = &mut state.network_popup {
+                match result {
+                    Ok(addresses) => popup.addresses = Some(addresses),
+                    Err(error) => {
+                        state.network_popup = None;
+                        state.error_message = Some(format!("Could not list network addresses: {}", error));
+                    }
+                }
+            }
+        }
+        AppEvent::QualityProbed { id, result } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                match result {
+                    Ok(report) => {
+                        for mismatch in &report.mismatches {
+                            item.push_log(format!("Quality check: {}", mismatch));
+                        }
+                        item.quality_report = Some(report);
+                    }
+                    Err(error) => item.push_log(format!("Quality check skipped: {}", error)),
+                }
+            }
+        }
+        AppEvent::StageFinished { id, index, result } => {
+            release_post_worker(state, action_tx);
+            let Some(item) = state.queue.iter_mut().find(|item| item.id == id) else {
+                return;
+            };
+            let Some(label) = item.post_processing.get(index).map(|run| run.stage.label()) else {
+                return;
+            };
+            let failed = result.is_err();
+            item.timings.leave(chrono::Utc::now());
+            item.post_processing[index].status = match result {
+                Ok(commands::postprocess::StageOutput::File(path)) => {
+                    item.file_path = Some(path.display().to_string());
+                    item.push_log(format!("Post-processing: {} done", label));
+                    commands::postprocess::StageStatus::Done
+                }
+                Ok(commands::postprocess::StageOutput::Skipped(reason)) => {
+                    item.push_log(format!("Post-processing: {} skipped, {}", label, reason));
+                    commands::postprocess::StageStatus::Skipped(reason)
+                }
+                Err(error) => {
+                    item.push_log(format!("[error] Post-processing: {} failed: {}", label, error));
+                    state.notice = Some(format!(
+                        "{} failed on {}, press P to retry it or O to skip it",
+                        label,
+                        item.title.as_deref().unwrap_or(&item.url)
+                    ));
+                    commands::postprocess::StageStatus::Failed(error)
+                }
+            };
+            if !failed {
+                continue_chain(state, id, index, action_tx);
+            }
+        }
+        AppEvent::StageSkipped { id, index } => {
+            let Some(item) = state.queue.iter_mut().find(|item| item.id == id) else {
+                return;
+            };
+            let Some(run) = item.post_processing.get_mut(index) else {
+                return;
+            };
+            if !matches!(run.status, commands::postprocess::StageStatus::Failed(_)) {
+                return;
+            }
+            run.status = commands::postprocess::StageStatus::Skipped("skipped after it failed".to_string());
+            let message = format!("Post-processing: {} skipped after it failed", run.stage.label());
+            item.push_log(message);
+            continue_chain(state, id, index, action_tx);
+        }
+        AppEvent::CommentsArchived { id, result } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                let (status, message) = match result {
+                    Ok((count, path)) => (
+                        app_state::CommentArchive::Saved { count, path: path.clone() },
+                        format!("Archived {} comments to {}", count, path),
+                    ),
+                    Err(error) => (
+                        app_state::CommentArchive::Failed(error.clone()),
+                        format!("Comment archiving failed: {}", error),
+                    ),
+                };
+                item.comments = Some(status);
+                item.push_log(message);
+            }
+        }
+        AppEvent::AvailabilityChecked { id, result } => {
+            let checks = &mut state.availability_checks;
+            checks.in_flight = checks.in_flight.saturating_sub(1);
+            let Some(item) = state.queue.iter_mut().find(|item| item.id == id) else {
+                return;
+            };
+            match result {
+                Ok(()) => item.mark_available(),
+                Err(error) if commands::troubleshoot::source_gone(&error) => {
+                    checks.unavailable += 1;
+                    item.mark_unavailable(error);
+                    if state.config.wayback_fallback {
+                        queue_action(action_tx, DownloadAction::WaybackLookup { id, asked: false });
+                    }
+                }
+                // Network trouble and the like say nothing about the video
+                Err(error) => item.push_log(format!("[warning] Availability check failed: {}", error)),
+            }
+            if checks.waiting.is_empty() && checks.in_flight == 0 && checks.unavailable > 0 {
+                state.notice = Some(format!(
+                    "{} restored item(s) are no longer available - w: look for a Wayback Machine copy, X: remove them all",
+                    checks.unavailable
+                ));
+                checks.unavailable = 0;
+            }
+        }
+        AppEvent::WaybackLooked { id, asked, result } => {
+            let Some(item) = state.queue.iter_mut().find(|item| item.id == id) else {
+                return;
+            };
+            match result {
+                Ok(Some(snapshot)) => {
+                    item.push_log(format!("Wayback Machine snapshot: {}", snapshot));
+                    // One offer at a time, the rest keep the snapshot in their log
+                    if state.wayback_popup.is_none() {
+                        state.notice = None;
+                        state.wayback_popup = Some(app_state::WaybackPopup { item_id: id, snapshot });
+                    }
+                }
+                Ok(None) => {
+                    item.push_log("The Wayback Machine has no snapshot");
+                    if asked {
+                        state.notice = Some("The Wayback Machine has no snapshot of this video".to_string());
+                    }
+                }
+                Err(error) => {
+                    item.push_log(format!("[warning] Wayback Machine lookup failed: {}", error));
+                    if asked {
+                        state.error_message = Some(format!("Wayback Machine lookup failed: {}", error));
+                    }
+                }
+            }
+        }
+        AppEvent::MetadataRefreshed { id, result } => {
+            let Some(refresh) = state.metadata_refresh.as_mut() else {
+                return;
+            };
+            refresh.remaining = refresh.remaining.saturating_sub(1);
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                match result {
+                    Ok(fetched) => {
+                        refresh.updated += 1;
+                        item.title = Some(fetched.title);
+                        item.duration = fetched.duration;
+                        if !item.chapters_inferred {
+                            item.chapters = fetched.chapters;
+                        }
+                        item.archive_key = fetched.archive_key.or(item.archive_key.take());
+                        item.metadata = fetched.metadata;
+                        item.metadata.counts_fetched_at = Some(chrono::Utc::now());
+                        item.mark_available();
+                    }
+                    Err(error) if commands::troubleshoot::source_gone(&error) => {
+                        refresh.gone += 1;
+                        item.mark_unavailable(error);
+                        if state.config.wayback_fallback {
+                            queue_action(action_tx, DownloadAction::WaybackLookup { id, asked: false });
+                        }
+                    }
+                    Err(error) => {
+                        refresh.failed += 1;
+                        item.push_log(format!("[warning] Refreshing metadata failed: {}", error));
+                    }
+                }
+            }
+            if refresh.remaining == 0 {
+                let refresh = state.metadata_refresh.take().unwrap_or_default();
+                let mut summary = format!("Metadata refreshed: {} updated", refresh.updated);
+                if refresh.gone > 0 {
+                    summary.push_str(&format!(", {} removed at the source", refresh.gone));
+                }
+                if refresh.failed > 0 {
+                    summary.push_str(&format!(", {} failed (see logs)", refresh.failed));
+                }
+                state.notice = Some(summary);
+            }
+        }
+        AppEvent::EngagementFetched { id, result } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.metadata.refreshing = false;
+                match result {
+                    Ok(engagement) => {
+                        // Keep what the format fetch found when this extractor leaves a field out
+                        let metadata = &mut item.metadata;
+                        metadata.view_count = engagement.view_count.or(metadata.view_count);
+                        metadata.like_count = engagement.like_count.or(metadata.like_count);
+                        metadata.live_status = engagement.live_status.or(metadata.live_status.take());
+                        metadata.counts_fetched_at = Some(chrono::Utc::now());
+                    }
+                    Err(error) => item.push_log(format!("[warning] Refreshing view counts failed: {}", error)),
+                }
+            }
+        }
+        AppEvent::ContentHashed { id, result } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                let mut entry = history::HistoryEntry::from_item(item);
+                match result {
+                    Ok(hash) => {
+                        let path = item.file_path.clone().unwrap_or_default();
+                        if let Some(existing) = state.history.find_duplicate(&hash, &path) {
+                            let existing_path = existing.file_path.clone().unwrap_or_default();
+                            item.push_log(format!("Duplicate of {}", existing_path));
+                            state.duplicate_popup = Some(app_state::DuplicatePopup {
+                                item_id: id,
+                                new_path: path,
+                                existing_path,
+                            });
+                        }
+                        entry.content_hash = Some(hash);
+                    }
+                    Err(e) => item.push_log(format!("[error] Could not hash file: {}", e)),
+                }
+                if let Err(e) = state.history.record(entry) {
+                    tracing::warn!("Failed to record history: {}", e);
+                }
+            }
+            state.refresh_history_search();
+        }
+        AppEvent::HookFinished { id, output } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                for line in output {
+                    item.push_log(line);
+                }
+            }
+        }
+        AppEvent::ArchiveExported { path, result } => match result {
+            Ok(count) => {
+                state.notice = Some(format!(
+                    "Exported {} entries to {} (use with yt-dlp --download-archive)",
+                    count, path
+                ));
+            }
+            Err(error) => {
+                state.error_message = Some(format!("Failed to export archive to {}: {}", path, error));
+            }
+        },
+        AppEvent::BugReportExported { path, result } => match result {
+            Ok(()) => state.notice = Some(format!("Saved bug report to {}, read it over before attaching it", path)),
+            Err(error) => state.error_message = Some(format!("Failed to save bug report to {}: {}", path, error)),
+        },
+        AppEvent::BundleExported { path, result } => match result {
+            Ok((items, partial)) => {
+                state.notice = Some(format!(
+                    "Saved {} items and a manifest of {} partial files to {} - copy the partial files along",
+                    items, partial, path
+                ));
+            }
+            Err(error) => {
+                state.error_message = Some(format!("Failed to save state bundle to {}: {}", path, error));
+            }
+        },
+        AppEvent::BundleLoaded { path, result } => match result {
+            Ok(bundle) => {
+                let archive = bundle.archive.clone();
+                let (items, report) = bundle.restore(&state.queue, &state.output_dir);
+                // Restored items may be days old, check their sources in the background
+                let restored = items.iter().filter(|i| !i.status.is_completed() && i.file_path.is_none());
+                state.availability_checks.waiting.extend(restored.map(|i| i.id));
+                state.apply(app_state::Command::AddItems(items));
+                if let Err(e) = state.archive.extend(archive) {
+                    tracing::warn!("Failed to update download archive: {}", e);
+                }
+                for name in &report.partial_missing {
+                    state.push_app_log(format!("Partial file not found in {}: {}", state.output_dir, name));
+                }
+                state.notice = Some(format!(
+                    "Restored {} items from {} ({} partial files found, {} missing - see L)",
+                    report.items_added,
+                    path,
+                    report.partial_present,
+                    report.partial_missing.len()
+                ));
+            }
+            Err(error) => {
+                state.error_message = Some(format!("Failed to restore state bundle {}: {}", path, error));
+            }
+        },
+        AppEvent::SilenceDetected { id, result } => {
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                match result {
+                    Ok((points, duration)) => {
+                        item.album_split = Some(app_state::AlbumSplit::AwaitingSplitPoints);
+                        state.split_points_popup = Some(app_state::SplitPointsPopup {
+                            item_id: id,
+                            points,
+                            selected_index: 0,
+                            duration,
+                        });
+                    }
+                    Err(error) => {
+                        item.album_split = Some(app_state::AlbumSplit::Failed(error));
+                    }
+                }
+            }
+        }
+        AppEvent::AlbumSplitFinished { id, result } => {
+            release_post_worker(state, action_tx);
+            if let Some(item) = state.queue.iter_mut().find(|item| item.id == id) {
+                item.timings.leave(chrono::Utc::now());
+                item.album_split = Some(match result {
+                    Ok((folder, tracks)) => app_state::AlbumSplit::Done { folder, tracks },
+                    Err(error) => app_state::AlbumSplit::Failed(error),
+                });
+            }
+        }
     }
 }
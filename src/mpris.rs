@@ -0,0 +1,255 @@
+use tokio::sync::{mpsc, watch};
+
+use crate::app_state::{AppState, DownloadStatus, events::DownloadAction};
+
+/// What media players and desktop widgets are shown of the queue
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct QueueStatus {
+    pub active: usize,
+    pub paused: usize,
+}
+
+impl QueueStatus {
+    pub fn of(state: &AppState) -> Self {
+        let count = |status: DownloadStatus| state.queue.iter().filter(|i| i.status == status).count();
+        Self {
+            active: count(DownloadStatus::Downloading),
+            paused: count(DownloadStatus::Paused),
+        }
+    }
+
+    /// MPRIS playback status: downloading counts as playing
+    pub fn playback_status(&self) -> &'static str {
+        match (self.active, self.paused) {
+            (0, 0) => "Stopped",
+            (0, _) => "Paused",
+            _ => "Playing",
+        }
+    }
+
+    /// Shown as the track title
+    pub fn title(&self) -> String {
+        match (self.active, self.paused) {
+            (0, 0) => "No downloads active".to_string(),
+            (0, paused) => format!("{} download(s) paused", paused),
+            (1, _) => "1 download active".to_string(),
+            (active, _) => format!("{} downloads active", active),
+        }
+    }
+}
+
+/// Expose the queue over MPRIS on the session bus, so media keys and desktop widgets can
+/// pause and resume it. Runs until the status channel closes; errors mean there is no session bus.
+#[cfg(target_os = "linux")]
+pub async fn serve(action_tx: mpsc::Sender<DownloadAction>, mut status: watch::Receiver<QueueStatus>) -> anyhow::Result<()> {
+    const PATH: &str = "/org/mpris/MediaPlayer2";
+
+    let player = linux::Player { action_tx, status: status.clone() };
+    // Every instance needs its own name, the suffix is what the spec suggests
+    let connection = zbus::connection::Builder::session()?
+        .name(format!("org.mpris.MediaPlayer2.gorlock.instance{}", std::process::id()))?
+        .serve_at(PATH, linux::Root)?
+        .serve_at(PATH, player)?
+        .build()
+        .await?;
+    let player = connection.object_server().interface::<_, linux::Player>(PATH).await?;
+
+    while status.changed().await.is_ok() {
+        let emitter = player.signal_emitter();
+        let current = player.get().await;
+        current.playback_status_changed(emitter).await?;
+        current.metadata_changed(emitter).await?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub async fn serve(_action_tx: mpsc::Sender<DownloadAction>, _status: watch::Receiver<QueueStatus>) -> anyhow::Result<()> {
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::collections::HashMap;
+    use tokio::sync::{mpsc, watch};
+    use zbus::interface;
+    use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+
+    use super::QueueStatus;
+    use crate::app_state::events::DownloadAction;
+
+    /// org.mpris.MediaPlayer2, the part every player has to implement
+    pub struct Root;
+
+    #[interface(name = "org.mpris.MediaPlayer2")]
+    impl Root {
+        fn raise(&self) {}
+
+        fn quit(&self) {}
+
+        #[zbus(property)]
+        fn can_quit(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_raise(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn has_track_list(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn identity(&self) -> &str {
+            "gorlock"
+        }
+
+        #[zbus(property)]
+        fn supported_uri_schemes(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        #[zbus(property)]
+        fn supported_mime_types(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    /// org.mpris.MediaPlayer2.Player: play and pause act on the whole queue
+    pub struct Player {
+        pub action_tx: mpsc::Sender<DownloadAction>,
+        pub status: watch::Receiver<QueueStatus>,
+    }
+
+    #[interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl Player {
+        async fn play_pause(&self) {
+            let action = match self.status.borrow().active {
+                0 => DownloadAction::ResumeAll,
+                _ => DownloadAction::PauseAll,
+            };
+            let _ = self.action_tx.send(action).await;
+        }
+
+        async fn play(&self) {
+            let _ = self.action_tx.send(DownloadAction::ResumeAll).await;
+        }
+
+        async fn pause(&self) {
+            let _ = self.action_tx.send(DownloadAction::PauseAll).await;
+        }
+
+        async fn stop(&self) {
+            let _ = self.action_tx.send(DownloadAction::PauseAll).await;
+        }
+
+        fn next(&self) {}
+
+        fn previous(&self) {}
+
+        fn seek(&self, _offset: i64) {}
+
+        fn set_position(&self, _track_id: ObjectPath<'_>, _position: i64) {}
+
+        fn open_uri(&self, _uri: String) {}
+
+        #[zbus(property)]
+        fn playback_status(&self) -> &str {
+            self.status.borrow().playback_status()
+        }
+
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let status = *self.status.borrow();
+            let mut metadata = HashMap::new();
+            let track_id = ObjectPath::from_static_str_unchecked("/org/mpris/MediaPlayer2/TrackList/NoTrack");
+            if let Ok(value) = OwnedValue::try_from(Value::from(track_id)) {
+                metadata.insert("mpris:trackid".to_string(), value);
+            }
+            if let Ok(value) = OwnedValue::try_from(Value::from(status.title())) {
+                metadata.insert("xesam:title".to_string(), value);
+            }
+            metadata
+        }
+
+        #[zbus(property)]
+        fn rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn minimum_rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn maximum_rate(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn volume(&self) -> f64 {
+            1.0
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            0
+        }
+
+        #[zbus(property)]
+        fn can_go_next(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_go_previous(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_play(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_pause(&self) -> bool {
+            true
+        }
+
+        #[zbus(property)]
+        fn can_seek(&self) -> bool {
+            false
+        }
+
+        #[zbus(property)]
+        fn can_control(&self) -> bool {
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::DownloadItem;
+
+    #[test]
+    fn test_queue_status() {
+        let mut state = AppState::default();
+        assert_eq!(QueueStatus::of(&state).playback_status(), "Stopped");
+
+        for status in [DownloadStatus::Downloading, DownloadStatus::Downloading, DownloadStatus::Paused] {
+            let mut item = DownloadItem::new("https://example.com".to_string());
+            item.status = status;
+            state.queue.push(item);
+        }
+        let status = QueueStatus::of(&state);
+        assert_eq!(status, QueueStatus { active: 2, paused: 1 });
+        assert_eq!(status.playback_status(), "Playing");
+        assert_eq!(status.title(), "2 downloads active");
+    }
+}
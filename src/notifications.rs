@@ -0,0 +1,345 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::app_state::{DownloadItem, DownloadStatus, format_duration_from_seconds};
+
+/// Choices for how long a run has to take before its end is worth a notification
+pub const MIN_RUN_MINUTES: &[u32] = &[0, 5, 10, 30, 60];
+
+/// Telegram and email notifications, nothing is sent until a backend is filled in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Notifications {
+    pub telegram_bot_token: Option<String>,
+    pub telegram_chat_id: Option<String>,
+    /// "host:port", port 465 uses TLS from the start and any other STARTTLS
+    pub smtp_server: Option<String>,
+    pub smtp_user: Option<String>,
+    pub smtp_password: Option<String>,
+    pub email_from: Option<String>,
+    pub email_to: Option<String>,
+    pub on_queue_finished: bool,
+    pub on_failure: bool,
+    /// Runs shorter than this finish without a notification
+    pub min_run_minutes: u32,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self {
+            telegram_bot_token: None,
+            telegram_chat_id: None,
+            smtp_server: None,
+            smtp_user: None,
+            smtp_password: None,
+            email_from: None,
+            email_to: None,
+            on_queue_finished: true,
+            on_failure: true,
+            min_run_minutes: 10,
+        }
+    }
+}
+
+/// Rows of the notifications panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NotifyField {
+    TelegramBotToken,
+    TelegramChatId,
+    SmtpServer,
+    SmtpUser,
+    SmtpPassword,
+    EmailFrom,
+    EmailTo,
+    OnQueueFinished,
+    OnFailure,
+    MinRunMinutes,
+}
+
+impl NotifyField {
+    pub const ALL: &'static [NotifyField] = &[
+        NotifyField::TelegramBotToken,
+        NotifyField::TelegramChatId,
+        NotifyField::SmtpServer,
+        NotifyField::SmtpUser,
+        NotifyField::SmtpPassword,
+        NotifyField::EmailFrom,
+        NotifyField::EmailTo,
+        NotifyField::OnQueueFinished,
+        NotifyField::OnFailure,
+        NotifyField::MinRunMinutes,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            NotifyField::TelegramBotToken => "Telegram bot token",
+            NotifyField::TelegramChatId => "Telegram chat ID",
+            NotifyField::SmtpServer => "SMTP server (host:port)",
+            NotifyField::SmtpUser => "SMTP user",
+            NotifyField::SmtpPassword => "SMTP password",
+            NotifyField::EmailFrom => "Email from",
+            NotifyField::EmailTo => "Email to",
+            NotifyField::OnQueueFinished => "When the queue finishes",
+            NotifyField::OnFailure => "When an item fails",
+            NotifyField::MinRunMinutes => "Only for runs of at least",
+        }
+    }
+}
+
+impl Notifications {
+    /// The text a field is edited as, `None` for the ones cycled with ←/→
+    pub fn text_mut(&mut self, field: NotifyField) -> Option<&mut Option<String>> {
+        match field {
+            NotifyField::TelegramBotToken => Some(&mut self.telegram_bot_token),
+            NotifyField::TelegramChatId => Some(&mut self.telegram_chat_id),
+            NotifyField::SmtpServer => Some(&mut self.smtp_server),
+            NotifyField::SmtpUser => Some(&mut self.smtp_user),
+            NotifyField::SmtpPassword => Some(&mut self.smtp_password),
+            NotifyField::EmailFrom => Some(&mut self.email_from),
+            NotifyField::EmailTo => Some(&mut self.email_to),
+            NotifyField::OnQueueFinished | NotifyField::OnFailure | NotifyField::MinRunMinutes => None,
+        }
+    }
+
+    /// Display value, with secrets masked
+    pub fn display(&self, field: NotifyField) -> String {
+        let text = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".to_string());
+        let secret = |value: &Option<String>| if value.is_some() { "••••••".to_string() } else { "-".to_string() };
+        let on_off = |value: bool| if value { "on" } else { "off" }.to_string();
+        match field {
+            NotifyField::TelegramBotToken => secret(&self.telegram_bot_token),
+            NotifyField::TelegramChatId => text(&self.telegram_chat_id),
+            NotifyField::SmtpServer => text(&self.smtp_server),
+            NotifyField::SmtpUser => text(&self.smtp_user),
+            NotifyField::SmtpPassword => secret(&self.smtp_password),
+            NotifyField::EmailFrom => text(&self.email_from),
+            NotifyField::EmailTo => text(&self.email_to),
+            NotifyField::OnQueueFinished => on_off(self.on_queue_finished),
+            NotifyField::OnFailure => on_off(self.on_failure),
+            NotifyField::MinRunMinutes => format!("{} min", self.min_run_minutes),
+        }
+    }
+
+    /// Flip a toggle or step through the run length choices
+    pub fn adjust(&mut self, field: NotifyField, delta: i32) {
+        match field {
+            NotifyField::OnQueueFinished => self.on_queue_finished = !self.on_queue_finished,
+            NotifyField::OnFailure => self.on_failure = !self.on_failure,
+            NotifyField::MinRunMinutes => {
+                let index = MIN_RUN_MINUTES.iter().position(|m| *m == self.min_run_minutes).unwrap_or(0) as i32;
+                self.min_run_minutes = MIN_RUN_MINUTES[(index + delta).clamp(0, MIN_RUN_MINUTES.len() as i32 - 1) as usize];
+            }
+            _ => {}
+        }
+    }
+
+    fn telegram(&self) -> Option<(&str, &str)> {
+        Some((self.telegram_bot_token.as_deref()?, self.telegram_chat_id.as_deref()?))
+    }
+
+    fn email(&self) -> Option<(&str, &str, &str)> {
+        Some((self.smtp_server.as_deref()?, self.email_from.as_deref()?, self.email_to.as_deref()?))
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.telegram().is_some() || self.email().is_some()
+    }
+
+    /// Summary for the settings list
+    pub fn summary(&self) -> String {
+        let backends: Vec<&str> = [
+            self.telegram().map(|_| "Telegram"),
+            self.email().map(|_| "email"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if backends.is_empty() {
+            "off".to_string()
+        } else {
+            backends.join(" + ")
+        }
+    }
+}
+
+/// A notification, the subject doubles as the first line on Telegram
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub subject: String,
+    pub body: String,
+}
+
+impl Message {
+    pub fn test() -> Self {
+        Self {
+            subject: "gorlock test notification".to_string(),
+            body: "Notifications from gorlock will arrive here.".to_string(),
+        }
+    }
+
+    /// For an item that failed for good, including after the troubleshooting retries
+    pub fn item_failed(item: &DownloadItem, error: &str) -> Self {
+        let title = item.title.as_deref().unwrap_or(&item.url);
+        let mut body = format!("{}\n{}\n\n{}", title, item.url, error);
+        if !item.retry_args.is_empty() {
+            body.push_str(&format!("\n\nRetried with: {}", crate::commands::troubleshoot::redact_args(&item.retry_args).join(" ")));
+        }
+        Self {
+            subject: format!("Download failed: {}", title),
+            body,
+        }
+    }
+}
+
+/// What happened since the queue last went idle, for the "queue finished" notification
+#[derive(Debug, Clone, Default)]
+pub struct Run {
+    started: Option<DateTime<Utc>>,
+    completed: usize,
+    failed: Vec<String>,
+}
+
+impl Run {
+    pub fn start(&mut self) {
+        self.started.get_or_insert_with(Utc::now);
+    }
+
+    pub fn record(&mut self, item: &DownloadItem) {
+        if item.status.is_completed() {
+            self.completed += 1;
+        } else if matches!(item.status, DownloadStatus::Failed | DownloadStatus::Unavailable) {
+            self.failed.push(item.title.clone().unwrap_or_else(|| item.url.clone()));
+        }
+    }
+
+    /// End the run once nothing is downloading or waiting. The message is there when the run
+    /// took long enough and the setting is on.
+    pub fn finish(&mut self, queue: &[DownloadItem], settings: &Notifications, now: DateTime<Utc>) -> Option<Message> {
+        let busy = queue
+            .iter()
+            .any(|i| matches!(i.status, DownloadStatus::Downloading | DownloadStatus::FetchingInfo | DownloadStatus::Queued));
+        if busy {
+            return None;
+        }
+        let run = std::mem::take(self);
+        let seconds = (now - run.started?).num_seconds().max(0) as u64;
+        if !settings.on_queue_finished || seconds < settings.min_run_minutes as u64 * 60 {
+            return None;
+        }
+
+        let mut body = format!(
+            "{} downloaded, {} failed in {}.",
+            run.completed,
+            run.failed.len(),
+            format_duration_from_seconds(seconds)
+        );
+        for title in &run.failed {
+            body.push_str(&format!("\nFailed: {}", title));
+        }
+        Some(Message {
+            subject: "gorlock: queue finished".to_string(),
+            body,
+        })
+    }
+}
+
+async fn send_telegram(token: &str, chat_id: &str, message: &Message) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+        .timeout(Duration::from_secs(20))
+        .header("Content-Type", "application/json")
+        .body(
+            serde_json::json!({
+                "chat_id": chat_id,
+                "text": format!("{}\n\n{}", message.subject, message.body),
+            })
+            .to_string(),
+        )
+        .send()
+        .await
+        // The bot token is part of the URL, which stays out of errors shown and logged
+        .map_err(|e| e.without_url())?;
+    let reply: serde_json::Value = serde_json::from_str(&response.text().await.map_err(|e| e.without_url())?)?;
+    if reply["ok"] != true {
+        return Err(anyhow!("{}", reply["description"].as_str().unwrap_or("Telegram refused the message")));
+    }
+    Ok(())
+}
+
+async fn send_email(settings: &Notifications, server: &str, from: &str, to: &str, message: &Message) -> Result<()> {
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor};
+
+    let (host, port) = match server.rsplit_once(':') {
+        Some((host, port)) => (host, port.parse().map_err(|_| anyhow!("bad SMTP port in {}", server))?),
+        None => (server, 587),
+    };
+    let email = lettre::Message::builder()
+        .from(from.parse()?)
+        .to(to.parse()?)
+        .subject(&message.subject)
+        .body(message.body.clone())?;
+
+    let mut transport = if port == 465 {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+    }
+    .port(port)
+    .timeout(Some(Duration::from_secs(20)));
+    if let Some(user) = &settings.smtp_user {
+        transport = transport.credentials(Credentials::new(user.clone(), settings.smtp_password.clone().unwrap_or_default()));
+    }
+    transport.build().send(email).await?;
+    Ok(())
+}
+
+/// Send to every configured backend, the result per backend by name
+pub async fn send(settings: &Notifications, message: &Message) -> Vec<(&'static str, Result<()>)> {
+    let mut results = Vec::new();
+    if let Some((token, chat_id)) = settings.telegram() {
+        results.push(("Telegram", send_telegram(token, chat_id, message).await));
+    }
+    if let Some((server, from, to)) = settings.email() {
+        results.push(("email", send_email(settings, server, from, to, message).await));
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_finish() {
+        let settings = Notifications::default();
+        let now = Utc::now();
+        let mut done = DownloadItem::new("https://example.com/1".to_string());
+        done.status = DownloadStatus::Completed;
+        let mut failed = DownloadItem::new("https://example.com/2".to_string());
+        failed.title = Some("Broken".to_string());
+        failed.status = DownloadStatus::Failed;
+        let mut waiting = DownloadItem::new("https://example.com/3".to_string());
+        waiting.status = DownloadStatus::Queued;
+
+        let mut run = Run::default();
+        run.start();
+        run.started = Some(now - chrono::Duration::minutes(20));
+        run.record(&done);
+        run.record(&failed);
+        let mut queue = vec![done, failed, waiting];
+        assert_eq!(run.finish(&queue, &settings, now), None);
+
+        queue[2].status = DownloadStatus::Ready;
+        let message = run.finish(&queue, &settings, now).unwrap();
+        assert_eq!(message.body, "1 downloaded, 1 failed in 20m 0s.\nFailed: Broken");
+        // The run is over, the next one starts fresh
+        assert_eq!(run.finish(&queue, &settings, now), None);
+
+        // Short runs don't notify
+        run.started = Some(now - chrono::Duration::minutes(2));
+        assert_eq!(run.finish(&queue, &settings, now), None);
+    }
+}
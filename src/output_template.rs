@@ -0,0 +1,161 @@
+use regex::Regex;
+use std::collections::HashMap;
+
+use crate::app_state::DownloadItem;
+
+lazy_static::lazy_static! {
+    /// A `%(field)s` reference or an escaped `%%`
+    static ref FIELD: Regex = Regex::new(r"%%|%\(([^)]*)\)([-0-9.]*)([sdf])").unwrap();
+}
+
+/// A yt-dlp output template field offered in the template editor
+pub struct TemplateField {
+    pub name: &'static str,
+    /// What gets inserted, with a useful format where the field has one
+    pub insert: &'static str,
+    pub description: &'static str,
+    /// Shown in the preview when no queue item is selected
+    pub example: &'static str,
+}
+
+pub const TEMPLATE_FIELDS: &[TemplateField] = &[
+    TemplateField { name: "title", insert: "%(title)s", description: "Video title", example: "Big Buck Bunny" },
+    TemplateField { name: "ext", insert: "%(ext)s", description: "File extension, keep it at the end", example: "mp4" },
+    TemplateField { name: "id", insert: "%(id)s", description: "Video ID on the site", example: "aqz-KE-bpKQ" },
+    TemplateField { name: "uploader", insert: "%(uploader)s", description: "Uploader or channel name", example: "Blender" },
+    TemplateField {
+        name: "upload_date",
+        insert: "%(upload_date>%Y-%m-%d)s",
+        description: "Upload date; after > comes a strftime format, without it YYYYMMDD",
+        example: "20140110",
+    },
+    TemplateField { name: "duration_string", insert: "%(duration_string)s", description: "Length as H:MM:SS", example: "10:34" },
+    TemplateField { name: "resolution", insert: "%(resolution)s", description: "Width x height of the chosen format", example: "1920x1080" },
+    TemplateField { name: "height", insert: "%(height)sp", description: "Height of the chosen format", example: "1080" },
+    TemplateField { name: "format_id", insert: "%(format_id)s", description: "yt-dlp format code", example: "137+140" },
+    TemplateField { name: "view_count", insert: "%(view_count)s", description: "Views when fetched", example: "1234567" },
+    TemplateField { name: "like_count", insert: "%(like_count)s", description: "Likes when fetched", example: "23456" },
+    TemplateField { name: "extractor", insert: "%(extractor)s", description: "Site the video comes from", example: "youtube" },
+    TemplateField {
+        name: "playlist_title",
+        insert: "%(playlist_title)s",
+        description: "Playlist name, NA outside playlists",
+        example: "Open Movies",
+    },
+    TemplateField {
+        name: "playlist_index",
+        insert: "%(playlist_index)03d",
+        description: "Position in the playlist; 03d pads it to three digits",
+        example: "7",
+    },
+];
+
+/// Field values of a queue item, as far as they are known before downloading
+pub fn item_values(item: &DownloadItem, ext: &str) -> HashMap<&'static str, String> {
+    let mut values = HashMap::new();
+    let mut set = |name: &'static str, value: Option<String>| {
+        if let Some(value) = value {
+            values.insert(name, value);
+        }
+    };
+    set("title", item.title.clone());
+    set("ext", Some(ext.to_string()));
+    // Archive keys are "<extractor> <id>", known from the URL for some sites before fetching
+    let archive_key = item.archive_key.clone().or_else(|| crate::archive::archive_key_for_url(&item.url));
+    let key = archive_key.as_deref().and_then(|k| k.split_once(' '));
+    set("extractor", key.map(|(extractor, _)| extractor.to_string()));
+    set("id", key.map(|(_, id)| id.to_string()));
+    set("uploader", item.metadata.uploader.clone());
+    set("upload_date", item.metadata.upload_date.clone());
+    set("duration_string", item.duration.clone());
+    set("view_count", item.metadata.view_count.map(|c| c.to_string()));
+    set("like_count", item.metadata.like_count.map(|c| c.to_string()));
+    if let Some(format) = &item.format {
+        set("format_id", Some(format.format_id.clone()));
+        set("resolution", format.resolution.clone());
+        let height = format.resolution.as_deref().and_then(|r| r.split_once('x')).map(|(_, h)| h.to_string());
+        set("height", height);
+    }
+    values
+}
+
+/// Extension the item's file will get: the merge container, or the format's own
+pub fn item_ext(item: &DownloadItem, merge_container: &str) -> String {
+    item.container
+        .clone()
+        .or_else(|| item.format.as_ref().map(|f| f.ext.clone()))
+        .unwrap_or_else(|| merge_container.to_string())
+}
+
+/// Example values for every field, for previews without an item
+pub fn example_values() -> HashMap<&'static str, String> {
+    TEMPLATE_FIELDS.iter().map(|f| (f.name, f.example.to_string())).collect()
+}
+
+/// Fill in a template the way yt-dlp would: `%(a,b)s` alternatives, `%(field>%Y)s` date
+/// formats, `%(field|default)s` defaults, `%(n)03d` padding, and "NA" for anything unknown
+pub fn substitute(template: &str, values: &HashMap<&str, String>) -> String {
+    FIELD
+        .replace_all(template, |caps: &regex::Captures| {
+            let Some(body) = caps.get(1) else {
+                return "%".to_string();
+            };
+            let (fields, default) = match body.as_str().split_once('|') {
+                Some((fields, default)) => (fields, Some(default)),
+                None => (body.as_str(), None),
+            };
+            let value = fields.split(',').find_map(|alternative| {
+                let (name, date_format) = match alternative.split_once('>') {
+                    Some((name, format)) => (name, Some(format)),
+                    None => (alternative, None),
+                };
+                let value = values.get(name.trim())?;
+                Some(match date_format {
+                    Some(format) => format_date(value, format),
+                    None => value.clone(),
+                })
+            });
+            let Some(value) = value else {
+                return default.unwrap_or("NA").to_string();
+            };
+            match (&caps[3], caps[2].trim_start_matches('0').parse::<usize>(), value.parse::<i64>()) {
+                ("d", Ok(width), Ok(number)) => format!("{:0width$}", number, width = width),
+                // Values can't add directories, yt-dlp swaps slashes for a look-alike
+                _ => value.replace('/', "⧸"),
+            }
+        })
+        .into_owned()
+}
+
+/// "20140110" with "%Y-%m-%d" -> "2014-01-10", other values pass through
+fn format_date(value: &str, format: &str) -> String {
+    match chrono::NaiveDate::parse_from_str(value, "%Y%m%d") {
+        Ok(date) => date.format(format).to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let values: HashMap<&str, String> = [
+            ("title", "AC/DC Live".to_string()),
+            ("ext", "mkv".to_string()),
+            ("upload_date", "20250307".to_string()),
+            ("playlist_index", "7".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            substitute("%(upload_date>%Y-%m-%d)s - %(title)s.%(ext)s", &values),
+            "2025-03-07 - AC⧸DC Live.mkv"
+        );
+        assert_eq!(substitute("%(playlist_index)03d %(uploader)s", &values), "007 NA");
+        assert_eq!(substitute("%(uploader,title)s 100%%", &values), "AC⧸DC Live 100%");
+        assert_eq!(substitute("%(channel|Unknown)s/%(title)s", &values), "Unknown/AC⧸DC Live");
+    }
+}
@@ -0,0 +1,144 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::config;
+
+/// Unpinned URLs kept, oldest dropped first
+pub const MAX_RECENT: usize = 50;
+
+/// Where a recent URL came from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RecentSource {
+    Added,
+    Clipboard,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecentUrl {
+    pub url: String,
+    pub source: RecentSource,
+    pub seen_at: DateTime<Utc>,
+    /// Pinned URLs stay at the top and are never dropped
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// URLs recently added or copied, persisted to recent_urls.json in the data directory
+#[derive(Debug, Default)]
+pub struct RecentUrls {
+    pub entries: Vec<RecentUrl>,
+    path: Option<PathBuf>,
+}
+
+impl RecentUrls {
+    pub fn load() -> Self {
+        let path = config::data_dir().map(|dir| dir.join("recent_urls.json"));
+        let entries = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self { entries, path }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+
+    /// Put a URL first among the unpinned ones, a URL seen again moves up rather than repeating
+    pub fn record(&mut self, url: &str, source: RecentSource) {
+        let pinned = match self.entries.iter().position(|e| e.url == url) {
+            Some(i) => self.entries.remove(i).pinned,
+            None => false,
+        };
+        let entry = RecentUrl {
+            url: url.to_string(),
+            source,
+            seen_at: Utc::now(),
+            pinned,
+        };
+        let first_unpinned = self.entries.iter().position(|e| !e.pinned).unwrap_or(self.entries.len());
+        self.entries.insert(if pinned { 0 } else { first_unpinned }, entry);
+
+        let mut unpinned = 0;
+        self.entries.retain(|e| {
+            unpinned += usize::from(!e.pinned);
+            e.pinned || unpinned <= MAX_RECENT
+        });
+    }
+
+    /// Pin or unpin an entry, pinned ones are listed first. Returns the entry's new index.
+    pub fn toggle_pin(&mut self, index: usize) -> usize {
+        if index >= self.entries.len() {
+            return index;
+        }
+        let mut entry = self.entries.remove(index);
+        entry.pinned = !entry.pinned;
+        let pinned_count = self.entries.iter().filter(|e| e.pinned).count();
+        let target = if entry.pinned { 0 } else { pinned_count };
+        self.entries.insert(target, entry);
+        target
+    }
+}
+
+/// Whether copied text is a single URL worth remembering
+pub fn looks_like_url(text: &str) -> bool {
+    let text = text.trim();
+    (text.starts_with("https://") || text.starts_with("http://"))
+        && !text.contains(char::is_whitespace)
+        && reqwest::Url::parse(text).is_ok()
+}
+
+lazy_static::lazy_static! {
+    /// Kept open for the whole run: on X11 copied text is only there while its owner lives
+    static ref CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(arboard::Clipboard::new().ok());
+}
+
+/// Text on the system clipboard, `None` without a clipboard or when it holds something else
+pub fn read_clipboard() -> Option<String> {
+    CLIPBOARD.lock().ok()?.as_mut()?.get_text().ok()
+}
+
+pub fn copy_to_clipboard(text: &str) -> Result<()> {
+    let mut clipboard = CLIPBOARD.lock().map_err(|_| anyhow!("clipboard unavailable"))?;
+    let clipboard = clipboard.as_mut().ok_or_else(|| anyhow!("no clipboard available"))?;
+    clipboard.set_text(text)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_pin() {
+        let mut recent = RecentUrls::default();
+        recent.record("https://a.example/1", RecentSource::Added);
+        recent.record("https://a.example/2", RecentSource::Clipboard);
+        recent.record("https://a.example/1", RecentSource::Clipboard);
+        let urls: Vec<&str> = recent.entries.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(urls, ["https://a.example/1", "https://a.example/2"]);
+
+        assert_eq!(recent.toggle_pin(1), 0);
+        for i in 0..MAX_RECENT + 5 {
+            recent.record(&format!("https://b.example/{}", i), RecentSource::Added);
+        }
+        assert_eq!(recent.entries.len(), MAX_RECENT + 1);
+        assert_eq!(recent.entries[0].url, "https://a.example/2");
+        assert!(recent.entries[0].pinned);
+
+        assert!(looks_like_url(" https://youtu.be/abc \n"));
+        assert!(!looks_like_url("see https://youtu.be/abc"));
+    }
+}
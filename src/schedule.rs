@@ -0,0 +1,279 @@
+use anyhow::{Result, anyhow};
+use chrono::{DateTime, Datelike, Duration, Local, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use crate::config;
+
+/// Cron-like rule: "minute hour day-of-month month day-of-week".
+///
+/// Each field takes `*`, numbers, ranges (`1-5`), lists (`1,15`) and steps (`*/15`).
+/// Days of the week are 0-7 (0 and 7 are Sunday) or names (`mon`, `fri`...).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronRule {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days: Vec<u32>,
+    months: Vec<u32>,
+    weekdays: Vec<u32>,
+    /// Whether day-of-month / day-of-week were restricted, for cron's "either matches" rule
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// A recurring live recording of a channel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: Uuid,
+    /// Channel live URL, e.g. https://www.youtube.com/@channel/live
+    pub url: String,
+    /// Rule as typed, parsed on load
+    pub rule: String,
+    /// Stop the recording after this long, `None` records until the stream ends
+    #[serde(default)]
+    pub stop_after_minutes: Option<u32>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Last time the rule fired, so a minute is never triggered twice
+    #[serde(default)]
+    pub last_run: Option<DateTime<Utc>>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// Recording schedules, persisted to schedules.json in the config directory
+#[derive(Debug, Default)]
+pub struct Schedules {
+    pub entries: Vec<Schedule>,
+    path: Option<PathBuf>,
+}
+
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronRule {
+    pub fn parse(rule: &str) -> Result<Self> {
+        let fields: Vec<&str> = rule.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(anyhow!("expected 5 fields (minute hour day month weekday), got {}", fields.len()));
+        };
+
+        let mut weekdays = parse_field(&weekday.to_lowercase(), 0, 7, "weekday")?;
+        // 7 is Sunday too
+        for day in &mut weekdays {
+            *day %= 7;
+        }
+        weekdays.sort_unstable();
+        weekdays.dedup();
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, "minute")?,
+            hours: parse_field(hour, 0, 23, "hour")?,
+            days: parse_field(day, 1, 31, "day")?,
+            months: parse_field(month, 1, 12, "month")?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    /// Whether the rule fires in the minute containing `time`
+    pub fn matches(&self, time: &DateTime<Local>) -> bool {
+        let day = self.days.contains(&time.day());
+        let weekday = self.weekdays.contains(&time.weekday().num_days_from_sunday());
+        // Like cron: when both day fields are restricted, either one matching is enough
+        let day_matches = match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        };
+
+        self.minutes.contains(&time.minute())
+            && self.hours.contains(&time.hour())
+            && self.months.contains(&time.month())
+            && day_matches
+    }
+
+    /// First time strictly after `after` the rule fires, looking up to a year ahead
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = after + Duration::days(366);
+
+        while time <= limit {
+            if !self.hours.contains(&time.hour()) {
+                // Skip to the next hour
+                time += Duration::minutes(60 - time.minute() as i64);
+                continue;
+            }
+            if self.matches(&time) {
+                return Some(time);
+            }
+            time += Duration::minutes(1);
+        }
+        None
+    }
+}
+
+/// Parse one cron field into the sorted values it allows
+fn parse_field(field: &str, min: u32, max: u32, name: &str) -> Result<Vec<u32>> {
+    let value = |text: &str| -> Result<u32> {
+        let number = match WEEKDAY_NAMES.iter().position(|day| *day == text) {
+            Some(index) if name == "weekday" => index as u32,
+            _ => text
+                .parse()
+                .map_err(|_| anyhow!("invalid {} '{}'", name, text))?,
+        };
+        if number < min || number > max {
+            return Err(anyhow!("{} {} out of range {}-{}", name, number, min, max));
+        }
+        Ok(number)
+    };
+
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| anyhow!("invalid step in '{}'", part))?,
+            ),
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // "5/10" means from 5 to the end in steps of 10
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            return Err(anyhow!("empty {} range '{}'", name, part));
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+impl Schedule {
+    pub fn new(url: String, rule: String, stop_after_minutes: Option<u32>) -> Result<Self> {
+        CronRule::parse(&rule)?;
+        Ok(Self {
+            id: Uuid::new_v4(),
+            url,
+            rule,
+            stop_after_minutes,
+            enabled: true,
+            last_run: None,
+        })
+    }
+
+    /// Parse the "<5 cron fields> <url> [minutes]" line typed in the Schedules panel
+    pub fn from_line(line: &str) -> Result<Self> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.len() < 6 {
+            return Err(anyhow!("expected \"<minute> <hour> <day> <month> <weekday> <url> [minutes]\""));
+        }
+        let stop_after_minutes = match words.get(6) {
+            Some(minutes) => Some(
+                minutes
+                    .parse()
+                    .map_err(|_| anyhow!("invalid recording length '{}'", minutes))?,
+            ),
+            None => None,
+        };
+        Self::new(words[5].to_string(), words[..5].join(" "), stop_after_minutes)
+    }
+
+    pub fn cron(&self) -> Option<CronRule> {
+        CronRule::parse(&self.rule).ok()
+    }
+
+    /// Next time this schedule will fire
+    pub fn next_run(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        self.cron()?.next_after(now)
+    }
+}
+
+impl Schedules {
+    /// Load schedules from the config directory, dropping rules that no longer parse
+    pub fn load() -> Self {
+        let path = config::config_dir().map(|dir| dir.join("schedules.json"));
+        let entries: Vec<Schedule> = path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+
+        Self {
+            entries: entries.into_iter().filter(|s| s.cron().is_some()).collect(),
+            path,
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(&self.entries)?)?;
+        Ok(())
+    }
+
+    /// Schedules firing in the current minute that haven't run yet, marking them as run
+    pub fn take_due(&mut self, now: DateTime<Local>) -> Vec<Schedule> {
+        let Some(minute) = now.with_second(0).and_then(|t| t.with_nanosecond(0)) else {
+            return Vec::new();
+        };
+
+        let mut due = Vec::new();
+        for schedule in self.entries.iter_mut().filter(|s| s.enabled) {
+            let already_ran = schedule
+                .last_run
+                .is_some_and(|last| last >= minute.with_timezone(&Utc));
+            if !already_ran && schedule.cron().is_some_and(|rule| rule.matches(&now)) {
+                schedule.last_run = Some(now.with_timezone(&Utc));
+                due.push(schedule.clone());
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_cron_rule() {
+        // Fridays at 20:00
+        let rule = CronRule::parse("0 20 * * fri").unwrap();
+        let friday = Local.with_ymd_and_hms(2026, 10, 16, 20, 0, 30).unwrap();
+        assert!(rule.matches(&friday));
+        assert!(!rule.matches(&(friday + Duration::minutes(1))));
+        assert_eq!(rule.next_after(friday), Some(Local.with_ymd_and_hms(2026, 10, 23, 20, 0, 0).unwrap()));
+
+        let rule = CronRule::parse("*/15 9-17 * * 1-5").unwrap();
+        assert_eq!(rule.minutes, vec![0, 15, 30, 45]);
+        assert_eq!(rule.weekdays, vec![1, 2, 3, 4, 5]);
+        assert_eq!(CronRule::parse("0 0 * * 7").unwrap().weekdays, vec![0]);
+
+        assert!(CronRule::parse("0 20 * *").is_err());
+        assert!(CronRule::parse("60 20 * * *").is_err());
+        assert!(CronRule::parse("0 20 * * funday").is_err());
+
+        let schedule = Schedule::from_line("30 19 * * sat https://example.com/live 90").unwrap();
+        assert_eq!(schedule.rule, "30 19 * * sat");
+        assert_eq!(schedule.stop_after_minutes, Some(90));
+    }
+}
@@ -0,0 +1,209 @@
+use rhai::{Array, CallFnOptions, Dynamic, Engine, Map, Scope, AST};
+use std::sync::{Arc, Mutex};
+
+use crate::app_state::DownloadItem;
+use crate::config;
+use crate::hooks::HookEvent;
+
+/// User scripts (*.rhai in the scripts directory) that react to queue events.
+///
+/// Each script may define `on_add`, `pre_download`, `post_download` and `on_fail`.
+/// The item is bound to `this`: scripts can change `this.tags` and `this.output_dir`,
+/// and set `this.reject = "reason"` in `on_add`/`pre_download` to refuse the item.
+pub struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<(String, AST)>,
+    /// Lines printed by scripts during the current call
+    output: Arc<Mutex<Vec<String>>>,
+}
+
+impl std::fmt::Debug for ScriptHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names: Vec<&str> = self.scripts.iter().map(|(name, _)| name.as_str()).collect();
+        f.debug_struct("ScriptHost").field("scripts", &names).finish()
+    }
+}
+
+impl Default for ScriptHost {
+    fn default() -> Self {
+        Self::from_sources(Vec::new()).0
+    }
+}
+
+impl ScriptHost {
+    /// Compile every script in the scripts directory, returning load errors alongside
+    pub fn load() -> (Self, Vec<String>) {
+        let mut sources = Vec::new();
+        let mut errors = Vec::new();
+
+        if let Some(dir) = scripts_dir() {
+            let mut paths: Vec<_> = std::fs::read_dir(&dir)
+                .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+                .unwrap_or_default();
+            paths.retain(|p| p.extension().is_some_and(|ext| ext == "rhai"));
+            paths.sort();
+
+            for path in paths {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                match std::fs::read_to_string(&path) {
+                    Ok(source) => sources.push((name, source)),
+                    Err(e) => errors.push(format!("[script {}] failed to read: {}", name, e)),
+                }
+            }
+        }
+
+        let (host, compile_errors) = Self::from_sources(sources);
+        errors.extend(compile_errors);
+        (host, errors)
+    }
+
+    /// Build a host from (name, source) pairs
+    pub fn from_sources(sources: Vec<(String, String)>) -> (Self, Vec<String>) {
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let mut engine = Engine::new();
+
+        // Scripts run on the UI thread, keep runaway loops short
+        engine.set_max_operations(100_000);
+
+        let print_output = output.clone();
+        engine.on_print(move |text| print_output.lock().unwrap().push(text.to_string()));
+        let debug_output = output.clone();
+        engine.on_debug(move |text, _, _| debug_output.lock().unwrap().push(text.to_string()));
+
+        engine.register_fn("matches", |text: &str, pattern: &str| {
+            regex::Regex::new(pattern).map(|re| re.is_match(text)).unwrap_or(false)
+        });
+
+        let mut scripts = Vec::new();
+        let mut errors = Vec::new();
+        for (name, source) in sources {
+            match engine.compile(&source) {
+                Ok(ast) => scripts.push((name, ast)),
+                Err(e) => errors.push(format!("[script {}] {}", name, e)),
+            }
+        }
+
+        (Self { engine, scripts, output }, errors)
+    }
+
+    pub fn len(&self) -> usize {
+        self.scripts.len()
+    }
+
+    /// Run every script's handler for `event` against the item.
+    /// Returns the rejection reason if a script refused the item.
+    pub fn run(&self, event: HookEvent, item: &mut DownloadItem) -> Option<String> {
+        let function = event.name().replace('-', "_");
+        let can_reject = matches!(event, HookEvent::OnAdd | HookEvent::PreDownload);
+
+        for (name, ast) in &self.scripts {
+            if !ast.iter_functions().any(|f| f.name == function && f.params.is_empty()) {
+                continue;
+            }
+
+            let mut this = Dynamic::from_map(item_to_map(item));
+            let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+            let result = self
+                .engine
+                .call_fn_with_options::<Dynamic>(options, &mut Scope::new(), ast, &function, ());
+
+            for line in self.output.lock().unwrap().drain(..) {
+                item.push_log(format!("[script {}] {}", name, line));
+            }
+
+            if let Err(e) = result {
+                item.push_log(format!("[error] [script {}] {}: {}", name, function, e));
+                continue;
+            }
+
+            if let Some(map) = this.try_cast::<Map>() {
+                let reject = apply_map(&map, item);
+                if let Some(reason) = reject.filter(|_| can_reject) {
+                    item.push_log(format!("[script {}] rejected: {}", name, reason));
+                    return Some(reason);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Directory scanned for *.rhai scripts
+pub fn scripts_dir() -> Option<std::path::PathBuf> {
+    config::config_dir().map(|dir| dir.join("scripts"))
+}
+
+/// Item fields exposed to scripts as `this`
+fn item_to_map(item: &DownloadItem) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), item.id.to_string().into());
+    map.insert("url".into(), item.url.clone().into());
+    map.insert("title".into(), item.title.clone().unwrap_or_default().into());
+    map.insert("uploader".into(), item.metadata.uploader.clone().unwrap_or_default().into());
+    map.insert("upload_date".into(), item.metadata.upload_date.clone().unwrap_or_default().into());
+    map.insert("view_count".into(), item.metadata.view_count.map_or(Dynamic::UNIT, |n| (n as i64).into()));
+    map.insert("like_count".into(), item.metadata.like_count.map_or(Dynamic::UNIT, |n| (n as i64).into()));
+    map.insert("status".into(), item.status.to_string().into());
+    map.insert("file_path".into(), item.file_path.clone().unwrap_or_default().into());
+    map.insert("error".into(), item.error.clone().unwrap_or_default().into());
+    let tags: Array = item.tags.iter().cloned().map(Dynamic::from).collect();
+    map.insert("tags".into(), tags.into());
+    map.insert("output_dir".into(), item.output_dir.clone().unwrap_or_default().into());
+    map.insert("reject".into(), Dynamic::UNIT);
+    map
+}
+
+/// Copy back the fields scripts may change, returning a rejection reason if one was set
+fn apply_map(map: &Map, item: &mut DownloadItem) -> Option<String> {
+    if let Some(tags) = map.get("tags").and_then(|t| t.clone().try_cast::<Array>()) {
+        item.tags = tags.into_iter().map(|t| t.to_string()).collect();
+        item.tags.dedup();
+    }
+
+    if let Some(dir) = map.get("output_dir").and_then(|d| d.clone().into_string().ok()) {
+        item.output_dir = (!dir.trim().is_empty()).then_some(dir);
+    }
+
+    map.get("reject").and_then(|r| match r.clone().into_string() {
+        Ok(reason) if !reason.is_empty() => Some(reason),
+        // `this.reject = true` works too
+        _ => r.as_bool().ok().filter(|b| *b).map(|_| "rejected by script".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_tag_reroute_and_reject() {
+        let source = r#"
+            fn on_add() {
+                if matches(this.url, "music\\.youtube") {
+                    this.tags.push("music");
+                    this.output_dir = "/srv/music";
+                }
+                if this.url.contains("shorts") {
+                    this.reject = "no shorts";
+                }
+            }
+        "#;
+        let (host, errors) = ScriptHost::from_sources(vec![("rules.rhai".into(), source.into())]);
+        assert!(errors.is_empty());
+
+        let mut item = DownloadItem::new("https://music.youtube.com/watch?v=abc".to_string());
+        assert_eq!(host.run(HookEvent::OnAdd, &mut item), None);
+        assert_eq!(item.tags, vec!["music"]);
+        assert_eq!(item.output_dir.as_deref(), Some("/srv/music"));
+
+        let mut item = DownloadItem::new("https://www.youtube.com/shorts/abc".to_string());
+        assert_eq!(host.run(HookEvent::OnAdd, &mut item).as_deref(), Some("no shorts"));
+
+        let (_, errors) = ScriptHost::from_sources(vec![("broken.rhai".into(), "fn on_add( {".into())]);
+        assert_eq!(errors.len(), 1);
+    }
+}
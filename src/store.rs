@@ -0,0 +1,120 @@
+use anyhow::{Result, bail};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// How long to wait for another gorlock instance to finish writing a shared file
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// An exclusive advisory lock on a data file, held until dropped. Other gorlock instances
+/// sharing the data folder (a daemon and a TUI, or several users) take turns writing.
+pub struct FileLock {
+    _file: File,
+}
+
+/// `<path>.lock` next to the file, so the lock survives the file being replaced
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Lock a data file for writing, creating its folder if needed
+pub fn lock(path: &Path) -> Result<FileLock> {
+    lock_within(path, LOCK_TIMEOUT)
+}
+
+/// Lock a data file for writing unless another instance is writing it, `None` then.
+/// For writes from the main loop, which can't wait.
+pub fn try_lock(path: &Path) -> Result<Option<FileLock>> {
+    let file = open_lock_file(path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(FileLock { _file: file })),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+fn open_lock_file(path: &Path) -> Result<File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    Ok(OpenOptions::new().create(true).truncate(false).write(true).open(lock_path(path))?)
+}
+
+fn lock_within(path: &Path, timeout: Duration) -> Result<FileLock> {
+    let file = open_lock_file(path)?;
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(FileLock { _file: file }),
+            Err(TryLockError::WouldBlock) if started.elapsed() < timeout => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => {
+                bail!("{} is locked by another gorlock instance", path.display())
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+    }
+}
+
+/// Replace a file's contents all at once, so readers never see it half written
+pub fn write_atomic(path: &Path, contents: &str) -> Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".tmp-{}", std::process::id()));
+    let temp = path.with_file_name(name);
+    std::fs::write(&temp, contents)?;
+    std::fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// What was appended to a file after the first `offset` bytes, and the file's length.
+/// A file shorter than `offset` was rewritten by someone else and is read whole.
+pub fn read_appended(path: &Path, offset: u64) -> Result<(String, u64)> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok((String::new(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    let len = file.metadata()?.len();
+    let start = if offset > len { 0 } else { offset };
+    file.seek(SeekFrom::Start(start))?;
+    let mut text = String::new();
+    file.read_to_string(&mut text)?;
+    Ok((text, len))
+}
+
+/// Length of a file, 0 when it doesn't exist
+pub fn file_len(path: &Path) -> u64 {
+    std::fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_file() {
+        let dir = std::env::temp_dir().join(format!("gorlock-store-{}", std::process::id()));
+        let path = dir.join("history.jsonl");
+
+        let held = lock(&path).unwrap();
+        assert!(lock_within(&path, Duration::from_millis(100)).is_err());
+        assert!(try_lock(&path).unwrap().is_none());
+        drop(held);
+        assert!(lock_within(&path, Duration::from_millis(100)).is_ok());
+
+        write_atomic(&path, "one\n").unwrap();
+        let (text, len) = read_appended(&path, 0).unwrap();
+        assert_eq!((text.as_str(), len), ("one\n", 4));
+        std::fs::write(&path, "one\ntwo\n").unwrap();
+        assert_eq!(read_appended(&path, len).unwrap().0, "two\n");
+        // Rewritten shorter by someone else: read again from the start
+        write_atomic(&path, "x\n").unwrap();
+        assert_eq!(read_appended(&path, 8).unwrap().0, "x\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,8 +6,18 @@ use ratatui::{
     widgets::{Block, Borders, Clear, Gauge, List, ListItem, ListState, Paragraph, Wrap},
 };
 
-use crate::app_state::{AppState, DownloadStatus, Panel, format_bytes};
+use crate::app_state::{
+    AlbumSplit, AppState, CommentArchive, DownloadStatus, LogSource, Panel, ThumbnailEmbed, format_bytes, format_timestamp,
+};
+use crate::app_state::breaker::BreakerState;
+use crate::app_state::log::LogLevel;
+use crate::app_state::progress::{bar_cells, format_eta};
+use crate::app_state::queue_view::format_upload_date;
+use crate::commands::engagement::{format_count, live_status_label};
 use crate::commands::is_valid_url;
+use crate::commands::troubleshoot::RetryStep;
+use crate::output_template;
+use crate::config::SettingField;
 
 const GORLOCK_ASCII: &str = r#"┌────────────────────────────────────────────────────┐
 │      _____ ____  ____  _     ____  ____  _  __     │
@@ -41,6 +51,27 @@ impl App {
         f.render_widget(header, area);
     }
 
+    /// Render the network guard warning in place of the header
+    fn render_network_hold(&self, f: &mut Frame, area: Rect, reason: &str) {
+        let lines = vec![
+            Line::from(Span::styled(
+                "⚠ DOWNLOADS HELD BY THE NETWORK GUARD ⚠",
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(Span::styled(reason.to_string(), Style::default().fg(Color::White))),
+            Line::from(Span::styled(
+                "Nothing starts until the condition holds again; paused items resume with p",
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+        let banner = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .wrap(Wrap { trim: true })
+            .style(Style::default().bg(Color::Red))
+            .block(Block::default().borders(Borders::ALL).border_style(Style::default().fg(Color::Red)));
+        f.render_widget(banner, area);
+    }
+
     /// Render the complete UI
     pub fn render(&mut self, f: &mut Frame, state: &AppState) {
         let size = f.size();
@@ -56,8 +87,11 @@ impl App {
             ])
             .split(size);
         
-        // Render ASCII header
-        self.render_header(f, chunks[0]);
+        // Render ASCII header, replaced by a warning while the network guard holds downloads
+        match state.network_hold() {
+            Some(reason) => self.render_network_hold(f, chunks[0], &reason),
+            None => self.render_header(f, chunks[0]),
+        }
 
         // Split main area into queue (left) and details (right)
         let main_chunks = Layout::default()
@@ -95,15 +129,108 @@ impl App {
         if state.playlist_preview.is_some() {
             self.render_playlist_preview(f, size, state);
         }
+
+        if state.settings_popup.is_some() {
+            self.render_settings_popup(f, size, state);
+        }
+
+        if state.sites_popup.is_some() {
+            self.render_sites_popup(f, size, state);
+        }
+
+        if state.tokens_popup.is_some() {
+            self.render_tokens_popup(f, size, state);
+        }
+
+        if state.notifications_popup.is_some() {
+            self.render_notifications_popup(f, size, state);
+        }
+
+        if state.template_editor.is_some() {
+            self.render_template_editor(f, size, state);
+        }
+
+        if state.split_points_popup.is_some() {
+            self.render_split_points_popup(f, size, state);
+        }
+
+        if state.log_viewer.is_some() {
+            self.render_log_viewer(f, size, state);
+        }
+
+        if state.description_popup.is_some() {
+            self.render_description_popup(f, size, state);
+        }
+
+        if state.network_popup.is_some() {
+            self.render_network_popup(f, size, state);
+        }
+
+        if state.retry_wizard.is_some() {
+            self.render_retry_wizard(f, size, state);
+        }
+
+        if state.collision_report.is_some() {
+            self.render_collision_report(f, size, state);
+        }
+
+        if state.live_popup.is_some() {
+            self.render_live_popup(f, size, state);
+        }
+
+        if state.cancel_popup.is_some() {
+            self.render_cancel_popup(f, size, state);
+        }
+
+        if state.duplicate_popup.is_some() {
+            self.render_duplicate_popup(f, size, state);
+        }
+
+        if state.wayback_popup.is_some() {
+            self.render_wayback_popup(f, size, state);
+        }
+
+        if state.history_popup.is_some() {
+            self.render_history_popup(f, size, state);
+        }
+
+        if state.recent_popup.is_some() {
+            self.render_recent_popup(f, size, state);
+        }
+
+        if state.macros_popup.is_some() {
+            self.render_macros_popup(f, size, state);
+        }
+
+        if state.schedules_popup.is_some() {
+            self.render_schedules_popup(f, size, state);
+        }
+
+        if state.diagnostics_popup.is_some() {
+            self.render_diagnostics_popup(f, size, state);
+        }
+
+        if state.prompt.is_some() {
+            self.render_prompt(f, size, state);
+        }
+
+        if state.palette.is_some() {
+            self.render_palette(f, size, state);
+        }
+
+        // Help overlay sits above everything else
+        if state.show_help {
+            self.render_help_overlay(f, size);
+        }
     }
 
     /// Render the download queue list
     fn render_queue(&mut self, f: &mut Frame, area: Rect, state: &AppState) {
-        let items: Vec<ListItem> = state
-            .queue
+        let visible = state.visible_indices();
+        let items: Vec<ListItem> = visible
             .iter()
-            .enumerate()
-            .map(|(i, item)| {
+            .map(|&i| {
+                let item = &state.queue[i];
                 // Use clean title, fallback to shortened URL if no title
                 let title = if let Some(title) = &item.title {
                     // Clean up the title - remove common prefixes/suffixes
@@ -119,32 +246,70 @@ impl App {
                 
                 let status_style = match item.status {
                     DownloadStatus::Completed => Style::default().fg(Color::Green),
+                    DownloadStatus::CompletedWithWarnings => Style::default().fg(Color::LightYellow),
                     DownloadStatus::Failed => Style::default().fg(Color::Red),
                     DownloadStatus::Downloading => Style::default().fg(Color::Yellow),
                     DownloadStatus::Paused => Style::default().fg(Color::Cyan),
+                    DownloadStatus::Suspect => Style::default().fg(Color::LightRed),
+                    DownloadStatus::Unavailable => Style::default().fg(Color::Magenta),
                     _ => Style::default(),
                 };
 
-                let progress_bar = if item.progress.percent > 0.0 {
-                    format!(" [{:.1}%]", item.progress.percent)
+                // A bar while there's partial data, in the status color
+                let progress_bar = if item.progress.percent > 0.0 && !item.status.is_completed() {
+                    format!(" {} {:.0}%", bar_cells(item.progress.percent, 10), item.progress.percent)
                 } else {
                     String::new()
                 };
+                let bar_style = match item.status {
+                    DownloadStatus::Downloading | DownloadStatus::Paused | DownloadStatus::Failed => status_style,
+                    _ => Style::default().fg(Color::Blue),
+                };
+
+                // Uploader and upload date once metadata is fetched
+                let mut source = String::new();
+                if let Some(uploader) = &item.metadata.uploader {
+                    source.push_str(&format!(" · {}", uploader));
+                }
+                if let Some(date) = &item.metadata.upload_date {
+                    source.push_str(&format!(" · {}", format_upload_date(date)));
+                }
 
                 let line = Line::from(vec![
                     Span::styled(format!("{}. {}", i + 1, title), Style::default()),
-                    Span::styled(progress_bar, Style::default().fg(Color::Blue)),
+                    Span::styled(source, Style::default().fg(Color::DarkGray)),
+                    Span::styled(progress_bar, bar_style),
                     Span::styled(format!(" ({})", item.status), status_style),
                 ]);
 
-                ListItem::new(line)
+                // Comment archiving shows as a sub-task under its item
+                match &item.comments {
+                    Some(comments) => {
+                        let (text, color) = comment_archive_status(comments);
+                        ListItem::new(vec![
+                            line,
+                            Line::from(vec![
+                                Span::styled("   └ comments: ", Style::default().fg(Color::DarkGray)),
+                                Span::styled(text, Style::default().fg(color)),
+                            ]),
+                        ])
+                    }
+                    None => ListItem::new(line),
+                }
             })
             .collect();
 
+        let mut title = match &state.queue_filter {
+            Some(filter) => format!("Download Queue [{}/{} match \"{}\"]", visible.len(), state.queue.len(), filter.query),
+            None => "Download Queue".to_string(),
+        };
+        if let Some((done, total, percent)) = state.batch_progress() {
+            title.push_str(&format!(" · batch {} {:.0}% ({}/{} done)", bar_cells(percent, 10), percent, done, total));
+        }
         let list = List::new(items)
             .block(
                 Block::default()
-                    .title("Download Queue")
+                    .title(title)
                     .borders(Borders::ALL)
                     .border_style(if state.current_panel == Panel::Queue {
                         Style::default().fg(Color::Yellow)
@@ -159,20 +324,16 @@ impl App {
             )
             .highlight_symbol(">> ");
 
-        // Update list state selection
-        if !state.queue.is_empty() {
-            self.list_state
-                .select(Some(state.selected_index.min(state.queue.len() - 1)));
-        } else {
-            self.list_state.select(None);
-        }
+        // Update list state selection, the list only holds the visible items
+        self.list_state
+            .select(visible.iter().position(|i| *i == state.selected_index));
 
         f.render_stateful_widget(list, area, &mut self.list_state);
     }
 
     /// Render the details panel
     fn render_details(&self, f: &mut Frame, area: Rect, state: &AppState) {
-        let selected_item = state.queue.get(state.selected_index);
+        let selected_item = state.selected_item();
 
         let content = if let Some(item) = selected_item {
             let mut lines = vec![];
@@ -195,6 +356,47 @@ impl App {
                 ]));
             }
             
+            // Uploader and upload date
+            if let Some(uploader) = &item.metadata.uploader {
+                lines.push(Line::from(vec![
+                    Span::styled("Uploader: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(uploader),
+                ]));
+            }
+            if let Some(date) = &item.metadata.upload_date {
+                lines.push(Line::from(vec![
+                    Span::styled("Uploaded: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(format_upload_date(date)),
+                ]));
+            }
+
+            // View/like counts and live status, refreshed with `v`
+            let metadata = &item.metadata;
+            if metadata.view_count.is_some() || metadata.like_count.is_some() || metadata.refreshing {
+                let mut parts = Vec::new();
+                if let Some(views) = metadata.view_count {
+                    parts.push(format!("{} views", format_count(views)));
+                }
+                if let Some(likes) = metadata.like_count {
+                    parts.push(format!("{} likes", format_count(likes)));
+                }
+                if metadata.refreshing {
+                    parts.push("refreshing...".to_string());
+                } else if let Some(fetched_at) = metadata.counts_fetched_at {
+                    parts.push(format!("as of {} min ago", (chrono::Utc::now() - fetched_at).num_minutes()));
+                }
+                lines.push(Line::from(vec![
+                    Span::styled("Stats: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(parts.join(" · ")),
+                ]));
+            }
+            if let Some(status) = metadata.live_status.as_deref().filter(|s| *s != "not_live") {
+                lines.push(Line::from(vec![
+                    Span::styled("Stream: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(live_status_label(status)),
+                ]));
+            }
+
             // Format details
             if let Some(format) = &item.format {
                 lines.push(Line::from(vec![
@@ -224,12 +426,176 @@ impl App {
                 }
             }
             
+            // Live recording state
+            if let Some(live) = &item.live {
+                let text = match live.recording_since {
+                    Some(since) => {
+                        let minutes = (chrono::Utc::now() - since).num_minutes();
+                        let state_text = if live.stopping { "finishing" } else { "recording" };
+                        let mut text = format!("{} for {} min, stops {}", state_text, minutes, live.stop_label());
+                        if live.disk_usage > 0 {
+                            text.push_str(&format!(", {} on disk", format_bytes(live.disk_usage)));
+                        }
+                        if let Some(rate) = live.write_rate {
+                            text.push_str(&format!(" at {}/s", format_bytes(rate as u64)));
+                        }
+                        text
+                    }
+                    None => format!("will record from {}, stop {}", live.start.label(), live.stop_label()),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("Live: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::styled(text, Style::default().fg(Color::Red)),
+                ]));
+            }
+
+            // Source address binding
+            if let Some(address) = &item.source_address {
+                lines.push(Line::from(vec![
+                    Span::styled("Source IP: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(address),
+                ]));
+            }
+
+            // Tags and output override set by scripts
+            if !item.tags.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("Tags: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::styled(item.tags.join(", "), Style::default().fg(Color::Magenta)),
+                ]));
+            }
+            if let Some(dir) = &item.output_dir {
+                lines.push(Line::from(vec![
+                    Span::styled("Output: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(dir),
+                ]));
+            }
+
+            // Thumbnail embedding result
+            if let Some(thumbnail) = &item.thumbnail {
+                let (text, color) = match thumbnail {
+                    ThumbnailEmbed::Pending => ("pending".to_string(), Color::Gray),
+                    ThumbnailEmbed::Embedded => ("embedded".to_string(), Color::Green),
+                    ThumbnailEmbed::Failed(reason) => (format!("failed - {}", reason), Color::Red),
+                    ThumbnailEmbed::Skipped(reason) => (format!("skipped - {}", reason), Color::Yellow),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("Thumbnail: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::styled(text, Style::default().fg(color)),
+                ]));
+            }
+
+            // ffprobe report of the finished file
+            if let Some(report) = &item.quality_report {
+                let mut parts = Vec::new();
+                if let (Some(w), Some(h)) = (report.width, report.height) {
+                    parts.push(format!("{}x{}", w, h));
+                }
+                parts.extend(report.video_codec.clone());
+                if let Some(codec) = &report.audio_codec {
+                    match report.audio_channels {
+                        Some(channels) => parts.push(format!("{} {}ch", codec, channels)),
+                        None => parts.push(codec.clone()),
+                    }
+                }
+                if let Some(bitrate) = report.bitrate {
+                    parts.push(format!("{:.1} Mb/s", bitrate as f64 / 1_000_000.0));
+                }
+                if let Some(duration) = report.duration {
+                    let secs = duration.round() as u64;
+                    parts.push(format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60));
+                }
+                let color = if report.mismatches.is_empty() { Color::Green } else { Color::Yellow };
+                lines.push(Line::from(vec![
+                    Span::styled("Received: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::styled(parts.join(" · "), Style::default().fg(color)),
+                ]));
+                for mismatch in &report.mismatches {
+                    lines.push(Line::from(Span::styled(
+                        format!("  ⚠ {}", mismatch),
+                        Style::default().fg(Color::Yellow),
+                    )));
+                }
+            }
+
+            // Comment archiving sub-task
+            if let Some(comments) = &item.comments {
+                let (text, color) = comment_archive_status(comments);
+                lines.push(Line::from(vec![
+                    Span::styled("Comments: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::styled(text, Style::default().fg(color)),
+                ]));
+            }
+
+            // Chapters, which album splits cut along
+            if !item.chapters.is_empty() {
+                let source = if item.chapters_inferred { " (from the description tracklist)" } else { "" };
+                lines.push(Line::from(vec![
+                    Span::styled("Chapters: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(format!("{}{}", item.chapters.len(), source)),
+                ]));
+            }
+
+            // Album split post-processing
+            if let Some(split) = &item.album_split {
+                let (text, color) = match split {
+                    AlbumSplit::Requested if item.chapters.is_empty() => {
+                        ("requested (no chapters yet)".to_string(), Color::Gray)
+                    }
+                    AlbumSplit::Requested => (format!("requested ({} chapters)", item.chapters.len()), Color::Gray),
+                    AlbumSplit::DetectingSilence => ("no chapters, detecting silences...".to_string(), Color::Yellow),
+                    AlbumSplit::AwaitingSplitPoints => ("waiting for split points".to_string(), Color::Yellow),
+                    AlbumSplit::Splitting => ("splitting...".to_string(), Color::Yellow),
+                    AlbumSplit::Done { folder, tracks } => (format!("{} tracks in {}", tracks, folder), Color::Green),
+                    AlbumSplit::Failed(reason) => (format!("failed - {}", reason), Color::Red),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("Album split: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::styled(text, Style::default().fg(color)),
+                ]));
+            }
+
+            // Post-processing chain, one colored stage after the other
+            if !item.post_processing.is_empty() {
+                use crate::commands::postprocess::StageStatus;
+                let mut spans = vec![Span::styled(
+                    "Post-processing: ",
+                    Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan),
+                )];
+                for (i, run) in item.post_processing.iter().enumerate() {
+                    if i > 0 {
+                        spans.push(Span::styled(" → ", Style::default().fg(Color::DarkGray)));
+                    }
+                    let (mark, color) = match run.status {
+                        StageStatus::Waiting => ("", Color::DarkGray),
+                        StageStatus::Running => ("…", Color::Yellow),
+                        StageStatus::Done => (" ✓", Color::Green),
+                        StageStatus::Skipped(_) => (" -", Color::Gray),
+                        StageStatus::Failed(_) => (" ✗", Color::Red),
+                    };
+                    spans.push(Span::styled(format!("{}{}", run.stage.label(), mark), Style::default().fg(color)));
+                }
+                lines.push(Line::from(spans));
+                if let Some(reason) = item.post_processing.iter().find_map(|run| match &run.status {
+                    StageStatus::Failed(reason) => Some(reason),
+                    _ => None,
+                }) {
+                    lines.push(Line::from(Span::styled(
+                        format!("  {} - press P to retry", reason),
+                        Style::default().fg(Color::Red),
+                    )));
+                }
+            }
+
             // Status with color coding
             let status_color = match item.status {
                 crate::app_state::DownloadStatus::Completed => Color::Green,
+                crate::app_state::DownloadStatus::CompletedWithWarnings => Color::LightYellow,
                 crate::app_state::DownloadStatus::Failed => Color::Red,
                 crate::app_state::DownloadStatus::Downloading => Color::Yellow,
                 crate::app_state::DownloadStatus::Paused => Color::Cyan,
+                crate::app_state::DownloadStatus::Suspect => Color::LightRed,
+                crate::app_state::DownloadStatus::Unavailable => Color::Magenta,
                 _ => Color::White,
             };
             
@@ -244,6 +610,22 @@ impl App {
                 Span::styled("Added: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
                 Span::raw(created_time),
             ]));
+            if let (Some(started), Some(elapsed)) = (item.started_at, item.elapsed()) {
+                let took = match item.finished_at {
+                    Some(_) => format!(", took {}", format_eta(elapsed.num_seconds().max(0) as u64)),
+                    None => String::new(),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled("Started: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(format!("{}{}", started.with_timezone(&chrono::Local).format("%H:%M:%S"), took)),
+                ]));
+            }
+            if !item.timings.is_empty() {
+                lines.push(Line::from(vec![
+                    Span::styled("Time spent: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(item.timings.breakdown(chrono::Utc::now())),
+                ]));
+            }
             
             
             // Output directory
@@ -270,26 +652,52 @@ impl App {
                     Style::default().add_modifier(Modifier::BOLD).fg(Color::Yellow),
                 )]));
                 
-                if let Some(speed) = &item.progress.speed {
+                if let Some(speed) = item.progress.speed_label() {
                     lines.push(Line::from(vec![
                         Span::styled("  Speed: ", Style::default().fg(Color::Gray)),
                         Span::raw(speed),
                     ]));
                 }
                 
-                if let Some(eta) = &item.progress.eta {
+                if let Some(eta) = item.progress.eta_label() {
                     lines.push(Line::from(vec![
                         Span::styled("  ETA: ", Style::default().fg(Color::Gray)),
                         Span::raw(eta),
                     ]));
                 }
                 
-                if let Some(total_size) = &item.progress.total_size {
+                if let Some(elapsed) = item.elapsed() {
+                    let seconds = elapsed.num_seconds().max(0) as u64;
+                    let average = item
+                        .downloaded_bytes()
+                        .filter(|_| seconds > 0)
+                        .map(|bytes| format!(" • average {}/s", format_bytes(bytes / seconds)))
+                        .unwrap_or_default();
+                    lines.push(Line::from(vec![
+                        Span::styled("  Elapsed: ", Style::default().fg(Color::Gray)),
+                        Span::raw(format!("{}{}", format_eta(seconds), average)),
+                    ]));
+                }
+
+                if let Some(total_size) = item.progress.total_label() {
                     lines.push(Line::from(vec![
                         Span::styled("  Total: ", Style::default().fg(Color::Gray)),
                         Span::raw(total_size),
                     ]));
                 }
+
+                if let (Some(index), Some(count)) =
+                    (item.progress.fragment_index, item.progress.fragment_count)
+                {
+                    let concurrency = item
+                        .concurrent_fragments
+                        .map(|n| format!(" • {} concurrent", n))
+                        .unwrap_or_default();
+                    lines.push(Line::from(vec![
+                        Span::styled("  Fragments: ", Style::default().fg(Color::Gray)),
+                        Span::raw(format!("{}/{}{}", index, count, concurrency)),
+                    ]));
+                }
             }
             
             lines
@@ -336,13 +744,11 @@ impl App {
                     "{:.1}%{}{}",
                     item.progress.percent,
                     item.progress
-                        .speed
-                        .as_ref()
+                        .speed_label()
                         .map(|s| format!(" @ {}", s))
                         .unwrap_or_default(),
                     item.progress
-                        .eta
-                        .as_ref()
+                        .eta_label()
                         .map(|s| format!(" ETA {}", s))
                         .unwrap_or_default()
                 );
@@ -404,20 +810,78 @@ impl App {
         let help_text = if state.input_mode {
             "ESC: exit input | Enter: add URL | Ctrl+C: quit"
         } else if state.format_popup.is_some() {
-            "↑/↓: navigate formats | Enter: select & download | t: toggle audio-only | ESC: cancel"
+            "↑/↓: navigate formats | Enter: select & download | t: toggle audio-only | m: container | ESC: cancel"
         } else {
-            "i: input URL | f: fetch formats | d: delete | q: quit | ↑/↓: navigate"
+            "i: input URL | f: fetch formats | d: delete | s: settings | ?: help | q: quit | ↑/↓: navigate"
+        };
+
+        let batch_info = state
+            .batch
+            .as_ref()
+            .map(|batch| {
+                let items = state.queue.iter().filter(|i| i.batch_id == Some(batch.batch_id));
+                let (done, total) = items.fold((0, 0), |(done, total), i| {
+                    let finished = !matches!(
+                        i.status,
+                        DownloadStatus::Queued | DownloadStatus::Downloading | DownloadStatus::FetchingInfo
+                    );
+                    (done + finished as usize, total + 1)
+                });
+                let stopping = if batch.stop_after_current { ", stopping" } else { "" };
+                format!("Batch {}/{}{} | ", done, total, stopping)
+            })
+            .unwrap_or_default();
+
+        let recording = match &state.macro_recording {
+            Some(steps) => format!("REC macro ({} steps) | ", steps.len()),
+            None => String::new(),
         };
+        // Count and first key of a command being typed, like vim's showcmd
+        let typed = match (state.key_count, state.pending_key) {
+            (None, None) => String::new(),
+            (count, key) => format!(
+                "{}{} | ",
+                count.map(|n| n.to_string()).unwrap_or_default(),
+                key.map(String::from).unwrap_or_default()
+            ),
+        };
+
+        // How busy the download slots and the post-processing workers are
+        let downloading = state.queue.iter().filter(|i| i.status == DownloadStatus::Downloading).count();
+        let slots = match state.config.download_slots {
+            0 => "∞".to_string(),
+            slots => slots.to_string(),
+        };
+        let post_waiting = match state.post_workers.waiting() {
+            0 => String::new(),
+            waiting => format!(" +{}", waiting),
+        };
+        let pools = format!(
+            "DL {}/{} | PP {}/{}{} | ",
+            downloading,
+            slots,
+            state.post_workers.running(),
+            state.config.post_processing_workers.max(1),
+            post_waiting
+        );
 
         let status_info = format!(
-            " {} items | Output: {} ",
+            " {}{}{}{}{} items | Profile: {} | Output: {} ",
+            typed,
+            recording,
+            batch_info,
+            pools,
             state.queue.len(),
+            state.config.active_profile,
             state.output_dir
         );
 
-        let status = Paragraph::new(help_text)
-            .style(Style::default().fg(Color::Gray))
-            .alignment(Alignment::Left);
+        // Notices replace the help text until the next key press
+        let status = match &state.notice {
+            Some(notice) => Paragraph::new(notice.as_str()).style(Style::default().fg(Color::Green)),
+            None => Paragraph::new(help_text).style(Style::default().fg(Color::Gray)),
+        }
+        .alignment(Alignment::Left);
 
         let info_width = status_info.len() as u16;
         let info = Paragraph::new(status_info)
@@ -469,17 +933,29 @@ impl App {
                         Style::default()
                     };
 
-                    ListItem::new(Line::from(vec![
-                        Span::styled(format.display_name(), style),
-                    ]))
+                    let mut spans = vec![Span::styled(format.display_name(), style)];
+                    for badge in format.badges() {
+                        spans.push(Span::raw(" "));
+                        spans.push(Span::styled(format!("[{}]", badge), badge_style(&badge)));
+                    }
+
+                    if let Some(warning) = popup.transcode_warning(format) {
+                        let hint = match &warning.suggested_container {
+                            Some(container) => format!(" ⚠ will re-encode, slower (use {})", container),
+                            None => " ⚠ will re-encode, slower".to_string(),
+                        };
+                        spans.push(Span::styled(hint, Style::default().fg(Color::Red)));
+                    }
+
+                    ListItem::new(Line::from(spans))
                 })
                 .collect();
 
-            let title = if popup.audio_only_filter {
-                "Select Format (Audio Only)"
-            } else {
-                "Select Format (All)"
-            };
+            let title = format!(
+                "Select Format ({}) → {}",
+                if popup.audio_only_filter { "Audio Only" } else { "All" },
+                popup.container
+            );
             
             let list = List::new(items)
                 .block(
@@ -501,7 +977,7 @@ impl App {
             };
 
             let help = Paragraph::new(
-                "↑/↓: navigate | Enter: select | ESC: cancel | t: toggle audio-only",
+                "↑/↓: navigate | Enter: select | ESC: cancel | t: toggle audio-only | m: container",
             )
             .style(Style::default().fg(Color::Gray))
             .alignment(Alignment::Center);
@@ -603,13 +1079,19 @@ impl App {
             f.render_widget(Clear, popup_area);
 
             // Create title with count and total duration
+            let archived = preview.entries.iter().filter(|e| e.archived).count();
             let title = format!(
-                "Playlist Preview - {} items{}",
+                "Playlist Preview - {} items{}{}",
                 preview.entries.len(),
                 preview.total_duration
                     .as_ref()
                     .map(|d| format!(" • {}", d))
-                    .unwrap_or_default()
+                    .unwrap_or_default(),
+                if archived > 0 {
+                    format!(" • {} already archived", archived)
+                } else {
+                    String::new()
+                }
             );
 
             // Create list items
@@ -619,17 +1101,21 @@ impl App {
                 .enumerate()
                 .map(|(i, entry)| {
                     let is_selected = i == preview.selected_index;
-                    let style = if is_selected {
+                    let mut style = if is_selected {
                         Style::default().add_modifier(Modifier::REVERSED)
                     } else {
                         Style::default()
                     };
+                    if entry.archived {
+                        style = style.fg(Color::DarkGray).add_modifier(Modifier::CROSSED_OUT);
+                    }
 
                     let duration_str = entry.duration.as_deref().unwrap_or("");
+                    let mark = if preview.marked.get(i) == Some(&true) { "[x] " } else { "" };
                     let line = if !duration_str.is_empty() {
-                        format!("{}. {} ({})", i + 1, entry.title, duration_str)
+                        format!("{}{}. {} ({})", mark, i + 1, entry.title, duration_str)
                     } else {
-                        format!("{}. {}", i + 1, entry.title)
+                        format!("{}{}. {}", mark, i + 1, entry.title)
                     };
 
                     ListItem::new(Line::from(vec![Span::styled(line, style)]))
@@ -661,7 +1147,7 @@ impl App {
             };
 
             let help = Paragraph::new(
-                "↑/↓: navigate | Enter: add all to queue | ESC: cancel",
+                "↑/↓: navigate | Space: mark | Enter: add marked or all to queue (skips archived) | ESC: cancel",
             )
             .style(Style::default().fg(Color::Green))
             .alignment(Alignment::Center);
@@ -670,24 +1156,1485 @@ impl App {
         }
     }
 
-    /// Helper function to create a centered rectangle
-    fn centered_rect(&self, percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-        let popup_layout = Layout::default()
+    /// Render the log viewer (item or application log)
+    fn render_log_viewer(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(viewer) = &state.log_viewer {
+            let popup_area = self.centered_rect(80, 70, area);
+
+            // Clear background
+            f.render_widget(Clear, popup_area);
+
+            let filter = match viewer.level {
+                LogLevel::Info => "all",
+                LogLevel::Warning => "warnings+errors",
+                LogLevel::Error => "errors",
+            };
+            let title = match viewer.source {
+                LogSource::App => format!("Application log, {} (Tab: item log, f: filter, b: bug report)", filter),
+                LogSource::Item(id) => format!(
+                    "Log - {}, {} (Tab: application log, f: filter, b: bug report)",
+                    state
+                        .queue
+                        .iter()
+                        .find(|i| i.id == id)
+                        .and_then(|i| i.title.as_deref())
+                        .unwrap_or("item"),
+                    filter
+                ),
+            };
+
+            let log = state.filtered_log(viewer.source, viewer.level);
+            let lines: Vec<Line> = match log[..] {
+                [] => vec![Line::from(Span::styled(
+                    "No log entries yet",
+                    Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                ))],
+                _ => log
+                    .iter()
+                    .map(|entry| {
+                        let color = match entry.level {
+                            LogLevel::Error => Color::Red,
+                            LogLevel::Warning => Color::Yellow,
+                            _ if matches!(entry.stage.as_deref(), Some("hook" | "script")) => Color::Cyan,
+                            LogLevel::Info => Color::White,
+                        };
+                        Line::from(Span::styled(entry.line(), Style::default().fg(color)))
+                    })
+                    .collect(),
+            };
+
+            // Keep the selected line in view
+            let visible = popup_area.height.saturating_sub(2) as usize;
+            let offset = viewer.scroll.saturating_sub(visible.saturating_sub(1));
+
+            let log = Paragraph::new(lines)
+                .block(
+                    Block::default()
+                        .title(title)
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Cyan)),
+                )
+                .scroll((offset as u16, 0));
+
+            f.render_widget(log, popup_area);
+        }
+    }
+
+    /// Render the description viewer, with the links found in it below the text
+    fn render_description_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.description_popup else {
+            return;
+        };
+        let Some(item) = state.queue.iter().find(|i| i.id == popup.item_id) else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 80, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let links_height = if popup.links.is_empty() { 0 } else { (popup.links.len() as u16 + 2).min(10) };
+        let chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Percentage((100 - percent_y) / 2),
-                Constraint::Percentage(percent_y),
-                Constraint::Percentage((100 - percent_y) / 2),
-            ])
-            .split(r);
+            .constraints([Constraint::Min(3), Constraint::Length(links_height)])
+            .split(popup_area);
 
-        Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage((100 - percent_x) / 2),
-                Constraint::Percentage(percent_x),
-                Constraint::Percentage((100 - percent_x) / 2),
-            ])
-            .split(popup_layout[1])[1]
+        let text = Paragraph::new(item.metadata.description.as_deref().unwrap_or_default())
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Description - {} (↑/↓ scroll, ESC: close)",
+                        item.title.as_deref().unwrap_or("item")
+                    ))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((popup.scroll as u16, 0));
+        f.render_widget(text, chunks[0]);
+
+        if popup.links.is_empty() {
+            return;
+        }
+        let items: Vec<ListItem> = popup
+            .links
+            .iter()
+            .zip(&popup.marked)
+            .map(|(link, marked)| {
+                let mark = if *marked { "[x] " } else { "[ ] " };
+                ListItem::new(Line::from(vec![
+                    Span::styled(mark, Style::default().fg(Color::Green)),
+                    Span::raw(link.as_str()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Links: {} (Tab: next, Space: mark, Enter: add to queue)",
+                        popup.links.len()
+                    ))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(popup.selected_link));
+        f.render_stateful_widget(list, chunks[1], &mut list_state);
+    }
+
+    /// Render text prompt popup
+    fn render_prompt(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(prompt) = &state.prompt {
+            let popup_area = self.centered_rect(70, 20, area);
+            let input_area = Rect {
+                x: popup_area.x,
+                y: popup_area.y + popup_area.height.saturating_sub(3) / 2,
+                width: popup_area.width,
+                height: 3,
+            };
+
+            // Clear background
+            f.render_widget(Clear, input_area);
+
+            let input = Paragraph::new(prompt.input.as_str())
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .title(match prompt.kind.pick() {
+                            Some(_) => format!("{} (Enter: confirm, Ctrl+O: browse, ESC: cancel)", prompt.kind.title()),
+                            None => format!("{} (Enter: confirm, ESC: cancel)", prompt.kind.title()),
+                        })
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Cyan)),
+                );
+
+            f.render_widget(input, input_area);
+            f.set_cursor(
+                input_area.x + prompt.input.chars().count() as u16 + 1,
+                input_area.y + 1,
+            );
+        }
+    }
+
+    /// Render the command palette: search input above the matching actions
+    fn render_palette(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(palette) = &state.palette else {
+            return;
+        };
+        let popup_area = self.centered_rect(60, 50, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(3)])
+            .split(popup_area);
+
+        let input = Paragraph::new(palette.query.as_str())
+            .style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .title("Command palette (Enter: run | ESC: close)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            );
+        f.render_widget(input, chunks[0]);
+        f.set_cursor(chunks[0].x + palette.query.chars().count() as u16 + 1, chunks[0].y + 1);
+
+        let matches = crate::ui::palette::matches(state, &palette.query);
+        let mut items: Vec<ListItem> = matches
+            .iter()
+            .enumerate()
+            .map(|(i, action)| {
+                let style = if i == palette.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<7}", action.keys), style.fg(Color::Yellow)),
+                    Span::styled(action.label, style),
+                ]))
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No matching actions",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        f.render_widget(list, chunks[1]);
+    }
+
+    /// Render live recording options
+    fn render_live_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.live_popup else {
+            return;
+        };
+        let Some(live) = state
+            .queue
+            .iter()
+            .find(|i| i.id == popup.item_id)
+            .and_then(|i| i.live.as_ref())
+        else {
+            return;
+        };
+
+        let popup_area = self.centered_rect(60, 30, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let recording = live.recording_since.is_some();
+        let start = if recording {
+            format!("{} (recording)", live.start.label())
+        } else {
+            format!("◀ {} ▶", live.start.label())
+        };
+        let rows = [("Start from", start), ("Stop", format!("◀ {} ▶", live.stop_label()))];
+
+        let mut lines: Vec<Line> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<12}", label), Style::default().fg(Color::Cyan)),
+                    Span::styled(value.clone(), style),
+                ])
+            })
+            .collect();
+
+        lines.push(Line::from(""));
+        if live.stream_started_at.is_none() {
+            lines.push(Line::from(Span::styled(
+                "Stream start unknown: rewinding records from the start where supported",
+                Style::default().fg(Color::Yellow),
+            )));
+        }
+        if let Some(deadline) = live.deadline() {
+            lines.push(Line::from(Span::styled(
+                format!("Stops at {}", deadline.with_timezone(&chrono::Local).format("%H:%M:%S")),
+                Style::default().fg(Color::Gray),
+            )));
+        }
+        lines.push(Line::from(Span::styled(
+            "↑/↓: field | ←/→: change | Enter/ESC: close",
+            Style::default().fg(Color::Gray),
+        )));
+
+        let paragraph = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title("Live recording (DVR)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Red)),
+        );
+
+        f.render_widget(paragraph, popup_area);
+    }
+
+    /// Render the stop mode selection popup
+    fn render_cancel_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(popup) = &state.cancel_popup {
+            let popup_area = self.centered_rect(50, 25, area);
+
+            // Clear background
+            f.render_widget(Clear, popup_area);
+
+            let items: Vec<ListItem> = popup
+                .options
+                .iter()
+                .enumerate()
+                .map(|(i, mode)| {
+                    let style = if i == popup.selected_index {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(Line::from(Span::styled(mode.label(), style)))
+                })
+                .collect();
+
+            let list = List::new(items).block(
+                Block::default()
+                    .title("Stop download (Enter: confirm, ESC: back)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Red)),
+            );
+
+            f.render_widget(list, popup_area);
+        }
+    }
+
+    /// Render the source address picker
+    fn render_network_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.network_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(60, 50, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let current = state
+            .queue
+            .iter()
+            .find(|i| i.id == popup.item_id)
+            .and_then(|i| i.source_address.clone());
+        let profile_default = state
+            .config
+            .active_profile()
+            .source_address
+            .clone()
+            .unwrap_or_else(|| "default route".to_string());
+
+        let mut items = vec![ListItem::new(format!("Profile default ({})", profile_default))];
+        match &popup.addresses {
+            None => items.push(ListItem::new(Span::styled(
+                "Detecting local addresses...",
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            ))),
+            Some(addresses) => items.extend(addresses.iter().map(|a| {
+                let address = a.address.to_string();
+                let marker = if current.as_deref() == Some(address.as_str()) { " (current)" } else { "" };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<10}", a.interface), Style::default().fg(Color::Cyan)),
+                    Span::raw(address),
+                    Span::styled(marker, Style::default().fg(Color::Green)),
+                ]))
+            })),
+        }
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title("Source address (Enter: this item, p: profile, ESC: close)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(popup.selected_index));
+        f.render_stateful_widget(list, popup_area, &mut list_state);
+    }
+
+    /// Render the file name check shown before a playlist batch starts
+    fn render_collision_report(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(report) = &state.collision_report else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 70, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let title_of = |id: &uuid::Uuid| {
+            state
+                .queue
+                .iter()
+                .find(|i| i.id == *id)
+                .map(|i| i.title.clone().unwrap_or_else(|| i.url.clone()))
+                .unwrap_or_default()
+        };
+
+        let mut lines = vec![Line::from(format!(
+            "Checked {} items: {} file names collide, {} files already exist.",
+            report.checked,
+            report.collisions.len(),
+            report.existing.len()
+        ))];
+        if report.is_clean() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "All file names are unique now. Press Enter to start the batch.",
+                Style::default().fg(Color::Green),
+            )));
+        }
+        for (path, ids) in &report.collisions {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(path.clone(), Style::default().fg(Color::Yellow))));
+            lines.extend(ids.iter().map(|id| Line::from(format!("  • {}", title_of(id)))));
+        }
+        if !report.existing.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled(
+                "Already on disk, yt-dlp would skip these:",
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+            for (path, id) in &report.existing {
+                lines.push(Line::from(vec![
+                    Span::styled(path.clone(), Style::default().fg(Color::Yellow)),
+                    Span::styled(format!("  ({})", title_of(id)), Style::default().fg(Color::Gray)),
+                ]));
+            }
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Batch file names (n: add index | i: add video ID | Enter: start | ESC: cancel)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .wrap(Wrap { trim: false })
+            .scroll((report.scroll as u16, 0));
+        f.render_widget(paragraph, popup_area);
+    }
+
+    /// Render the troubleshooting wizard for a failed extraction
+    fn render_retry_wizard(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(wizard) = &state.retry_wizard else {
+            return;
+        };
+        let popup_area = self.centered_rect(70, 60, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let title = state
+            .queue
+            .iter()
+            .find(|i| i.id == wizard.item_id)
+            .and_then(|i| i.title.clone())
+            .unwrap_or_else(|| "item".to_string());
+        // yt-dlp's last line is the actual error, earlier ones are warnings
+        let error = wizard.error.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("unknown error");
+
+        let mut lines = vec![
+            Line::from(Span::styled(error.to_string(), Style::default().fg(Color::Red))),
+            Line::from(""),
+        ];
+        for (i, step) in RetryStep::ALL.iter().enumerate() {
+            let style = if i == wizard.selected_index {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            let mut spans = vec![Span::styled(format!("{}. {:<28}", i + 1, step.label()), style)];
+            match (wizard.choice(*step), step) {
+                (Some(choice), RetryStep::Proxy) => spans.push(Span::styled(choice, Style::default().fg(Color::Yellow))),
+                (Some(choice), _) => spans.push(Span::styled(format!("‹ {} ›", choice), Style::default().fg(Color::Yellow))),
+                (None, RetryStep::Proxy) => spans.push(Span::styled("asks for a URL", Style::default().fg(Color::Gray))),
+                (None, _) => {}
+            }
+            if wizard.running == Some(*step) {
+                spans.push(Span::styled("  trying...", Style::default().fg(Color::Yellow)));
+            } else if wizard.suggested == *step {
+                spans.push(Span::styled("  suggested", Style::default().fg(Color::Green)));
+            }
+            lines.push(Line::from(spans));
+            lines.push(Line::from(Span::styled(
+                format!("   {}", step.explanation()),
+                Style::default().fg(Color::Gray),
+            )));
+        }
+
+        if !wizard.results.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Tried:", Style::default().add_modifier(Modifier::BOLD))));
+            for (tried, result) in &wizard.results {
+                let (mark, detail, color) = match result {
+                    Ok(summary) => ("✓", summary.as_str(), Color::Green),
+                    Err(error) => ("✗", error.lines().last().unwrap_or_default(), Color::Red),
+                };
+                lines.push(Line::from(vec![
+                    Span::styled(format!("{} {}: ", mark, tried), Style::default().fg(color)),
+                    Span::raw(detail.to_string()),
+                ]));
+            }
+        }
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(format!(
+                        "Troubleshoot - {} (Enter: try | ←/→: browser/client | p: proxy | ESC: close)",
+                        title
+                    ))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+        f.render_widget(paragraph, popup_area);
+    }
+
+    /// Render the queue diagnostics report
+    fn render_diagnostics_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.diagnostics_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 70, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let issue_lines = (popup.issues.len().max(1) * 2) as u16 + 2;
+        let breakers = state.breakers.sites();
+        let breaker_lines = breakers.len().clamp(1, 4) as u16 + 2;
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Max(issue_lines),
+                Constraint::Length(4),
+                Constraint::Length(breaker_lines),
+                Constraint::Min(5),
+            ])
+            .split(popup_area);
+
+        let mut items: Vec<ListItem> = popup
+            .issues
+            .iter()
+            .map(|issue| {
+                ListItem::new(vec![
+                    Line::from(Span::styled(issue.describe(state), Style::default().fg(Color::Yellow))),
+                    Line::from(Span::styled(
+                        format!("  repair: {}", issue.repair_label()),
+                        Style::default().fg(Color::Gray),
+                    )),
+                ])
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "Queue is healthy, no inconsistent states found",
+                Style::default().fg(Color::Green),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title(format!("Queue diagnostics: {} issue(s) (Enter: repair all, ESC: close)", popup.issues.len()))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+        f.render_widget(list, chunks[0]);
+
+        // Where the queue's time went: network (fetch, download) or transcoding (merge, post-process)
+        let summary = state.timing_summary();
+        let timing_lines = match summary.bottleneck() {
+            Some(bottleneck) => {
+                let mut shares = vec![];
+                for (phase, percent) in summary.shares() {
+                    let color = if phase.is_network() { Color::Blue } else { Color::Magenta };
+                    shares.push(Span::styled(format!("{} {:.0}%  ", phase.label(), percent), Style::default().fg(color)));
+                }
+                vec![
+                    Line::from(shares),
+                    Line::from(Span::styled(
+                        format!("Over {} item(s) the bottleneck is {}", summary.items, bottleneck),
+                        Style::default().fg(Color::Gray),
+                    )),
+                ]
+            }
+            None => vec![Line::from(Span::styled("Nothing timed yet", Style::default().fg(Color::Gray)))],
+        };
+        let timings = Paragraph::new(timing_lines).block(
+            Block::default()
+                .title("Where the time goes")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        f.render_widget(timings, chunks[1]);
+
+        // Sites failing with timeouts or 5xx errors and whether their downloads are held back
+        let now = std::time::Instant::now();
+        let mut breaker_items: Vec<Line> = breakers
+            .iter()
+            .map(|(site, breaker)| {
+                let (state_label, color) = match breaker.state(now) {
+                    BreakerState::Closed => ("closed".to_string(), Color::Yellow),
+                    BreakerState::Open(left) => (format!("open, {}s left", left.as_secs() + 1), Color::Red),
+                    BreakerState::HalfOpen => ("half-open, trying one".to_string(), Color::Magenta),
+                };
+                Line::from(vec![
+                    Span::styled(format!("{:<22}", site), Style::default().fg(Color::Cyan)),
+                    Span::styled(format!("{:<24}", state_label), Style::default().fg(color)),
+                    Span::styled(
+                        format!("{} failure(s), last: {}", breaker.failures, breaker.last_error),
+                        Style::default().fg(Color::Gray),
+                    ),
+                ])
+            })
+            .collect();
+        if breaker_items.is_empty() {
+            breaker_items.push(Line::from(Span::styled(
+                "No site is failing with timeouts or server errors",
+                Style::default().fg(Color::Green),
+            )));
+        }
+        let breaker_block = Paragraph::new(breaker_items).block(
+            Block::default()
+                .title("Site breakers")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        f.render_widget(breaker_block, chunks[2]);
+
+        // What gorlock runs with, so a container's environment can be checked from inside
+        let config = &state.config;
+        let mut lines = Vec::new();
+        for o in &config.env_overrides {
+            lines.push(match &o.error {
+                None => Line::from(vec![
+                    Span::styled(format!("{} ", o.var), Style::default().fg(Color::Magenta)),
+                    Span::styled(format!("sets {}", o.key), Style::default().fg(Color::Gray)),
+                ]),
+                Some(error) => Line::from(vec![
+                    Span::styled(format!("{} ", o.var), Style::default().fg(Color::Red)),
+                    Span::styled(format!("ignored: {}", error), Style::default().fg(Color::Red)),
+                ]),
+            });
+        }
+        if !lines.is_empty() {
+            lines.push(Line::from(""));
+        }
+        for (key, value) in config.effective_settings() {
+            let from_env = config.set_by_env(&key);
+            lines.push(Line::from(vec![
+                Span::styled(format!("{:<22}", key), Style::default().fg(if from_env { Color::Magenta } else { Color::Cyan })),
+                Span::raw(value),
+            ]));
+        }
+
+        let settings = Paragraph::new(lines).scroll((popup.scroll, 0)).block(
+            Block::default()
+                .title("Settings in effect, magenta from GORLOCK_* variables (↑/↓: scroll)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        f.render_widget(settings, chunks[3]);
+    }
+
+    /// Render the schedules panel
+    fn render_schedules_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.schedules_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 60, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let now = chrono::Local::now();
+        let mut items: Vec<ListItem> = state
+            .schedules
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, schedule)| {
+                let next = match schedule.next_run(now) {
+                    _ if !schedule.enabled => "disabled".to_string(),
+                    Some(next) => format!("next {}", next.format("%a %d %b %H:%M")),
+                    None => "never".to_string(),
+                };
+                let length = schedule
+                    .stop_after_minutes
+                    .map(|minutes| format!("{} min", minutes))
+                    .unwrap_or_else(|| "until end".to_string());
+
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else if !schedule.enabled {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<18}", schedule.rule), style.fg(Color::Cyan)),
+                    Span::styled(format!("{:<22}", next), style.fg(Color::Yellow)),
+                    Span::styled(format!("{:<11}", length), style),
+                    Span::styled(schedule.url.clone(), style),
+                ]))
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No schedules yet. Press a and enter e.g. \"0 20 * * fri https://www.youtube.com/@channel/live 120\"",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Schedules (a: add | Space: enable/disable | x: delete | ESC: close)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+        f.render_widget(list, popup_area);
+    }
+
+    fn render_macros_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.macros_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 60, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let mut items: Vec<ListItem> = state
+            .config
+            .macros
+            .iter()
+            .enumerate()
+            .map(|(i, recorded)| {
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                let key = recorded.key.map(String::from).unwrap_or_else(|| "-".to_string());
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<3}", key), style.fg(Color::Yellow)),
+                    Span::styled(format!("{:<20}", recorded.name), style.fg(Color::Cyan)),
+                    Span::styled(recorded.summary(), style),
+                ]))
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No macros yet. Press Q in the queue, do what you want repeated on an item, then Q again",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Macros (Enter: run on selected item | b: bind key | x: delete | ESC: close)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+        f.render_widget(list, popup_area);
+    }
+
+    fn render_recent_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.recent_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 60, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let mut items: Vec<ListItem> = state
+            .recent_urls
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| {
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                let source = match entry.source {
+                    crate::recent::RecentSource::Added => "added",
+                    crate::recent::RecentSource::Clipboard => "copied",
+                };
+                let seen = entry.seen_at.with_timezone(&chrono::Local).format("%d %b %H:%M").to_string();
+                ListItem::new(Line::from(vec![
+                    Span::styled(if entry.pinned { "* " } else { "  " }, style.fg(Color::Yellow)),
+                    Span::styled(format!("{:<8}", source), style.fg(Color::Cyan)),
+                    Span::styled(format!("{:<14}", seen), style.fg(Color::Gray)),
+                    Span::styled(entry.url.clone(), style),
+                ]))
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No URLs yet. URLs you add, and ones you copy while gorlock runs, show up here",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Recent URLs (Enter: add | c: copy | p: pin | x: delete | ESC: close)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+        f.render_widget(list, popup_area);
+    }
+
+    /// Render the output template editor with its field browser and a live preview
+    fn render_template_editor(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(editor) = &state.template_editor else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 70, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Length(3), Constraint::Min(3)])
+            .split(popup_area);
+
+        let input = Paragraph::new(editor.input.as_str())
+            .style(Style::default().fg(Color::Yellow))
+            .block(
+                Block::default()
+                    .title("File name template (Enter: save, ESC: cancel)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            );
+        f.render_widget(input, chunks[0]);
+        f.set_cursor(chunks[0].x + editor.input.chars().count() as u16 + 1, chunks[0].y + 1);
+
+        // Fill in the selected item's metadata, or examples when there is none
+        let item = editor.item_id.and_then(|id| state.queue.iter().find(|i| i.id == id));
+        let (values, source) = match item {
+            Some(item) => {
+                let ext = output_template::item_ext(item, &state.config.merge_container);
+                let name = item.title.clone().unwrap_or_else(|| item.url.clone());
+                (output_template::item_values(item, &ext), name)
+            }
+            None => (output_template::example_values(), "example values".to_string()),
+        };
+        let output_dir = item
+            .and_then(|i| i.output_dir.clone())
+            .unwrap_or_else(|| state.output_dir.clone());
+        let preview = Paragraph::new(format!(
+            "{}/{}",
+            output_dir.trim_end_matches('/'),
+            output_template::substitute(&editor.input, &values)
+        ))
+        .block(
+            Block::default()
+                .title(format!("Preview ({})", source))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        f.render_widget(preview, chunks[1]);
+
+        let items: Vec<ListItem> = output_template::TEMPLATE_FIELDS
+            .iter()
+            .map(|field| {
+                let value = output_template::substitute(field.insert, &values);
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<28}", field.insert), Style::default().fg(Color::Cyan)),
+                    Span::styled(format!("{:<24}", truncate(&value, 22)), Style::default().fg(Color::Yellow)),
+                    Span::styled(field.description, Style::default().fg(Color::Gray)),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title("Fields (↑/↓: browse, Tab: insert)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .highlight_symbol(">> ");
+        let mut list_state = ListState::default();
+        list_state.select(Some(editor.selected_field));
+        f.render_stateful_widget(list, chunks[2], &mut list_state);
+    }
+
+    /// Render the per-site extractor options panel
+    fn render_sites_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.sites_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(80, 50, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let mut items: Vec<ListItem> = state
+            .config
+            .site_options
+            .iter()
+            .enumerate()
+            .map(|(i, site)| {
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                let client = site.player_client.as_deref().unwrap_or("auto");
+                let impersonate = site.impersonate.as_deref().unwrap_or("-");
+                let limit = match site.max_concurrent {
+                    0 => "any".to_string(),
+                    n => n.to_string(),
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<24}", site.domain), style.fg(Color::Cyan)),
+                    Span::styled(format!("client ‹ {} ›  ", client), style.fg(Color::Yellow)),
+                    Span::styled(format!("as {:<10}", impersonate), style),
+                    Span::styled(format!("max {:<4}", limit), style.fg(Color::Magenta)),
+                    Span::styled(format!("every {:<5}", format!("{}s", site.min_start_spacing_secs)), style.fg(Color::Magenta)),
+                    Span::styled(site.extractor_args.join(" "), style.fg(Color::Gray)),
+                ]))
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No site options yet. Press a and enter a domain, e.g. youtube.com",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Site extractor options (a: add | ←/→: player client | i: impersonate | e: extractor args | c: max at once | s: start spacing | x: delete | ESC: close)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+        f.render_widget(list, popup_area);
+    }
+
+    fn render_tokens_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.tokens_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(70, 50, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let mut items: Vec<ListItem> = state
+            .config
+            .api_tokens
+            .iter()
+            .enumerate()
+            .map(|(i, token)| {
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                let scope_color = match token.scope {
+                    crate::web::TokenScope::ReadOnly => Color::Green,
+                    crate::web::TokenScope::AddOnly => Color::Yellow,
+                    crate::web::TokenScope::Full => Color::Red,
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<24}", token.name), style.fg(Color::Cyan)),
+                    Span::styled(format!("{:<15}", token.scope.label()), style.fg(scope_color)),
+                    Span::styled(token.masked(), style.fg(Color::Gray)),
+                ]))
+            })
+            .collect();
+
+        if items.is_empty() {
+            items.push(ListItem::new(Line::from(Span::styled(
+                "No tokens yet. Press a to create one, e.g. for a browser extension that only adds URLs",
+                Style::default().fg(Color::Gray),
+            ))));
+        }
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Web API tokens, used by --daemon (a: add | s: scope | c: copy | x: delete | ESC: close)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+        f.render_widget(list, popup_area);
+    }
+
+    /// Render the notification settings panel
+    fn render_notifications_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        use crate::notifications::NotifyField;
+
+        let Some(popup) = &state.notifications_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(70, 50, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let items: Vec<ListItem> = NotifyField::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let style = if i == popup.selected_index {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{:<28}", field.label()), style.fg(Color::Cyan)),
+                    Span::styled(state.config.notifications.display(*field), style.fg(Color::Yellow)),
+                ]))
+            })
+            .collect();
+
+        let list = List::new(items).block(
+            Block::default()
+                .title("Notifications (Enter: edit | ←/→: change | t: send test | ESC: close)")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+
+        f.render_widget(list, popup_area);
+    }
+
+    /// Render the duplicate download warning
+    fn render_duplicate_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(popup) = &state.duplicate_popup {
+            let popup_area = self.centered_rect(70, 30, area);
+
+            // Clear background
+            f.render_widget(Clear, popup_area);
+
+            let lines = vec![
+                Line::from(Span::styled(
+                    "This download has the same content as a file already in your library.",
+                    Style::default().fg(Color::Yellow),
+                )),
+                Line::from(""),
+                Line::from(vec![
+                    Span::styled("New:      ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(popup.new_path.as_str()),
+                ]),
+                Line::from(vec![
+                    Span::styled("Existing: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                    Span::raw(popup.existing_path.as_str()),
+                ]),
+                Line::from(""),
+                Line::from(Span::styled(
+                    "d: delete the new copy | k: keep both",
+                    Style::default().fg(Color::Gray),
+                )),
+            ];
+
+            let warning = Paragraph::new(lines)
+                .wrap(Wrap { trim: false })
+                .block(
+                    Block::default()
+                        .title("Duplicate download")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Yellow)),
+                );
+
+            f.render_widget(warning, popup_area);
+        }
+    }
+
+    /// Render the offer to switch a deleted video to its Wayback Machine copy
+    fn render_wayback_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        let Some(popup) = &state.wayback_popup else {
+            return;
+        };
+        let popup_area = self.centered_rect(70, 30, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let title = state
+            .queue
+            .iter()
+            .find(|i| i.id == popup.item_id)
+            .map(|i| i.title.clone().unwrap_or_else(|| i.url.clone()))
+            .unwrap_or_default();
+        let lines = vec![
+            Line::from(Span::styled(
+                "This video is gone at the source, but the Wayback Machine has a copy.",
+                Style::default().fg(Color::Yellow),
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Video:    ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                Span::raw(title),
+            ]),
+            Line::from(vec![
+                Span::styled("Archived: ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+                Span::raw(popup.snapshot.as_str()),
+            ]),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Enter: download the archived copy instead | Esc: keep the item as it is",
+                Style::default().fg(Color::Gray),
+            )),
+        ];
+
+        let offer = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+            Block::default()
+                .title("Wayback Machine")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Yellow)),
+        );
+
+        f.render_widget(offer, popup_area);
+    }
+
+    /// Render the history search popup, only building rows for the visible window
+    fn render_history_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(popup) = &state.history_popup {
+            let popup_area = self.centered_rect(80, 70, area);
+
+            // Clear background
+            f.render_widget(Clear, popup_area);
+
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Min(0)])
+                .split(popup_area);
+
+            let input = Paragraph::new(popup.query.as_str())
+                .style(Style::default().fg(Color::Yellow))
+                .block(
+                    Block::default()
+                        .title("Search history (title, uploader, tags)")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::Cyan)),
+                );
+            f.render_widget(input, chunks[0]);
+            f.set_cursor(chunks[0].x + popup.query.chars().count() as u16 + 1, chunks[0].y + 1);
+
+            // Keep the selection in view without materialising every row
+            let visible = chunks[1].height.saturating_sub(2) as usize;
+            let offset = popup.selected_index.saturating_sub(visible.saturating_sub(1));
+
+            let items: Vec<ListItem> = popup
+                .results
+                .iter()
+                .enumerate()
+                .skip(offset)
+                .take(visible)
+                .filter_map(|(i, &entry_index)| {
+                    let entry = state.history.get(entry_index)?;
+                    let style = if i == popup.selected_index {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    let mut spans = vec![
+                        Span::styled(
+                            entry.completed_at.format("%Y-%m-%d ").to_string(),
+                            Style::default().fg(Color::Gray),
+                        ),
+                        Span::styled(entry.title.clone().unwrap_or_else(|| entry.url.clone()), style),
+                    ];
+                    if let Some(uploader) = &entry.uploader {
+                        spans.push(Span::styled(format!(" • {}", uploader), Style::default().fg(Color::Cyan)));
+                    }
+                    if let Some(seconds) = entry.wall_time_secs {
+                        spans.push(Span::styled(format!(" ({})", format_eta(seconds)), Style::default().fg(Color::Gray)));
+                    }
+                    if !entry.tags.is_empty() {
+                        spans.push(Span::styled(
+                            format!(" [{}]", entry.tags.join(", ")),
+                            Style::default().fg(Color::Magenta),
+                        ));
+                    }
+                    Some(ListItem::new(Line::from(spans)))
+                })
+                .collect();
+
+            let title = format!(
+                "{} of {} • Enter: queue again • ESC: close",
+                popup.results.len(),
+                state.history.len()
+            );
+            let list = List::new(items).block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            );
+
+            f.render_widget(list, chunks[1]);
+        }
+    }
+
+    /// Render the album split point editor
+    fn render_split_points_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(popup) = &state.split_points_popup {
+            let popup_area = self.centered_rect(60, 60, area);
+
+            // Clear background
+            f.render_widget(Clear, popup_area);
+
+            let tracks = crate::commands::album::tracks_from_split_points(&popup.points);
+            let mut items: Vec<ListItem> = popup
+                .points
+                .iter()
+                .enumerate()
+                .map(|(i, point)| {
+                    let style = if i == popup.selected_index {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("Split {:>2} at {}", i + 1, format_timestamp(*point)), style),
+                    ]))
+                })
+                .collect();
+
+            if items.is_empty() {
+                items.push(ListItem::new(Line::from(Span::styled(
+                    "No silences found - press n to add a split point",
+                    Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                ))));
+            }
+
+            let title = format!(
+                "Album split - {} tracks{}",
+                tracks.len(),
+                popup
+                    .duration
+                    .map(|d| format!(" • {}", format_timestamp(d)))
+                    .unwrap_or_default()
+            );
+
+            let list = List::new(items).block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Magenta)),
+            );
+
+            f.render_widget(list, popup_area);
+
+            // Help text at bottom
+            let help_area = Rect {
+                x: popup_area.x + 1,
+                y: popup_area.y + popup_area.height - 2,
+                width: popup_area.width - 2,
+                height: 1,
+            };
+
+            let help = Paragraph::new(
+                "←/→: move 1s (Shift: 10s) | n: add | x: remove | Enter: split | ESC: cancel",
+            )
+            .style(Style::default().fg(Color::Gray))
+            .alignment(Alignment::Center);
+
+            f.render_widget(help, help_area);
+        }
+    }
+
+    /// Render settings popup
+    fn render_settings_popup(&self, f: &mut Frame, area: Rect, state: &AppState) {
+        if let Some(popup) = &state.settings_popup {
+            let popup_area = self.centered_rect(60, 50, area);
+
+            // Clear background
+            f.render_widget(Clear, popup_area);
+
+            let items: Vec<ListItem> = SettingField::ALL
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let style = if i == popup.selected_index {
+                        Style::default().add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::default()
+                    };
+
+                    ListItem::new(Line::from(vec![
+                        Span::styled(format!("{:<34}", field.label()), style),
+                        Span::styled(
+                            format!("‹ {} ›", state.config.display_setting(*field)),
+                            Style::default().fg(Color::Yellow),
+                        ),
+                    ]))
+                })
+                .collect();
+
+            let list = List::new(items).block(
+                Block::default()
+                    .title(format!("Settings (profile: {})", state.config.active_profile))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            );
+
+            f.render_widget(list, popup_area);
+
+            // Help text at bottom
+            let help_area = Rect {
+                x: popup_area.x + 1,
+                y: popup_area.y + popup_area.height - 2,
+                width: popup_area.width - 2,
+                height: 1,
+            };
+
+            let help = Paragraph::new("↑/↓: navigate | ←/→: change | Enter: edit file name / site options | ESC: close")
+                .style(Style::default().fg(Color::Gray))
+                .alignment(Alignment::Center);
+
+            f.render_widget(help, help_area);
+        }
+    }
+
+    /// Render help overlay with keybindings and the format badge legend
+    fn render_help_overlay(&self, f: &mut Frame, area: Rect) {
+        let popup_area = self.centered_rect(60, 70, area);
+
+        // Clear background
+        f.render_widget(Clear, popup_area);
+
+        let heading = Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan);
+        let key_style = Style::default().fg(Color::Yellow);
+
+        let keys = [
+            ("i", "Enter URL input mode"),
+            ("f", "Fetch formats for selected item"),
+            ("d", "Delete selected item"),
+            ("u/^R", "Undo / redo queue edits, format choices, settings"),
+            ("N", "Bind downloads to a local address / interface"),
+            ("e", "Show description and the links in it"),
+            ("F", "Troubleshoot a failed extraction"),
+            ("v", "Refresh view/like counts and live status"),
+            ("V", "Refresh metadata of the selected item (5V: five items)"),
+            ("M", "Refresh metadata of items not downloaded yet"),
+            ("w", "Look up an unavailable item on the Wayback Machine"),
+            ("X", "Remove all unavailable items"),
+            ("o", "Sort queue: added, uploader, upload date, title"),
+            ("/", "Filter queue (uploader:<name>, date:<YYYY-MM>)"),
+            ("p", "Pause/resume download"),
+            ("c", "Cancel download"),
+            ("s", "Settings"),
+            ("a", "Toggle album split (chapters → tracks)"),
+            ("P", "Retry a failed post-processing stage"),
+            ("O", "Skip a failed post-processing stage"),
+            ("L", "Show log (Tab: item / app, f: level, b: bug report)"),
+            ("T", "Live recording options (rewind, stop after)"),
+            ("W", "Schedules (recurring live recordings)"),
+            ("U", "Recently added and copied URLs"),
+            ("Q", "Start / stop recording a macro"),
+            ("K", "Macros (@1-@9 replay on the selected item)"),
+            ("5j/3d", "Counts: move 5 down, delete 3 items"),
+            ("gg/G", "First / last item (12G: twelfth)"),
+            ("ma/'a", "Set mark a / jump to it"),
+            ("D", "Diagnostics (stuck items, settings in effect)"),
+            ("S", "Save state bundle (move a job to another machine)"),
+            ("R", "Restore state bundle"),
+            ("B", "Download the selected item's playlist batch"),
+            ("C", "Stop gracefully (after fragment / after item)"),
+            ("H", "Search download history"),
+            ("I", "Import download archive / ID list"),
+            ("E", "Export archive for yt-dlp --download-archive"),
+            ("t", "Toggle audio-only (format popup)"),
+            ("m", "Cycle merge container (format popup)"),
+            ("Tab", "Switch panels"),
+            ("^K", "Command palette (search actions)"),
+            ("?", "Toggle this help"),
+            ("q", "Quit"),
+        ];
+
+        let mut lines = vec![Line::from(Span::styled("Keybindings", heading))];
+        for (key, action) in keys {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<6}", key), key_style),
+                Span::raw(action),
+            ]));
+        }
+
+        let legend = [
+            ("[AV1]", "Video codec family (AV1, VP9, H.264, HEVC)"),
+            ("[HDR10]", "High dynamic range (HDR10, HDR10+, HLG, DV)"),
+            ("[60fps]", "Frame rate"),
+            ("[Opus 160k]", "Audio codec and average bitrate"),
+            ("⚠", "Codecs don't fit the container, will re-encode"),
+        ];
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Format badges", heading)));
+        for (badge, meaning) in legend {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<12}", badge), badge_style(badge.trim_matches(['[', ']']))),
+                Span::raw(meaning),
+            ]));
+        }
+
+        let help = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title("Help (press any key to close)")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(help, popup_area);
+    }
+
+    /// Helper function to create a centered rectangle
+    fn centered_rect(&self, percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+        let popup_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ])
+            .split(r);
+
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ])
+            .split(popup_layout[1])[1]
+    }
+}
+
+/// Pick a color for a format badge based on what it describes
+fn badge_style(badge: &str) -> Style {
+    let color = if badge.starts_with("HDR") || badge == "HLG" || badge == "DV" {
+        Color::Yellow
+    } else if badge.ends_with("fps") {
+        Color::Green
+    } else if matches!(badge, "AV1" | "VP9" | "VP8" | "H.264" | "HEVC") {
+        Color::Magenta
+    } else {
+        Color::Cyan
+    };
+
+    Style::default().fg(color)
+}
+
+/// Status text and color of a comment archiving sub-task
+fn comment_archive_status(comments: &CommentArchive) -> (String, Color) {
+    match comments {
+        CommentArchive::Running(max) => (format!("archiving up to {}...", max), Color::Yellow),
+        CommentArchive::Saved { count, path } => (format!("{} saved to {}", count, path), Color::Green),
+        CommentArchive::Failed(reason) => (format!("failed - {}", reason), Color::Red),
+    }
+}
+
+/// Cut `text` to `max` characters, marking the cut with "…"
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
     }
+    let mut cut: String = text.chars().take(max.saturating_sub(1)).collect();
+    cut.push('…');
+    cut
 }
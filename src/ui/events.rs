@@ -1,7 +1,9 @@
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use tokio::sync::mpsc;
 
-use crate::app_state::{AppState, Panel, DownloadStatus, events::*};
+use crate::app_state::{AppState, Command, ItemEdit, Panel, DownloadStatus, LogSource, LogViewer, events::*, log::LogLevel};
+use crate::config::SettingField;
+use crate::macros::MacroStep;
 
 /// Handle input events and update application state
 pub async fn handle_input(
@@ -40,12 +42,162 @@ async fn handle_key_event(
         return;
     }
 
+    // Notices only last until the next key press
+    state.notice = None;
+
+    // Any key closes the help overlay
+    if state.show_help {
+        state.show_help = false;
+        return;
+    }
+
+    // The command palette runs actions by replaying their keys, with itself closed
+    if state.palette.is_some() {
+        if let Some(key) = handle_palette_input(key, state) {
+            Box::pin(handle_key_event(key, state, action_tx)).await;
+        }
+        return;
+    }
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('k') {
+        match crate::ui::palette::context(state) {
+            Some(_) => state.palette = Some(crate::app_state::CommandPalette::default()),
+            None => state.notice = Some("No palette actions here".to_string()),
+        }
+        return;
+    }
+
     // Handle format popup if active
     if state.format_popup.is_some() {
         handle_format_popup_input(key, state, action_tx).await;
         return;
     }
 
+    // Handle log viewer if active
+    if state.log_viewer.is_some() {
+        handle_log_viewer_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle description viewer if active
+    if state.description_popup.is_some() {
+        handle_description_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle source address picker if active
+    if state.network_popup.is_some() {
+        handle_network_input(key, state);
+        return;
+    }
+
+    // Handle batch file name report if active
+    if state.collision_report.is_some() {
+        handle_collision_report_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle troubleshooting wizard if active
+    if state.retry_wizard.is_some() {
+        handle_retry_wizard_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle live recording options if active
+    if state.live_popup.is_some() {
+        handle_live_popup_input(key, state);
+        return;
+    }
+
+    // Handle stop mode selection if active
+    if state.cancel_popup.is_some() {
+        handle_cancel_popup_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle duplicate warning if active
+    if state.duplicate_popup.is_some() {
+        handle_duplicate_input(key, state);
+        return;
+    }
+
+    // Handle the Wayback Machine offer if active
+    if state.wayback_popup.is_some() {
+        handle_wayback_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle the macros panel if active
+    if state.macros_popup.is_some() {
+        handle_macros_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle the recent URLs popup if active
+    if state.recent_popup.is_some() {
+        handle_recent_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle history search if active
+    if state.history_popup.is_some() {
+        handle_history_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle text prompt if active
+    if state.prompt.is_some() {
+        handle_prompt_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle diagnostics report if active
+    if state.diagnostics_popup.is_some() {
+        handle_diagnostics_input(key, state);
+        return;
+    }
+
+    // Handle schedules panel if active
+    if state.schedules_popup.is_some() {
+        handle_schedules_input(key, state);
+        return;
+    }
+
+    // Handle output template editor if active, it opens above settings
+    if state.template_editor.is_some() {
+        handle_template_editor_input(key, state);
+        return;
+    }
+
+    // Handle per-site options panel if active, it opens above settings
+    if state.sites_popup.is_some() {
+        handle_sites_input(key, state);
+        return;
+    }
+
+    // Handle web API tokens if active, they open above settings
+    if state.tokens_popup.is_some() {
+        handle_tokens_input(key, state);
+        return;
+    }
+
+    // Handle notification settings if active, they open above settings
+    if state.notifications_popup.is_some() {
+        handle_notifications_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle album split point editor if active
+    if state.split_points_popup.is_some() {
+        handle_split_points_input(key, state, action_tx).await;
+        return;
+    }
+
+    // Handle settings popup if active
+    if state.settings_popup.is_some() {
+        handle_settings_popup_input(key, state);
+        return;
+    }
+
     // Handle playlist preview popup if active
     if state.playlist_preview.is_some() {
         handle_playlist_preview_input(key, state, action_tx).await;
@@ -104,6 +256,27 @@ async fn handle_navigation_mode(
     state: &mut AppState,
     action_tx: &mpsc::Sender<DownloadAction>,
 ) {
+    // Vim-style counts ("5j", "3d") and two-key commands (gg, ma, 'a, @1)
+    if let Some(prefix) = state.pending_key.take() {
+        let count = state.key_count.take();
+        if let KeyCode::Char(c) = key.code {
+            handle_prefixed_key(prefix, c, count, state, action_tx).await;
+        }
+        return;
+    }
+    if let KeyCode::Char(c @ '0'..='9') = key.code {
+        if c != '0' || state.key_count.is_some() {
+            let digit = c as usize - '0' as usize;
+            state.key_count = Some((state.key_count.unwrap_or(0) * 10 + digit).min(MAX_KEY_COUNT));
+            return;
+        }
+    }
+    if let KeyCode::Char(c @ ('g' | 'm' | '\'' | '@')) = key.code {
+        state.pending_key = Some(c);
+        return;
+    }
+    let count = state.key_count.take();
+
     match key.code {
         KeyCode::Char('q') => {
             state.should_quit = true;
@@ -112,18 +285,217 @@ async fn handle_navigation_mode(
             state.input_mode = true;
             state.current_panel = Panel::Input;
         }
+        KeyCode::Char('?') => {
+            state.show_help = true;
+        }
+        KeyCode::Char('s') => {
+            state.settings_popup = Some(crate::app_state::SettingsPopup::default());
+        }
+        KeyCode::Char('L') => {
+            // Selected item's log, or the application log when the queue is empty
+            let source = match state.selected_item() {
+                Some(item) => LogSource::Item(item.id),
+                None => LogSource::App,
+            };
+            state.log_viewer = Some(LogViewer {
+                source,
+                level: LogLevel::Info,
+                scroll: state.log_lines(source).len().saturating_sub(1),
+            });
+        }
+        KeyCode::Char('e') => {
+            if let Some(item) = state.selected_item() {
+                match &item.metadata.description {
+                    Some(description) => {
+                        state.description_popup =
+                            Some(crate::app_state::DescriptionPopup::new(item.id, description));
+                    }
+                    None => state.notice = Some("No description (fetch formats first with f)".to_string()),
+                }
+            }
+        }
+        KeyCode::Char('N') => {
+            if let Some(item) = state.selected_item() {
+                state.network_popup = Some(crate::app_state::NetworkPopup {
+                    item_id: item.id,
+                    addresses: None,
+                    selected_index: 0,
+                });
+                queue_action(action_tx, DownloadAction::DetectAddresses);
+            }
+        }
+        KeyCode::Char('F') => {
+            match state.selected_item() {
+                Some(item) if item.status == DownloadStatus::Failed => {
+                    let error = item.error.clone().unwrap_or_default();
+                    state.retry_wizard = Some(crate::app_state::RetryWizard::new(item.id, error));
+                }
+                Some(_) => state.notice = Some("Only failed items can be troubleshot".to_string()),
+                None => {}
+            }
+        }
+        KeyCode::Char('S') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::ExportBundle,
+                input: "gorlock-bundle.json".to_string(),
+            });
+        }
+        KeyCode::Char('R') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::ImportBundle,
+                input: String::new(),
+            });
+        }
+        KeyCode::Char('T') => {
+            // Time-shift / stop options for live streams
+            match state.selected_item() {
+                Some(item) if item.live.is_some() => {
+                    state.live_popup = Some(crate::app_state::LivePopup {
+                        item_id: item.id,
+                        selected_index: 0,
+                    });
+                }
+                Some(_) => state.notice = Some("Not a live stream (fetch formats first with f)".to_string()),
+                None => {}
+            }
+        }
+        KeyCode::Char('D') => {
+            let issues = crate::app_state::diagnostics::scan(state);
+            if !issues.is_empty() {
+                state.push_app_log(format!("Diagnostics found {} issue(s)", issues.len()));
+            }
+            state.diagnostics_popup = Some(crate::app_state::DiagnosticsPopup { issues, scroll: 0 });
+        }
+        KeyCode::Char('W') => {
+            state.schedules_popup = Some(crate::app_state::SchedulesPopup::default());
+        }
+        KeyCode::Char('U') => {
+            state.recent_popup = Some(crate::app_state::RecentPopup::default());
+        }
+        KeyCode::Char('Q') => match state.macro_recording.as_ref().map(Vec::len) {
+            None => {
+                state.macro_recording = Some(Vec::new());
+                state.notice = Some("Recording a macro: pick formats, profile, album split, then Q to stop".to_string());
+            }
+            Some(0) => {
+                state.macro_recording = None;
+                state.notice = Some("Nothing recorded, macro discarded".to_string());
+            }
+            Some(_) => {
+                state.prompt = Some(crate::app_state::PromptPopup {
+                    kind: crate::app_state::PromptKind::MacroName,
+                    input: String::new(),
+                });
+            }
+        },
+        KeyCode::Char('K') => {
+            state.macros_popup = Some(crate::app_state::MacrosPopup::default());
+        }
+        KeyCode::Char('G') => {
+            // "5G" goes to the fifth item, like in vim
+            let moved = state.select_nth_visible(count.map_or(usize::MAX, |n| n.saturating_sub(1)));
+            if moved {
+                selection_moved(state, action_tx).await;
+            }
+        }
+        KeyCode::Char('B') => {
+            start_batch(state, action_tx, true).await;
+        }
+        KeyCode::Char('P') => {
+            // Retry the failed post-processing stage, the chain carries on from there
+            if let Some(item) = state.selected_item() {
+                let failed = item
+                    .post_processing
+                    .iter()
+                    .position(|run| matches!(run.status, crate::commands::postprocess::StageStatus::Failed(_)));
+                if let Some(index) = failed {
+                    let _ = action_tx.send(DownloadAction::RunStage { id: item.id, index }).await;
+                }
+            }
+        }
+        KeyCode::Char('O') => {
+            // Skip the failed post-processing stage, the rest of the chain and the follow-up run
+            if let Some(item) = state.selected_item() {
+                let failed = item
+                    .post_processing
+                    .iter()
+                    .position(|run| matches!(run.status, crate::commands::postprocess::StageStatus::Failed(_)));
+                if let Some(index) = failed {
+                    let _ = action_tx.send(DownloadAction::SkipStage { id: item.id, index }).await;
+                }
+            }
+        }
+        KeyCode::Char('C') => {
+            // Graceful stop options for the selected download
+            if let Some(item) = state.selected_item() {
+                if matches!(item.status, DownloadStatus::Downloading | DownloadStatus::Paused) {
+                    let mut options = vec![crate::app_state::CancelMode::Now];
+                    if item.status == DownloadStatus::Downloading
+                        && item.format.as_ref().is_some_and(|f| f.is_fragmented())
+                    {
+                        options.push(crate::app_state::CancelMode::AfterFragment);
+                    }
+                    if item.batch_id.is_some() && item.batch_id == state.batch.as_ref().map(|b| b.batch_id) {
+                        options.push(crate::app_state::CancelMode::AfterCurrentItem);
+                    }
+                    if item.live.is_some() && item.status == DownloadStatus::Downloading {
+                        options.push(crate::app_state::CancelMode::FinishRecording);
+                    }
+                    state.cancel_popup = Some(crate::app_state::CancelPopup {
+                        item_id: item.id,
+                        options,
+                        selected_index: 0,
+                    });
+                }
+            }
+        }
+        KeyCode::Char('H') => {
+            state.history_popup = Some(crate::app_state::HistoryPopup::default());
+            state.refresh_history_search();
+        }
+        KeyCode::Char('I') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::ImportArchive,
+                input: String::new(),
+            });
+        }
+        KeyCode::Char('E') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::ExportArchive,
+                input: "archive.txt".to_string(),
+            });
+        }
+        KeyCode::Char('a') => {
+            // Toggle album split post-processing before the download starts
+            if let Some(item) = state.selected_item() {
+                if matches!(
+                    item.status,
+                    DownloadStatus::Pending | DownloadStatus::Ready | DownloadStatus::Failed
+                ) {
+                    let id = item.id;
+                    let split = match item.album_split {
+                        Some(_) => None,
+                        None => Some(crate::app_state::AlbumSplit::Requested),
+                    };
+                    if split.is_some() {
+                        state.record_step(MacroStep::AlbumSplit);
+                    } else if let Some(steps) = &mut state.macro_recording {
+                        steps.retain(|s| *s != MacroStep::AlbumSplit);
+                    }
+                    state.apply(Command::EditItems(vec![(id, ItemEdit::AlbumSplit(split))]));
+                }
+            }
+        }
         KeyCode::Up | KeyCode::Char('k') => {
-            if !state.queue.is_empty() && state.selected_index > 0 {
-                state.selected_index -= 1;
-                // Prefetch formats for the newly selected item if not already fetched
-                prefetch_formats_for_selected_item(state, action_tx).await;
+            let moved = state.step_selection(-(count.unwrap_or(1) as isize));
+            if moved {
+                selection_moved(state, action_tx).await;
             }
         }
         KeyCode::Down | KeyCode::Char('j') => {
-            if !state.queue.is_empty() && state.selected_index < state.queue.len() - 1 {
-                state.selected_index += 1;
-                // Prefetch formats for the newly selected item if not already fetched
-                prefetch_formats_for_selected_item(state, action_tx).await;
+            let moved = state.step_selection(count.unwrap_or(1) as isize);
+            if moved {
+                selection_moved(state, action_tx).await;
             }
         }
         KeyCode::Tab => {
@@ -134,7 +506,7 @@ async fn handle_navigation_mode(
             };
         }
         KeyCode::Char('f') => {
-            if let Some(item) = state.queue.get(state.selected_index) {
+            if let Some(item) = state.selected_item() {
                 if matches!(
                     item.status,
                     crate::app_state::DownloadStatus::Pending
@@ -146,19 +518,95 @@ async fn handle_navigation_mode(
             }
         }
         KeyCode::Char('d') => {
-            if !state.queue.is_empty() {
-                let item = &state.queue[state.selected_index];
-                let _ = action_tx.send(DownloadAction::RemoveItem(item.id)).await;
-
-                // Remove from queue immediately for UI responsiveness
-                state.queue.remove(state.selected_index);
-                if state.selected_index >= state.queue.len() && !state.queue.is_empty() {
-                    state.selected_index = state.queue.len() - 1;
+            // "3d" deletes the selected item and the two below it
+            let ids = state.visible_ids_from_selection(count.unwrap_or(1));
+            if ids.len() > 1 {
+                state.notice = Some(format!("Deleted {} items, u brings them back", ids.len()));
+            }
+            if !ids.is_empty() {
+                state.apply(Command::RemoveItems(ids));
+            }
+        }
+        KeyCode::Char('u') => {
+            state.notice = Some(match state.undo() {
+                Some(label) => format!("Undid {}", label),
+                None => "Nothing to undo".to_string(),
+            });
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            state.notice = Some(match state.redo() {
+                Some(label) => format!("Redid {}", label),
+                None => "Nothing to redo".to_string(),
+            });
+        }
+        KeyCode::Char('o') => {
+            // Cycle the sort order, keeping the selected item selected
+            let selected = state.selected_item().map(|i| i.id);
+            state.queue_sort = state.queue_sort.next();
+            let order = state.queue_sort.order(&state.queue);
+            state.apply(crate::app_state::Command::SetOrder(order));
+            if let Some(index) = selected.and_then(|id| state.queue.iter().position(|i| i.id == id)) {
+                state.selected_index = index;
+            }
+            state.notice = Some(format!("Queue sorted by {}", state.queue_sort.label()));
+        }
+        KeyCode::Char('v') => {
+            if let Some(item) = state.selected_item() {
+                queue_action(action_tx, DownloadAction::RefreshEngagement { id: item.id, force: true });
+            }
+        }
+        KeyCode::Char('w') => {
+            match state.selected_item() {
+                Some(item) if item.status == crate::app_state::DownloadStatus::Unavailable => {
+                    let _ = action_tx.send(DownloadAction::WaybackLookup { id: item.id, asked: true }).await;
                 }
+                _ => state.notice = Some("Select an unavailable item to look it up on the Wayback Machine".to_string()),
+            }
+        }
+        KeyCode::Char('X') => {
+            let gone: Vec<_> = state
+                .queue
+                .iter()
+                .filter(|i| i.status == crate::app_state::DownloadStatus::Unavailable)
+                .map(|i| i.id)
+                .collect();
+            if gone.is_empty() {
+                state.notice = Some("No unavailable items in the queue".to_string());
+            } else {
+                state.notice = Some(format!("Removed {} unavailable item(s), u brings them back", gone.len()));
+                state.apply(Command::RemoveItems(gone));
+            }
+        }
+        KeyCode::Char('M') => {
+            // A filter narrows the refresh down to the items it shows
+            let ids = state.refreshable_items();
+            if ids.is_empty() {
+                state.notice = Some("Nothing waiting to download to refresh".to_string());
+            } else if state.metadata_refresh.is_some() {
+                state.notice = Some("A metadata refresh is already running".to_string());
+            } else {
+                let _ = action_tx.send(DownloadAction::RefreshMetadata(ids)).await;
+            }
+        }
+        KeyCode::Char('V') => {
+            // "5V" refreshes the selected item and the four below it
+            let ids = state.refreshable_from_selection(count.unwrap_or(1));
+            if ids.is_empty() {
+                state.notice = Some("Nothing waiting to download to refresh".to_string());
+            } else if state.metadata_refresh.is_some() {
+                state.notice = Some("A metadata refresh is already running".to_string());
+            } else {
+                let _ = action_tx.send(DownloadAction::RefreshMetadata(ids)).await;
             }
         }
+        KeyCode::Char('/') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::FilterQueue,
+                input: state.queue_filter.as_ref().map(|f| f.query.clone()).unwrap_or_default(),
+            });
+        }
         KeyCode::Char('p') => {
-            if let Some(item) = state.queue.get(state.selected_index) {
+            if let Some(item) = state.selected_item() {
                 match item.status {
                     crate::app_state::DownloadStatus::Downloading => {
                         let _ = action_tx.send(DownloadAction::PauseDownload(item.id)).await;
@@ -171,7 +619,7 @@ async fn handle_navigation_mode(
             }
         }
         KeyCode::Char('c') => {
-            if let Some(item) = state.queue.get(state.selected_index) {
+            if let Some(item) = state.selected_item() {
                 if matches!(
                     item.status,
                     crate::app_state::DownloadStatus::Downloading
@@ -219,17 +667,26 @@ async fn handle_format_popup_input(
             KeyCode::Enter => {
                 if let Some(selected_format) = filtered_formats.get(popup.selected_index).cloned() {
                     let item_id = popup.item_id;
+                    let container = popup.container.clone();
                     let selected_format = selected_format.clone();
                     
                     // Close popup first
                     state.format_popup = None;
+                    state.record_step(MacroStep::Format {
+                        height: crate::macros::format_height(&selected_format),
+                        audio_only: selected_format.is_audio_only,
+                        container: container.clone(),
+                    });
+                    state.record_step(MacroStep::Start);
                     
                     // Update the item with selected format
-                    if let Some(item) = state.queue.iter_mut().find(|item| item.id == item_id) {
-                        item.format = Some(selected_format);
-                        item.status = crate::app_state::DownloadStatus::Ready;
-                    }
-                    
+                    state.apply(Command::SetFormat {
+                        id: item_id,
+                        format: Some(Box::new(selected_format)),
+                        container: Some(container),
+                        status: DownloadStatus::Ready,
+                    });
+
                     // Start download
                     let _ = action_tx.send(DownloadAction::StartDownload(item_id)).await;
                 }
@@ -239,6 +696,15 @@ async fn handle_format_popup_input(
                 popup.audio_only_filter = !popup.audio_only_filter;
                 popup.selected_index = 0; // Reset selection when filtering
             }
+            KeyCode::Char('m') => {
+                // Cycle merge container
+                let current = crate::config::MERGE_CONTAINERS
+                    .iter()
+                    .position(|c| *c == popup.container)
+                    .unwrap_or(0);
+                let next = (current + 1) % crate::config::MERGE_CONTAINERS.len();
+                popup.container = crate::config::MERGE_CONTAINERS[next].to_string();
+            }
             KeyCode::Esc => {
                 state.format_popup = None;
             }
@@ -247,43 +713,1251 @@ async fn handle_format_popup_input(
     }
 }
 
-/// Handle input when playlist preview popup is active
-async fn handle_playlist_preview_input(
+/// Handle input when the log viewer is active
+async fn handle_log_viewer_input(key: KeyEvent, state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some((source, level)) = state.log_viewer.as_ref().map(|v| (v.source, v.level)) else {
+        return;
+    };
+
+    // Tab switches between the item log and the application log, f cycles the level shown
+    if matches!(key.code, KeyCode::Tab | KeyCode::Char('f')) {
+        let (source, level) = match (key.code, source, state.selected_item()) {
+            (KeyCode::Char('f'), _, _) => (source, level.next()),
+            (_, LogSource::App, Some(item)) => (LogSource::Item(item.id), level),
+            _ => (LogSource::App, level),
+        };
+        state.log_viewer = Some(LogViewer {
+            source,
+            level,
+            scroll: state.filtered_log(source, level).len().saturating_sub(1),
+        });
+        return;
+    }
+
+    // Save a bug report about the item shown, or just the app, next to the downloads
+    if key.code == KeyCode::Char('b') {
+        let item = match source {
+            LogSource::Item(id) => Some(id),
+            LogSource::App => None,
+        };
+        let report = crate::bug_report::BugReport::capture(state, item);
+        let name = format!("gorlock-bug-report-{}.md", report.created_at.format("%Y%m%d-%H%M%S"));
+        let path = std::path::Path::new(&state.output_dir).join(name).to_string_lossy().to_string();
+        let _ = action_tx.send(DownloadAction::ExportBugReport { path, report }).await;
+        return;
+    }
+
+    let last = state.filtered_log(source, level).len().saturating_sub(1);
+    let Some(viewer) = &mut state.log_viewer else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => viewer.scroll = viewer.scroll.saturating_sub(1).min(last),
+        KeyCode::Down | KeyCode::Char('j') => viewer.scroll = (viewer.scroll + 1).min(last),
+        KeyCode::PageUp => viewer.scroll = viewer.scroll.saturating_sub(10).min(last),
+        KeyCode::PageDown => viewer.scroll = (viewer.scroll + 10).min(last),
+        KeyCode::Home | KeyCode::Char('g') => viewer.scroll = 0,
+        KeyCode::End | KeyCode::Char('G') => viewer.scroll = last,
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('L') => state.log_viewer = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the description viewer: scroll the text, pick links and enqueue them
+async fn handle_description_input(
     key: KeyEvent,
     state: &mut AppState,
-    _action_tx: &mpsc::Sender<DownloadAction>,
+    action_tx: &mpsc::Sender<DownloadAction>,
 ) {
-    if let Some(preview) = &mut state.playlist_preview {
-        match key.code {
-            KeyCode::Up | KeyCode::Char('k') => {
-                if preview.selected_index > 0 {
-                    preview.selected_index -= 1;
-                }
+    let last = state
+        .description_popup
+        .as_ref()
+        .and_then(|popup| state.queue.iter().find(|i| i.id == popup.item_id))
+        .and_then(|item| item.metadata.description.as_ref())
+        .map_or(0, |description| description.lines().count().saturating_sub(1));
+    let Some(popup) = &mut state.description_popup else {
+        return;
+    };
+    let link_count = popup.links.len();
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.scroll = popup.scroll.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => popup.scroll = (popup.scroll + 1).min(last),
+        KeyCode::PageUp => popup.scroll = popup.scroll.saturating_sub(10),
+        KeyCode::PageDown => popup.scroll = (popup.scroll + 10).min(last),
+        KeyCode::Home | KeyCode::Char('g') => popup.scroll = 0,
+        KeyCode::Tab if link_count > 0 => popup.selected_link = (popup.selected_link + 1) % link_count,
+        KeyCode::BackTab if link_count > 0 => {
+            popup.selected_link = (popup.selected_link + link_count - 1) % link_count;
+        }
+        KeyCode::Char(' ') if link_count > 0 => {
+            let marked = &mut popup.marked[popup.selected_link];
+            *marked = !*marked;
+        }
+        KeyCode::Enter => {
+            let links = popup.links_to_enqueue();
+            if links.is_empty() {
+                return;
             }
-            KeyCode::Down | KeyCode::Char('j') => {
-                if preview.selected_index + 1 < preview.entries.len() {
-                    preview.selected_index += 1;
-                }
+            state.description_popup = None;
+            state.notice = Some(format!("Adding {} link(s) from the description", links.len()));
+            for link in links {
+                let _ = action_tx.send(DownloadAction::AddUrl(link)).await;
             }
-            KeyCode::Enter => {
-                // Confirm: add all entries to queue
-                let entries = std::mem::take(&mut preview.entries);
-                state.playlist_preview = None;
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('e') => state.description_popup = None,
+        _ => {}
+    }
+}
 
-                for e in entries {
-                    let mut item = crate::app_state::DownloadItem::new(e.url);
-                    item.title = Some(e.title);
-                    item.duration = e.duration;
-                    item.status = crate::app_state::DownloadStatus::Pending;
-                    state.queue.push(item);
-                }
+/// Handle input in the diagnostics report
+fn handle_diagnostics_input(key: KeyEvent, state: &mut AppState) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            if let Some(popup) = &mut state.diagnostics_popup {
+                popup.scroll = popup.scroll.saturating_sub(1);
             }
-            KeyCode::Esc => {
-                // Cancel
-                state.playlist_preview = None;
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            if let Some(popup) = &mut state.diagnostics_popup {
+                popup.scroll = popup.scroll.saturating_add(1);
             }
-            _ => {}
         }
+        KeyCode::Enter | KeyCode::Char('r') => {
+            if let Some(popup) = state.diagnostics_popup.take().filter(|p| !p.issues.is_empty()) {
+                let fixed = crate::app_state::diagnostics::repair(state, &popup.issues);
+                state.push_app_log(format!("Diagnostics repaired {} issue(s)", fixed));
+                state.notice = Some(format!("Repaired {} issue(s)", fixed));
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.diagnostics_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the schedules panel
+fn handle_schedules_input(key: KeyEvent, state: &mut AppState) {
+    let Some(popup) = &mut state.schedules_popup else {
+        return;
+    };
+    let count = state.schedules.entries.len();
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => {
+            popup.selected_index = (popup.selected_index + 1).min(count.saturating_sub(1));
+        }
+        KeyCode::Char('a') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::AddSchedule,
+                input: String::new(),
+            });
+        }
+        KeyCode::Char(' ') | KeyCode::Enter => {
+            let mut entries = state.schedules.entries.clone();
+            if let Some(schedule) = entries.get_mut(popup.selected_index) {
+                schedule.enabled = !schedule.enabled;
+                state.apply(Command::SetSchedules(entries));
+            }
+        }
+        KeyCode::Char('x') | KeyCode::Delete if popup.selected_index < count => {
+            let mut entries = state.schedules.entries.clone();
+            entries.remove(popup.selected_index);
+            popup.selected_index = popup.selected_index.min(count.saturating_sub(2));
+            state.apply(Command::SetSchedules(entries));
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('W') => state.schedules_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the command palette, returning the key of the action to run
+fn handle_palette_input(key: KeyEvent, state: &mut AppState) -> Option<KeyEvent> {
+    let count = crate::ui::palette::matches(state, &state.palette.as_ref()?.query).len();
+    let popup = state.palette.as_mut()?;
+
+    match key.code {
+        KeyCode::Up => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down => popup.selected_index = (popup.selected_index + 1).min(count.saturating_sub(1)),
+        KeyCode::Enter => {
+            let query = std::mem::take(&mut popup.query);
+            let index = popup.selected_index;
+            state.palette = None;
+            return crate::ui::palette::matches(state, &query).get(index).map(|a| a.key_event());
+        }
+        KeyCode::Esc => state.palette = None,
+        KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => state.palette = None,
+        KeyCode::Backspace => {
+            popup.query.pop();
+            popup.selected_index = 0;
+        }
+        KeyCode::Char(c) => {
+            popup.query.push(c);
+            popup.selected_index = 0;
+        }
+        _ => {}
+    }
+    None
+}
+
+/// Counts above this are typos, not queue positions
+const MAX_KEY_COUNT: usize = 99_999;
+
+/// Second key of a two-key queue command
+async fn handle_prefixed_key(
+    prefix: char,
+    key: char,
+    count: Option<usize>,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    match (prefix, key) {
+        ('g', 'g') if state.select_nth_visible(count.map_or(0, |n| n.saturating_sub(1))) => {
+            selection_moved(state, action_tx).await;
+        }
+        ('m', 'a'..='z') => {
+            if let Some(item) = state.selected_item() {
+                state.marks.insert(key, item.id);
+                state.notice = Some(format!("Mark {} set, '{} jumps back here", key, key));
+            }
+        }
+        ('\'', 'a'..='z') => match state.marks.get(&key).and_then(|id| state.queue.iter().position(|i| i.id == *id)) {
+            Some(index) if state.is_visible(&state.queue[index]) => {
+                state.selected_index = index;
+                selection_moved(state, action_tx).await;
+            }
+            Some(_) => state.notice = Some(format!("The item marked {} is hidden by the filter", key)),
+            None => state.notice = Some(format!("No mark {}", key)),
+        },
+        ('@', key) => match state.config.macros.iter().position(|m| m.key == Some(key)) {
+            Some(index) => run_macro(state, index, action_tx).await,
+            None => state.notice = Some(format!("No macro on @{}, record one with Q", key)),
+        },
+        _ => {}
+    }
+}
+
+/// Prefetch formats and refresh stale counts for a newly selected item
+async fn selection_moved(state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    prefetch_formats_for_selected_item(state, action_tx).await;
+    refresh_stale_engagement(state, action_tx);
+}
+
+/// Save the recorded steps as a macro, bound to the first free key. A macro with the same name is replaced.
+fn save_recorded_macro(state: &mut AppState, name: String) {
+    let Some(steps) = state.macro_recording.take() else {
+        return;
+    };
+    let mut config = state.config.clone();
+    let name = if name.is_empty() { format!("Macro {}", config.macros.len() + 1) } else { name };
+    let key = match config.macros.iter_mut().find(|m| m.name == name) {
+        Some(existing) => {
+            existing.steps = steps;
+            existing.key
+        }
+        None => {
+            let key = crate::macros::MACRO_KEYS
+                .iter()
+                .copied()
+                .find(|k| !config.macros.iter().any(|m| m.key == Some(*k)));
+            config.macros.push(crate::macros::Macro { name: name.clone(), key, steps });
+            key
+        }
+    };
+    state.apply(Command::SetConfig(Box::new(config)));
+    state.notice = Some(match key {
+        Some(key) => format!("Saved macro \"{}\", press @{} on an item to replay it", name, key),
+        None => format!("Saved macro \"{}\", run it from the macros panel (K)", name),
+    });
+}
+
+/// Replay a macro on the selected item
+async fn run_macro(state: &mut AppState, index: usize, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(recorded) = state.config.macros.get(index).cloned() else {
+        return;
+    };
+    match state.selected_item() {
+        Some(item) if matches!(item.status, DownloadStatus::Pending | DownloadStatus::Ready | DownloadStatus::Failed) => {
+            let id = item.id;
+            state.notice = Some(format!("Running macro \"{}\"", recorded.name));
+            if let Some(action) = crate::macros::replay(state, id, recorded.steps, None) {
+                let _ = action_tx.send(action).await;
+            }
+        }
+        Some(_) => state.notice = Some("Macros run on items that haven't started downloading".to_string()),
+        None => state.notice = Some("Select an item to run the macro on".to_string()),
+    }
+}
+
+/// Handle input in the macros panel
+async fn handle_macros_input(key: KeyEvent, state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(popup) = &mut state.macros_popup else {
+        return;
+    };
+    let count = state.config.macros.len();
+    let index = popup.selected_index;
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => popup.selected_index = (index + 1).min(count.saturating_sub(1)),
+        KeyCode::Enter if index < count => {
+            state.macros_popup = None;
+            run_macro(state, index, action_tx).await;
+        }
+        KeyCode::Char('b') if index < count => {
+            // Cycle through the free keys, then unbound
+            let mut config = state.config.clone();
+            let taken: Vec<char> = config.macros.iter().filter_map(|m| m.key).collect();
+            let current = config.macros[index].key;
+            let start = current.and_then(|k| crate::macros::MACRO_KEYS.iter().position(|c| *c == k)).map_or(0, |i| i + 1);
+            config.macros[index].key = crate::macros::MACRO_KEYS[start.min(crate::macros::MACRO_KEYS.len())..]
+                .iter()
+                .copied()
+                .find(|k| !taken.contains(k));
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Char('x') | KeyCode::Delete if index < count => {
+            let mut config = state.config.clone();
+            config.macros.remove(index);
+            popup.selected_index = index.min(count.saturating_sub(2));
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('K') => state.macros_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the web API tokens panel
+fn handle_tokens_input(key: KeyEvent, state: &mut AppState) {
+    let Some(popup) = &mut state.tokens_popup else {
+        return;
+    };
+    let count = state.config.api_tokens.len();
+    let index = popup.selected_index;
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => popup.selected_index = (index + 1).min(count.saturating_sub(1)),
+        KeyCode::Char('a') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::AddApiToken,
+                input: String::new(),
+            });
+        }
+        KeyCode::Char('s') | KeyCode::Right | KeyCode::Char('l') if index < count => {
+            let mut config = state.config.clone();
+            let token = &mut config.api_tokens[index];
+            token.scope = token.scope.next();
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Char('c') if index < count => {
+            state.notice = Some(match crate::recent::copy_to_clipboard(&state.config.api_tokens[index].token) {
+                Ok(()) => "Token copied to the clipboard".to_string(),
+                Err(e) => format!("Could not copy: {}", e),
+            });
+        }
+        KeyCode::Char('x') | KeyCode::Delete if index < count => {
+            let mut config = state.config.clone();
+            config.api_tokens.remove(index);
+            popup.selected_index = index.min(count.saturating_sub(2));
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.tokens_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the recent URLs popup
+async fn handle_recent_input(key: KeyEvent, state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(popup) = &mut state.recent_popup else {
+        return;
+    };
+    let count = state.recent_urls.entries.len();
+    let selected = state.recent_urls.entries.get(popup.selected_index).map(|e| e.url.clone());
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => {
+            popup.selected_index = (popup.selected_index + 1).min(count.saturating_sub(1));
+        }
+        KeyCode::Enter => {
+            if let Some(url) = selected {
+                state.recent_popup = None;
+                state.is_loading = true;
+                state.loading_message = Some("Fetching video information...".to_string());
+                state.current_panel = Panel::Queue;
+                let _ = action_tx.send(DownloadAction::AddUrl(url)).await;
+            }
+        }
+        KeyCode::Char('c') => {
+            if let Some(url) = selected {
+                state.notice = Some(match crate::recent::copy_to_clipboard(&url) {
+                    Ok(()) => "Copied to the clipboard".to_string(),
+                    Err(e) => format!("Could not copy: {}", e),
+                });
+            }
+        }
+        KeyCode::Char('p') if popup.selected_index < count => {
+            popup.selected_index = state.recent_urls.toggle_pin(popup.selected_index);
+            save_recent_urls(state);
+        }
+        KeyCode::Char('x') | KeyCode::Delete if popup.selected_index < count => {
+            state.recent_urls.entries.remove(popup.selected_index);
+            popup.selected_index = popup.selected_index.min(count.saturating_sub(2));
+            save_recent_urls(state);
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U') => state.recent_popup = None,
+        _ => {}
+    }
+}
+
+fn save_recent_urls(state: &mut AppState) {
+    if let Err(e) = state.recent_urls.save() {
+        state.error_message = Some(format!("Failed to save recent URLs: {}", e));
+    }
+}
+
+/// Handle input in the batch file name report
+async fn handle_collision_report_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    let Some(report) = &mut state.collision_report else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => report.scroll = report.scroll.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => report.scroll += 1,
+        KeyCode::Char('n') | KeyCode::Char('i') => {
+            let mode = if key.code == KeyCode::Char('n') {
+                crate::app_state::Uniquify::Index
+            } else {
+                crate::app_state::Uniquify::VideoId
+            };
+            let report = report.clone();
+            state.collision_report = Some(state.uniquify_batch(&report, mode));
+        }
+        KeyCode::Enter => {
+            // Start the report's batch even if the selection moved
+            let batch_id = report.batch_id;
+            state.collision_report = None;
+            if let Some(index) = state.queue.iter().position(|i| i.batch_id == Some(batch_id)) {
+                state.selected_index = index;
+            }
+            start_batch(state, action_tx, false).await;
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.collision_report = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the troubleshooting wizard
+async fn handle_retry_wizard_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    let Some(wizard) = &mut state.retry_wizard else {
+        return;
+    };
+    let last = crate::commands::troubleshoot::RetryStep::ALL.len() - 1;
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => wizard.selected_index = wizard.selected_index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => wizard.selected_index = (wizard.selected_index + 1).min(last),
+        KeyCode::Left | KeyCode::Char('h') => wizard.cycle_choice(-1),
+        KeyCode::Right | KeyCode::Char('l') => wizard.cycle_choice(1),
+        KeyCode::Char('p') if wizard.selected_step() == crate::commands::troubleshoot::RetryStep::Proxy => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::RetryProxy,
+                input: wizard.proxy.clone().unwrap_or_default(),
+            });
+        }
+        KeyCode::Enter if wizard.running.is_none() => {
+            if wizard.args(wizard.selected_step()).is_none() {
+                state.prompt = Some(crate::app_state::PromptPopup {
+                    kind: crate::app_state::PromptKind::RetryProxy,
+                    input: String::new(),
+                });
+            } else {
+                run_retry_step(state, action_tx).await;
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.retry_wizard = None,
+        _ => {}
+    }
+}
+
+/// Try the wizard's selected step
+async fn run_retry_step(state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    let Some(wizard) = &mut state.retry_wizard else {
+        return;
+    };
+    let step = wizard.selected_step();
+    let Some(args) = wizard.args(step) else {
+        return;
+    };
+    wizard.running = Some(step);
+    let action = DownloadAction::RetryExtraction {
+        id: wizard.item_id,
+        step,
+        tried: wizard.describe(step),
+        args,
+    };
+    let _ = action_tx.send(action).await;
+}
+
+/// Handle input in the output template editor
+fn handle_template_editor_input(key: KeyEvent, state: &mut AppState) {
+    let Some(editor) = &mut state.template_editor else {
+        return;
+    };
+    let fields = crate::output_template::TEMPLATE_FIELDS;
+
+    match key.code {
+        KeyCode::Up => editor.selected_field = editor.selected_field.saturating_sub(1),
+        KeyCode::Down => editor.selected_field = (editor.selected_field + 1).min(fields.len() - 1),
+        KeyCode::Tab => editor.input.push_str(fields[editor.selected_field].insert),
+        KeyCode::Char(c) => editor.input.push(c),
+        KeyCode::Backspace => {
+            editor.input.pop();
+        }
+        KeyCode::Enter => {
+            let template = editor.input.trim().to_string();
+            if template.is_empty() {
+                state.error_message = Some("The file name template can't be empty".to_string());
+                return;
+            }
+            if !template.contains("%(ext)s") {
+                state.notice = Some("No %(ext)s in the template, files will be saved without an extension".to_string());
+            }
+            state.template_editor = None;
+            let mut config = state.config.clone();
+            config.output_template = template;
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Esc => state.template_editor = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the per-site extractor options panel
+fn handle_sites_input(key: KeyEvent, state: &mut AppState) {
+    let Some(popup) = &mut state.sites_popup else {
+        return;
+    };
+    let count = state.config.site_options.len();
+    let index = popup.selected_index;
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => popup.selected_index = (index + 1).min(count.saturating_sub(1)),
+        KeyCode::Char('a') => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::AddSite,
+                input: String::new(),
+            });
+        }
+        KeyCode::Char('e') if index < count => {
+            state.prompt = Some(crate::app_state::PromptPopup {
+                kind: crate::app_state::PromptKind::SiteExtractorArgs,
+                input: state.config.site_options[index].extractor_args.join(" "),
+            });
+        }
+        KeyCode::Left | KeyCode::Char('h') | KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('i') if index < count => {
+            let delta = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) { -1 } else { 1 };
+            let mut config = state.config.clone();
+            let site = &mut config.site_options[index];
+            if key.code == KeyCode::Char('i') {
+                site.cycle_impersonate(1);
+            } else {
+                site.cycle_player_client(delta);
+            }
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Char('c') | KeyCode::Char('s') if index < count => {
+            let mut config = state.config.clone();
+            let site = &mut config.site_options[index];
+            if key.code == KeyCode::Char('c') {
+                site.cycle_max_concurrent();
+            } else {
+                site.cycle_start_spacing();
+            }
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Char('x') | KeyCode::Delete if index < count => {
+            let mut config = state.config.clone();
+            config.site_options.remove(index);
+            popup.selected_index = index.min(count.saturating_sub(2));
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.sites_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the notification settings panel
+async fn handle_notifications_input(key: KeyEvent, state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    use crate::notifications::NotifyField;
+
+    let Some(popup) = &mut state.notifications_popup else {
+        return;
+    };
+    let field = NotifyField::ALL[popup.selected_index.min(NotifyField::ALL.len() - 1)];
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => popup.selected_index = (popup.selected_index + 1).min(NotifyField::ALL.len() - 1),
+        KeyCode::Char('t') => {
+            if !state.config.notifications.is_configured() {
+                state.error_message = Some("Fill in a Telegram bot and chat, or an SMTP server with from and to, first".to_string());
+                return;
+            }
+            state.notice = Some("Sending a test notification...".to_string());
+            let message = crate::notifications::Message::test();
+            let _ = action_tx.send(DownloadAction::Notify { message, test: true }).await;
+        }
+        KeyCode::Enter | KeyCode::Left | KeyCode::Char('h') | KeyCode::Right | KeyCode::Char('l') => {
+            let mut config = state.config.clone();
+            if let Some(value) = config.notifications.text_mut(field) {
+                // Secrets start empty rather than showing what is saved
+                let secret = matches!(field, NotifyField::TelegramBotToken | NotifyField::SmtpPassword);
+                state.prompt = Some(crate::app_state::PromptPopup {
+                    kind: crate::app_state::PromptKind::NotificationField,
+                    input: value.clone().filter(|_| !secret).unwrap_or_default(),
+                });
+                return;
+            }
+            let delta = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) { -1 } else { 1 };
+            config.notifications.adjust(field, delta);
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.notifications_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the live recording popup; the stop condition stays editable while recording
+fn handle_live_popup_input(key: KeyEvent, state: &mut AppState) {
+    let Some(popup) = &mut state.live_popup else {
+        return;
+    };
+    let Some(live) = state
+        .queue
+        .iter_mut()
+        .find(|i| i.id == popup.item_id)
+        .and_then(|i| i.live.as_mut())
+    else {
+        state.live_popup = None;
+        return;
+    };
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = 0,
+        KeyCode::Down | KeyCode::Char('j') => popup.selected_index = 1,
+        KeyCode::Left | KeyCode::Char('h') | KeyCode::Right | KeyCode::Char('l') => {
+            let delta: isize = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) { -1 } else { 1 };
+            let step = |len: usize, current: usize| (current as isize + delta).clamp(0, len as isize - 1) as usize;
+
+            match popup.selected_index {
+                // The start point can't change once recording
+                0 if live.recording_since.is_none() => {
+                    let steps = crate::app_state::LIVE_START_STEPS;
+                    let current = steps.iter().position(|s| *s == live.start).unwrap_or(0);
+                    live.start = steps[step(steps.len(), current)];
+                }
+                1 => {
+                    let steps = crate::app_state::LIVE_STOP_STEPS;
+                    let current = steps.iter().position(|s| *s == live.stop_after_minutes).unwrap_or(0);
+                    live.stop_after_minutes = steps[step(steps.len(), current)];
+                }
+                _ => {}
+            }
+        }
+        KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => state.live_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the source address picker: Enter binds the item, `p` the active profile
+fn handle_network_input(key: KeyEvent, state: &mut AppState) {
+    let Some(popup) = &mut state.network_popup else {
+        return;
+    };
+    let rows = popup.addresses.as_ref().map_or(1, |a| a.len() + 1);
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => popup.selected_index = (popup.selected_index + 1).min(rows - 1),
+        KeyCode::Enter => {
+            let (item_id, address) = (popup.item_id, popup.selected_address());
+            state.network_popup = None;
+            state.notice = Some(match &address {
+                Some(address) => format!("Downloads of this item connect from {}", address),
+                None => "This item uses the profile's source address".to_string(),
+            });
+            state.apply(Command::EditItems(vec![(item_id, ItemEdit::SourceAddress(address))]));
+        }
+        KeyCode::Char('p') => {
+            let address = popup.selected_address();
+            state.network_popup = None;
+            let mut config = state.config.clone();
+            let profile = config.active_profile.clone();
+            if let Some(p) = config.profiles.iter_mut().find(|p| p.name == profile) {
+                p.source_address = address.clone();
+            }
+            state.notice = Some(match address {
+                Some(address) => format!("Profile {} connects from {}", profile, address),
+                None => format!("Profile {} uses the default route", profile),
+            });
+            state.apply(Command::SetConfig(Box::new(config)));
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.network_popup = None,
+        _ => {}
+    }
+}
+
+/// Download the selected item's playlist batch one item at a time. With `check_names`, file
+/// name collisions are reported first and the batch waits for the report to be confirmed.
+async fn start_batch(state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>, check_names: bool) {
+    let Some(batch_id) = state.selected_item().and_then(|i| i.batch_id) else {
+        state.notice = Some("Not part of a playlist batch".to_string());
+        return;
+    };
+    if state.batch.is_some() {
+        state.notice = Some("A batch is already running".to_string());
+        return;
+    }
+    if check_names {
+        let report = state.check_batch_names(batch_id);
+        if !report.is_clean() {
+            state.collision_report = Some(report);
+            return;
+        }
+    }
+
+    let container = state.config.merge_container.clone();
+    let mut queued = 0;
+    for item in state.queue.iter_mut().filter(|i| i.batch_id == Some(batch_id)) {
+        if crate::app_state::batch_names::will_download(item) {
+            // Items nobody picked a format for get yt-dlp's best
+            if item.format.is_none() {
+                item.format = Some(crate::app_state::FormatInfo::best(&container));
+            }
+            item.status = DownloadStatus::Queued;
+            queued += 1;
+        }
+    }
+    if queued == 0 {
+        state.notice = Some("Nothing left to download in this batch".to_string());
+        return;
+    }
+
+    state.batch = Some(crate::app_state::BatchRun {
+        batch_id,
+        stop_after_current: false,
+    });
+    state.notice = Some(format!("Downloading {} items from the batch", queued));
+    if let Some(next) = state.next_batch_item() {
+        let _ = action_tx.send(DownloadAction::StartDownload(next)).await;
+    }
+}
+
+/// Handle input in the stop mode popup
+async fn handle_cancel_popup_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    let Some(popup) = &mut state.cancel_popup else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => {
+            popup.selected_index = (popup.selected_index + 1).min(popup.options.len().saturating_sub(1));
+        }
+        KeyCode::Enter => {
+            let id = popup.item_id;
+            let mode = popup.options[popup.selected_index];
+            state.cancel_popup = None;
+
+            match mode {
+                crate::app_state::CancelMode::Now => {
+                    let _ = action_tx.send(DownloadAction::CancelDownload(id)).await;
+                }
+                crate::app_state::CancelMode::AfterFragment => {
+                    let _ = action_tx.send(DownloadAction::StopAfterFragment(id)).await;
+                }
+                crate::app_state::CancelMode::FinishRecording => {
+                    let _ = action_tx.send(DownloadAction::FinishRecording(id)).await;
+                }
+                crate::app_state::CancelMode::AfterCurrentItem => {
+                    if let Some(batch) = &mut state.batch {
+                        batch.stop_after_current = true;
+                        state.notice = Some("Batch will stop after the current item".to_string());
+                    }
+                }
+            }
+        }
+        KeyCode::Esc | KeyCode::Char('q') => state.cancel_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the duplicate warning: delete the new copy or keep both
+fn handle_duplicate_input(key: KeyEvent, state: &mut AppState) {
+    let Some(popup) = &state.duplicate_popup else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Char('d') | KeyCode::Char('y') => {
+            let popup = state.duplicate_popup.take().unwrap();
+            let item = state.queue.iter_mut().find(|i| i.id == popup.item_id);
+            match std::fs::remove_file(&popup.new_path) {
+                Ok(()) => {
+                    if let Some(item) = item {
+                        item.file_path = Some(popup.existing_path.clone());
+                        item.push_log(format!("Deleted duplicate {}", popup.new_path));
+                    }
+                    state.notice = Some(format!("Deleted duplicate, kept {}", popup.existing_path));
+                }
+                Err(e) => state.error_message = Some(format!("Failed to delete {}: {}", popup.new_path, e)),
+            }
+        }
+        KeyCode::Char('k') | KeyCode::Char('n') | KeyCode::Esc => {
+            state.notice = Some(format!("Kept both copies of {}", popup.existing_path));
+            state.duplicate_popup = None;
+        }
+        _ => {}
+    }
+}
+
+/// Handle input in the Wayback Machine offer: switch the item to the archived copy, or keep it as it is
+async fn handle_wayback_input(key: KeyEvent, state: &mut AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    match key.code {
+        KeyCode::Enter | KeyCode::Char('y') => {
+            let Some(popup) = state.wayback_popup.take() else {
+                return;
+            };
+            let Some(item) = state.queue.iter_mut().find(|i| i.id == popup.item_id) else {
+                return;
+            };
+            item.push_log(format!("Switched from {} to its Wayback Machine copy", item.url));
+            state.apply(Command::EditItems(vec![(popup.item_id, ItemEdit::Source {
+                url: popup.snapshot,
+                status: DownloadStatus::FetchingInfo,
+                error: None,
+            })]));
+            let _ = action_tx.send(DownloadAction::FetchFormats(popup.item_id)).await;
+        }
+        KeyCode::Char('n') | KeyCode::Esc => state.wayback_popup = None,
+        _ => {}
+    }
+}
+
+/// Handle input in the history search popup (typing filters the results)
+async fn handle_history_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    let Some(popup) = &mut state.history_popup else {
+        return;
+    };
+
+    match key.code {
+        KeyCode::Up => popup.selected_index = popup.selected_index.saturating_sub(1),
+        KeyCode::Down => {
+            popup.selected_index = (popup.selected_index + 1).min(popup.results.len().saturating_sub(1));
+        }
+        KeyCode::PageUp => popup.selected_index = popup.selected_index.saturating_sub(20),
+        KeyCode::PageDown => {
+            popup.selected_index = (popup.selected_index + 20).min(popup.results.len().saturating_sub(1));
+        }
+        KeyCode::Enter => {
+            // Queue the selected entry again
+            let entry = popup
+                .results
+                .get(popup.selected_index)
+                .and_then(|&i| state.history.get(i))
+                .cloned();
+            state.history_popup = None;
+
+            if let Some(entry) = entry {
+                let mut item = crate::app_state::DownloadItem::new(entry.url);
+                item.title = entry.title;
+                item.tags = entry.tags;
+                let id = item.id;
+                state.apply(Command::AddItems(vec![item]));
+                state.notice = Some("Queued again from history".to_string());
+                let _ = action_tx.send(DownloadAction::FetchFormats(id)).await;
+            }
+        }
+        KeyCode::Esc => state.history_popup = None,
+        KeyCode::Char(c) => {
+            popup.query.push(c);
+            popup.selected_index = 0;
+            state.refresh_history_search();
+        }
+        KeyCode::Backspace => {
+            popup.query.pop();
+            popup.selected_index = 0;
+            state.refresh_history_search();
+        }
+        _ => {}
+    }
+}
+
+/// Handle input when a text prompt is active
+async fn handle_prompt_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    if let Some(prompt) = &mut state.prompt {
+        match key.code {
+            KeyCode::Enter => {
+                let kind = prompt.kind;
+                let input = prompt.input.trim().to_string();
+                state.prompt = None;
+                // An empty filter clears it, every other prompt needs input
+                if kind == crate::app_state::PromptKind::FilterQueue {
+                    state.queue_filter = crate::app_state::QueueFilter::parse(&input);
+                    state.select_visible();
+                    if let Some(filter) = &state.queue_filter {
+                        let shown = state.visible_indices().len();
+                        state.notice = Some(format!("{} of {} items match \"{}\"", shown, state.queue.len(), filter.query));
+                    }
+                    return;
+                }
+                if kind == crate::app_state::PromptKind::RetryProxy {
+                    if let Some(wizard) = state.retry_wizard.as_mut().filter(|_| !input.is_empty()) {
+                        wizard.proxy = Some(input);
+                        run_retry_step(state, action_tx).await;
+                    }
+                    return;
+                }
+                if kind == crate::app_state::PromptKind::SiteExtractorArgs {
+                    let index = state.sites_popup.as_ref().map_or(0, |p| p.selected_index);
+                    let mut config = state.config.clone();
+                    if let Some(site) = config.site_options.get_mut(index) {
+                        site.extractor_args = input.split_whitespace().map(String::from).collect();
+                        state.apply(Command::SetConfig(Box::new(config)));
+                    }
+                    return;
+                }
+                if kind == crate::app_state::PromptKind::MacroName {
+                    save_recorded_macro(state, input);
+                    return;
+                }
+                if kind == crate::app_state::PromptKind::NotificationField {
+                    let index = state.notifications_popup.as_ref().map_or(0, |p| p.selected_index);
+                    let mut config = state.config.clone();
+                    if let Some(value) = crate::notifications::NotifyField::ALL.get(index).and_then(|f| config.notifications.text_mut(*f)) {
+                        *value = Some(input).filter(|i| !i.is_empty());
+                        state.apply(Command::SetConfig(Box::new(config)));
+                    }
+                    return;
+                }
+                if input.is_empty() {
+                    return;
+                }
+
+                match kind {
+                    crate::app_state::PromptKind::ImportArchive => {
+                        let _ = action_tx.send(DownloadAction::ImportArchive(expand_home(&input))).await;
+                    }
+                    crate::app_state::PromptKind::ExportArchive => {
+                        let _ = action_tx.send(DownloadAction::ExportArchive(expand_home(&input))).await;
+                    }
+                    crate::app_state::PromptKind::ExportBundle => {
+                        let _ = action_tx.send(DownloadAction::ExportBundle(expand_home(&input))).await;
+                    }
+                    crate::app_state::PromptKind::ImportBundle => {
+                        let _ = action_tx.send(DownloadAction::ImportBundle(expand_home(&input))).await;
+                    }
+                    crate::app_state::PromptKind::AddSchedule => match crate::schedule::Schedule::from_line(&input) {
+                        Ok(schedule) => {
+                            let mut entries = state.schedules.entries.clone();
+                            entries.push(schedule);
+                            state.apply(Command::SetSchedules(entries));
+                            if let Some(popup) = &mut state.schedules_popup {
+                                popup.selected_index = state.schedules.entries.len() - 1;
+                            }
+                        }
+                        Err(e) => state.error_message = Some(format!("Invalid schedule: {}", e)),
+                    },
+                    crate::app_state::PromptKind::DownloadDir => {
+                        let dir = expand_home(&input);
+                        if !std::path::Path::new(&dir).is_dir() {
+                            state.error_message = Some(format!("{} is not a folder", dir));
+                            return;
+                        }
+                        let mut config = state.config.clone();
+                        config.download_dir = Some(dir);
+                        state.apply(Command::SetConfig(Box::new(config)));
+                    }
+                    crate::app_state::PromptKind::AddApiToken => {
+                        let token = crate::web::ApiToken::generate(input, crate::web::TokenScope::ReadOnly);
+                        state.notice = Some(match crate::recent::copy_to_clipboard(&token.token) {
+                            Ok(()) => format!("New read-only token {} copied to the clipboard, s changes its scope", token.token),
+                            Err(_) => format!("New read-only token {}, s changes its scope", token.token),
+                        });
+                        let mut config = state.config.clone();
+                        config.api_tokens.push(token);
+                        let added = config.api_tokens.len() - 1;
+                        state.apply(Command::SetConfig(Box::new(config)));
+                        if let Some(popup) = &mut state.tokens_popup {
+                            popup.selected_index = added;
+                        }
+                    }
+                    crate::app_state::PromptKind::AddSite => {
+                        // Accept a pasted URL as well as a bare domain
+                        let domain = input
+                            .trim_start_matches("https://")
+                            .trim_start_matches("http://")
+                            .trim_start_matches("www.")
+                            .split('/')
+                            .next()
+                            .unwrap_or_default()
+                            .to_lowercase();
+                        if state.config.site_options.iter().any(|s| s.domain == domain) {
+                            state.error_message = Some(format!("{} already has options", domain));
+                            return;
+                        }
+                        let mut config = state.config.clone();
+                        config.site_options.push(crate::config::SiteOptions {
+                            domain,
+                            ..Default::default()
+                        });
+                        let added = config.site_options.len() - 1;
+                        state.apply(Command::SetConfig(Box::new(config)));
+                        if let Some(popup) = &mut state.sites_popup {
+                            popup.selected_index = added;
+                        }
+                    }
+                    crate::app_state::PromptKind::FilterQueue
+                    | crate::app_state::PromptKind::SiteExtractorArgs
+                    | crate::app_state::PromptKind::NotificationField
+                    | crate::app_state::PromptKind::MacroName
+                    | crate::app_state::PromptKind::RetryProxy => {}
+                }
+            }
+            KeyCode::Esc => {
+                if prompt.kind == crate::app_state::PromptKind::MacroName {
+                    state.macro_recording = None;
+                    state.notice = Some("Macro discarded".to_string());
+                }
+                state.prompt = None;
+            }
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) && prompt.kind.pick().is_some() => {
+                let _ = action_tx.send(DownloadAction::PickPath(prompt.kind)).await;
+            }
+            KeyCode::Char(c) => {
+                prompt.input.push(c);
+            }
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Expand a leading `~` to the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix("~/"), crate::config::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest).to_string_lossy().to_string(),
+        _ => path.to_string(),
+    }
+}
+
+/// Handle input when the album split point editor is active
+async fn handle_split_points_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    if let Some(popup) = &mut state.split_points_popup {
+        // Shift moves points in bigger steps
+        let step = if key.modifiers.contains(KeyModifiers::SHIFT) { 10.0 } else { 1.0 };
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                popup.selected_index = popup.selected_index.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                popup.selected_index = (popup.selected_index + 1).min(popup.points.len().saturating_sub(1));
+            }
+            KeyCode::Left | KeyCode::Char('h') => popup.nudge_selected(-step),
+            KeyCode::Right | KeyCode::Char('l') => popup.nudge_selected(step),
+            KeyCode::Char('n') => popup.add_point(),
+            KeyCode::Char('x') | KeyCode::Delete => popup.remove_selected(),
+            KeyCode::Enter => {
+                let id = popup.item_id;
+                let tracks = crate::commands::album::tracks_from_split_points(&popup.points);
+                state.split_points_popup = None;
+
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    item.album_split = Some(crate::app_state::AlbumSplit::Splitting);
+                }
+                let _ = action_tx.send(DownloadAction::SplitAlbum { id, tracks }).await;
+            }
+            KeyCode::Esc => {
+                let id = popup.item_id;
+                state.split_points_popup = None;
+                if let Some(item) = state.queue.iter_mut().find(|i| i.id == id) {
+                    item.album_split = Some(crate::app_state::AlbumSplit::Failed(
+                        "Split cancelled".to_string(),
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handle input when settings popup is active
+fn handle_settings_popup_input(key: KeyEvent, state: &mut AppState) {
+    if let Some(popup) = &mut state.settings_popup {
+        let field = popup.selected_field();
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                popup.selected_index = popup.selected_index.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                popup.selected_index = (popup.selected_index + 1).min(SettingField::ALL.len() - 1);
+            }
+            KeyCode::Enter if field == SettingField::OutputTemplate => {
+                state.template_editor = Some(crate::app_state::TemplateEditor {
+                    input: state.config.output_template.clone(),
+                    selected_field: 0,
+                    item_id: state.selected_item().map(|item| item.id),
+                });
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter if field == SettingField::DownloadDir => {
+                state.prompt = Some(crate::app_state::PromptPopup {
+                    kind: crate::app_state::PromptKind::DownloadDir,
+                    input: state.output_dir.clone(),
+                });
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter if field == SettingField::SiteOptions => {
+                state.sites_popup = Some(crate::app_state::SitesPopup::default());
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter if field == SettingField::Notifications => {
+                state.notifications_popup = Some(crate::app_state::NotificationsPopup::default());
+            }
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter if field == SettingField::ApiTokens => {
+                state.tokens_popup = Some(crate::app_state::TokensPopup::default());
+            }
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => {
+                let delta = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) { -1 } else { 1 };
+                let mut config = state.config.clone();
+                config.adjust_setting(field, delta);
+                state.apply(Command::SetConfig(Box::new(config)));
+                if field == SettingField::ActiveProfile {
+                    state.record_step(MacroStep::Profile(state.config.active_profile.clone()));
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                state.settings_popup = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Handle input when playlist preview popup is active
+async fn handle_playlist_preview_input(
+    key: KeyEvent,
+    state: &mut AppState,
+    action_tx: &mpsc::Sender<DownloadAction>,
+) {
+    if let Some(preview) = &mut state.playlist_preview {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                if preview.selected_index > 0 {
+                    preview.selected_index -= 1;
+                }
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                if preview.selected_index + 1 < preview.entries.len() {
+                    preview.selected_index += 1;
+                }
+            }
+            KeyCode::Char(' ') => {
+                if let Some(marked) = preview.marked.get_mut(preview.selected_index) {
+                    *marked = !*marked;
+                }
+            }
+            KeyCode::Enter => {
+                // Confirm: add the marked entries, or all when none are, to the queue,
+                // skipping what the archive says we already have
+                let mut entries = std::mem::take(&mut preview.entries);
+                if preview.marked.contains(&true) {
+                    let mut marked = preview.marked.iter();
+                    entries.retain(|_| marked.next() == Some(&true));
+                }
+                state.playlist_preview = None;
+
+                let mut rejected = 0;
+                let mut added = Vec::new();
+                let batch_id = uuid::Uuid::new_v4();
+                for e in entries.into_iter().filter(|e| !e.archived) {
+                    let mut item = crate::app_state::DownloadItem::new(e.url);
+                    item.batch_id = Some(batch_id);
+                    item.title = Some(e.title);
+                    item.duration = e.duration;
+                    item.status = crate::app_state::DownloadStatus::Pending;
+                    if state.scripts.run(crate::hooks::HookEvent::OnAdd, &mut item).is_some() {
+                        rejected += 1;
+                        continue;
+                    }
+                    added.push(item);
+                }
+                // One undo step for the whole playlist
+                let ids: Vec<uuid::Uuid> = added.iter().map(|i| i.id).collect();
+                if !added.is_empty() {
+                    state.apply(Command::AddItems(added));
+                }
+                for id in ids {
+                    let _ = action_tx
+                        .send(DownloadAction::RunHook { id, event: crate::hooks::HookEvent::OnAdd })
+                        .await;
+                }
+                if rejected > 0 {
+                    state.notice = Some(format!("Scripts rejected {} playlist entries", rejected));
+                }
+            }
+            KeyCode::Esc => {
+                // Cancel
+                state.playlist_preview = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Refresh the selected item's view counts when auto-refresh is on and they are older than the cache TTL
+fn refresh_stale_engagement(state: &AppState, action_tx: &mpsc::Sender<DownloadAction>) {
+    if !state.config.fetch_engagement {
+        return;
+    }
+    let Some(item) = state.selected_item() else {
+        return;
+    };
+    // Items without fetched info get their counts with the formats
+    let Some(fetched_at) = item.metadata.counts_fetched_at else {
+        return;
+    };
+    let ttl = chrono::Duration::from_std(crate::commands::engagement::ENGAGEMENT_TTL).unwrap_or_default();
+    if chrono::Utc::now() - fetched_at > ttl {
+        queue_action(action_tx, DownloadAction::RefreshEngagement { id: item.id, force: false });
     }
 }
 
@@ -292,11 +1966,11 @@ async fn prefetch_formats_for_selected_item(
     state: &AppState,
     action_tx: &mpsc::Sender<DownloadAction>,
 ) {
-    if let Some(item) = state.queue.get(state.selected_index) {
+    if let Some(item) = state.selected_item() {
         // Only prefetch if the item hasn't been fetched yet
         if matches!(item.status, DownloadStatus::Pending) {
             // Send a fetch formats action in background (non-blocking)
-            let _ = action_tx.try_send(DownloadAction::FetchFormats(item.id));
+            queue_action(action_tx, DownloadAction::FetchFormats(item.id));
         }
     }
 }
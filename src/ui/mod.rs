@@ -1,6 +1,8 @@
 pub mod app;
 pub mod components;
 pub mod events;
+pub mod monitor;
+pub mod palette;
 
 pub use app::App;
 pub use events::handle_input;
@@ -0,0 +1,82 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+use crate::app_state::progress::bar_cells;
+use crate::web::monitor::{Connection, Monitor};
+
+/// Colors match the status line of the queue's details panel
+fn status_color(status: &str) -> Color {
+    match status {
+        "Completed" => Color::Green,
+        "Completed (warnings)" => Color::LightYellow,
+        "Failed" => Color::Red,
+        "Downloading" => Color::Yellow,
+        "Paused" => Color::Cyan,
+        "Suspect" => Color::LightRed,
+        "Unavailable" => Color::Magenta,
+        _ => Color::White,
+    }
+}
+
+/// Draw a daemon's queue: a header with the connection, the items, and the selected item's error
+pub fn render(f: &mut Frame, monitor: &Monitor) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(3), Constraint::Length(4), Constraint::Length(1)])
+        .split(f.size());
+
+    let items = &monitor.snapshot.items;
+    let active = items.iter().filter(|i| i.status == "Downloading").count();
+    let (connection, color) = match &monitor.connection {
+        Connection::Connecting => ("connecting...".to_string(), Color::Yellow),
+        Connection::Connected => (format!("{} items, {} downloading", items.len(), active), Color::Green),
+        Connection::Lost(reason) => (format!("connection lost, retrying ({})", reason), Color::Red),
+    };
+    let header = Line::from(vec![
+        Span::styled(" gorlock monitor ", Style::default().add_modifier(Modifier::BOLD).fg(Color::Cyan)),
+        Span::raw(format!("{} | ", monitor.daemon)),
+        Span::styled(connection, Style::default().fg(color)),
+    ]);
+    f.render_widget(Paragraph::new(header), chunks[0]);
+
+    let rows: Vec<ListItem> = items
+        .iter()
+        .map(|item| {
+            let rate = match (&item.speed, &item.eta) {
+                (Some(speed), Some(eta)) => format!("{} ETA {}", speed, eta),
+                (Some(speed), None) => speed.clone(),
+                _ => item.size.clone().unwrap_or_default(),
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:<21}", item.status), Style::default().fg(status_color(&item.status))),
+                Span::styled(format!("{:<11}", bar_cells(item.percent, 10)), Style::default().fg(Color::Green)),
+                Span::raw(format!("{:>5.1}% ", item.percent)),
+                Span::styled(format!("{:<22}", rate), Style::default().fg(Color::Gray)),
+                Span::raw(item.title.clone()),
+            ]))
+        })
+        .collect();
+    let list = List::new(rows)
+        .block(Block::default().title("Queue (read-only)").borders(Borders::ALL))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut list_state = ListState::default().with_selected((!items.is_empty()).then_some(monitor.selected_index));
+    f.render_stateful_widget(list, chunks[1], &mut list_state);
+
+    let detail = match items.get(monitor.selected_index).and_then(|i| i.error.as_ref()) {
+        Some(error) => Span::styled(error.clone(), Style::default().fg(Color::Red)),
+        None => Span::styled(monitor.snapshot.notice.clone().unwrap_or_default(), Style::default().fg(Color::Gray)),
+    };
+    f.render_widget(
+        Paragraph::new(Line::from(detail)).wrap(Wrap { trim: true }).block(Block::default().borders(Borders::ALL)),
+        chunks[2],
+    );
+
+    let help = Paragraph::new("Read-only view of a daemon | ↑/↓: select | g/G: first/last | q: quit")
+        .style(Style::default().fg(Color::Gray));
+    f.render_widget(help, chunks[3]);
+}
@@ -0,0 +1,275 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::app_state::{AppState, DownloadItem, DownloadStatus};
+
+/// An action the command palette offers, run by replaying its key binding
+pub struct PaletteAction {
+    pub code: KeyCode,
+    pub ctrl: bool,
+    /// Binding shown next to the label, so the palette also teaches the keys
+    pub keys: &'static str,
+    pub label: &'static str,
+    /// Whether the action does anything in the current state
+    pub applies: fn(&AppState) -> bool,
+}
+
+impl PaletteAction {
+    pub fn key_event(&self) -> KeyEvent {
+        let modifiers = if self.ctrl { KeyModifiers::CONTROL } else { KeyModifiers::NONE };
+        KeyEvent::new(self.code, modifiers)
+    }
+}
+
+const fn action(c: char, keys: &'static str, label: &'static str, applies: fn(&AppState) -> bool) -> PaletteAction {
+    PaletteAction { code: KeyCode::Char(c), ctrl: false, keys, label, applies }
+}
+
+const fn key(code: KeyCode, keys: &'static str, label: &'static str) -> PaletteAction {
+    PaletteAction { code, ctrl: false, keys, label, applies: always }
+}
+
+fn always(_: &AppState) -> bool {
+    true
+}
+
+fn selected(state: &AppState) -> Option<&DownloadItem> {
+    state.selected_item()
+}
+
+fn selected_is(state: &AppState, statuses: &[DownloadStatus]) -> bool {
+    selected(state).is_some_and(|item| statuses.contains(&item.status))
+}
+
+fn has_selection(state: &AppState) -> bool {
+    selected(state).is_some()
+}
+
+fn can_fetch(state: &AppState) -> bool {
+    selected_is(state, &[DownloadStatus::Pending, DownloadStatus::Ready, DownloadStatus::Failed])
+}
+
+fn is_running(state: &AppState) -> bool {
+    selected_is(state, &[DownloadStatus::Downloading, DownloadStatus::Paused])
+}
+
+fn is_failed(state: &AppState) -> bool {
+    selected_is(state, &[DownloadStatus::Failed])
+}
+
+fn is_unavailable(state: &AppState) -> bool {
+    selected_is(state, &[DownloadStatus::Unavailable])
+}
+
+fn any_unavailable(state: &AppState) -> bool {
+    state.queue.iter().any(|i| i.status == DownloadStatus::Unavailable)
+}
+
+fn has_description(state: &AppState) -> bool {
+    selected(state).is_some_and(|item| item.metadata.description.is_some())
+}
+
+fn is_live(state: &AppState) -> bool {
+    selected(state).is_some_and(|item| item.live.is_some())
+}
+
+fn stage_failed(state: &AppState) -> bool {
+    selected(state).is_some_and(|item| {
+        item.post_processing
+            .iter()
+            .any(|run| matches!(run.status, crate::commands::postprocess::StageStatus::Failed(_)))
+    })
+}
+
+fn in_batch(state: &AppState) -> bool {
+    selected(state).is_some_and(|item| item.batch_id.is_some())
+}
+
+fn selected_refreshable(state: &AppState) -> bool {
+    !state.refreshable_from_selection(1).is_empty()
+}
+
+fn has_refreshable(state: &AppState) -> bool {
+    !state.refreshable_items().is_empty()
+}
+
+fn not_empty(state: &AppState) -> bool {
+    !state.queue.is_empty()
+}
+
+const QUEUE: &[PaletteAction] = &[
+    action('i', "i", "Add a URL", always),
+    action('U', "U", "Recently added and copied URLs", always),
+    action('f', "f", "Fetch formats for the selected item", can_fetch),
+    action('p', "p", "Pause or resume the selected download", is_running),
+    action('c', "c", "Cancel the selected download", is_running),
+    action('C', "C", "Stop the selected download gracefully", is_running),
+    action('d', "d", "Delete the selected item", has_selection),
+    action('F', "F", "Troubleshoot the failed extraction", is_failed),
+    action('w', "w", "Look up the unavailable item on the Wayback Machine", is_unavailable),
+    action('X', "X", "Remove all unavailable items", any_unavailable),
+    action('e', "e", "Show the description and its links", has_description),
+    action('v', "v", "Refresh view/like counts and live status", has_selection),
+    action('a', "a", "Toggle album split (chapters → tracks)", can_fetch),
+    action('P', "P", "Retry the failed post-processing stage", stage_failed),
+    action('O', "O", "Skip the failed post-processing stage", stage_failed),
+    action('T', "T", "Live recording options", is_live),
+    action('B', "B", "Download the playlist batch", in_batch),
+    action('N', "N", "Bind downloads to a local address", has_selection),
+    action('L', "L", "Show the log", always),
+    action('V', "V", "Refresh metadata of the selected item", selected_refreshable),
+    action('M', "M", "Refresh metadata of items not downloaded yet", has_refreshable),
+    action('o', "o", "Sort the queue", not_empty),
+    action('/', "/", "Filter the queue", always),
+    action('u', "u", "Undo", always),
+    PaletteAction { code: KeyCode::Char('r'), ctrl: true, keys: "^R", label: "Redo", applies: always },
+    action('D', "D", "Diagnostics and settings in effect", always),
+    action('H', "H", "Search download history", always),
+    action('W', "W", "Schedules", always),
+    action('Q', "Q", "Start or stop recording a macro", always),
+    action('K', "K", "Macros", always),
+    action('s', "s", "Settings", always),
+    action('S', "S", "Save state bundle", always),
+    action('R', "R", "Restore state bundle", always),
+    action('I', "I", "Import download archive", always),
+    action('E', "E", "Export download archive", always),
+    action('?', "?", "Keyboard shortcuts", always),
+    action('q', "q", "Quit", always),
+];
+
+const FORMATS: &[PaletteAction] = &[
+    key(KeyCode::Enter, "Enter", "Download the highlighted format"),
+    key(KeyCode::Char('t'), "t", "Toggle audio-only formats"),
+    key(KeyCode::Char('m'), "m", "Cycle merge container"),
+    key(KeyCode::Esc, "Esc", "Close the format list"),
+];
+
+const LOG: &[PaletteAction] = &[
+    key(KeyCode::Tab, "Tab", "Switch between item and application log"),
+    key(KeyCode::Char('f'), "f", "Show all entries, warnings and errors, or errors only"),
+    key(KeyCode::Char('b'), "b", "Save a bug report with the latest warnings and errors"),
+    key(KeyCode::Home, "g", "Jump to the first line"),
+    key(KeyCode::End, "G", "Jump to the last line"),
+    key(KeyCode::Esc, "Esc", "Close the log"),
+];
+
+const PLAYLIST: &[PaletteAction] = &[
+    key(KeyCode::Char(' '), "Space", "Mark the entry to queue only the marked ones"),
+    key(KeyCode::Enter, "Enter", "Queue the marked entries, or all of them"),
+    key(KeyCode::Esc, "Esc", "Close the preview"),
+];
+
+const SCHEDULES: &[PaletteAction] = &[
+    key(KeyCode::Char('a'), "a", "Add a schedule"),
+    key(KeyCode::Char(' '), "Space", "Enable or disable the highlighted schedule"),
+    key(KeyCode::Char('x'), "x", "Delete the highlighted schedule"),
+    key(KeyCode::Esc, "Esc", "Close schedules"),
+];
+
+const MACROS: &[PaletteAction] = &[
+    key(KeyCode::Enter, "Enter", "Run the highlighted macro on the selected item"),
+    key(KeyCode::Char('b'), "b", "Bind the highlighted macro to a key"),
+    key(KeyCode::Char('x'), "x", "Delete the highlighted macro"),
+    key(KeyCode::Esc, "Esc", "Close macros"),
+];
+
+const RECENT: &[PaletteAction] = &[
+    key(KeyCode::Enter, "Enter", "Add the highlighted URL again"),
+    key(KeyCode::Char('c'), "c", "Copy the highlighted URL"),
+    key(KeyCode::Char('p'), "p", "Pin or unpin the highlighted URL"),
+    key(KeyCode::Char('x'), "x", "Forget the highlighted URL"),
+    key(KeyCode::Esc, "Esc", "Close recent URLs"),
+];
+
+/// The actions for what's on screen: the open popup's, or the queue's. `None` for popups
+/// that take typed input or are a single question.
+pub fn context(state: &AppState) -> Option<&'static [PaletteAction]> {
+    if state.input_mode || state.prompt.is_some() || state.history_popup.is_some() {
+        return None;
+    }
+    let popups: [(bool, &'static [PaletteAction]); 6] = [
+        (state.format_popup.is_some(), FORMATS),
+        (state.log_viewer.is_some(), LOG),
+        (state.macros_popup.is_some(), MACROS),
+        (state.recent_popup.is_some(), RECENT),
+        (state.schedules_popup.is_some(), SCHEDULES),
+        (state.playlist_preview.is_some(), PLAYLIST),
+    ];
+    if let Some((_, actions)) = popups.iter().find(|(open, _)| *open) {
+        return Some(actions);
+    }
+    let other_popup = state.description_popup.is_some()
+        || state.network_popup.is_some()
+        || state.collision_report.is_some()
+        || state.retry_wizard.is_some()
+        || state.live_popup.is_some()
+        || state.cancel_popup.is_some()
+        || state.duplicate_popup.is_some()
+        || state.wayback_popup.is_some()
+        || state.diagnostics_popup.is_some()
+        || state.template_editor.is_some()
+        || state.sites_popup.is_some()
+        || state.tokens_popup.is_some()
+        || state.notifications_popup.is_some()
+        || state.split_points_popup.is_some()
+        || state.settings_popup.is_some();
+    (!other_popup).then_some(QUEUE)
+}
+
+/// Actions that apply right now and match the query, best match first
+pub fn matches(state: &AppState, query: &str) -> Vec<&'static PaletteAction> {
+    let actions = context(state).unwrap_or_default();
+    let mut scored: Vec<(i32, &'static PaletteAction)> = actions
+        .iter()
+        .filter(|a| (a.applies)(state))
+        .filter_map(|a| fuzzy_score(query, a.label).map(|score| (score, a)))
+        .collect();
+    // Stable, so equal scores keep the table's order
+    scored.sort_by_key(|(score, _)| -score);
+    scored.into_iter().map(|(_, a)| a).collect()
+}
+
+/// Score `text` against `query` when the query's characters appear in it in order.
+/// Runs of adjacent characters and word starts score higher.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut next = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = next + text[next..].iter().position(|&c| c == q)?;
+        score += 1;
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 2;
+        }
+        previous = Some(found);
+        next = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_palette_matches() {
+        assert_eq!(fuzzy_score("", "Settings"), Some(0));
+        assert_eq!(fuzzy_score("xyz", "Settings"), None);
+        assert!(fuzzy_score("set", "Settings") > fuzzy_score("set", "Save state bundle"));
+        assert!(fuzzy_score("sb", "Save state bundle").is_some());
+
+        let mut state = AppState::default();
+        let labels = |state: &AppState, query| matches(state, query).iter().map(|a| a.label).collect::<Vec<_>>();
+        assert!(!labels(&state, "").contains(&"Cancel the selected download"));
+        let mut item = DownloadItem::new("https://example.com/v".to_string());
+        item.status = DownloadStatus::Downloading;
+        state.queue.push(item);
+        assert_eq!(labels(&state, "cancel")[0], "Cancel the selected download");
+
+        state.recent_popup = Some(crate::app_state::RecentPopup::default());
+        assert_eq!(labels(&state, "pin"), ["Pin or unpin the highlighted URL"]);
+    }
+}
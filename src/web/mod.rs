@@ -0,0 +1,360 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, watch};
+use uuid::Uuid;
+
+use crate::app_state::{AppState, events::DownloadAction};
+
+pub mod monitor;
+mod stream;
+pub mod tokens;
+
+pub use stream::StreamEvent;
+pub use tokens::{Access, ApiToken, Auth, TokenScope};
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Requests bodies are a URL at most, anything bigger is refused
+const MAX_BODY: usize = 16 * 1024;
+
+/// Longest request line or header line read, and most headers read, before giving up
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+
+/// How often an idle event stream sends a comment, so proxies don't close it
+const KEEPALIVE: Duration = Duration::from_secs(15);
+
+const EVENT_STREAM_HEAD: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+
+/// Settings for `--daemon`: run without the TUI and serve the web UI
+#[derive(Debug, Clone)]
+pub struct WebOptions {
+    pub listen: String,
+    /// Full-control token, required as `?token=` when set. Scoped tokens come from the settings.
+    pub token: Option<String>,
+}
+
+impl WebOptions {
+    /// Parse `--daemon [--listen ADDR] [--token TOKEN]`, `None` without `--daemon`
+    pub fn from_args(args: &[String]) -> Option<Result<Self>> {
+        args.iter().position(|a| a == "--daemon")?;
+        let value = |flag: &str| -> Result<Option<String>> {
+            match args.iter().position(|a| a == flag) {
+                Some(i) => args
+                    .get(i + 1)
+                    .cloned()
+                    .map(Some)
+                    .ok_or_else(|| anyhow!("{} needs a value", flag)),
+                None => Ok(None),
+            }
+        };
+
+        Some((|| {
+            Ok(Self {
+                listen: value("--listen")?.unwrap_or_else(|| "127.0.0.1:8787".to_string()),
+                token: value("--token")?.or_else(|| std::env::var("GORLOCK_WEB_TOKEN").ok()),
+            })
+        })())
+    }
+}
+
+/// One queue item as the web UI and `--monitor` show it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebItem {
+    pub id: Uuid,
+    pub title: String,
+    pub status: String,
+    pub percent: f64,
+    pub speed: Option<String>,
+    pub eta: Option<String>,
+    pub size: Option<String>,
+    pub error: Option<String>,
+    pub can_pause: bool,
+    pub can_resume: bool,
+    pub can_cancel: bool,
+    /// Time spent per phase, e.g. "fetch 3s · download 1m 20s"
+    #[serde(default)]
+    pub timings: Option<String>,
+}
+
+/// The queue as JSON, published to the event streams whenever it changes
+pub fn snapshot(state: &AppState) -> String {
+    use crate::app_state::DownloadStatus;
+
+    let items: Vec<WebItem> = state
+        .queue
+        .iter()
+        .map(|item| WebItem {
+            id: item.id,
+            title: item.title.clone().unwrap_or_else(|| item.url.clone()),
+            status: item.status.to_string(),
+            percent: item.progress.percent,
+            speed: item.progress.speed_label().filter(|_| item.status == DownloadStatus::Downloading),
+            eta: item.progress.eta_label().filter(|_| item.status == DownloadStatus::Downloading),
+            size: item.progress.total_label(),
+            error: item.error.clone(),
+            can_pause: item.status == DownloadStatus::Downloading,
+            can_resume: item.status == DownloadStatus::Paused,
+            can_cancel: matches!(item.status, DownloadStatus::Downloading | DownloadStatus::Paused),
+            timings: (!item.timings.is_empty()).then(|| item.timings.breakdown(chrono::Utc::now())),
+        })
+        .collect();
+    let summary = state.timing_summary();
+    let phases: Vec<_> = summary
+        .shares()
+        .into_iter()
+        .map(|(phase, percent)| serde_json::json!({ "phase": phase.label(), "percent": percent }))
+        .collect();
+    serde_json::json!({
+        "items": items,
+        "notice": state.error_message.as_ref().or(state.notice.as_ref()),
+        "timings": { "items": summary.items, "phases": phases, "bottleneck": summary.bottleneck() },
+    })
+    .to_string()
+}
+
+/// How requests reach the daemon's main loop and what it publishes
+#[derive(Clone)]
+pub struct Control {
+    pub action_tx: mpsc::Sender<DownloadAction>,
+    /// The latest queue snapshot
+    pub snapshots: watch::Receiver<String>,
+    /// Download events as JSON, for `/api/stream`
+    pub events: broadcast::Sender<String>,
+}
+
+/// What a request asks for
+#[derive(Debug, PartialEq)]
+enum Route {
+    Index,
+    Events,
+    Stream,
+    Add,
+    Pause(Uuid),
+    Resume(Uuid),
+    Cancel(Uuid),
+    NotFound,
+}
+
+fn route(method: &str, path: &str) -> Route {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        ("GET", [""]) => Route::Index,
+        ("GET", ["api", "events"]) => Route::Events,
+        ("GET", ["api", "stream"]) => Route::Stream,
+        ("POST", ["api", "add"]) => Route::Add,
+        ("POST", ["api", "items", id, command]) => match (Uuid::parse_str(id), *command) {
+            (Ok(id), "pause") => Route::Pause(id),
+            (Ok(id), "resume") => Route::Resume(id),
+            (Ok(id), "cancel") => Route::Cancel(id),
+            _ => Route::NotFound,
+        },
+        _ => Route::NotFound,
+    }
+}
+
+impl Route {
+    fn access(&self) -> Access {
+        match self {
+            Route::Index | Route::Events | Route::Stream | Route::NotFound => Access::Read,
+            Route::Add => Access::Add,
+            Route::Pause(_) | Route::Resume(_) | Route::Cancel(_) => Access::Control,
+        }
+    }
+}
+
+/// Value of `name` in a query string, percent-decoded
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// Undo `encodeURIComponent` and form encoding ("+" for a space)
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| std::str::from_utf8(h).ok());
+        match (bytes[i], hex.and_then(|h| u8::from_str_radix(h, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Read one line of the request, refusing lines longer than `MAX_LINE`
+async fn read_line(reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>, line: &mut String) -> Result<usize> {
+    let read = (&mut *reader).take(MAX_LINE).read_line(line).await?;
+    if read as u64 == MAX_LINE && !line.ends_with('\n') {
+        return Err(anyhow!("request line longer than {} bytes", MAX_LINE));
+    }
+    Ok(read)
+}
+
+/// Accept connections until the daemon exits. Actions go to the main loop like key presses do.
+/// `auth` follows the settings, a revoked token stops working with the next request.
+pub async fn serve(listener: TcpListener, auth: watch::Receiver<Auth>, control: Control) {
+    loop {
+        let Ok((stream, _)) = listener.accept().await else {
+            continue;
+        };
+        let (auth, control) = (auth.clone(), control.clone());
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, auth, control).await {
+                tracing::debug!("Web request failed: {}", e);
+            }
+        });
+    }
+}
+
+/// Serve one request, the connection closes afterwards
+async fn handle_connection(stream: TcpStream, auth: watch::Receiver<Auth>, mut control: Control) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut request_line = String::new();
+    read_line(&mut reader, &mut request_line).await?;
+    let mut parts = request_line.split_whitespace();
+    let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let mut content_length = 0;
+    let (mut content_type, mut origin, mut host) = (None, None, None);
+    for read in 0.. {
+        let mut header = String::new();
+        if read_line(&mut reader, &mut header).await? == 0 || header.trim().is_empty() {
+            break;
+        }
+        if read == MAX_HEADERS {
+            return respond(&mut writer, "431 Request Header Fields Too Large", "text/plain", "Too many headers").await;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let value = value.trim().to_string();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "content-length" => content_length = value.parse().unwrap_or(0),
+                "content-type" => content_type = Some(value),
+                "origin" => origin = Some(value),
+                "host" => host = Some(value),
+                _ => {}
+            }
+        }
+    }
+    // Browsers say where a request comes from. A page on another site only gets through with a
+    // token, which it can't know unless the user gave it one, e.g. to a browser extension.
+    let token = query_param(query, "token");
+    let own_origin = |origin: &str| origin.split_once("://").is_some_and(|(_, o)| host.as_deref() == Some(o));
+    if token.is_none() && origin.is_some_and(|origin| !own_origin(&origin)) {
+        return respond(&mut writer, "403 Forbidden", "text/plain", "Cross-origin requests are not allowed").await;
+    }
+    let json = content_type.is_some_and(|t| t.split(';').next().is_some_and(|t| t.trim().eq_ignore_ascii_case("application/json")));
+    if method == "POST" && !json {
+        return respond(&mut writer, "415 Unsupported Media Type", "text/plain", "Expected Content-Type: application/json").await;
+    }
+    if content_length > MAX_BODY {
+        return respond(&mut writer, "413 Payload Too Large", "text/plain", "Too large").await;
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body).await?;
+
+    let route = route(method, path);
+    let allowed = || auth.borrow().check(token.as_deref(), route.access());
+    if let Err((status, message)) = allowed() {
+        return respond(&mut writer, status, "text/plain", message).await;
+    }
+
+    let action = match route {
+        Route::Index => return respond(&mut writer, "200 OK", "text/html; charset=utf-8", INDEX_HTML).await,
+        Route::Events => {
+            writer.write_all(EVENT_STREAM_HEAD).await?;
+            // A stream ends once its token is revoked, checked with every write
+            while allowed().is_ok() {
+                let data = control.snapshots.borrow_and_update().clone();
+                writer.write_all(format!("data: {}\n\n", data).as_bytes()).await?;
+                // A comment now and then, writing to a closed connection ends the stream
+                while tokio::time::timeout(KEEPALIVE, control.snapshots.changed()).await.is_err() && allowed().is_ok() {
+                    writer.write_all(b": keepalive\n\n").await?;
+                }
+            }
+            return Ok(());
+        }
+        Route::Stream => {
+            let mut events = control.events.subscribe();
+            writer.write_all(EVENT_STREAM_HEAD).await?;
+            while allowed().is_ok() {
+                match tokio::time::timeout(KEEPALIVE, events.recv()).await {
+                    Ok(Ok(event)) => writer.write_all(format!("data: {}\n\n", event).as_bytes()).await?,
+                    // A slow client misses some progress updates rather than holding up the rest
+                    Ok(Err(broadcast::error::RecvError::Lagged(_))) => continue,
+                    Ok(Err(broadcast::error::RecvError::Closed)) => return Ok(()),
+                    Err(_) => writer.write_all(b": keepalive\n\n").await?,
+                }
+            }
+            return Ok(());
+        }
+        Route::Add => {
+            let url = serde_json::from_slice::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v["url"].as_str().map(|u| u.trim().to_string()))
+                .filter(|u| u.starts_with("http://") || u.starts_with("https://"));
+            match url {
+                Some(url) => DownloadAction::AddRemote(url),
+                None => return respond(&mut writer, "400 Bad Request", "text/plain", "Expected {\"url\": \"https://...\"}").await,
+            }
+        }
+        Route::Pause(id) => DownloadAction::PauseDownload(id),
+        Route::Resume(id) => DownloadAction::ResumeDownload(id),
+        Route::Cancel(id) => DownloadAction::CancelDownload(id),
+        Route::NotFound => return respond(&mut writer, "404 Not Found", "text/plain", "Not found").await,
+    };
+    control.action_tx.send(action).await?;
+    respond(&mut writer, "202 Accepted", "text/plain", "OK").await
+}
+
+async fn respond(writer: &mut (impl AsyncWriteExt + Unpin), status: &str, content_type: &str, body: &str) -> Result<()> {
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        content_type,
+        body.len()
+    );
+    writer.write_all(head.as_bytes()).await?;
+    writer.write_all(body.as_bytes()).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_route() {
+        let id = Uuid::new_v4();
+        assert_eq!(route("GET", "/"), Route::Index);
+        assert_eq!(route("POST", "/api/add"), Route::Add);
+        assert_eq!(route("POST", &format!("/api/items/{}/pause", id)), Route::Pause(id));
+        assert_eq!(route("GET", &format!("/api/items/{}/pause", id)), Route::NotFound);
+        assert_eq!(route("POST", "/api/items/nope/cancel"), Route::NotFound);
+
+        assert_eq!(query_param("a=1&token=s3cret", "token").as_deref(), Some("s3cret"));
+        assert_eq!(query_param("token=a%2Bb%2F%3D+c", "token").as_deref(), Some("a+b/= c"));
+        assert_eq!(query_param("", "token"), None);
+    }
+}
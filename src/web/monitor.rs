@@ -0,0 +1,216 @@
+use anyhow::{Result, anyhow};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use ratatui::{Terminal, backend::CrosstermBackend};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use super::WebItem;
+
+/// Wait before reconnecting to a daemon that went away
+const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// Settings for `--monitor`: show a running daemon's queue, read-only
+#[derive(Debug, Clone)]
+pub struct MonitorOptions {
+    /// Address of the daemon's web UI
+    pub daemon: String,
+    pub token: Option<String>,
+}
+
+impl MonitorOptions {
+    /// Parse `--monitor [URL] [--token TOKEN]`, `None` without `--monitor`
+    pub fn from_args(args: &[String]) -> Option<Result<Self>> {
+        let at = args.iter().position(|a| a == "--monitor")?;
+        let daemon = args
+            .get(at + 1)
+            .filter(|a| !a.starts_with("--"))
+            .cloned()
+            .unwrap_or_else(|| "http://127.0.0.1:8787".to_string());
+        let token = match args.iter().position(|a| a == "--token") {
+            Some(i) => match args.get(i + 1) {
+                Some(token) => Some(token.clone()),
+                None => return Some(Err(anyhow!("--token needs a value"))),
+            },
+            None => std::env::var("GORLOCK_WEB_TOKEN").ok(),
+        };
+        Some(Ok(Self { daemon, token }))
+    }
+
+    /// The daemon's queue snapshot stream
+    fn events_url(&self) -> Result<reqwest::Url> {
+        let base = if self.daemon.contains("://") { self.daemon.clone() } else { format!("http://{}", self.daemon) };
+        let mut url = reqwest::Url::parse(&base)?.join("/api/events")?;
+        if let Some(token) = &self.token {
+            url.query_pairs_mut().append_pair("token", token);
+        }
+        Ok(url)
+    }
+}
+
+/// The queue as the daemon publishes it
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Snapshot {
+    pub items: Vec<WebItem>,
+    pub notice: Option<String>,
+}
+
+/// State of the link to the daemon
+#[derive(Debug, Clone, PartialEq)]
+pub enum Connection {
+    Connecting,
+    Connected,
+    /// Lost, with why; reconnecting
+    Lost(String),
+}
+
+/// What the monitor shows
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    pub daemon: String,
+    pub snapshot: Snapshot,
+    pub connection: Connection,
+    pub selected_index: usize,
+}
+
+enum Update {
+    Connected,
+    Snapshot(Snapshot),
+    Lost(String),
+}
+
+/// Split complete server-sent events off the front of `buffer`, returning their data.
+/// Bytes are only decoded once their event is complete, a chunk may end inside a character.
+fn take_events(buffer: &mut Vec<u8>) -> Vec<String> {
+    let mut events = Vec::new();
+    while let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+        let bytes: Vec<u8> = buffer.drain(..end + 2).collect();
+        let event = String::from_utf8_lossy(&bytes);
+        let data: Vec<&str> = event.lines().filter_map(|line| line.strip_prefix("data: ")).collect();
+        if !data.is_empty() {
+            events.push(data.join("\n"));
+        }
+    }
+    events
+}
+
+/// Follow the daemon's snapshots, reconnecting whenever the stream ends
+async fn follow(url: reqwest::Url, tx: mpsc::Sender<Update>) {
+    let client = reqwest::Client::new();
+    loop {
+        let result: Result<()> = async {
+            let mut response = client.get(url.clone()).send().await?.error_for_status()?;
+            tx.send(Update::Connected).await?;
+            let mut buffer: Vec<u8> = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                buffer.extend_from_slice(&chunk);
+                for data in take_events(&mut buffer) {
+                    tx.send(Update::Snapshot(serde_json::from_str(&data)?)).await?;
+                }
+            }
+            Err(anyhow!("the daemon closed the connection"))
+        }
+        .await;
+        let reason = result.err().map_or_else(String::new, |e| e.to_string());
+        if tx.send(Update::Lost(reason)).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// Show a daemon's queue until q is pressed. Nothing here sends actions: the keys only move the selection.
+pub async fn run(options: MonitorOptions) -> Result<()> {
+    let url = options.events_url()?;
+    let (tx, mut updates) = mpsc::channel(16);
+    let follower = tokio::spawn(follow(url, tx));
+
+    let (key_tx, mut keys) = mpsc::channel(32);
+    let input_task = tokio::spawn(async move {
+        loop {
+            if event::poll(Duration::from_millis(250)).unwrap_or(false) {
+                if let Ok(Event::Key(key)) = event::read() {
+                    if key.kind != KeyEventKind::Release && key_tx.send(key).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+
+    enable_raw_mode()?;
+    let mut stdout = std::io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let mut monitor = Monitor {
+        daemon: options.daemon,
+        snapshot: Snapshot::default(),
+        connection: Connection::Connecting,
+        selected_index: 0,
+    };
+    let mut resized = tokio::time::interval(Duration::from_millis(500));
+
+    let result = loop {
+        if let Err(e) = terminal.draw(|f| crate::ui::monitor::render(f, &monitor)) {
+            break Err(e.into());
+        }
+        tokio::select! {
+            Some(update) = updates.recv() => match update {
+                Update::Connected => monitor.connection = Connection::Connected,
+                Update::Snapshot(snapshot) => monitor.snapshot = snapshot,
+                Update::Lost(reason) => monitor.connection = Connection::Lost(reason),
+            },
+            Some(key) = keys.recv() => {
+                let last = monitor.snapshot.items.len().saturating_sub(1);
+                match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break Ok(()),
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break Ok(()),
+                    KeyCode::Up | KeyCode::Char('k') => monitor.selected_index = monitor.selected_index.saturating_sub(1),
+                    KeyCode::Down | KeyCode::Char('j') => monitor.selected_index += 1,
+                    KeyCode::Home | KeyCode::Char('g') => monitor.selected_index = 0,
+                    KeyCode::End | KeyCode::Char('G') => monitor.selected_index = last,
+                    _ => {}
+                }
+                monitor.selected_index = monitor.selected_index.min(last);
+            }
+            // Redraw now and then so a resized terminal doesn't wait for the next snapshot
+            _ = resized.tick() => {}
+        }
+    };
+
+    follower.abort();
+    input_task.abort();
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_monitor_stream() {
+        let args: Vec<String> = ["gorlock", "--monitor", "nas:8787", "--token", "s3cret"].iter().map(|a| a.to_string()).collect();
+        let options = MonitorOptions::from_args(&args).unwrap().unwrap();
+        assert_eq!(options.events_url().unwrap().as_str(), "http://nas:8787/api/events?token=s3cret");
+
+        let mut buffer = b": keepalive\n\ndata: {\"items\": []}\n\ndata: {\"it".to_vec();
+        assert_eq!(take_events(&mut buffer), ["{\"items\": []}"]);
+        assert_eq!(buffer, b"data: {\"it");
+
+        // A character split across chunks comes out whole
+        let word = "data: {\"title\": \"caf\u{e9}\"}\n\n".as_bytes();
+        let split = word.len() - 5;
+        let mut buffer = word[..split].to_vec();
+        assert!(take_events(&mut buffer).is_empty());
+        buffer.extend_from_slice(&word[split..]);
+        assert_eq!(take_events(&mut buffer), ["{\"title\": \"caf\u{e9}\"}"]);
+    }
+}
@@ -0,0 +1,91 @@
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::app_state::{AppState, DownloadProgress, events::AppEvent};
+
+/// A download event for `/api/stream`, taken from the AppEvents the TUI handles
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Progress { id: Uuid, progress: DownloadProgress },
+    Completed { id: Uuid, file: Option<String>, warning: Option<String> },
+    Failed { id: Uuid, error: String },
+    /// Stopped with the partial download kept
+    Stopped { id: Uuid, reason: String },
+    FormatsFetched { id: Uuid, formats: usize, is_live: bool },
+    FormatsFetchFailed { id: Uuid, error: String },
+}
+
+impl StreamEvent {
+    /// The stream's view of an event, `None` for events outside dashboards' interest
+    pub fn from_app_event(event: &AppEvent) -> Option<Self> {
+        Some(match event {
+            AppEvent::ProgressUpdate { id, progress } => StreamEvent::Progress { id: *id, progress: progress.clone() },
+            AppEvent::DownloadCompleted { id, outcome } => StreamEvent::Completed {
+                id: *id,
+                file: outcome.destination.clone(),
+                warning: outcome.warning.clone(),
+            },
+            AppEvent::DownloadFailed { id, error } => StreamEvent::Failed { id: *id, error: error.clone() },
+            AppEvent::DownloadStopped { id, reason } => StreamEvent::Stopped { id: *id, reason: reason.clone() },
+            AppEvent::FormatsFetched { id, formats, is_live, .. } => StreamEvent::FormatsFetched {
+                id: *id,
+                formats: formats.len(),
+                is_live: *is_live,
+            },
+            AppEvent::FormatsFetchFailed { id, error } => StreamEvent::FormatsFetchFailed { id: *id, error: error.clone() },
+            _ => return None,
+        })
+    }
+
+    fn id(&self) -> Uuid {
+        match self {
+            StreamEvent::Progress { id, .. }
+            | StreamEvent::Completed { id, .. }
+            | StreamEvent::Failed { id, .. }
+            | StreamEvent::Stopped { id, .. }
+            | StreamEvent::FormatsFetched { id, .. }
+            | StreamEvent::FormatsFetchFailed { id, .. } => *id,
+        }
+    }
+
+    /// One JSON line, with the item's title and status once the event has been handled.
+    /// Progress carries the averaged speed and ETA rather than yt-dlp's raw numbers.
+    pub fn into_json(mut self, state: &AppState) -> String {
+        let item = state.queue.iter().find(|i| i.id == self.id());
+        if let (StreamEvent::Progress { progress, .. }, Some(item)) = (&mut self, item) {
+            *progress = item.progress.clone();
+        }
+        let mut value = serde_json::to_value(&self).unwrap_or_default();
+        if let (Some(object), Some(item)) = (value.as_object_mut(), item) {
+            object.insert("title".into(), item.title.clone().unwrap_or_else(|| item.url.clone()).into());
+            object.insert("url".into(), item.url.clone().into());
+            object.insert("status".into(), item.status.to_string().into());
+        }
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_state::DownloadItem;
+
+    #[test]
+    fn test_stream_event_json() {
+        let mut state = AppState::default();
+        let mut item = DownloadItem::new("https://example.com/v/1".to_string());
+        item.title = Some("Clip".to_string());
+        let id = item.id;
+        state.queue.push(item);
+
+        let event = AppEvent::DownloadFailed { id, error: "HTTP Error 403".to_string() };
+        let json: serde_json::Value = serde_json::from_str(&StreamEvent::from_app_event(&event).unwrap().into_json(&state)).unwrap();
+        assert_eq!(json["type"], "failed");
+        assert_eq!(json["error"], "HTTP Error 403");
+        assert_eq!(json["title"], "Clip");
+        assert_eq!(json["id"], id.to_string());
+
+        assert!(StreamEvent::from_app_event(&AppEvent::Tick).is_none());
+    }
+}
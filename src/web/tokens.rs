@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+/// What an API token may do
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TokenScope {
+    /// See the queue and follow its events, for dashboards and `--monitor`
+    #[default]
+    ReadOnly,
+    /// Add URLs and nothing else, for browser extensions and share menus
+    AddOnly,
+    /// Everything, pausing and cancelling included
+    Full,
+}
+
+impl TokenScope {
+    pub const ALL: &'static [TokenScope] = &[TokenScope::ReadOnly, TokenScope::AddOnly, TokenScope::Full];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read-only",
+            TokenScope::AddOnly => "add-only",
+            TokenScope::Full => "full control",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let index = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    fn allows(&self, access: Access) -> bool {
+        match self {
+            TokenScope::ReadOnly => access == Access::Read,
+            TokenScope::AddOnly => access == Access::Add,
+            TokenScope::Full => true,
+        }
+    }
+}
+
+/// A named token for the daemon's web API, managed in settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub name: String,
+    pub token: String,
+    #[serde(default)]
+    pub scope: TokenScope,
+}
+
+impl ApiToken {
+    /// A new random token
+    pub fn generate(name: String, scope: TokenScope) -> Self {
+        Self {
+            name,
+            token: uuid::Uuid::new_v4().simple().to_string(),
+            scope,
+        }
+    }
+
+    /// The token's start, enough to tell tokens apart on screen
+    pub fn masked(&self) -> String {
+        format!("{}…", self.token.chars().take(6).collect::<String>())
+    }
+}
+
+/// What a request needs its token to allow
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Read,
+    Add,
+    Control,
+}
+
+/// Who may use the daemon's API: the `--token` has full control, settings tokens their scope
+#[derive(Debug, Clone, Default)]
+pub struct Auth {
+    pub token: Option<String>,
+    pub tokens: Vec<ApiToken>,
+    /// A token was set at some point, revoking the last one mustn't open the API
+    closed: bool,
+}
+
+impl Auth {
+    pub fn new(token: Option<String>, tokens: Vec<ApiToken>) -> Self {
+        let closed = token.is_some() || !tokens.is_empty();
+        Self { token, tokens, closed }
+    }
+
+    /// Take the settings' tokens, `false` when they didn't change. Once closed the API stays closed.
+    pub fn set_tokens(&mut self, tokens: &[ApiToken]) -> bool {
+        if self.tokens == tokens {
+            return false;
+        }
+        self.tokens = tokens.to_vec();
+        self.closed |= !tokens.is_empty();
+        true
+    }
+
+    /// Without any token, ever, the API is open
+    pub fn is_open(&self) -> bool {
+        !self.closed
+    }
+
+    /// Check a request's `?token=`, the error is the HTTP status and message to answer with
+    pub fn check(&self, given: Option<&str>, access: Access) -> Result<(), (&'static str, &'static str)> {
+        if self.is_open() {
+            return Ok(());
+        }
+        let scope = match given {
+            Some(given) if self.token.as_deref() == Some(given) => TokenScope::Full,
+            Some(given) => match self.tokens.iter().find(|t| t.token == given) {
+                Some(token) => token.scope,
+                None => return Err(("401 Unauthorized", "Add ?token=... to the address")),
+            },
+            None => return Err(("401 Unauthorized", "Add ?token=... to the address")),
+        };
+        if scope.allows(access) {
+            Ok(())
+        } else {
+            Err(("403 Forbidden", "This token isn't allowed to do that"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes() {
+        let extension = ApiToken::generate("extension".to_string(), TokenScope::AddOnly);
+        let dashboard = ApiToken::generate("dashboard".to_string(), TokenScope::ReadOnly);
+        let auth = Auth::new(Some("s3cret".to_string()), vec![extension.clone(), dashboard.clone()]);
+        assert!(auth.check(Some("s3cret"), Access::Control).is_ok());
+        assert!(auth.check(Some(&extension.token), Access::Add).is_ok());
+        assert_eq!(auth.check(Some(&extension.token), Access::Control).unwrap_err().0, "403 Forbidden");
+        assert!(auth.check(Some(&dashboard.token), Access::Read).is_ok());
+        assert!(auth.check(Some(&dashboard.token), Access::Add).is_err());
+        assert_eq!(auth.check(Some("guess"), Access::Read).unwrap_err().0, "401 Unauthorized");
+        assert!(Auth::default().check(None, Access::Control).is_ok());
+
+        // Revoking the last token leaves the API closed rather than open to anyone
+        let mut auth = Auth::new(None, vec![extension.clone()]);
+        assert!(auth.set_tokens(&[]) && auth.tokens.is_empty());
+        assert_eq!(auth.check(None, Access::Control).unwrap_err().0, "401 Unauthorized");
+        assert!(auth.check(Some(&extension.token), Access::Read).is_err());
+        let mut auth = Auth::new(None, Vec::new());
+        assert!(!auth.set_tokens(&[]) && auth.is_open());
+        auth.set_tokens(&[dashboard]);
+        assert!(auth.check(None, Access::Read).is_err());
+    }
+}